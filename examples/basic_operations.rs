@@ -1,7 +1,7 @@
 use graph::{
     Config,
     graph::{Graph, new_graph},
-    types::{Node, Edge, NodeId, EdgeId, EntityType, Properties, TemporalRange, Timestamp},
+    types::{Node, Edge, NodeId, EdgeId, EntityType, Properties, TemporalFilter, TemporalRange, Timestamp},
 };
 use chrono::Utc;
 use std::collections::HashMap;
@@ -100,8 +100,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("🔍 Querying the graph...");
     
     // Retrieve nodes
-    let retrieved_person = graph.get_node(person_id).await?;
-    let retrieved_org = graph.get_node(org_id).await?;
+    let retrieved_person = graph.get_node(person_id, TemporalFilter::current()).await?;
+    let retrieved_org = graph.get_node(org_id, TemporalFilter::current()).await?;
     
     println!("👤 Person: {} ({})", 
         retrieved_person.label, 
@@ -114,7 +114,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     );
     
     // Get connected nodes
-    let connected_orgs = graph.get_connected_nodes(person_id, None).await?;
+    let connected_orgs = graph.get_connected_nodes(person_id, TemporalFilter::current()).await?;
     println!("🔗 {} is connected to {} organizations", retrieved_person.label, connected_orgs.len());
     
     for org in connected_orgs {
@@ -122,7 +122,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     }
     
     // Get edges for a node
-    let person_edges = graph.get_edges_for_node(person_id, None).await?;
+    let person_edges = graph.get_edges_for_node(person_id, TemporalFilter::current()).await?;
     println!("↔️ {} has {} relationships", retrieved_person.label, person_edges.len());
     
     for edge in person_edges {
@@ -157,7 +157,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     };
     
     // Update the person node
-    graph.update_node(updated_person).await?;
+    graph.update_node(updated_person, None).await?;
     println!("✅ Updated person node with new properties (future valid time)");
     
     // Create a new version of the edge (e.g., changed role)
@@ -182,7 +182,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     };
     
     // Update the edge
-    graph.update_edge(updated_edge).await?;
+    graph.update_edge(updated_edge, None).await?;
     println!("✅ Updated edge with new properties (future valid time)");
     
     println!("\n✨ Example completed successfully!");
@@ -32,9 +32,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     
     // Initialize underlying services
     let graph = graph::graph::new_graph(&config).await?;
-    let memory = graph::memory::MemorySystem::new(
-        graph::memory::OpenSearchMemory::new(&config).await?
-    ).await?;
+    let client = graph::memory::MemorySystem::create_client(&config).await?;
+    let memory = graph::memory::MemorySystem::new(client, "memory".to_string(), config.memory_size).await?;
     let temporal_index = graph::temporal::DynamoDBTemporal::new(&config).await?;
     
     // Create hybrid graph
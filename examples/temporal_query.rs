@@ -1,7 +1,7 @@
 use graph::{
     Config,
     graph::{Graph, new_graph},
-    types::{Node, Edge, NodeId, EdgeId, EntityType, Properties, TemporalRange, Timestamp},
+    types::{Node, Edge, NodeId, EdgeId, EntityType, Properties, TemporalFilter, TemporalRange, Timestamp},
     temporal::{TimeRange, TemporalQueryBuilder, PropertyOperator, RelationshipDirection},
 };
 use chrono::{Utc, Duration};
@@ -111,7 +111,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("⏰ New timestamp: {}", one_month_later);
     
     // Update product - price drop
-    let retrieved_product = graph.get_node(product_id).await?;
+    let retrieved_product = graph.get_node(product_id, TemporalFilter::current()).await?;
     let mut updated_product_props = retrieved_product.properties.0.clone();
     updated_product_props.insert("price".to_string(), 799.99.into());
     updated_product_props.insert("note".to_string(), "Price reduced for summer sale".into());
@@ -131,8 +131,9 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         },
     };
     
-    // Update the product node
-    graph.update_node(updated_product).await?;
+    // Update the product node, remembering when the original price was still recorded
+    let before_price_change = Timestamp::now();
+    graph.update_node(updated_product, None).await?;
     println!("✅ Updated product with new price at timestamp: {}", one_month_later);
     
     // ---- Create state at time 2 (two months later) ----
@@ -144,7 +145,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("⏰ New timestamp: {}", two_months_later);
     
     // Customer becomes a premium member
-    let retrieved_customer = graph.get_node(customer_id).await?;
+    let retrieved_customer = graph.get_node(customer_id, TemporalFilter::current()).await?;
     let mut updated_customer_props = retrieved_customer.properties.0.clone();
     updated_customer_props.insert("membership_level".to_string(), "Gold".into());
     updated_customer_props.insert("upgrade_date".to_string(), two_months_later.to_rfc3339().into());
@@ -164,8 +165,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         },
     };
     
-    // Update the customer node
-    graph.update_node(updated_customer).await?;
+    // Update the customer node, failing if it changed since it was read
+    graph.update_node(updated_customer, retrieved_customer.version()).await?;
     println!("✅ Updated customer with Gold membership at timestamp: {}", two_months_later);
     
     // Customer purchases the product
@@ -199,7 +200,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     
     // Query product at initial time
     println!("\n📊 Product at initial time:");
-    let initial_product = graph.get_node(product_id).await?;
+    let initial_product = graph.get_node(product_id, TemporalFilter::current().as_of(before_price_change)).await?;
     println!("  Name: {}", initial_product.label);
     println!("  Price: {}", initial_product.properties.0.get("price").unwrap());
     println!("  In Stock: {}", initial_product.properties.0.get("in_stock").unwrap());
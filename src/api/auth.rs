@@ -0,0 +1,387 @@
+//! API authentication
+//!
+//! Requests carry a credential in `Authorization: Bearer <credential>` or
+//! `X-API-Key: <credential>`. An [`Authenticator`] turns the credential into
+//! [`Claims`], which the middleware stores in the request extensions for
+//! scope checks and handlers.
+
+use async_trait::async_trait;
+use jsonwebtoken::{
+    decode, decode_header,
+    jwk::{AlgorithmParameters, JwkSet},
+    Algorithm, DecodingKey, Validation,
+};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::{
+    api::error::{ApiError, ApiResult},
+    config::{ApiKeyConfig, AuthMode, Config},
+    error::{Error, Result},
+    types::TenantId,
+};
+
+/// Scope required to read nodes, edges and knowledge
+pub const GRAPH_READ: &str = "graph:read";
+
+/// Scope required to create, update and delete nodes, edges and knowledge
+pub const GRAPH_WRITE: &str = "graph:write";
+
+/// Identity of an authenticated caller
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Claims {
+    /// Subject the credential was issued to
+    pub subject: String,
+    /// Tenant the subject belongs to
+    pub tenant: Option<String>,
+    /// Scopes granted to the credential
+    pub scopes: Vec<String>,
+}
+
+impl Claims {
+    /// Whether the credential grants `scope`
+    pub fn has_scope(&self, scope: &str) -> bool {
+        self.scopes.iter().any(|s| s == scope)
+    }
+
+    /// Tenant whose data the caller may access; the default tenant when the
+    /// credential names none
+    pub fn tenant_id(&self) -> ApiResult<TenantId> {
+        match &self.tenant {
+            Some(tenant) => TenantId::new(tenant.as_str())
+                .map_err(|_| ApiError::Forbidden(format!("Invalid tenant {:?}", tenant))),
+            None => Ok(TenantId::default()),
+        }
+    }
+}
+
+/// Verifies request credentials
+#[async_trait]
+pub trait Authenticator: Send + Sync {
+    /// Resolve a credential to the caller's claims
+    async fn authenticate(&self, credential: &str) -> ApiResult<Claims>;
+}
+
+/// Hex-encoded SHA-256 hash of an API key, as stored in [`ApiKeyConfig::hash`]
+pub fn hash_api_key(key: &str) -> String {
+    Sha256::digest(key.as_bytes())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Source of hashed API keys
+#[async_trait]
+pub trait ApiKeyStore: Send + Sync {
+    /// Look up the key with the given hash
+    async fn find(&self, hash: &str) -> Result<Option<ApiKeyConfig>>;
+}
+
+/// API keys held in memory, loaded from the config or a key store file
+#[derive(Debug, Default)]
+pub struct StaticKeyStore {
+    keys: HashMap<String, ApiKeyConfig>,
+}
+
+impl StaticKeyStore {
+    /// Create a store holding `keys`
+    pub fn new(keys: impl IntoIterator<Item = ApiKeyConfig>) -> Self {
+        Self {
+            keys: keys.into_iter()
+                .map(|key| (key.hash.to_lowercase(), key))
+                .collect(),
+        }
+    }
+
+    /// Load a JSON array of API keys
+    pub async fn from_file(path: &str) -> Result<Self> {
+        let contents = tokio::fs::read_to_string(path).await?;
+        let keys: Vec<ApiKeyConfig> = serde_json::from_str(&contents)
+            .map_err(|e| Error::ConfigurationError(format!("Invalid API key file {}: {}", path, e)))?;
+        Ok(Self::new(keys))
+    }
+
+    /// Add the keys from another store
+    pub fn extend(&mut self, other: StaticKeyStore) {
+        self.keys.extend(other.keys);
+    }
+}
+
+#[async_trait]
+impl ApiKeyStore for StaticKeyStore {
+    async fn find(&self, hash: &str) -> Result<Option<ApiKeyConfig>> {
+        Ok(self.keys.get(hash).cloned())
+    }
+}
+
+/// Authenticates requests by the hash of their API key
+pub struct ApiKeyAuthenticator {
+    store: Arc<dyn ApiKeyStore>,
+}
+
+impl ApiKeyAuthenticator {
+    /// Create an authenticator backed by `store`
+    pub fn new(store: Arc<dyn ApiKeyStore>) -> Self {
+        Self { store }
+    }
+}
+
+#[async_trait]
+impl Authenticator for ApiKeyAuthenticator {
+    async fn authenticate(&self, credential: &str) -> ApiResult<Claims> {
+        let key = self.store.find(&hash_api_key(credential)).await?
+            .ok_or_else(|| ApiError::Unauthorized("Unknown API key".to_string()))?;
+
+        Ok(Claims {
+            subject: key.subject,
+            tenant: key.tenant,
+            scopes: key.scopes,
+        })
+    }
+}
+
+/// Registered and custom claims read from a JWT
+#[derive(Debug, Deserialize)]
+struct TokenClaims {
+    sub: String,
+    #[serde(default)]
+    tenant: Option<String>,
+    /// Space-delimited scopes, as in RFC 8693
+    #[serde(default)]
+    scope: Option<String>,
+    #[serde(default)]
+    scopes: Vec<String>,
+}
+
+/// Authenticates requests with JWTs signed by HS256 or RS256
+///
+/// RS256 keys are selected by the token's `kid`; a token without one is
+/// accepted when the key set holds a single key.
+#[derive(Default)]
+pub struct JwtAuthenticator {
+    secret: Option<DecodingKey>,
+    rsa_keys: Vec<(Option<String>, DecodingKey)>,
+    issuer: Option<String>,
+    audience: Option<String>,
+}
+
+impl JwtAuthenticator {
+    /// Create an authenticator that accepts no keys until some are added
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Accept HS256 tokens signed with `secret`
+    pub fn with_secret(mut self, secret: &[u8]) -> Self {
+        self.secret = Some(DecodingKey::from_secret(secret));
+        self
+    }
+
+    /// Accept RS256 tokens signed by the RSA keys in `jwks`
+    pub fn with_jwks(mut self, jwks: &JwkSet) -> Result<Self> {
+        for jwk in &jwks.keys {
+            if let AlgorithmParameters::RSA(_) = jwk.algorithm {
+                let key = DecodingKey::from_jwk(jwk)
+                    .map_err(|e| Error::ConfigurationError(format!("Invalid JWK: {}", e)))?;
+                self.rsa_keys.push((jwk.common.key_id.clone(), key));
+            }
+        }
+        Ok(self)
+    }
+
+    /// Accept RS256 tokens signed by the RSA keys in a JWKS file
+    pub async fn with_jwks_file(self, path: &str) -> Result<Self> {
+        let contents = tokio::fs::read_to_string(path).await?;
+        let jwks: JwkSet = serde_json::from_str(&contents)
+            .map_err(|e| Error::ConfigurationError(format!("Invalid JWKS file {}: {}", path, e)))?;
+        self.with_jwks(&jwks)
+    }
+
+    /// Require the `iss` claim to equal `issuer`
+    pub fn with_issuer(mut self, issuer: impl Into<String>) -> Self {
+        self.issuer = Some(issuer.into());
+        self
+    }
+
+    /// Require the `aud` claim to contain `audience`
+    pub fn with_audience(mut self, audience: impl Into<String>) -> Self {
+        self.audience = Some(audience.into());
+        self
+    }
+
+    fn decoding_key(&self, algorithm: Algorithm, kid: Option<&str>) -> Option<&DecodingKey> {
+        match algorithm {
+            Algorithm::HS256 => self.secret.as_ref(),
+            Algorithm::RS256 => match kid {
+                Some(kid) => self.rsa_keys.iter()
+                    .find(|(key_id, _)| key_id.as_deref() == Some(kid))
+                    .map(|(_, key)| key),
+                None if self.rsa_keys.len() == 1 => Some(&self.rsa_keys[0].1),
+                None => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+#[async_trait]
+impl Authenticator for JwtAuthenticator {
+    async fn authenticate(&self, credential: &str) -> ApiResult<Claims> {
+        let header = decode_header(credential)
+            .map_err(|e| ApiError::Unauthorized(format!("Malformed token: {}", e)))?;
+        let key = self.decoding_key(header.alg, header.kid.as_deref())
+            .ok_or_else(|| ApiError::Unauthorized("No key for token".to_string()))?;
+
+        let mut validation = Validation::new(header.alg);
+        if let Some(issuer) = &self.issuer {
+            validation.set_issuer(&[issuer]);
+        }
+        match &self.audience {
+            Some(audience) => validation.set_audience(&[audience]),
+            None => validation.validate_aud = false,
+        }
+
+        let token = decode::<TokenClaims>(credential, key, &validation)
+            .map_err(|e| ApiError::Unauthorized(format!("Invalid token: {}", e)))?;
+        let claims = token.claims;
+        let mut scopes = claims.scopes;
+        if let Some(scope) = claims.scope {
+            scopes.extend(scope.split_whitespace().map(str::to_string));
+        }
+
+        Ok(Claims {
+            subject: claims.sub,
+            tenant: claims.tenant,
+            scopes,
+        })
+    }
+}
+
+/// Build the authenticator selected by `config`, or `None` when auth is disabled
+pub async fn new_authenticator(config: &Config) -> Result<Option<Arc<dyn Authenticator>>> {
+    match config.auth_mode {
+        AuthMode::None => Ok(None),
+        AuthMode::ApiKey => {
+            let mut store = StaticKeyStore::new(config.api_keys.clone());
+            if let Some(path) = &config.api_keys_path {
+                store.extend(StaticKeyStore::from_file(path).await?);
+            }
+            Ok(Some(Arc::new(ApiKeyAuthenticator::new(Arc::new(store)))))
+        }
+        AuthMode::Jwt => {
+            let mut authenticator = JwtAuthenticator::new();
+            if let Some(secret) = &config.jwt_secret {
+                authenticator = authenticator.with_secret(secret.as_bytes());
+            }
+            if let Some(path) = &config.jwks_path {
+                authenticator = authenticator.with_jwks_file(path).await?;
+            }
+            if config.jwt_secret.is_none() && config.jwks_path.is_none() {
+                return Err(Error::ConfigurationError(
+                    "JWT auth requires JWT_SECRET or JWKS_FILE".to_string(),
+                ));
+            }
+            if let Some(issuer) = &config.jwt_issuer {
+                authenticator = authenticator.with_issuer(issuer.clone());
+            }
+            if let Some(audience) = &config.jwt_audience {
+                authenticator = authenticator.with_audience(audience.clone());
+            }
+            Ok(Some(Arc::new(authenticator)))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jsonwebtoken::{encode, EncodingKey, Header};
+    use serde_json::json;
+
+    // Test-only RSA key; the modulus below is its public half
+    const RSA_PRIVATE_KEY: &str = include_str!("../../tests/fixtures/jwt_rsa.pem");
+    const RSA_MODULUS: &str = "uwTSC8uNiSusp9dFdwpGtaExDW_q1GMJMic04HIZQZX1YRP48EVm1hVywbuN4FD4Y7lSvFugnvThrux6LTlZZrRFPdFaAxOYaY6d4noDapH5yoPQG0czqcB7U9bKcIf06aWC03RYFaVndj8jHJBswMT-dinsYz1w_oMzWCnj0XAFZVnev3RCSYWCHSLVsnqkzzKpxj5zXFrPPm9l6FcqrZ6wBlXusPD9dgL84AboKpv3gETNd5EVD9B_RQymA0wVBFnCbywmHYEXvEU2qQYpCQMIo9ynpB1UTvBPPHCT3XzW7IWvl79P10NNWjPSSkGZhCIMWAOPhzcOznvpcS2j4Q";
+
+    fn expires_in(seconds: i64) -> i64 {
+        chrono::Utc::now().timestamp() + seconds
+    }
+
+    #[tokio::test]
+    async fn test_api_key_authentication() {
+        let store = StaticKeyStore::new(vec![ApiKeyConfig {
+            hash: hash_api_key("reader-key"),
+            subject: "reporting".to_string(),
+            tenant: Some("acme".to_string()),
+            scopes: vec![GRAPH_READ.to_string()],
+        }]);
+        let authenticator = ApiKeyAuthenticator::new(Arc::new(store));
+
+        let claims = authenticator.authenticate("reader-key").await.unwrap();
+        assert_eq!(claims.subject, "reporting");
+        assert_eq!(claims.tenant.as_deref(), Some("acme"));
+        assert!(claims.has_scope(GRAPH_READ));
+        assert!(!claims.has_scope(GRAPH_WRITE));
+
+        assert!(authenticator.authenticate("other-key").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_hs256_token() {
+        let authenticator = JwtAuthenticator::new()
+            .with_secret(b"shared-secret")
+            .with_issuer("https://issuer.example");
+        let key = EncodingKey::from_secret(b"shared-secret");
+
+        let token = encode(&Header::default(), &json!({
+            "sub": "user-1",
+            "tenant": "acme",
+            "scope": "graph:read graph:write",
+            "iss": "https://issuer.example",
+            "exp": expires_in(60),
+        }), &key).unwrap();
+        let claims = authenticator.authenticate(&token).await.unwrap();
+        assert_eq!(claims.subject, "user-1");
+        assert_eq!(claims.tenant.as_deref(), Some("acme"));
+        assert!(claims.has_scope(GRAPH_WRITE));
+
+        // Expired, wrongly issued and wrongly signed tokens are rejected
+        let expired = encode(&Header::default(), &json!({
+            "sub": "user-1", "iss": "https://issuer.example", "exp": expires_in(-3600),
+        }), &key).unwrap();
+        assert!(authenticator.authenticate(&expired).await.is_err());
+        let foreign = encode(&Header::default(), &json!({
+            "sub": "user-1", "iss": "https://other.example", "exp": expires_in(60),
+        }), &key).unwrap();
+        assert!(authenticator.authenticate(&foreign).await.is_err());
+        let forged = encode(&Header::default(), &json!({
+            "sub": "user-1", "iss": "https://issuer.example", "exp": expires_in(60),
+        }), &EncodingKey::from_secret(b"guessed")).unwrap();
+        assert!(authenticator.authenticate(&forged).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_rs256_token_from_jwks() {
+        let jwks: JwkSet = serde_json::from_value(json!({
+            "keys": [{ "kty": "RSA", "kid": "test-key", "alg": "RS256", "n": RSA_MODULUS, "e": "AQAB" }]
+        })).unwrap();
+        let authenticator = JwtAuthenticator::new().with_jwks(&jwks).unwrap();
+        let key = EncodingKey::from_rsa_pem(RSA_PRIVATE_KEY.as_bytes()).unwrap();
+
+        let mut header = Header::new(Algorithm::RS256);
+        header.kid = Some("test-key".to_string());
+        let token = encode(&header, &json!({
+            "sub": "service-1",
+            "scopes": [GRAPH_READ],
+            "exp": expires_in(60),
+        }), &key).unwrap();
+        let claims = authenticator.authenticate(&token).await.unwrap();
+        assert_eq!(claims.subject, "service-1");
+        assert_eq!(claims.scopes, vec![GRAPH_READ.to_string()]);
+
+        header.kid = Some("rotated-key".to_string());
+        let token = encode(&header, &json!({ "sub": "service-1", "exp": expires_in(60) }), &key).unwrap();
+        assert!(authenticator.authenticate(&token).await.is_err());
+    }
+}
@@ -8,6 +8,9 @@ use thiserror::Error;
 
 use crate::error::Error as CoreError;
 
+/// Non-standard status of a request cancelled before it completed, as used by nginx
+pub const CLIENT_CLOSED_REQUEST: u16 = 499;
+
 /// API-specific errors
 #[derive(Debug, Error)]
 pub enum ApiError {
@@ -20,9 +23,18 @@ pub enum ApiError {
     #[error("Unauthorized: {0}")]
     Unauthorized(String),
 
+    #[error("Forbidden: {0}")]
+    Forbidden(String),
+
     #[error("Rate limit exceeded: {0}")]
     RateLimitExceeded(String),
 
+    #[error("Precondition required: {0}")]
+    PreconditionRequired(String),
+
+    #[error("Service unavailable: {0}")]
+    Unavailable(String),
+
     #[error("Internal error: {0}")]
     Internal(String),
 
@@ -30,16 +42,43 @@ pub enum ApiError {
     Core(#[from] CoreError),
 }
 
-impl IntoResponse for ApiError {
-    fn into_response(self) -> Response {
-        let (status, error_message) = match self {
+impl ApiError {
+    /// HTTP status and client-facing message for this error
+    pub fn into_status_and_message(self) -> (StatusCode, String) {
+        match self {
             ApiError::NotFound(msg) => (StatusCode::NOT_FOUND, msg),
             ApiError::BadRequest(msg) => (StatusCode::BAD_REQUEST, msg),
             ApiError::Unauthorized(msg) => (StatusCode::UNAUTHORIZED, msg),
+            ApiError::Forbidden(msg) => (StatusCode::FORBIDDEN, msg),
             ApiError::RateLimitExceeded(msg) => (StatusCode::TOO_MANY_REQUESTS, msg),
+            ApiError::PreconditionRequired(msg) => (StatusCode::PRECONDITION_REQUIRED, msg),
+            ApiError::Unavailable(msg) => (StatusCode::SERVICE_UNAVAILABLE, msg),
             ApiError::Internal(msg) => (StatusCode::INTERNAL_SERVER_ERROR, msg),
-            ApiError::Core(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
-        };
+            ApiError::Core(e) => {
+                let status = match &e {
+                    CoreError::NodeNotFound(_)
+                    | CoreError::EdgeNotFound(_)
+                    | CoreError::EntityNotFound(_)
+                    | CoreError::NotFound(_) => StatusCode::NOT_FOUND,
+                    CoreError::InvalidInput(_)
+                    | CoreError::InvalidId(_)
+                    | CoreError::ValidationError(_)
+                    | CoreError::InvalidQueryFormat(_)
+                    | CoreError::InvalidTemporalRange(_) => StatusCode::BAD_REQUEST,
+                    CoreError::VersionConflict { .. } => StatusCode::PRECONDITION_FAILED,
+                    CoreError::Cancelled => StatusCode::from_u16(CLIENT_CLOSED_REQUEST)
+                        .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR),
+                    _ => StatusCode::INTERNAL_SERVER_ERROR,
+                };
+                (status, e.to_string())
+            }
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let (status, error_message) = self.into_status_and_message();
 
         let body = Json(json!({
             "error": {
@@ -1,24 +1,52 @@
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
-    response::IntoResponse,
+    extract::{
+        ws::{close_code, CloseFrame, Message, WebSocket, WebSocketUpgrade},
+        Path, Query, State,
+    },
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
     Json,
 };
+use std::collections::HashSet;
+use std::future::Future;
 use std::sync::Arc;
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 use serde_json::json;
+use tokio::sync::mpsc;
 use uuid::Uuid;
 use utoipa::ToSchema;
 
 use crate::{
-    api::{ApiState, ApiError, ApiResult},
+    api::{health::HealthChecker, ApiState, ApiError, ApiResult, TenantScope},
+    api::stream::{event_stream, StreamFormat, STREAM_BUFFER},
     api::models::*,
-    types::{Node, Edge, NodeId, EdgeId, EntityId, EntityType, Properties, TemporalRange, Timestamp},
+    changes::{ChangeEvent, ChangeFilter, ChangeKind, ChangeSubscription, Snapshot},
+    hybrid::{query::{HybridQueryBuilder, ScoredNode}, HybridGraph, HybridQuery, StageResult, VectorizedEdge, VectorizedNode},
+    memory::MemoryEntry,
+    temporal::TemporalIndexEntry,
+    error::Error as CoreError,
+    types::{Node, Edge, NodeId, EdgeId, EntityId, EntityType, Properties, TemporalFilter, TemporalRange, Timestamp, VersionToken, Versioned},
 };
 
+/// Node and edge property linking extracted knowledge to its source document
+pub const SOURCE_DOCUMENT_PROPERTY: &str = "source_document";
+
+/// Content type of the Prometheus text exposition format
+const PROMETHEUS_CONTENT_TYPE: &str = "text/plain; version=0.0.4";
+
+/// Versions in a page of history when no limit is given
+const DEFAULT_HISTORY_LIMIT: usize = 20;
+
+/// Largest page of history a client may request
+const MAX_HISTORY_LIMIT: usize = 100;
+
+/// Number of results returned by a knowledge query when no limit is given
+const DEFAULT_QUERY_LIMIT: usize = 10;
+
 /// Check health status of the API
 /// 
-/// Returns the health status, including uptime and component status.
+/// Probes every backend and returns the overall status, uptime and the
+/// status and latency of each component.
 #[utoipa::path(
     get,
     path = "/health",
@@ -31,28 +59,85 @@ use crate::{
 pub async fn health_check(
     State(state): State<Arc<ApiState>>
 ) -> Result<Json<HealthCheckResponse>, ApiError> {
-    let uptime = state.uptime().as_secs();
-    
-    // For now, just return a simple healthy status without checking components
-    // This is a temporary fix to get the code to compile
-    let components = vec![
-        ComponentHealth {
-            name: "api".to_string(),
-            status: ComponentStatus::Healthy,
-            details: None,
-        }
-    ];
-    
-    let status = "healthy";
-    
-    let response = HealthCheckResponse {
-        status: status.to_string(),
+    Ok(Json(health_report(&state).await))
+}
+
+/// Liveness probe
+/// 
+/// Reports that the process is up and serving requests. Backends are not probed.
+#[utoipa::path(
+    get,
+    path = "/health/live",
+    tag = "health",
+    responses(
+        (status = 200, description = "The API is running", body = HealthCheckResponse)
+    )
+)]
+#[axum::debug_handler]
+pub async fn liveness(
+    State(state): State<Arc<ApiState>>
+) -> Json<HealthCheckResponse> {
+    Json(HealthCheckResponse {
+        status: ComponentStatus::Healthy.as_str().to_string(),
         version: env!("CARGO_PKG_VERSION").to_string(),
-        uptime,
-        components,
+        uptime: state.uptime().as_secs(),
+        components: Vec::new(),
+    })
+}
+
+/// Readiness probe
+/// 
+/// Probes every backend and fails while any of them is unhealthy, so the
+/// instance is taken out of rotation. Degraded backends still serve traffic.
+#[utoipa::path(
+    get,
+    path = "/health/ready",
+    tag = "health",
+    responses(
+        (status = 200, description = "The API can serve traffic", body = HealthCheckResponse),
+        (status = 503, description = "A backend is unhealthy", body = HealthCheckResponse)
+    )
+)]
+#[axum::debug_handler]
+pub async fn readiness(
+    State(state): State<Arc<ApiState>>
+) -> (StatusCode, Json<HealthCheckResponse>) {
+    let report = health_report(&state).await;
+    let status = if report.status == ComponentStatus::Unhealthy.as_str() {
+        StatusCode::SERVICE_UNAVAILABLE
+    } else {
+        StatusCode::OK
     };
-    
-    Ok(Json(response))
+    (status, Json(report))
+}
+
+/// Prometheus metrics
+/// 
+/// Returns request, storage and cache metrics in the Prometheus text exposition format.
+#[utoipa::path(
+    get,
+    path = "/metrics",
+    tag = "health",
+    responses(
+        (status = 200, description = "Metrics in Prometheus text format", body = String, content_type = "text/plain")
+    )
+)]
+#[axum::debug_handler]
+pub async fn metrics(
+    State(state): State<Arc<ApiState>>
+) -> impl IntoResponse {
+    let body = state.metrics.as_ref().map(|metrics| metrics.render()).unwrap_or_default();
+    ([(header::CONTENT_TYPE, PROMETHEUS_CONTENT_TYPE)], body)
+}
+
+async fn health_report(state: &ApiState) -> HealthCheckResponse {
+    let components = state.health.check().await;
+    HealthCheckResponse {
+        status: HealthChecker::rollup(&components).as_str().to_string(),
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        uptime: state.uptime().as_secs(),
+        components,
+    }
 }
 
 /// Get API version information
@@ -77,35 +162,28 @@ pub async fn version() -> impl IntoResponse {
 
 /// Create a new node
 /// 
-/// Creates a new node in the graph with the specified properties and returns its ID.
+/// Creates a new node in the graph with the specified properties and returns it.
 #[utoipa::path(
     post,
     path = "/nodes",
     tag = "nodes",
     request_body = CreateNodeRequest,
     responses(
-        (status = 201, description = "Node created successfully"),
+        (status = 201, description = "Node created successfully", body = Node,
+            headers(("ETag" = String, description = "Version token of the node"))),
         (status = 400, description = "Invalid request"),
         (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Missing scope"),
         (status = 500, description = "Internal server error")
     )
 )]
-#[axum::debug_handler]
+#[axum::debug_handler(state = Arc<ApiState>)]
 pub async fn create_node(
-    State(state): State<Arc<ApiState>>,
+    scope: TenantScope,
     Json(request): Json<CreateNodeRequest>,
 ) -> ApiResult<impl IntoResponse> {
-    // TODO: Implement actual node creation
-    let node = Node {
-        id: NodeId(Uuid::new_v4()),
-        entity_type: EntityType::Node,
-        label: "Example Node".to_string(),
-        properties: Properties::new(),
-        valid_time: TemporalRange::from_now(),
-        transaction_time: TemporalRange::from_now(),
-    };
-    
-    Ok(Json(node))
+    let node = insert_node(&scope, request).await?;
+    Ok((StatusCode::CREATED, etag(node.version()), Json(node)))
 }
 
 /// Create multiple nodes in a batch
@@ -120,107 +198,172 @@ pub async fn create_node(
         (status = 200, description = "Batch processing results", body = BatchOperationResponse),
         (status = 400, description = "Invalid request"),
         (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Missing scope"),
         (status = 500, description = "Internal server error")
     )
 )]
-#[axum::debug_handler]
+#[axum::debug_handler(state = Arc<ApiState>)]
 pub async fn create_nodes_batch(
-    State(state): State<Arc<ApiState>>,
+    scope: TenantScope,
     Json(request): Json<BatchCreateNodesRequest>,
-) -> ApiResult<impl IntoResponse> {
-    // TODO: Implement batch node creation
-    let response = BatchOperationResponse {
-        success_count: request.nodes.len(),
+) -> ApiResult<Json<BatchOperationResponse>> {
+    let mut response = BatchOperationResponse {
+        success_count: 0,
+        ids: Vec::with_capacity(request.nodes.len()),
         failures: vec![],
     };
     
+    for (index, node) in request.nodes.into_iter().enumerate() {
+        match insert_node(&scope, node).await {
+            Ok(node) => {
+                response.success_count += 1;
+                response.ids.push(node.id.0);
+            }
+            Err(e) => response.failures.push(BatchOperationError { index, error: e.to_string() }),
+        }
+    }
+    
     Ok(Json(response))
 }
 
 /// Get a node by ID
 /// 
-/// Retrieves a node from the graph by its unique identifier.
+/// Retrieves a node from the graph by its unique identifier, as it is now or
+/// as it was at the given valid and transaction times.
 #[utoipa::path(
     get,
     path = "/nodes/{id}",
     tag = "nodes",
     params(
-        ("id" = String, Path, description = "Node UUID")
+        ("id" = String, Path, description = "Node UUID"),
+        ("as_of" = Option<String>, Query, description = "RFC 3339 valid time the node must be valid at"),
+        ("tx_as_of" = Option<String>, Query, description = "RFC 3339 transaction time to read the node as of")
     ),
     responses(
-        (status = 200, description = "Node found", body = Node),
+        (status = 200, description = "Node found", body = VersionedNode,
+            headers(("ETag" = String, description = "Version token of the returned node version"))),
         (status = 404, description = "Node not found"),
         (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Missing scope"),
         (status = 500, description = "Internal server error")
     )
 )]
-#[axum::debug_handler]
+#[axum::debug_handler(state = Arc<ApiState>)]
 pub async fn get_node(
-    State(state): State<Arc<ApiState>>,
+    scope: TenantScope,
     Path(id): Path<String>,
+    Query(params): Query<AsOfParams>,
 ) -> ApiResult<impl IntoResponse> {
-    // Parse UUID from path
-    let node_id = Uuid::parse_str(&id)
-        .map_err(|_| ApiError::BadRequest("Invalid node ID format".to_string()))?;
+    let node_id = parse_node_id(&id)?;
+    let node = scope.graph.get_node(node_id, as_of_filter(&params)).await?;
+    let version_id = node.version_id();
     
-    // TODO: Implement actual node retrieval
-    let node = Node {
-        id: NodeId(node_id),
-        entity_type: EntityType::Node,
-        label: "Example Node".to_string(),
-        properties: Properties::new(),
-        valid_time: TemporalRange::from_now(),
-        transaction_time: TemporalRange::from_now(),
-    };
+    Ok((etag(node.version()), Json(VersionedNode { node, version_id })))
+}
+
+/// Get the history of a node
+/// 
+/// Lists every recorded version of a node, oldest first, including versions
+/// that have since been replaced or deleted.
+#[utoipa::path(
+    get,
+    path = "/nodes/{id}/history",
+    tag = "nodes",
+    params(
+        ("id" = String, Path, description = "Node UUID"),
+        ("from" = Option<String>, Query, description = "RFC 3339 time; only versions valid at or after it"),
+        ("to" = Option<String>, Query, description = "RFC 3339 time; only versions valid at or before it"),
+        ("limit" = Option<usize>, Query, description = "Maximum number of versions to return"),
+        ("cursor" = Option<String>, Query, description = "next_cursor of the previous page")
+    ),
+    responses(
+        (status = 200, description = "Node history", body = NodeHistoryResponse),
+        (status = 404, description = "Node not found"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Missing scope"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[axum::debug_handler(state = Arc<ApiState>)]
+pub async fn node_history(
+    scope: TenantScope,
+    Path(id): Path<String>,
+    Query(params): Query<HistoryParams>,
+) -> ApiResult<Json<NodeHistoryResponse>> {
+    let node_id = parse_node_id(&id)?;
+    let (versions, next_cursor) = history_page(scope.graph.get_node_versions(node_id).await?, &params);
     
-    Ok(Json(node))
+    let versions = versions.into_iter()
+        .filter_map(|node| Some(NodeVersion {
+            version_id: node.version_id()?,
+            recorded_at: node.transaction_time.start?.0,
+            superseded_at: node.transaction_time.end.map(|ts| ts.0),
+            node,
+        }))
+        .collect();
+    Ok(Json(NodeHistoryResponse { versions, next_cursor }))
 }
 
 /// Update a node
 /// 
-/// Updates an existing node in the graph with the specified properties.
+/// Applies a partial update to an existing node and returns the new version.
+/// With `If-Match` set to the `ETag` of a previous read, the update is
+/// rejected if the node has changed since.
 #[utoipa::path(
     patch,
     path = "/nodes/{id}",
     tag = "nodes",
     params(
-        ("id" = String, Path, description = "Node UUID")
+        ("id" = String, Path, description = "Node UUID"),
+        ("If-Match" = Option<String>, Header, description = "ETag the node must still have; required when the server requires it")
     ),
     request_body = UpdateNodeRequest,
     responses(
-        (status = 200, description = "Node updated successfully"),
+        (status = 200, description = "Node updated successfully", body = Node,
+            headers(("ETag" = String, description = "Version token of the updated node"))),
         (status = 404, description = "Node not found"),
         (status = 400, description = "Invalid request"),
+        (status = 412, description = "Node changed since the If-Match version"),
+        (status = 428, description = "If-Match is required"),
         (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Missing scope"),
         (status = 500, description = "Internal server error")
     )
 )]
-#[axum::debug_handler]
+#[axum::debug_handler(state = Arc<ApiState>)]
 pub async fn update_node(
     State(state): State<Arc<ApiState>>,
+    scope: TenantScope,
     Path(id): Path<String>,
+    headers: HeaderMap,
     Json(request): Json<UpdateNodeRequest>,
 ) -> ApiResult<impl IntoResponse> {
-    // Parse UUID from path
-    let node_id = Uuid::parse_str(&id)
-        .map_err(|_| ApiError::BadRequest("Invalid node ID format".to_string()))?;
+    let node_id = parse_node_id(&id)?;
+    let mut node = scope.graph.get_node(node_id, TemporalFilter::current()).await?;
+    let expected = if_match(&state, &headers, node_id.to_string(), node.version())?;
+    let before = Snapshot::Node(node.clone());
     
-    // TODO: Implement actual node update
-    let node = Node {
-        id: NodeId(node_id),
-        entity_type: EntityType::Node,
-        label: "Updated Node".to_string(),
-        properties: Properties::new(),
-        valid_time: TemporalRange::from_now(),
-        transaction_time: TemporalRange::from_now(),
-    };
+    if let Some(label) = request.label {
+        node.label = label;
+    }
+    if let Some(properties) = request.properties {
+        merge_properties(&mut node.properties, properties);
+    }
+    if let Some(valid_time) = request.valid_time {
+        node.valid_time = valid_time;
+    }
     
-    Ok(Json(node))
+    scope.hybrid.update_node(VectorizedNode::new(node.clone(), None), expected).await?;
+    record_version(&scope, node_entity_id(&node), &node.valid_time).await?;
+    
+    let node = scope.graph.get_node(node_id, TemporalFilter::current()).await?;
+    publish_update(&scope, before, Snapshot::Node(node.clone()));
+    Ok((etag(node.version()), Json(node)))
 }
 
 /// Delete a node
 /// 
-/// Removes a node from the graph by its unique identifier.
+/// Removes a node and its incident edges from the graph.
 #[utoipa::path(
     delete,
     path = "/nodes/{id}",
@@ -229,24 +372,34 @@ pub async fn update_node(
         ("id" = String, Path, description = "Node UUID")
     ),
     responses(
-        (status = 200, description = "Node deleted successfully"),
+        (status = 200, description = "Node deleted successfully", body = DeleteResponse),
         (status = 404, description = "Node not found"),
         (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Missing scope"),
         (status = 500, description = "Internal server error")
     )
 )]
-#[axum::debug_handler]
+#[axum::debug_handler(state = Arc<ApiState>)]
 pub async fn delete_node(
-    State(state): State<Arc<ApiState>>,
+    scope: TenantScope,
     Path(id): Path<String>,
-) -> ApiResult<impl IntoResponse> {
-    // Parse UUID from path
-    let node_id = Uuid::parse_str(&id)
-        .map_err(|_| ApiError::BadRequest("Invalid node ID format".to_string()))?;
+) -> ApiResult<Json<DeleteResponse>> {
+    let node_id = parse_node_id(&id)?;
+    let node = scope.graph.get_node(node_id, TemporalFilter::current()).await?;
+    let edges = scope.graph.get_edges_for_node(node_id, TemporalFilter::current()).await?;
     
-    // TODO: Implement actual node deletion
+    scope.graph.delete_node(node_id).await?;
     
-    Ok(Json(json!({ "success": true, "id": id })))
+    // The graph removes incident edges along with the node
+    let now = Utc::now();
+    retire_version(&scope, &node_entity_id(&node), now).await?;
+    publish(&scope, ChangeKind::Deleted, Some(Snapshot::Node(node)), None);
+    for edge in edges {
+        retire_version(&scope, &edge_entity_id(edge.id), now).await?;
+        publish(&scope, ChangeKind::Deleted, Some(Snapshot::Edge(edge)), None);
+    }
+    
+    Ok(Json(DeleteResponse { success: true, id: node_id.0 }))
 }
 
 /// Create a new edge
@@ -258,30 +411,22 @@ pub async fn delete_node(
     tag = "edges",
     request_body = CreateEdgeRequest,
     responses(
-        (status = 201, description = "Edge created successfully"),
+        (status = 201, description = "Edge created successfully", body = Edge,
+            headers(("ETag" = String, description = "Version token of the edge"))),
         (status = 400, description = "Invalid request"),
         (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Missing scope"),
         (status = 404, description = "Source or target node not found"),
         (status = 500, description = "Internal server error")
     )
 )]
-#[axum::debug_handler]
+#[axum::debug_handler(state = Arc<ApiState>)]
 pub async fn create_edge(
-    State(state): State<Arc<ApiState>>,
+    scope: TenantScope,
     Json(request): Json<CreateEdgeRequest>,
 ) -> ApiResult<impl IntoResponse> {
-    // TODO: Implement actual edge creation
-    let edge = Edge {
-        id: EdgeId(Uuid::new_v4()),
-        source_id: NodeId(Uuid::new_v4()),
-        target_id: NodeId(Uuid::new_v4()),
-        label: "CONNECTS_TO".to_string(),
-        properties: Properties::new(),
-        valid_time: TemporalRange::from_now(),
-        transaction_time: TemporalRange::from_now(),
-    };
-    
-    Ok(Json(edge))
+    let edge = insert_edge(&scope, request).await?;
+    Ok((StatusCode::CREATED, etag(edge.version()), Json(edge)))
 }
 
 /// Create multiple edges in a batch
@@ -296,104 +441,171 @@ pub async fn create_edge(
         (status = 200, description = "Batch processing results", body = BatchOperationResponse),
         (status = 400, description = "Invalid request"),
         (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Missing scope"),
         (status = 500, description = "Internal server error")
     )
 )]
-#[axum::debug_handler]
+#[axum::debug_handler(state = Arc<ApiState>)]
 pub async fn create_edges_batch(
-    State(state): State<Arc<ApiState>>,
+    scope: TenantScope,
     Json(request): Json<BatchCreateEdgesRequest>,
-) -> ApiResult<impl IntoResponse> {
-    // TODO: Implement batch edge creation
-    let response = BatchOperationResponse {
-        success_count: request.edges.len(),
+) -> ApiResult<Json<BatchOperationResponse>> {
+    let mut response = BatchOperationResponse {
+        success_count: 0,
+        ids: Vec::with_capacity(request.edges.len()),
         failures: vec![],
     };
     
+    for (index, edge) in request.edges.into_iter().enumerate() {
+        match insert_edge(&scope, edge).await {
+            Ok(edge) => {
+                response.success_count += 1;
+                response.ids.push(edge.id.0);
+            }
+            Err(e) => response.failures.push(BatchOperationError { index, error: e.to_string() }),
+        }
+    }
+    
     Ok(Json(response))
 }
 
 /// Get an edge by ID
 /// 
-/// Retrieves an edge from the graph by its unique identifier.
+/// Retrieves an edge from the graph by its unique identifier, as it is now or
+/// as it was at the given valid and transaction times.
 #[utoipa::path(
     get,
     path = "/edges/{id}",
     tag = "edges",
     params(
-        ("id" = String, Path, description = "Edge UUID")
+        ("id" = String, Path, description = "Edge UUID"),
+        ("as_of" = Option<String>, Query, description = "RFC 3339 valid time the edge must be valid at"),
+        ("tx_as_of" = Option<String>, Query, description = "RFC 3339 transaction time to read the edge as of")
     ),
     responses(
-        (status = 200, description = "Edge found", body = Edge),
+        (status = 200, description = "Edge found", body = VersionedEdge,
+            headers(("ETag" = String, description = "Version token of the returned edge version"))),
         (status = 404, description = "Edge not found"),
         (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Missing scope"),
         (status = 500, description = "Internal server error")
     )
 )]
-#[axum::debug_handler]
+#[axum::debug_handler(state = Arc<ApiState>)]
 pub async fn get_edge(
-    State(state): State<Arc<ApiState>>,
+    scope: TenantScope,
     Path(id): Path<String>,
+    Query(params): Query<AsOfParams>,
 ) -> ApiResult<impl IntoResponse> {
-    // Parse UUID from path
-    let edge_id = Uuid::parse_str(&id)
-        .map_err(|_| ApiError::BadRequest("Invalid edge ID format".to_string()))?;
+    let edge_id = parse_edge_id(&id)?;
+    let edge = scope.graph.get_edge(edge_id, as_of_filter(&params)).await?;
+    let version_id = edge.version_id();
     
-    // TODO: Implement actual edge retrieval
-    let edge = Edge {
-        id: EdgeId(edge_id),
-        source_id: NodeId(Uuid::new_v4()),
-        target_id: NodeId(Uuid::new_v4()),
-        label: "CONNECTS_TO".to_string(),
-        properties: Properties::new(),
-        valid_time: TemporalRange::from_now(),
-        transaction_time: TemporalRange::from_now(),
-    };
+    Ok((etag(edge.version()), Json(VersionedEdge { edge, version_id })))
+}
+
+/// Get the history of an edge
+/// 
+/// Lists every recorded version of an edge, oldest first, including versions
+/// that have since been replaced or deleted.
+#[utoipa::path(
+    get,
+    path = "/edges/{id}/history",
+    tag = "edges",
+    params(
+        ("id" = String, Path, description = "Edge UUID"),
+        ("from" = Option<String>, Query, description = "RFC 3339 time; only versions valid at or after it"),
+        ("to" = Option<String>, Query, description = "RFC 3339 time; only versions valid at or before it"),
+        ("limit" = Option<usize>, Query, description = "Maximum number of versions to return"),
+        ("cursor" = Option<String>, Query, description = "next_cursor of the previous page")
+    ),
+    responses(
+        (status = 200, description = "Edge history", body = EdgeHistoryResponse),
+        (status = 404, description = "Edge not found"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Missing scope"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[axum::debug_handler(state = Arc<ApiState>)]
+pub async fn edge_history(
+    scope: TenantScope,
+    Path(id): Path<String>,
+    Query(params): Query<HistoryParams>,
+) -> ApiResult<Json<EdgeHistoryResponse>> {
+    let edge_id = parse_edge_id(&id)?;
+    let (versions, next_cursor) = history_page(scope.graph.get_edge_versions(edge_id).await?, &params);
     
-    Ok(Json(edge))
+    let versions = versions.into_iter()
+        .filter_map(|edge| Some(EdgeVersion {
+            version_id: edge.version_id()?,
+            recorded_at: edge.transaction_time.start?.0,
+            superseded_at: edge.transaction_time.end.map(|ts| ts.0),
+            edge,
+        }))
+        .collect();
+    Ok(Json(EdgeHistoryResponse { versions, next_cursor }))
 }
 
 /// Update an edge
 /// 
-/// Updates an existing edge in the graph with the specified properties.
+/// Applies a partial update to an existing edge and returns the new version.
+/// With `If-Match` set to the `ETag` of a previous read, the update is
+/// rejected if the edge has changed since. Labels cannot be changed; delete
+/// the edge and create a new one instead.
 #[utoipa::path(
     patch,
     path = "/edges/{id}",
     tag = "edges",
     params(
-        ("id" = String, Path, description = "Edge UUID")
+        ("id" = String, Path, description = "Edge UUID"),
+        ("If-Match" = Option<String>, Header, description = "ETag the edge must still have; required when the server requires it")
     ),
     request_body = UpdateEdgeRequest,
     responses(
-        (status = 200, description = "Edge updated successfully"),
+        (status = 200, description = "Edge updated successfully", body = Edge,
+            headers(("ETag" = String, description = "Version token of the updated edge"))),
         (status = 404, description = "Edge not found"),
-        (status = 400, description = "Invalid request"),
+        (status = 400, description = "Invalid request, or a change of label"),
+        (status = 412, description = "Edge changed since the If-Match version"),
+        (status = 428, description = "If-Match is required"),
         (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Missing scope"),
         (status = 500, description = "Internal server error")
     )
 )]
-#[axum::debug_handler]
+#[axum::debug_handler(state = Arc<ApiState>)]
 pub async fn update_edge(
     State(state): State<Arc<ApiState>>,
+    scope: TenantScope,
     Path(id): Path<String>,
+    headers: HeaderMap,
     Json(request): Json<UpdateEdgeRequest>,
 ) -> ApiResult<impl IntoResponse> {
-    // Parse UUID from path
-    let edge_id = Uuid::parse_str(&id)
-        .map_err(|_| ApiError::BadRequest("Invalid edge ID format".to_string()))?;
+    let edge_id = parse_edge_id(&id)?;
+    let mut edge = scope.graph.get_edge(edge_id, TemporalFilter::current()).await?;
+    let expected = if_match(&state, &headers, edge_id.to_string(), edge.version())?;
+    let before = Snapshot::Edge(edge.clone());
     
-    // TODO: Implement actual edge update
-    let edge = Edge {
-        id: EdgeId(edge_id),
-        source_id: NodeId(Uuid::new_v4()),
-        target_id: NodeId(Uuid::new_v4()),
-        label: "UPDATED_CONNECTION".to_string(),
-        properties: Properties::new(),
-        valid_time: TemporalRange::from_now(),
-        transaction_time: TemporalRange::from_now(),
-    };
+    // Graph backends keep the label with the edge's identity and cannot change it
+    if request.label.is_some_and(|label| label != edge.label) {
+        return Err(ApiError::BadRequest(
+            "Edge labels cannot be changed; delete the edge and create a new one".to_string(),
+        ));
+    }
+    if let Some(properties) = request.properties {
+        merge_properties(&mut edge.properties, properties);
+    }
+    if let Some(valid_time) = request.valid_time {
+        edge.valid_time = valid_time;
+    }
+    
+    scope.hybrid.update_edge(VectorizedEdge::new(edge.clone(), None), expected).await?;
+    record_version(&scope, edge_entity_id(edge_id), &edge.valid_time).await?;
     
-    Ok(Json(edge))
+    let edge = scope.graph.get_edge(edge_id, TemporalFilter::current()).await?;
+    publish_update(&scope, before, Snapshot::Edge(edge.clone()));
+    Ok((etag(edge.version()), Json(edge)))
 }
 
 /// Delete an edge
@@ -407,29 +619,219 @@ pub async fn update_edge(
         ("id" = String, Path, description = "Edge UUID")
     ),
     responses(
-        (status = 200, description = "Edge deleted successfully"),
+        (status = 200, description = "Edge deleted successfully", body = DeleteResponse),
         (status = 404, description = "Edge not found"),
         (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Missing scope"),
         (status = 500, description = "Internal server error")
     )
 )]
-#[axum::debug_handler]
+#[axum::debug_handler(state = Arc<ApiState>)]
 pub async fn delete_edge(
-    State(state): State<Arc<ApiState>>,
+    scope: TenantScope,
     Path(id): Path<String>,
-) -> ApiResult<impl IntoResponse> {
-    // Parse UUID from path
-    let edge_id = Uuid::parse_str(&id)
-        .map_err(|_| ApiError::BadRequest("Invalid edge ID format".to_string()))?;
+) -> ApiResult<Json<DeleteResponse>> {
+    let edge_id = parse_edge_id(&id)?;
+    let edge = scope.graph.get_edge(edge_id, TemporalFilter::current()).await?;
+    
+    scope.graph.delete_edge(edge_id).await?;
+    retire_version(&scope, &edge_entity_id(edge_id), Utc::now()).await?;
+    publish(&scope, ChangeKind::Deleted, Some(Snapshot::Edge(edge)), None);
+    
+    Ok(Json(DeleteResponse { success: true, id: edge_id.0 }))
+}
+
+/// Create a node through the hybrid store and record its first version
+pub(crate) async fn insert_node(scope: &TenantScope, request: CreateNodeRequest) -> ApiResult<Node> {
+    let node = Node {
+        id: NodeId(Uuid::new_v4()),
+        entity_type: request.entity_type,
+        label: request.label,
+        properties: request.properties,
+        valid_time: request.valid_time.unwrap_or_else(TemporalRange::from_now),
+        transaction_time: TemporalRange::from_now(),
+    };
     
-    // TODO: Implement actual edge deletion
+    let node_id = scope.hybrid.create_node(VectorizedNode::new(node.clone(), None)).await?;
+    record_version(scope, node_entity_id(&node), &node.valid_time).await?;
     
-    Ok(Json(json!({ "success": true, "id": id })))
+    let node = scope.graph.get_node(node_id, TemporalFilter::current()).await?;
+    publish(scope, ChangeKind::Created, None, Some(Snapshot::Node(node.clone())));
+    Ok(node)
+}
+
+/// Create an edge through the hybrid store and record its first version
+pub(crate) async fn insert_edge(scope: &TenantScope, request: CreateEdgeRequest) -> ApiResult<Edge> {
+    // Not every graph backend checks that both endpoints exist
+    for endpoint in [request.source_id, request.target_id] {
+        scope.graph.get_node(NodeId(endpoint), TemporalFilter::current()).await?;
+    }
+    
+    let edge = Edge {
+        id: EdgeId(Uuid::new_v4()),
+        source_id: NodeId(request.source_id),
+        target_id: NodeId(request.target_id),
+        label: request.label,
+        properties: request.properties,
+        valid_time: request.valid_time.unwrap_or_else(TemporalRange::from_now),
+        transaction_time: TemporalRange::from_now(),
+    };
+    
+    let edge_id = scope.hybrid.create_edge(VectorizedEdge::new(edge.clone(), None)).await?;
+    record_version(scope, edge_entity_id(edge_id), &edge.valid_time).await?;
+    
+    let edge = scope.graph.get_edge(edge_id, TemporalFilter::current()).await?;
+    publish(scope, ChangeKind::Created, None, Some(Snapshot::Edge(edge.clone())));
+    Ok(edge)
+}
+
+/// `ETag` header carrying an element's version token
+fn etag(version: Option<VersionToken>) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    if let Some(value) = version.and_then(|version| HeaderValue::from_str(&format!("\"{}\"", version)).ok()) {
+        headers.insert(header::ETAG, value);
+    }
+    headers
+}
+
+/// Version an update must apply to, from the request's `If-Match` header
+///
+/// Fails with a version conflict when none of the listed entity tags is the
+/// element's `current` version. `None` when the header is `*` or absent,
+/// which is rejected if the server requires `If-Match`.
+fn if_match(state: &ApiState, headers: &HeaderMap, id: String, current: Option<VersionToken>) -> ApiResult<Option<VersionToken>> {
+    let Some(value) = headers.get(header::IF_MATCH) else {
+        if state.require_if_match {
+            return Err(ApiError::PreconditionRequired(format!("If-Match is required to update {}", id)));
+        }
+        return Ok(None);
+    };
+    let value = value.to_str()
+        .map_err(|_| ApiError::BadRequest("Invalid If-Match header".to_string()))?;
+    if value.trim() == "*" {
+        return Ok(None);
+    }
+
+    let tags = value.split(',')
+        .map(|tag| {
+            tag.trim()
+                .strip_prefix('"')
+                .and_then(|tag| tag.strip_suffix('"'))
+                .and_then(|tag| tag.parse::<VersionToken>().ok())
+                .ok_or_else(|| ApiError::BadRequest(format!("Invalid entity tag in If-Match: {}", tag.trim())))
+        })
+        .collect::<ApiResult<Vec<_>>>()?;
+    match current {
+        Some(current) if !tags.contains(&current) => {
+            Err(CoreError::VersionConflict { id, expected: tags[0], current }.into())
+        }
+        _ => Ok(current),
+    }
+}
+
+fn parse_node_id(id: &str) -> ApiResult<NodeId> {
+    Uuid::parse_str(id)
+        .map(NodeId)
+        .map_err(|_| ApiError::BadRequest("Invalid node ID format".to_string()))
+}
+
+fn parse_edge_id(id: &str) -> ApiResult<EdgeId> {
+    Uuid::parse_str(id)
+        .map(EdgeId)
+        .map_err(|_| ApiError::BadRequest("Invalid edge ID format".to_string()))
+}
+
+fn node_entity_id(node: &Node) -> EntityId {
+    EntityId::new(node.entity_type.clone(), node.id.0.to_string())
+}
+
+fn edge_entity_id(id: EdgeId) -> EntityId {
+    EntityId::new(EntityType::Edge, id.0.to_string())
+}
+
+/// Merge patched properties into `properties`, removing keys set to `null`
+fn merge_properties(properties: &mut Properties, patch: Properties) {
+    for (key, value) in patch.0 {
+        if value.is_null() {
+            properties.0.remove(&key);
+        } else {
+            properties.0.insert(key, value);
+        }
+    }
+}
+
+/// Index a new valid-time version of a node or edge, retiring the previous one
+///
+/// The index only answers time-range knowledge queries; history and version
+/// ids are read from the versions the graph stores.
+async fn record_version(scope: &TenantScope, entity_id: EntityId, valid_time: &TemporalRange) -> ApiResult<()> {
+    let entry = TemporalIndexEntry::new(
+        entity_id,
+        valid_time.start.map_or(DateTime::<Utc>::MIN_UTC, |ts| ts.0),
+        valid_time.end.map_or(DateTime::<Utc>::MAX_UTC, |ts| ts.0),
+        Utc::now(),
+    )
+    .with_tenant(scope.tenant.clone());
+    scope.temporal.record(entry).await?;
+    Ok(())
+}
+
+/// Publish a change to the tenant's change feed subscribers
+///
+/// The event carries the version id of the element after the change, or of
+/// the version removed by it.
+fn publish(scope: &TenantScope, kind: ChangeKind, before: Option<Snapshot>, after: Option<Snapshot>) {
+    let version_id = after.as_ref().or(before.as_ref()).and_then(Snapshot::version_id);
+    let event = ChangeEvent::new(scope.tenant.clone(), kind, before, after).with_version(version_id);
+    scope.changes.publish(event);
+}
+
+/// Publish an update, preceded by the supersede of the version it replaced
+fn publish_update(scope: &TenantScope, before: Snapshot, after: Snapshot) {
+    publish(scope, ChangeKind::Superseded, Some(before.clone()), None);
+    publish(scope, ChangeKind::Updated, Some(before), Some(after));
+}
+
+/// Filter reading an element as of the requested valid and transaction times
+fn as_of_filter(params: &AsOfParams) -> TemporalFilter {
+    TemporalFilter {
+        valid_time: params.as_of.map(|at| TemporalRange::new(Some(Timestamp(at)), Some(Timestamp(at)))),
+        as_of_transaction: params.tx_as_of.map(Timestamp),
+    }
+}
+
+/// Page through the stored versions of a node or edge, oldest first
+///
+/// Keeps the versions valid during `from`..`to`, resuming after `cursor`.
+fn history_page<T: Versioned>(versions: Vec<T>, params: &HistoryParams) -> (Vec<T>, Option<Uuid>) {
+    let range = TemporalRange::new(params.from.map(Timestamp), params.to.map(Timestamp));
+    let mut remaining = versions.into_iter()
+        .filter(|version| version.valid_time().overlaps(&range))
+        .skip_while(|version| params.cursor.is_some_and(|cursor| version.version_id() != Some(cursor)))
+        .skip(usize::from(params.cursor.is_some()))
+        .peekable();
+    
+    let limit = params.limit.unwrap_or(DEFAULT_HISTORY_LIMIT).clamp(1, MAX_HISTORY_LIMIT);
+    let page: Vec<T> = remaining.by_ref().take(limit).collect();
+    
+    let next_cursor = match remaining.peek() {
+        Some(_) => page.last().and_then(Versioned::version_id),
+        None => None,
+    };
+    (page, next_cursor)
+}
+
+/// Close the indexed version of a node or edge, if any, at transaction time `at`
+async fn retire_version(scope: &TenantScope, entity_id: &EntityId, at: DateTime<Utc>) -> ApiResult<()> {
+    scope.temporal.retire(&scope.tenant, entity_id, at).await?;
+    Ok(())
 }
 
 /// Query knowledge from the graph
 /// 
-/// Performs a semantic query against the knowledge graph to retrieve relevant information.
+/// Performs a semantic query against the knowledge graph to retrieve relevant
+/// information. A query sent with a `request_id` can be cancelled with
+/// `DELETE /requests/{id}` while it runs.
 #[utoipa::path(
     post,
     path = "/knowledge/query",
@@ -437,35 +839,193 @@ pub async fn delete_edge(
     request_body = QueryRequest,
     responses(
         (status = 200, description = "Query results", body = QueryResponse),
-        (status = 400, description = "Invalid request"),
+        (status = 400, description = "Invalid request, or request id already running"),
         (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Missing scope"),
+        (status = 499, description = "Query cancelled"),
         (status = 500, description = "Internal server error")
     )
 )]
-#[axum::debug_handler]
+#[axum::debug_handler(state = Arc<ApiState>)]
 pub async fn query_knowledge(
     State(state): State<Arc<ApiState>>,
+    scope: TenantScope,
     Json(request): Json<QueryRequest>,
-) -> ApiResult<impl IntoResponse> {
-    // TODO: Implement actual knowledge query
-    let response = QueryResponse {
-        results: vec![
-            QueryResult {
-                id: Uuid::new_v4(),
-                content: "Example result content".to_string(),
-                confidence: 0.95,
-                timestamp: Utc::now(),
-            }
-        ],
-        context: vec![
-            "Context item 1".to_string(),
-            "Context item 2".to_string(),
-        ],
-    };
+) -> ApiResult<Json<QueryResponse>> {
+    let query = knowledge_query(&request)?;
+    let response = run_query(&state, &scope, request.request_id.as_deref(), async {
+        let result = scope.hybrid.execute_hybrid_query(query, None).await?;
+        query_response(&scope, result.nodes).await
+    }).await?;
     
     Ok(Json(response))
 }
 
+/// Stream a knowledge query
+/// 
+/// Runs a knowledge query, sending a `results` event with the fused results as
+/// each retrieval stage finishes and a `done` event with the final results and
+/// their context. Events are Server-Sent Events when the client accepts
+/// `text/event-stream`, and newline-delimited JSON otherwise. The query stops
+/// when the client disconnects or cancels its `request_id`.
+#[utoipa::path(
+    post,
+    path = "/knowledge/query/stream",
+    tag = "knowledge",
+    request_body = QueryRequest,
+    responses(
+        (status = 200, description = "Stream of `results`, `done` and `error` events", content_type = "application/x-ndjson"),
+        (status = 400, description = "Invalid request"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Missing scope"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[axum::debug_handler(state = Arc<ApiState>)]
+pub async fn query_knowledge_stream(
+    State(state): State<Arc<ApiState>>,
+    scope: TenantScope,
+    headers: HeaderMap,
+    Json(request): Json<QueryRequest>,
+) -> ApiResult<Response> {
+    let query = knowledge_query(&request)?;
+    
+    let (events, receiver) = mpsc::channel(STREAM_BUFFER);
+    tokio::spawn(async move {
+        let stream = async {
+            let event = run_query(&state, &scope, request.request_id.as_deref(), async {
+                let (stages, mut finished) = mpsc::channel::<StageResult>(STREAM_BUFFER);
+                let forward = async {
+                    while let Some(stage) = finished.recv().await {
+                        let results = stage.result.nodes.into_iter().map(query_result).collect();
+                        emit(Some(&events), QueryEvent::Results { stage: stage.stage, results }).await;
+                    }
+                };
+                let (result, ()) = tokio::join!(scope.hybrid.execute_hybrid_query_staged(query, None, stages), forward);
+                query_response(&scope, result?.nodes).await
+            }).await;
+            emit(Some(&events), event.map_or_else(|e| QueryEvent::Error(e.into()), QueryEvent::Done)).await;
+        };
+        
+        // Unlike ingestion, a query nobody is listening to any more is abandoned
+        tokio::select! {
+            _ = events.closed() => {}
+            _ = stream => {}
+        }
+    });
+    
+    Ok(event_stream(StreamFormat::from_headers(&headers), receiver))
+}
+
+/// Cancel a running request
+/// 
+/// Cancels the caller's knowledge query or MCP command running under the
+/// client-chosen `request_id`. The cancelled request fails with status 499, or
+/// with an `error` event when it is streamed.
+#[utoipa::path(
+    delete,
+    path = "/requests/{id}",
+    tag = "knowledge",
+    params(
+        ("id" = String, Path, description = "request_id the query was sent with")
+    ),
+    responses(
+        (status = 204, description = "Request cancelled"),
+        (status = 404, description = "No request with this id is running"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Missing scope")
+    )
+)]
+#[axum::debug_handler(state = Arc<ApiState>)]
+pub async fn cancel_request(
+    State(state): State<Arc<ApiState>>,
+    scope: TenantScope,
+    Path(id): Path<String>,
+) -> ApiResult<StatusCode> {
+    if state.requests.cancel(&scope.tenant, &id) {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(ApiError::NotFound(format!("No running request {}", id)))
+    }
+}
+
+/// Run a query, registered under `request_id` when given so that it can be cancelled
+async fn run_query<T, F>(state: &ApiState, scope: &TenantScope, request_id: Option<&str>, query: F) -> ApiResult<T>
+where
+    F: Future<Output = ApiResult<T>>,
+{
+    match request_id {
+        Some(id) => state.requests.run(&scope.tenant, id, query).await,
+        None => query.await,
+    }
+}
+
+/// Hybrid query answering a knowledge query request
+fn knowledge_query(request: &QueryRequest) -> ApiResult<HybridQuery> {
+    if request.query.trim().is_empty() {
+        return Err(ApiError::BadRequest("Query text must not be empty".to_string()));
+    }
+    
+    let mut query = HybridQueryBuilder::new()
+        .with_text(request.query.clone())
+        .limit(request.limit.unwrap_or(DEFAULT_QUERY_LIMIT).max(1));
+    if let Some(range) = query_time_range(request)? {
+        query = query.in_time_range(range);
+    }
+    Ok(query.build())
+}
+
+/// Query results along with the text of the documents they were extracted from
+async fn query_response(scope: &TenantScope, nodes: Vec<ScoredNode>) -> ApiResult<QueryResponse> {
+    let mut context = Vec::new();
+    let mut documents = HashSet::new();
+    for scored in &nodes {
+        let properties = &scored.node.node.properties;
+        if let Some(document_id) = properties.get(SOURCE_DOCUMENT_PROPERTY).and_then(|v| v.as_str()) {
+            if documents.insert(document_id.to_string()) {
+                if let Some(document) = scope.memory.get(document_id).await? {
+                    context.push(document.content);
+                }
+            }
+        }
+    }
+    
+    let results = nodes.into_iter().map(query_result).collect();
+    Ok(QueryResponse { results, context })
+}
+
+/// Knowledge query result for a scored node
+fn query_result(scored: ScoredNode) -> QueryResult {
+    let node = scored.node.node;
+    QueryResult {
+        id: node.id.0,
+        content: node.label,
+        entity_type: node.entity_type,
+        confidence: scored.score as f64,
+        timestamp: node.valid_time.start
+            .or(node.transaction_time.start)
+            .map_or_else(Utc::now, |ts| ts.0),
+    }
+}
+
+/// Valid-time range selected by a query's `timestamp` and `time_window`
+///
+/// The window ends at `timestamp`, or now when only a window is given. A
+/// timestamp without a window selects that instant.
+fn query_time_range(request: &QueryRequest) -> ApiResult<Option<TemporalRange>> {
+    match (request.timestamp, request.time_window) {
+        (None, None) => Ok(None),
+        (_, Some(window)) if window < 0 => {
+            Err(ApiError::BadRequest("time_window must not be negative".to_string()))
+        }
+        (timestamp, window) => {
+            let end = timestamp.unwrap_or_else(Utc::now);
+            let start = end - chrono::Duration::seconds(window.unwrap_or(0));
+            Ok(Some(TemporalRange::new(Some(Timestamp(start)), Some(Timestamp(end)))))
+        }
+    }
+}
+
 /// Store information in the graph
 /// 
 /// Processes and stores text information in the knowledge graph with entity and relationship extraction.
@@ -475,22 +1035,261 @@ pub async fn query_knowledge(
     tag = "knowledge",
     request_body = StoreRequest,
     responses(
-        (status = 200, description = "Information stored successfully"),
+        (status = 200, description = "Information stored successfully", body = StoreResponse),
         (status = 400, description = "Invalid request"),
         (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Missing scope"),
         (status = 500, description = "Internal server error")
     )
 )]
-#[axum::debug_handler]
+#[axum::debug_handler(state = Arc<ApiState>)]
 pub async fn store_information(
     State(state): State<Arc<ApiState>>,
+    scope: TenantScope,
     Json(request): Json<StoreRequest>,
-) -> ApiResult<impl IntoResponse> {
-    // TODO: Implement actual information storage
-    
-    Ok(Json(json!({
-        "success": true,
-        "message": "Information stored successfully",
-        "entities_extracted": 3
-    })))
-} 
\ No newline at end of file
+) -> ApiResult<Json<StoreResponse>> {
+    validate_store_request(&request)?;
+    
+    Ok(Json(store_document(&state, &scope, request, None).await?))
+}
+
+/// Stream the storing of information
+/// 
+/// Stores text like `/knowledge/store`, sending `entity` and `relationship`
+/// events once extraction finishes, a `persisted` event as each node, edge
+/// and the source document is written, and a `done` event with the summary.
+/// Events are Server-Sent Events when the client accepts `text/event-stream`,
+/// and newline-delimited JSON otherwise.
+#[utoipa::path(
+    post,
+    path = "/knowledge/store/stream",
+    tag = "knowledge",
+    request_body = StoreRequest,
+    responses(
+        (status = 200, description = "Stream of `entity`, `relationship`, `persisted`, `done` and `error` events", content_type = "application/x-ndjson"),
+        (status = 400, description = "Invalid request"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Missing scope"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[axum::debug_handler(state = Arc<ApiState>)]
+pub async fn store_information_stream(
+    State(state): State<Arc<ApiState>>,
+    scope: TenantScope,
+    headers: HeaderMap,
+    Json(request): Json<StoreRequest>,
+) -> ApiResult<Response> {
+    validate_store_request(&request)?;
+    
+    // Storing carries on if the client goes away, so no half-written document is left behind
+    let (events, receiver) = mpsc::channel(STREAM_BUFFER);
+    tokio::spawn(async move {
+        let event = match store_document(&state, &scope, request, Some(&events)).await {
+            Ok(response) => StoreEvent::Done(response),
+            Err(e) => StoreEvent::Error(e.into()),
+        };
+        emit(Some(&events), event).await;
+    });
+    
+    Ok(event_stream(StreamFormat::from_headers(&headers), receiver))
+}
+
+fn validate_store_request(request: &StoreRequest) -> ApiResult<()> {
+    if request.text.trim().is_empty() {
+        return Err(ApiError::BadRequest("Text must not be empty".to_string()));
+    }
+    Ok(())
+}
+
+/// Extract knowledge from the request's text and write it, with the text, to the tenant's stores
+///
+/// Progress is sent to `events` as it is made.
+async fn store_document(
+    state: &ApiState,
+    scope: &TenantScope,
+    request: StoreRequest,
+    events: Option<&mpsc::Sender<StoreEvent>>,
+) -> ApiResult<StoreResponse> {
+    let document_id = Uuid::new_v4();
+    let processed = state.rag.process_text(&request.text).await?;
+    
+    let entities: Vec<_> = processed.nodes.iter()
+        .map(|node| StoredEntity {
+            id: node.id.0,
+            text: node.label.clone(),
+            entity_type: node.entity_type.clone(),
+            confidence: confidence(&node.properties),
+        })
+        .collect();
+    let relationships: Vec<_> = processed.edges.iter()
+        .map(|edge| StoredRelationship {
+            id: edge.id.0,
+            source_id: edge.source_id.0,
+            target_id: edge.target_id.0,
+            relationship_type: edge.label.clone(),
+            confidence: confidence(&edge.properties),
+        })
+        .collect();
+    for entity in &entities {
+        emit(events, StoreEvent::Entity(entity.clone())).await;
+    }
+    for relationship in &relationships {
+        emit(events, StoreEvent::Relationship(relationship.clone())).await;
+    }
+    
+    let mut node_ids = Vec::with_capacity(processed.nodes.len());
+    for mut node in processed.nodes {
+        node.properties.insert(SOURCE_DOCUMENT_PROPERTY.to_string(), json!(document_id.to_string()));
+        node_ids.push(node.id.0.to_string());
+        let id = node.id.0;
+        scope.hybrid.create_node(VectorizedNode::new(node.clone(), None)).await?;
+        record_version(scope, node_entity_id(&node), &node.valid_time).await?;
+        let node = scope.graph.get_node(node.id, TemporalFilter::current()).await?;
+        publish(scope, ChangeKind::Created, None, Some(Snapshot::Node(node)));
+        emit(events, StoreEvent::Persisted { kind: PersistedKind::Node, id }).await;
+    }
+    
+    let mut edge_ids = Vec::with_capacity(processed.edges.len());
+    for mut edge in processed.edges {
+        edge.properties.insert(SOURCE_DOCUMENT_PROPERTY.to_string(), json!(document_id.to_string()));
+        edge_ids.push(edge.id.0.to_string());
+        let edge_id = edge.id;
+        scope.hybrid.create_edge(VectorizedEdge::new(edge.clone(), None)).await?;
+        record_version(scope, edge_entity_id(edge_id), &edge.valid_time).await?;
+        let edge = scope.graph.get_edge(edge_id, TemporalFilter::current()).await?;
+        publish(scope, ChangeKind::Created, None, Some(Snapshot::Edge(edge)));
+        emit(events, StoreEvent::Persisted { kind: PersistedKind::Edge, id: edge_id.0 }).await;
+    }
+    
+    // Keep the source text so queries can return it as supporting context
+    let mut document = MemoryEntry::new(document_id.to_string(), request.text);
+    document.node_type = Some(EntityType::Document);
+    document.metadata.insert("metadata".to_string(), json!({
+        "type": "document",
+        "node_ids": node_ids,
+        "edge_ids": edge_ids,
+    }));
+    if let Some(context) = request.context {
+        document.metadata.insert("context".to_string(), json!(context));
+    }
+    if let Some(metadata) = request.metadata {
+        document.metadata.insert("user_metadata".to_string(), metadata);
+    }
+    let snapshot = Snapshot::memory(&document);
+    scope.memory.store(document).await?;
+    publish(scope, ChangeKind::Created, None, Some(snapshot));
+    emit(events, StoreEvent::Persisted { kind: PersistedKind::Document, id: document_id }).await;
+    
+    Ok(StoreResponse {
+        document_id,
+        entities_extracted: entities.len(),
+        relationships_detected: relationships.len(),
+        entities,
+        relationships,
+    })
+}
+
+/// Send an event to a streaming client, if there is one
+async fn emit<E>(events: Option<&mpsc::Sender<E>>, event: E) {
+    if let Some(events) = events {
+        // A client that went away does not stop the work it asked for
+        let _ = events.send(event).await;
+    }
+}
+
+/// Subscribe to graph changes
+/// 
+/// Upgrades to a WebSocket that receives a JSON change event for each create,
+/// update, delete and supersede of the caller's nodes, edges and memories.
+/// Pass the `sequence` of the last event received as `since` to resume after
+/// a reconnect.
+#[utoipa::path(
+    get,
+    path = "/changes",
+    tag = "changes",
+    params(
+        ("label" = Option<String>, Query, description = "Comma-separated node or edge labels to receive changes for"),
+        ("entity_type" = Option<String>, Query, description = "Comma-separated entity types to receive changes for; edges have type Edge"),
+        ("node_id" = Option<String>, Query, description = "Comma-separated node UUIDs to receive changes for, including their edges"),
+        ("since" = Option<u64>, Query, description = "Sequence to resume after")
+    ),
+    responses(
+        (status = 101, description = "Switching to the WebSocket protocol"),
+        (status = 400, description = "Invalid filter, or changes after `since` are no longer retained"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Missing scope")
+    )
+)]
+#[axum::debug_handler(state = Arc<ApiState>)]
+pub async fn subscribe_changes(
+    scope: TenantScope,
+    Query(params): Query<ChangeFeedParams>,
+    ws: WebSocketUpgrade,
+) -> ApiResult<Response> {
+    let filter = change_filter(&scope, &params)?;
+    let subscription = scope.changes.subscribe(filter, params.since)?;
+    
+    Ok(ws.on_upgrade(move |socket| forward_changes(socket, subscription)))
+}
+
+/// Filter selecting the tenant's changes named by `params`
+fn change_filter(scope: &TenantScope, params: &ChangeFeedParams) -> ApiResult<ChangeFilter> {
+    fn list(values: &Option<String>) -> impl Iterator<Item = &str> {
+        values.iter().flat_map(|values| values.split(',')).map(str::trim).filter(|value| !value.is_empty())
+    }
+    
+    let entity_types = list(&params.entity_type)
+        .map(|value| value.parse()
+            .map_err(|_| ApiError::BadRequest(format!("Invalid entity type: {}", value))))
+        .collect::<ApiResult<_>>()?;
+    let node_ids = list(&params.node_id)
+        .map(|value| Uuid::parse_str(value)
+            .map_err(|_| ApiError::BadRequest("Invalid node ID format".to_string())))
+        .collect::<ApiResult<_>>()?;
+    
+    Ok(ChangeFilter {
+        tenant: Some(scope.tenant.clone()),
+        labels: list(&params.label).map(str::to_string).collect(),
+        entity_types,
+        node_ids,
+    })
+}
+
+/// Send changes to `socket` until the client leaves or falls too far behind
+async fn forward_changes(mut socket: WebSocket, mut subscription: ChangeSubscription) {
+    loop {
+        tokio::select! {
+            change = subscription.next() => match change {
+                Ok(event) => {
+                    let text = match serde_json::to_string(&*event) {
+                        Ok(text) => text,
+                        Err(e) => {
+                            tracing::warn!("Skipping change {}: {}", event.sequence, e);
+                            continue;
+                        }
+                    };
+                    if socket.send(Message::Text(text)).await.is_err() {
+                        return;
+                    }
+                }
+                Err(e) => {
+                    // The reason tells the client where to resume
+                    let close = CloseFrame { code: close_code::AGAIN, reason: e.to_string().into() };
+                    let _ = socket.send(Message::Close(Some(close))).await;
+                    return;
+                }
+            },
+            message = socket.recv() => match message {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => return,
+                // Pings are answered by the socket; nothing else is expected
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+}
+
+/// Extraction confidence recorded on a stored node or edge
+fn confidence(properties: &Properties) -> f64 {
+    properties.get("confidence").and_then(|v| v.as_f64()).unwrap_or(1.0)
+}
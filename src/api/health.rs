@@ -0,0 +1,397 @@
+//! Backend health probes
+//!
+//! A [`HealthChecker`] runs a [`HealthProbe`] against each backend the API
+//! depends on, concurrently and each under its own timeout, and rolls the
+//! results up into a single status for the `/health` and `/health/ready`
+//! routes.
+
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use aws_config::{meta::region::RegionProviderChain, BehaviorVersion, Region};
+use aws_sdk_dynamodb::{types::TableStatus, Client as DynamoDbClient};
+use futures::future::join_all;
+use opensearch::{cluster::ClusterHealthParts, OpenSearch};
+use serde_json::Value;
+
+use crate::{
+    config::{Config, GraphBackend, MemoryBackend},
+    error::{Error, Result},
+    graph::Graph,
+    hybrid::EmbeddingFunction,
+    memory::MemorySystem,
+    types::TemporalFilter,
+};
+use super::models::{ComponentHealth, ComponentStatus};
+
+/// Text embedded by the embedding probe
+const EMBEDDING_PROBE_TEXT: &str = "health check";
+
+/// Outcome of a probe that reached its backend
+#[derive(Debug, Clone)]
+pub struct ProbeResult {
+    /// Status reported by the backend
+    pub status: ComponentStatus,
+    /// Backend-specific details, such as a table or cluster status
+    pub details: Option<String>,
+}
+
+impl ProbeResult {
+    /// A healthy result with no details
+    pub fn healthy() -> Self {
+        Self { status: ComponentStatus::Healthy, details: None }
+    }
+
+    /// A result with the given status and details
+    pub fn with_details(status: ComponentStatus, details: impl Into<String>) -> Self {
+        Self { status, details: Some(details.into()) }
+    }
+}
+
+/// A cheap request that shows whether a backend is reachable and serving
+#[async_trait]
+pub trait HealthProbe: Send + Sync {
+    /// Component name reported in the health response
+    fn name(&self) -> &str;
+
+    /// Probe the backend; errors count as unhealthy
+    async fn probe(&self) -> Result<ProbeResult>;
+}
+
+/// Runs a trivial vertex lookup against a graph store
+pub struct GraphProbe {
+    name: String,
+    graph: Arc<dyn Graph>,
+}
+
+impl GraphProbe {
+    /// Probe `graph`, reporting it as `name`
+    pub fn new(name: impl Into<String>, graph: Arc<dyn Graph>) -> Self {
+        Self { name: name.into(), graph }
+    }
+}
+
+#[async_trait]
+impl HealthProbe for GraphProbe {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn probe(&self) -> Result<ProbeResult> {
+        // The nil id never names a vertex, so a healthy store answers with nothing
+        self.graph.get_vertex(&uuid::Uuid::nil().to_string(), TemporalFilter::current()).await?;
+        Ok(ProbeResult::healthy())
+    }
+}
+
+/// Describes a DynamoDB table and reports its status
+pub struct DynamoDBProbe {
+    client: Arc<DynamoDbClient>,
+    table: String,
+}
+
+impl DynamoDBProbe {
+    /// Probe `table` through `client`
+    pub fn new(client: Arc<DynamoDbClient>, table: impl Into<String>) -> Self {
+        Self { client, table: table.into() }
+    }
+}
+
+#[async_trait]
+impl HealthProbe for DynamoDBProbe {
+    fn name(&self) -> &str {
+        "dynamodb"
+    }
+
+    async fn probe(&self) -> Result<ProbeResult> {
+        let output = self.client
+            .describe_table()
+            .table_name(&self.table)
+            .send()
+            .await
+            .map_err(|e| Error::DynamoDB(format!("Failed to describe table {}: {}", self.table, e)))?;
+        let table_status = output.table().and_then(|t| t.table_status());
+        let status = match table_status {
+            Some(TableStatus::Active) => ComponentStatus::Healthy,
+            Some(TableStatus::Updating) => ComponentStatus::Degraded,
+            _ => ComponentStatus::Unhealthy,
+        };
+        let details = table_status.map_or("UNKNOWN", |s| s.as_str());
+        Ok(ProbeResult::with_details(status, format!("table {} is {}", self.table, details)))
+    }
+}
+
+/// Reads the OpenSearch cluster health
+pub struct OpenSearchProbe {
+    client: Arc<OpenSearch>,
+}
+
+impl OpenSearchProbe {
+    /// Probe the cluster behind `client`
+    pub fn new(client: Arc<OpenSearch>) -> Self {
+        Self { client }
+    }
+}
+
+#[async_trait]
+impl HealthProbe for OpenSearchProbe {
+    fn name(&self) -> &str {
+        "opensearch"
+    }
+
+    async fn probe(&self) -> Result<ProbeResult> {
+        let response = self.client
+            .cluster()
+            .health(ClusterHealthParts::None)
+            .send()
+            .await
+            .map_err(|e| Error::OpenSearch(format!("Failed to read cluster health: {}", e)))?;
+        if !response.status_code().is_success() {
+            return Err(Error::OpenSearch(format!("Cluster health returned {}", response.status_code())));
+        }
+        let body: Value = response.json().await
+            .map_err(|e| Error::OpenSearch(format!("Failed to parse cluster health: {}", e)))?;
+        let cluster_status = body["status"].as_str().unwrap_or("unknown");
+        let status = match cluster_status {
+            "green" => ComponentStatus::Healthy,
+            "yellow" => ComponentStatus::Degraded,
+            _ => ComponentStatus::Unhealthy,
+        };
+        Ok(ProbeResult::with_details(status, format!("cluster is {}", cluster_status)))
+    }
+}
+
+/// Embeds a short text and checks the vector has the model's dimension
+pub struct EmbeddingProbe {
+    embedding_function: Arc<dyn EmbeddingFunction>,
+}
+
+impl EmbeddingProbe {
+    /// Probe `embedding_function`
+    pub fn new(embedding_function: Arc<dyn EmbeddingFunction>) -> Self {
+        Self { embedding_function }
+    }
+}
+
+#[async_trait]
+impl HealthProbe for EmbeddingProbe {
+    fn name(&self) -> &str {
+        "embedding"
+    }
+
+    async fn probe(&self) -> Result<ProbeResult> {
+        let embedding = self.embedding_function.generate_embedding(EMBEDDING_PROBE_TEXT).await?;
+        let expected = self.embedding_function.embedding_dim();
+        let model = self.embedding_function.model_name();
+        if embedding.len() != expected {
+            return Ok(ProbeResult::with_details(
+                ComponentStatus::Unhealthy,
+                format!("{} returned {} dimensions, expected {}", model, embedding.len(), expected),
+            ));
+        }
+        Ok(ProbeResult::with_details(ComponentStatus::Healthy, model))
+    }
+}
+
+/// Probes every backend and rolls the results up into one status
+#[derive(Clone)]
+pub struct HealthChecker {
+    probes: Vec<Arc<dyn HealthProbe>>,
+    timeout: Duration,
+}
+
+impl Default for HealthChecker {
+    /// A checker without probes, which always reports healthy
+    fn default() -> Self {
+        Self::new(Duration::from_millis(2000))
+    }
+}
+
+impl HealthChecker {
+    /// Create a checker without probes; each probe added gets `timeout`
+    pub fn new(timeout: Duration) -> Self {
+        Self { probes: Vec::new(), timeout }
+    }
+
+    /// Add a probe
+    pub fn with_probe(mut self, probe: Arc<dyn HealthProbe>) -> Self {
+        self.probes.push(probe);
+        self
+    }
+
+    /// Probe the remote backends selected by `config`
+    ///
+    /// Neptune is probed through `graph`, and alongside it the DynamoDB
+    /// table holding the valid-time index, `config.temporal_table`.
+    /// In-process graph and memory backends are not probed; the embedding
+    /// model always is.
+    pub async fn from_config(
+        config: &Config,
+        graph: Arc<dyn Graph>,
+        embedding_function: Arc<dyn EmbeddingFunction>,
+    ) -> Result<Self> {
+        let mut checker = Self::new(Duration::from_millis(config.health_probe_timeout_ms));
+        if config.graph_backend == GraphBackend::Neptune {
+            let region = RegionProviderChain::first_try(Region::new(config.aws_region.clone()));
+            let shared_config = aws_config::defaults(BehaviorVersion::latest()).region(region).load().await;
+            let dynamodb = Arc::new(DynamoDbClient::new(&shared_config));
+            checker = checker
+                .with_probe(Arc::new(GraphProbe::new("neptune", graph)))
+                .with_probe(Arc::new(DynamoDBProbe::new(dynamodb, config.temporal_table.clone())));
+        }
+        if config.memory_backend == MemoryBackend::OpenSearch {
+            let client = MemorySystem::create_client(config).await?;
+            checker = checker.with_probe(Arc::new(OpenSearchProbe::new(client)));
+        }
+        Ok(checker.with_probe(Arc::new(EmbeddingProbe::new(embedding_function))))
+    }
+
+    /// Run every probe concurrently, each under the checker's timeout
+    pub async fn check(&self) -> Vec<ComponentHealth> {
+        join_all(self.probes.iter().map(|probe| self.run(probe.as_ref()))).await
+    }
+
+    async fn run(&self, probe: &dyn HealthProbe) -> ComponentHealth {
+        let started = Instant::now();
+        let outcome = tokio::time::timeout(self.timeout, probe.probe()).await;
+        let latency_ms = started.elapsed().as_millis() as u64;
+        let result = match outcome {
+            Ok(Ok(result)) => result,
+            Ok(Err(e)) => ProbeResult::with_details(ComponentStatus::Unhealthy, e.to_string()),
+            Err(_) => ProbeResult::with_details(
+                ComponentStatus::Unhealthy,
+                format!("timed out after {}ms", self.timeout.as_millis()),
+            ),
+        };
+        ComponentHealth {
+            name: probe.name().to_string(),
+            status: result.status,
+            details: result.details,
+            latency_ms,
+        }
+    }
+
+    /// Overall status: unhealthy if any component is, else degraded if any is
+    pub fn rollup(components: &[ComponentHealth]) -> ComponentStatus {
+        let any = |status| components.iter().any(|c| c.status == status);
+        if any(ComponentStatus::Unhealthy) {
+            ComponentStatus::Unhealthy
+        } else if any(ComponentStatus::Degraded) {
+            ComponentStatus::Degraded
+        } else {
+            ComponentStatus::Healthy
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hybrid::HashingEmbeddings;
+    use aws_sdk_dynamodb::config::Credentials;
+    use serde_json::json;
+    use wiremock::{matchers::any, Mock, MockServer, ResponseTemplate};
+
+    struct FakeProbe {
+        name: &'static str,
+        delay: Duration,
+        result: Option<ComponentStatus>,
+    }
+
+    #[async_trait]
+    impl HealthProbe for FakeProbe {
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        async fn probe(&self) -> Result<ProbeResult> {
+            tokio::time::sleep(self.delay).await;
+            match self.result {
+                Some(status) => Ok(ProbeResult { status, details: None }),
+                None => Err(Error::OperationFailed("connection refused".to_string())),
+            }
+        }
+    }
+
+    fn fake(name: &'static str, delay_ms: u64, result: Option<ComponentStatus>) -> Arc<dyn HealthProbe> {
+        Arc::new(FakeProbe { name, delay: Duration::from_millis(delay_ms), result })
+    }
+
+    fn component<'a>(components: &'a [ComponentHealth], name: &str) -> &'a ComponentHealth {
+        components.iter().find(|c| c.name == name).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_probes_time_out_and_roll_up() {
+        let checker = HealthChecker::new(Duration::from_millis(50))
+            .with_probe(fake("fast", 0, Some(ComponentStatus::Healthy)))
+            .with_probe(fake("busy", 0, Some(ComponentStatus::Degraded)))
+            .with_probe(fake("hung", 5_000, Some(ComponentStatus::Healthy)));
+
+        let started = Instant::now();
+        let components = checker.check().await;
+        assert!(started.elapsed() < Duration::from_secs(1));
+
+        assert_eq!(components.len(), 3);
+        assert_eq!(component(&components, "fast").status, ComponentStatus::Healthy);
+        assert_eq!(component(&components, "busy").status, ComponentStatus::Degraded);
+        let hung = component(&components, "hung");
+        assert_eq!(hung.status, ComponentStatus::Unhealthy);
+        assert!(hung.latency_ms >= 50);
+        assert!(hung.details.as_deref().unwrap().contains("timed out"));
+        assert_eq!(HealthChecker::rollup(&components), ComponentStatus::Unhealthy);
+        assert_eq!(HealthChecker::rollup(&components[..2]), ComponentStatus::Degraded);
+        assert_eq!(HealthChecker::rollup(&components[..1]), ComponentStatus::Healthy);
+        assert_eq!(HealthChecker::rollup(&[]), ComponentStatus::Healthy);
+    }
+
+    #[tokio::test]
+    async fn test_probe_errors_are_unhealthy() {
+        let checker = HealthChecker::default()
+            .with_probe(fake("down", 0, None))
+            .with_probe(Arc::new(EmbeddingProbe::new(Arc::new(HashingEmbeddings::new(8)))));
+        let components = checker.check().await;
+
+        let down = component(&components, "down");
+        assert_eq!(down.status, ComponentStatus::Unhealthy);
+        assert!(down.details.as_deref().unwrap().contains("connection refused"));
+        assert_eq!(component(&components, "embedding").status, ComponentStatus::Healthy);
+    }
+
+    #[tokio::test]
+    async fn test_dynamodb_and_opensearch_probes() {
+        let server = MockServer::start().await;
+        Mock::given(any())
+            .respond_with(|r: &wiremock::Request| {
+                if r.url.path() == "/_cluster/health" {
+                    ResponseTemplate::new(200).set_body_json(json!({ "status": "yellow" }))
+                } else {
+                    ResponseTemplate::new(200)
+                        .set_body_json(json!({ "Table": { "TableName": "graph-table", "TableStatus": "ACTIVE" } }))
+                }
+            })
+            .mount(&server)
+            .await;
+
+        let config = aws_sdk_dynamodb::Config::builder()
+            .behavior_version(BehaviorVersion::latest())
+            .endpoint_url(server.uri())
+            .region(Region::new("us-east-1"))
+            .credentials_provider(Credentials::new("test", "test", None, None, "test"))
+            .build();
+        let dynamodb = DynamoDBProbe::new(Arc::new(DynamoDbClient::from_conf(config)), "temporal-table");
+        let result = dynamodb.probe().await.unwrap();
+        assert_eq!(result.status, ComponentStatus::Healthy);
+        assert_eq!(result.details.as_deref(), Some("table temporal-table is ACTIVE"));
+
+        let mut config = Config::for_testing();
+        config.opensearch_endpoint = server.uri();
+        let opensearch = OpenSearchProbe::new(MemorySystem::create_client(&config).await.unwrap());
+        let result = opensearch.probe().await.unwrap();
+        assert_eq!(result.status, ComponentStatus::Degraded);
+        assert_eq!(result.details.as_deref(), Some("cluster is yellow"));
+    }
+}
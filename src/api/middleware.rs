@@ -1,108 +1,339 @@
 use std::{
-    time::Duration,
+    time::{Duration, Instant},
     collections::HashMap,
+    net::{IpAddr, SocketAddr},
     sync::{Arc, Mutex},
 };
 use axum::{
-    extract::State,
-    http::{Request, StatusCode, HeaderMap},
-    response::Response,
+    extract::{ConnectInfo, MatchedPath, State},
+    http::{header::{AUTHORIZATION, RETRY_AFTER}, HeaderMap, HeaderValue, Request, StatusCode},
+    response::{IntoResponse, Response},
     middleware::Next,
 };
 
-/// Authentication middleware to validate bearer tokens
-pub async fn auth(
-    headers: HeaderMap,
-    req: Request<axum::body::Body>,
+use crate::{
+    api::{
+        auth::{Authenticator, Claims},
+        error::{ApiError, ApiResult},
+    },
+    config::Config,
+    error::{Error, Result},
+    telemetry::record_request,
+};
+
+/// Route label for requests that matched no route
+const UNMATCHED_ROUTE: &str = "unmatched";
+
+/// Authentication middleware
+///
+/// Reads the credential from `Authorization: Bearer` or `X-API-Key` and stores
+/// the resulting [`Claims`] in the request extensions.
+pub async fn authenticate(
+    State(authenticator): State<Arc<dyn Authenticator>>,
+    mut req: Request<axum::body::Body>,
     next: Next,
-) -> Result<Response, StatusCode> {
-    // Get the Authorization header
-    let auth_header = headers.get("Authorization")
+) -> ApiResult<Response> {
+    let headers = req.headers();
+    let credential = headers.get(AUTHORIZATION)
         .and_then(|value| value.to_str().ok())
-        .and_then(|value| {
-            if value.starts_with("Bearer ") {
-                Some(value[7..].to_string())
-            } else {
-                None
-            }
-        });
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .or_else(|| headers.get("X-API-Key").and_then(|value| value.to_str().ok()))
+        .map(str::trim)
+        .ok_or_else(|| ApiError::Unauthorized("Missing credentials".to_string()))?;
     
-    // Check if token exists and matches
-    match auth_header {
-        Some(token) if token == "secret-api-token" => Ok(next.run(req).await),
-        _ => Err(StatusCode::UNAUTHORIZED)
+    let claims = authenticator.authenticate(credential).await?;
+    req.extensions_mut().insert(claims);
+    
+    Ok(next.run(req).await)
+}
+
+/// Scope check middleware, layered onto routes with `from_fn_with_state(scope, require_scope)`
+///
+/// Requests without [`Claims`] pass, as they only reach this layer when
+/// authentication is disabled.
+pub async fn require_scope(
+    State(scope): State<&'static str>,
+    req: Request<axum::body::Body>,
+    next: Next,
+) -> ApiResult<Response> {
+    match req.extensions().get::<Claims>() {
+        Some(claims) if !claims.has_scope(scope) => {
+            Err(ApiError::Forbidden(format!("Missing scope {}", scope)))
+        }
+        _ => Ok(next.run(req).await),
     }
 }
 
-/// Simple rate limiter based on client IP
+/// Token-bucket rate limiter keyed per client
+///
+/// Each client gets a bucket of `burst` tokens that refills at `per_second`
+/// tokens a second. A request spends its route's cost, one token unless set
+/// with [`RateLimiter::with_route_cost`]. Buckets idle long enough to have
+/// refilled are evicted.
 #[derive(Clone)]
 pub struct RateLimiter {
-    requests: Arc<Mutex<HashMap<String, (usize, std::time::Instant)>>>,
-    max_requests: usize,
-    window: Duration,
+    buckets: Arc<Mutex<Buckets>>,
+    burst: f64,
+    per_second: f64,
+    route_costs: Arc<HashMap<String, f64>>,
+    trusted_proxies: Arc<Vec<IpAddr>>,
+}
+
+struct Buckets {
+    clients: HashMap<String, Bucket>,
+    last_sweep: Instant,
+}
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// Outcome of spending tokens from a client's bucket
+#[derive(Debug, Clone, PartialEq)]
+pub struct RateLimitDecision {
+    /// Whether the request may proceed
+    pub allowed: bool,
+    /// Bucket capacity
+    pub limit: u64,
+    /// Whole tokens left after the request
+    pub remaining: u64,
+    /// Time until the bucket is full again
+    pub reset: Duration,
+    /// Time until the request could succeed, when it was refused
+    pub retry_after: Duration,
 }
 
 impl RateLimiter {
-    /// Create a new rate limiter
-    pub fn new(max_requests: usize, window: Duration) -> Self {
+    /// Create a limiter allowing bursts of `burst` requests, refilled at `per_second`
+    pub fn new(burst: u32, per_second: f64) -> Self {
         Self {
-            requests: Arc::new(Mutex::new(HashMap::new())),
-            max_requests,
-            window,
+            buckets: Arc::new(Mutex::new(Buckets {
+                clients: HashMap::new(),
+                last_sweep: Instant::now(),
+            })),
+            burst: f64::from(burst.max(1)),
+            per_second: per_second.max(f64::MIN_POSITIVE),
+            route_costs: Arc::new(HashMap::new()),
+            trusted_proxies: Arc::new(Vec::new()),
         }
     }
 
-    /// Check if a request should be rate limited
-    pub fn check(&self, client_ip: &str) -> bool {
-        let now = std::time::Instant::now();
-        let mut requests = self.requests.lock().unwrap();
+    /// Build the limiter selected by `config`, or `None` when rate limiting is disabled
+    ///
+    /// Batch writes and text extraction cost more than single-element requests.
+    pub fn from_config(config: &Config) -> Result<Option<Self>> {
+        if config.rate_limit_burst == 0 {
+            return Ok(None);
+        }
+        
+        let proxies = config.trusted_proxies.iter()
+            .map(|proxy| proxy.parse::<IpAddr>()
+                .map_err(|_| Error::ConfigurationError(format!("Invalid trusted proxy: {}", proxy))))
+            .collect::<Result<Vec<_>>>()?;
         
-        if let Some((count, timestamp)) = requests.get_mut(client_ip) {
-            if now.duration_since(*timestamp) > self.window {
-                // Reset if window has passed
-                *count = 1;
-                *timestamp = now;
-                true
-            } else if *count >= self.max_requests {
-                // Rate limit exceeded
-                false
-            } else {
-                // Increment count
-                *count += 1;
-                true
+        Ok(Some(Self::new(config.rate_limit_burst, config.rate_limit_per_second)
+            .with_route_cost("/nodes/batch", 10)
+            .with_route_cost("/edges/batch", 10)
+            .with_route_cost("/knowledge/store", 5)
+            .with_route_cost("/knowledge/store/stream", 5)
+            .with_trusted_proxies(proxies)))
+    }
+
+    /// Charge `cost` tokens for requests to the route `path`, as written in the router
+    ///
+    /// Costs above the burst size are capped so the route stays reachable.
+    pub fn with_route_cost(mut self, path: impl Into<String>, cost: u32) -> Self {
+        Arc::make_mut(&mut self.route_costs).insert(path.into(), f64::from(cost));
+        self
+    }
+
+    /// Honor `X-Forwarded-For` on requests arriving from these proxies
+    pub fn with_trusted_proxies(mut self, proxies: impl IntoIterator<Item = IpAddr>) -> Self {
+        self.trusted_proxies = Arc::new(proxies.into_iter().collect());
+        self
+    }
+
+    /// Number of clients currently tracked
+    pub fn tracked_clients(&self) -> usize {
+        self.buckets.lock().unwrap().clients.len()
+    }
+
+    /// Tokens charged for a request to `path`
+    pub fn route_cost(&self, path: &str) -> f64 {
+        self.route_costs.get(path).copied().unwrap_or(1.0).min(self.burst)
+    }
+
+    /// Spend `cost` tokens from `client`'s bucket
+    pub fn check(&self, client: &str, cost: f64) -> RateLimitDecision {
+        self.check_at(client, cost, Instant::now())
+    }
+
+    /// Whether `client` could spend `cost` tokens, without spending them
+    pub fn peek(&self, client: &str, cost: f64) -> RateLimitDecision {
+        self.decide(client, cost, Instant::now(), false)
+    }
+
+    fn check_at(&self, client: &str, cost: f64, now: Instant) -> RateLimitDecision {
+        self.decide(client, cost, now, true)
+    }
+
+    fn decide(&self, client: &str, cost: f64, now: Instant, spend: bool) -> RateLimitDecision {
+        let mut buckets = self.buckets.lock().unwrap();
+        
+        // A bucket idle for a full refill is indistinguishable from a new one
+        let idle = Duration::from_secs_f64(self.burst / self.per_second);
+        if now.duration_since(buckets.last_sweep) >= idle {
+            buckets.clients.retain(|_, bucket| now.duration_since(bucket.updated) < idle);
+            buckets.last_sweep = now;
+        }
+        
+        let bucket = buckets.clients
+            .entry(client.to_string())
+            .or_insert(Bucket { tokens: self.burst, updated: now });
+        let elapsed = now.duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.per_second).min(self.burst);
+        bucket.updated = now;
+        
+        let allowed = bucket.tokens >= cost;
+        let retry_after = if allowed {
+            if spend {
+                bucket.tokens -= cost;
             }
+            Duration::ZERO
         } else {
-            // First request from this client
-            requests.insert(client_ip.to_string(), (1, now));
-            true
+            Duration::from_secs_f64((cost - bucket.tokens) / self.per_second)
+        };
+        
+        RateLimitDecision {
+            allowed,
+            limit: self.burst as u64,
+            remaining: bucket.tokens.floor() as u64,
+            reset: Duration::from_secs_f64((self.burst - bucket.tokens) / self.per_second),
+            retry_after,
+        }
+    }
+
+    /// Key identifying the client that sent `req`
+    ///
+    /// Authenticated requests are keyed by subject; others by peer address,
+    /// taken from `X-Forwarded-For` when the peer is a trusted proxy.
+    pub fn client_key<B>(&self, req: &Request<B>) -> String {
+        match req.extensions().get::<Claims>() {
+            Some(claims) => match &claims.tenant {
+                Some(tenant) => format!("subject:{}/{}", tenant, claims.subject),
+                None => format!("subject:{}", claims.subject),
+            },
+            None => self.peer_key(req),
+        }
+    }
+
+    /// Key identifying the address that sent `req`, ignoring any credentials
+    pub fn peer_key<B>(&self, req: &Request<B>) -> String {
+        let peer = req.extensions().get::<ConnectInfo<SocketAddr>>().map(|info| info.0.ip());
+        match peer {
+            Some(peer) => format!("ip:{}", self.client_ip(peer, req.headers())),
+            None => "ip:unknown".to_string(),
+        }
+    }
+
+    /// Walk `X-Forwarded-For` back from the peer to the first untrusted hop
+    fn client_ip(&self, peer: IpAddr, headers: &HeaderMap) -> IpAddr {
+        if !self.trusted_proxies.contains(&peer) {
+            return peer;
+        }
+        
+        let hops: Vec<IpAddr> = headers.get_all("X-Forwarded-For")
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .filter_map(|hop| hop.trim().parse().ok())
+            .collect();
+        
+        let mut client = peer;
+        for hop in hops.into_iter().rev() {
+            client = hop;
+            if !self.trusted_proxies.contains(&hop) {
+                break;
+            }
         }
+        client
     }
 }
 
-/// Rate limiting middleware with state
+/// Rate limiting middleware, layered onto routes with `from_fn_with_state(limiter, rate_limit)`
+///
+/// Adds `X-RateLimit-Limit`, `X-RateLimit-Remaining` and `X-RateLimit-Reset`
+/// to every response, and `Retry-After` when the request is refused.
 pub async fn rate_limit(
     State(limiter): State<RateLimiter>,
     req: Request<axum::body::Body>,
     next: Next,
-) -> Result<Response, StatusCode> {
-    // In a real implementation, get client IP from request
-    // For demo purposes, we'll use a hardcoded value
-    let client_ip = "127.0.0.1";
+) -> Response {
+    let cost = req.extensions().get::<MatchedPath>()
+        .map_or(1.0, |path| limiter.route_cost(path.as_str()));
+    let decision = limiter.check(&limiter.client_key(&req), cost);
     
-    if !limiter.check(client_ip) {
-        return Err(StatusCode::TOO_MANY_REQUESTS);
+    let response = if decision.allowed {
+        next.run(req).await
+    } else {
+        refused(&decision)
+    };
+    with_rate_limit_headers(response, &decision)
+}
+
+/// Failed authentication limiting middleware, layered outside `authenticate`
+///
+/// Each `401` spends a token from the peer address's bucket, and requests are
+/// refused before authenticating while that bucket is empty, so credential
+/// guessing is limited although no subject is known yet.
+pub async fn limit_failed_auth(
+    State(limiter): State<RateLimiter>,
+    req: Request<axum::body::Body>,
+    next: Next,
+) -> Response {
+    let peer = limiter.peer_key(&req);
+    let decision = limiter.peek(&peer, 1.0);
+    if !decision.allowed {
+        return with_rate_limit_headers(refused(&decision), &decision);
     }
     
-    Ok(next.run(req).await)
+    let response = next.run(req).await;
+    if response.status() == StatusCode::UNAUTHORIZED {
+        let decision = limiter.check(&peer, 1.0);
+        return with_rate_limit_headers(response, &decision);
+    }
+    response
+}
+
+/// `429` response telling the client when to retry
+fn refused(decision: &RateLimitDecision) -> Response {
+    let mut response = ApiError::RateLimitExceeded("Too many requests".to_string()).into_response();
+    let retry_after = decision.retry_after.as_secs_f64().ceil() as u64;
+    response.headers_mut().insert(RETRY_AFTER, HeaderValue::from(retry_after.max(1)));
+    response
+}
+
+fn with_rate_limit_headers(mut response: Response, decision: &RateLimitDecision) -> Response {
+    let headers = response.headers_mut();
+    headers.insert("X-RateLimit-Limit", HeaderValue::from(decision.limit));
+    headers.insert("X-RateLimit-Remaining", HeaderValue::from(decision.remaining));
+    headers.insert("X-RateLimit-Reset", HeaderValue::from(decision.reset.as_secs_f64().ceil() as u64));
+    response
 }
 
 /// Logging middleware
+///
+/// Also records each request's latency and status, labelled with its route
+/// template rather than the raw path so ids do not multiply series.
 pub async fn request_logger(
     req: Request<axum::body::Body>,
     next: Next,
 ) -> Response {
     let path = req.uri().path().to_owned();
     let method = req.method().clone();
+    let route = req.extensions().get::<MatchedPath>()
+        .map_or_else(|| UNMATCHED_ROUTE.to_string(), |matched| matched.as_str().to_string());
     
     let start = std::time::Instant::now();
     let response = next.run(req).await;
@@ -115,6 +346,92 @@ pub async fn request_logger(
         latency = ?latency,
         "Request"
     );
+    record_request(method.as_str(), &route, response.status().as_u16(), latency);
     
     response
-} 
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request_from(peer: &str, forwarded_for: Option<&str>) -> Request<()> {
+        let mut request = Request::builder().uri("/nodes");
+        if let Some(forwarded_for) = forwarded_for {
+            request = request.header("X-Forwarded-For", forwarded_for);
+        }
+        let mut request = request.body(()).unwrap();
+        request.extensions_mut().insert(ConnectInfo(SocketAddr::new(peer.parse().unwrap(), 4000)));
+        request
+    }
+
+    #[test]
+    fn test_token_bucket_refills() {
+        let limiter = RateLimiter::new(3, 1.0);
+        let start = Instant::now();
+
+        assert!(limiter.check_at("a", 2.0, start).allowed);
+        let refused = limiter.check_at("a", 2.0, start);
+        assert!(!refused.allowed);
+        assert_eq!(refused.remaining, 1);
+        assert_eq!(refused.retry_after, Duration::from_secs(1));
+
+        // Other clients have their own buckets
+        assert!(limiter.check_at("b", 3.0, start).allowed);
+
+        let later = limiter.check_at("a", 2.0, start + Duration::from_secs(1));
+        assert!(later.allowed);
+        assert_eq!(later.remaining, 0);
+        assert_eq!(later.reset, Duration::from_secs(3));
+    }
+
+    #[test]
+    fn test_peek_does_not_spend() {
+        let limiter = RateLimiter::new(1, 1.0);
+
+        assert!(limiter.peek("a", 1.0).allowed);
+        assert!(limiter.peek("a", 1.0).allowed);
+        assert!(limiter.check("a", 1.0).allowed);
+        let refused = limiter.peek("a", 1.0);
+        assert!(!refused.allowed);
+        assert_eq!(refused.remaining, 0);
+    }
+
+    #[test]
+    fn test_idle_clients_are_evicted() {
+        let limiter = RateLimiter::new(2, 1.0);
+        let start = Instant::now();
+        limiter.check_at("a", 1.0, start);
+        limiter.check_at("b", 1.0, start + Duration::from_secs(1));
+        assert_eq!(limiter.tracked_clients(), 2);
+
+        limiter.check_at("c", 1.0, start + Duration::from_secs(2));
+        assert_eq!(limiter.tracked_clients(), 2);
+    }
+
+    #[test]
+    fn test_client_key_honors_trusted_proxies() {
+        let limiter = RateLimiter::new(10, 1.0)
+            .with_trusted_proxies(["10.0.0.1".parse().unwrap(), "10.0.0.2".parse().unwrap()]);
+
+        assert_eq!(limiter.client_key(&request_from("203.0.113.9", None)), "ip:203.0.113.9");
+        // Untrusted peers cannot choose their key
+        assert_eq!(
+            limiter.client_key(&request_from("203.0.113.9", Some("198.51.100.1"))),
+            "ip:203.0.113.9"
+        );
+        assert_eq!(
+            limiter.client_key(&request_from("10.0.0.1", Some("198.51.100.1, 192.0.2.7, 10.0.0.2"))),
+            "ip:192.0.2.7"
+        );
+
+        let mut request = request_from("10.0.0.1", None);
+        request.extensions_mut().insert(Claims {
+            subject: "reporting".to_string(),
+            tenant: Some("acme".to_string()),
+            scopes: Vec::new(),
+        });
+        assert_eq!(limiter.client_key(&request), "subject:acme/reporting");
+        assert_eq!(limiter.peer_key(&request), "ip:10.0.0.1");
+    }
+}
@@ -1,11 +1,17 @@
+pub mod auth;
 pub mod handlers;
 pub mod models;
 pub mod middleware;
 pub mod error;
+pub mod health;
+pub mod stream;
 
 use axum::{
+    async_trait,
+    extract::FromRequestParts,
+    http::request::Parts,
     middleware::{from_fn, from_fn_with_state},
-    routing::{get, post},
+    routing::{delete, get, patch, post},
     Router,
 };
 use std::{
@@ -16,49 +22,286 @@ use tower_http::trace::TraceLayer;
 use utoipa::OpenApi;
 use utoipa_swagger_ui::SwaggerUi;
 
-use crate::types::{Node, Edge, NodeId, EdgeId, EntityId, Properties, TemporalRange, Timestamp, EntityType};
+use crate::{
+    changes::ChangeFeed,
+    config::Config,
+    cancellation::RequestRegistry,
+    error::Result,
+    graph::{new_graph, Graph},
+    hybrid::{models::create_embedding_function, EmbeddingFunction, HybridStore},
+    mcp::{self, http::McpSessions, navigation::NavigationSessions, McpLimits},
+    memory::{new_memory, Memory},
+    rag::{RAGConfig, RAGSystem},
+    telemetry::{self, PrometheusHandle},
+    temporal::{graph::MockTemporalGraph, new_valid_time_index, ValidTimeIndex},
+    types::{Node, Edge, NodeId, EdgeId, EntityId, Properties, TemporalRange, Timestamp, EntityType, TenantId},
+};
 use self::{
     models::*,
     handlers::*,
-    middleware::{auth, RateLimiter, rate_limit, request_logger},
+    auth::{new_authenticator, Authenticator, Claims, GRAPH_READ, GRAPH_WRITE},
+    middleware::{authenticate, limit_failed_auth, require_scope, RateLimiter, rate_limit, request_logger},
     error::{ApiError, ApiResult},
+    health::HealthChecker,
 };
 
 /// API state shared across handlers
+///
+/// The stores here span every tenant. Handlers reach them through a
+/// [`TenantScope`], which restricts them to the caller's tenant.
 pub struct ApiState {
     start_time: Instant,
-    // Add more state fields as needed
+    /// Graph store holding nodes and edges
+    pub graph: Arc<dyn Graph>,
+    /// Valid-time index answering time-range knowledge queries
+    ///
+    /// Kept in the temporal DynamoDB table on AWS and in process otherwise.
+    /// Node and edge history is read from the versions the graph stores.
+    pub temporal: Arc<dyn ValidTimeIndex>,
+    /// Entity and relationship extraction
+    pub rag: Arc<RAGSystem>,
+    /// Graph and vector store answering knowledge queries
+    pub hybrid: Arc<HybridStore>,
+    /// Vector memory holding stored documents
+    pub memory: Arc<dyn Memory>,
+    /// Credential verification; requests are not authenticated when unset
+    pub authenticator: Option<Arc<dyn Authenticator>>,
+    /// Per-client request budget; requests are not limited when unset
+    pub rate_limiter: Option<RateLimiter>,
+    /// Backend probes behind the health and readiness routes
+    pub health: HealthChecker,
+    /// Recorder rendered by `/metrics`; the route is not mounted when unset
+    pub metrics: Option<PrometheusHandle>,
+    /// Changes made through the API, streamed by `/changes`
+    pub changes: Arc<ChangeFeed>,
+    /// Whether node and edge updates must carry an `If-Match` header
+    pub require_if_match: bool,
+    /// Sessions of MCP clients connected to `/mcp`
+    pub mcp_sessions: McpSessions,
+    /// Bounds on the results of MCP commands
+    pub mcp_limits: McpLimits,
+    /// Cursors of MCP clients exploring the graph with `Navigate`
+    pub navigation: NavigationSessions,
+    /// Queries and MCP commands running under a client-chosen request id
+    pub requests: RequestRegistry,
 }
 
 impl ApiState {
     /// Create a new API state
-    pub fn new() -> Self {
+    ///
+    /// `hybrid` is expected to wrap the same `graph`, `temporal` and `memory`
+    /// stores, so writes made through it are visible to the CRUD routes.
+    pub fn new(
+        graph: Arc<dyn Graph>,
+        temporal: Arc<dyn ValidTimeIndex>,
+        rag: Arc<RAGSystem>,
+        hybrid: Arc<HybridStore>,
+        memory: Arc<dyn Memory>,
+    ) -> Self {
         Self {
             start_time: Instant::now(),
+            graph,
+            temporal,
+            rag,
+            hybrid,
+            memory,
+            authenticator: None,
+            rate_limiter: None,
+            health: HealthChecker::default(),
+            metrics: None,
+            changes: Arc::new(ChangeFeed::default()),
+            require_if_match: false,
+            mcp_sessions: McpSessions::default(),
+            mcp_limits: McpLimits::default(),
+            navigation: NavigationSessions::default(),
+            requests: RequestRegistry::default(),
         }
     }
     
+    /// Require requests to authenticate with `authenticator`
+    pub fn with_authenticator(mut self, authenticator: Arc<dyn Authenticator>) -> Self {
+        self.authenticator = Some(authenticator);
+        self
+    }
+    
+    /// Limit each client's request rate with `rate_limiter`
+    pub fn with_rate_limiter(mut self, rate_limiter: RateLimiter) -> Self {
+        self.rate_limiter = Some(rate_limiter);
+        self
+    }
+    
+    /// Report backend health with `health`
+    pub fn with_health_checker(mut self, health: HealthChecker) -> Self {
+        self.health = health;
+        self
+    }
+    
+    /// Serve the metrics collected by `metrics` at `/metrics`
+    pub fn with_metrics(mut self, metrics: PrometheusHandle) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+    
+    /// Publish changes to `changes`, for instance to share one feed between servers
+    pub fn with_change_feed(mut self, changes: Arc<ChangeFeed>) -> Self {
+        self.changes = changes;
+        self
+    }
+    
+    /// Reject node and edge updates that do not send `If-Match`
+    pub fn with_required_if_match(mut self) -> Self {
+        self.require_if_match = true;
+        self
+    }
+    
+    /// Bound the results of MCP commands by `limits`
+    pub fn with_mcp_limits(mut self, limits: McpLimits) -> Self {
+        self.mcp_limits = limits;
+        self
+    }
+    
+    /// Expire MCP navigation sessions left unused for `idle_timeout`
+    pub fn with_navigation_idle_timeout(mut self, idle_timeout: Duration) -> Self {
+        self.navigation = NavigationSessions::new(idle_timeout);
+        self
+    }
+    
+    /// Build the graph, memory, embedding and RAG systems selected by `config`
+    ///
+    /// Also installs the global Prometheus recorder, so storage metrics are
+    /// collected from the first request.
+    pub async fn from_config(config: &Config) -> Result<Self> {
+        let metrics = telemetry::install();
+        let graph: Arc<dyn Graph> = Arc::new(new_graph(config).await?);
+        let memory = new_memory(config).await?;
+        let temporal = new_valid_time_index(config).await?;
+        let embedding_function: Arc<dyn EmbeddingFunction> = Arc::from(create_embedding_function(config)?);
+        let hybrid = Arc::new(HybridStore::from_shared(
+            config,
+            graph.clone(),
+            memory.clone(),
+            temporal.clone(),
+            embedding_function.clone(),
+        ));
+        let health = HealthChecker::from_config(config, graph.clone(), embedding_function).await?;
+        
+        let rag_config = RAGConfig {
+            entity_confidence_threshold: config.entity_extraction_confidence,
+            relationship_confidence_threshold: config.relationship_detection_confidence,
+            max_context_window: config.max_context_window,
+            batch_size: config.batch_size,
+            custom_entity_patterns: Vec::new(),
+            custom_relationship_patterns: Vec::new(),
+        };
+        // Extracted elements are persisted, and recorded in `temporal`, by the handlers
+        let rag = Arc::new(RAGSystem::new(rag_config, memory.clone(), Arc::new(MockTemporalGraph::new())).await?);
+        
+        let mut state = Self::new(graph, temporal, rag, hybrid, memory);
+        state.authenticator = new_authenticator(config).await?;
+        state.rate_limiter = RateLimiter::from_config(config)?;
+        state.health = health;
+        state.metrics = Some(metrics);
+        state.changes = Arc::new(ChangeFeed::new(config.change_feed_retention));
+        state.require_if_match = config.require_if_match;
+        state.mcp_limits = McpLimits::from_config(config);
+        state.mcp_sessions = McpSessions::from_config(config);
+        state.navigation = NavigationSessions::new(Duration::from_secs(config.mcp_navigation_idle_secs));
+        Ok(state)
+    }
+    
     /// Get API uptime
     pub fn uptime(&self) -> Duration {
         self.start_time.elapsed()
     }
+    
+    /// Stores restricted to `tenant`
+    pub fn scope(&self, tenant: TenantId) -> TenantScope {
+        let hybrid = self.hybrid.for_tenant(&tenant);
+        TenantScope {
+            graph: hybrid.graph(),
+            memory: hybrid.memory(),
+            hybrid,
+            temporal: self.temporal.clone(),
+            changes: self.changes.clone(),
+            tenant,
+            caller: None,
+        }
+    }
+}
+
+/// Stores restricted to the tenant of the calling client
+///
+/// Extracted from the request's [`Claims`]. Unauthenticated requests, and
+/// credentials without a tenant, use the default tenant.
+pub struct TenantScope {
+    /// Tenant every read and write is restricted to
+    pub tenant: TenantId,
+    /// Graph store holding the tenant's nodes and edges
+    pub graph: Arc<dyn Graph>,
+    /// Valid-time index, shared across tenants and keyed by entity id
+    ///
+    /// Stamp new entries with `tenant`, and only consult it for entities
+    /// already read through `graph`.
+    pub temporal: Arc<dyn ValidTimeIndex>,
+    /// Graph and vector store answering the tenant's knowledge queries
+    pub hybrid: HybridStore,
+    /// Vector memory holding the tenant's documents
+    pub memory: Arc<dyn Memory>,
+    /// Feed every tenant's changes are published to
+    ///
+    /// Subscribe with a [`ChangeFilter`](crate::changes::ChangeFilter) for `tenant`.
+    pub changes: Arc<ChangeFeed>,
+    /// Subject of the credential making the request, or `None` when the
+    /// request is not authenticated
+    pub caller: Option<String>,
+}
+
+impl TenantScope {
+    /// The same stores, used on behalf of `caller`
+    pub fn with_caller(mut self, caller: Option<String>) -> Self {
+        self.caller = caller;
+        self
+    }
+}
+
+#[async_trait]
+impl FromRequestParts<Arc<ApiState>> for TenantScope {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &Arc<ApiState>) -> ApiResult<Self> {
+        match parts.extensions.get::<Claims>() {
+            Some(claims) => Ok(state.scope(claims.tenant_id()?).with_caller(Some(claims.subject.clone()))),
+            None => Ok(state.scope(TenantId::default())),
+        }
+    }
 }
 
 #[derive(OpenApi)]
 #[openapi(
     paths(
         health_check,
+        liveness,
+        readiness,
+        metrics,
         version,
         create_node,
+        create_nodes_batch,
         get_node,
+        node_history,
         update_node,
         delete_node,
         create_edge,
+        create_edges_batch,
         get_edge,
+        edge_history,
         update_edge,
         delete_edge,
         query_knowledge,
+        query_knowledge_stream,
         store_information,
+        store_information_stream,
+        cancel_request,
+        subscribe_changes,
     ),
     components(
         schemas(
@@ -66,8 +309,12 @@ impl ApiState {
             VersionInfo, HealthCheckResponse, ComponentHealth, ComponentStatus,
             CreateNodeRequest, UpdateNodeRequest, 
             CreateEdgeRequest, UpdateEdgeRequest,
-            BatchCreateNodesRequest, BatchCreateEdgesRequest, BatchOperationError,
+            BatchCreateNodesRequest, BatchCreateEdgesRequest,
+            BatchOperationResponse, BatchOperationError, DeleteResponse,
             QueryRequest, QueryResponse, QueryResult, StoreRequest,
+            StoreResponse, StoredEntity, StoredRelationship,
+            VersionedNode, VersionedEdge, NodeVersion, EdgeVersion,
+            NodeHistoryResponse, EdgeHistoryResponse,
         )
     ),
     tags(
@@ -75,6 +322,7 @@ impl ApiState {
         (name = "nodes", description = "Node management endpoints"),
         (name = "edges", description = "Edge management endpoints"),
         (name = "knowledge", description = "Knowledge graph operations"),
+        (name = "changes", description = "Graph change feed"),
     ),
     info(
         title = "Temporal Knowledge Graph API",
@@ -85,15 +333,80 @@ impl ApiState {
 struct ApiDoc;
 
 /// Create the API router
-pub fn create_router() -> Router {
-    // Create API state
-    let state = Arc::new(ApiState::new());
+///
+/// Graph and knowledge routes require `graph:read` or `graph:write` when the
+/// state has an authenticator; health, version and docs stay public. Serve
+/// with `into_make_service_with_connect_info::<SocketAddr>()` so the rate
+/// limiter can key anonymous clients by peer address.
+pub fn create_router(state: Arc<ApiState>) -> Router {
+    let read = || from_fn_with_state(GRAPH_READ, require_scope);
+    let write = || from_fn_with_state(GRAPH_WRITE, require_scope);
     
-    // Create a basic router with health endpoints
-    let app = Router::new()
+    let mut protected = Router::new()
+        // Node routes
+        .route("/nodes", post(handlers::create_node).layer(write()))
+        .route("/nodes/batch", post(handlers::create_nodes_batch).layer(write()))
+        .route("/nodes/:id", get(handlers::get_node).layer(read())
+            .merge(patch(handlers::update_node)
+                .delete(handlers::delete_node)
+                .layer(write())))
+        .route("/nodes/:id/history", get(handlers::node_history).layer(read()))
+        
+        // Edge routes
+        .route("/edges", post(handlers::create_edge).layer(write()))
+        .route("/edges/batch", post(handlers::create_edges_batch).layer(write()))
+        .route("/edges/:id", get(handlers::get_edge).layer(read())
+            .merge(patch(handlers::update_edge)
+                .delete(handlers::delete_edge)
+                .layer(write())))
+        .route("/edges/:id/history", get(handlers::edge_history).layer(read()))
+        
+        // Knowledge routes
+        .route("/knowledge/store", post(handlers::store_information).layer(write()))
+        .route("/knowledge/store/stream", post(handlers::store_information_stream).layer(write()))
+        .route("/knowledge/query", post(handlers::query_knowledge).layer(read()))
+        .route("/knowledge/query/stream", post(handlers::query_knowledge_stream).layer(read()))
+        .route("/requests/:id", delete(handlers::cancel_request).layer(read()))
+        
+        // Change feed
+        .route("/changes", get(handlers::subscribe_changes).layer(read()))
+        
+        // Model Context Protocol; tools that write check `graph:write` themselves
+        .route("/mcp", post(mcp::http::post)
+            .get(mcp::http::stream)
+            .delete(mcp::http::delete)
+            .layer(read()));
+    
+    let mut public = Router::new()
         // Health routes
         .route("/health", get(handlers::health_check))
-        .route("/version", get(handlers::version))
+        .route("/version", get(handlers::version));
+    
+    // Orchestrator probes and scrapes are neither authenticated nor rate limited
+    let mut probes = Router::new()
+        .route("/health/live", get(handlers::liveness))
+        .route("/health/ready", get(handlers::readiness));
+    if state.metrics.is_some() {
+        probes = probes.route("/metrics", get(handlers::metrics));
+    }
+    
+    // Limits apply after authentication, so authenticated clients are keyed by subject
+    if let Some(limiter) = state.rate_limiter.clone() {
+        protected = protected.route_layer(from_fn_with_state(limiter.clone(), rate_limit));
+        public = public.route_layer(from_fn_with_state(limiter, rate_limit));
+    }
+    if let Some(authenticator) = state.authenticator.clone() {
+        protected = protected.route_layer(from_fn_with_state(authenticator, authenticate));
+        // Failed attempts have no subject, so they are charged to the peer address
+        if let Some(limiter) = state.rate_limiter.clone() {
+            protected = protected.route_layer(from_fn_with_state(limiter, limit_failed_auth));
+        }
+    }
+    
+    let app = Router::new()
+        .merge(probes)
+        .merge(public)
+        .merge(protected)
         
         // Swagger UI for API documentation
         .merge(SwaggerUi::new("/swagger-ui")
@@ -105,4 +418,4 @@ pub fn create_router() -> Router {
         .with_state(state);
         
     app
-} 
\ No newline at end of file
+}
@@ -4,6 +4,8 @@ use uuid::Uuid;
 use utoipa::ToSchema;
 
 use crate::types::{Node, Edge, EntityId, Timestamp, TemporalRange, NodeId, EntityType, Properties, EdgeId};
+use crate::hybrid::QueryStage;
+use super::error::ApiError;
 
 /// Request to store information in the graph
 #[derive(Debug, Clone, Deserialize, ToSchema)]
@@ -16,6 +18,49 @@ pub struct StoreRequest {
     pub metadata: Option<serde_json::Value>,
 }
 
+/// Response from storing information
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct StoreResponse {
+    /// ID of the stored source document
+    pub document_id: Uuid,
+    /// Number of entities extracted
+    pub entities_extracted: usize,
+    /// Number of relationships stored
+    pub relationships_detected: usize,
+    /// Nodes created for the extracted entities
+    pub entities: Vec<StoredEntity>,
+    /// Edges created for the detected relationships
+    pub relationships: Vec<StoredRelationship>,
+}
+
+/// Entity stored as a node
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct StoredEntity {
+    /// Node ID
+    pub id: Uuid,
+    /// Entity text
+    pub text: String,
+    /// Entity type
+    pub entity_type: EntityType,
+    /// Extraction confidence
+    pub confidence: f64,
+}
+
+/// Relationship stored as an edge
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct StoredRelationship {
+    /// Edge ID
+    pub id: Uuid,
+    /// Source node ID
+    pub source_id: Uuid,
+    /// Target node ID
+    pub target_id: Uuid,
+    /// Relationship type
+    pub relationship_type: String,
+    /// Detection confidence
+    pub confidence: f64,
+}
+
 /// Request to query knowledge from the graph
 #[derive(Debug, Clone, Deserialize, ToSchema)]
 pub struct QueryRequest {
@@ -23,8 +68,14 @@ pub struct QueryRequest {
     pub query: String,
     /// Optional timestamp to query at
     pub timestamp: Option<DateTime<Utc>>,
-    /// Optional time window in seconds
+    /// Optional time window in seconds, ending at `timestamp` or now
     pub time_window: Option<i64>,
+    /// Maximum number of results
+    #[serde(default)]
+    pub limit: Option<usize>,
+    /// Client-chosen id under which the query can be cancelled while it runs
+    #[serde(default)]
+    pub request_id: Option<String>,
 }
 
 /// Response from a knowledge query
@@ -43,12 +94,95 @@ pub struct QueryResult {
     pub id: Uuid,
     /// Content
     pub content: String,
+    /// Entity type of the node
+    pub entity_type: EntityType,
     /// Confidence score
     pub confidence: f64,
     /// Timestamp
     pub timestamp: DateTime<Utc>,
 }
 
+/// Query parameters selecting the changes a WebSocket subscriber receives
+///
+/// Lists are comma-separated; an absent list matches everything.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ChangeFeedParams {
+    /// Node or edge labels
+    pub label: Option<String>,
+    /// Entity types
+    pub entity_type: Option<String>,
+    /// Node IDs, matching the nodes and their edges
+    pub node_id: Option<String>,
+    /// Sequence to resume after
+    pub since: Option<u64>,
+}
+
+/// Event sent by a streamed knowledge query
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum QueryEvent {
+    /// Fused results of the retrieval stages finished so far
+    Results {
+        /// Stage that just finished
+        stage: QueryStage,
+        /// Results ranked across the finished stages
+        results: Vec<QueryResult>,
+    },
+    /// Final results with their supporting context
+    Done(QueryResponse),
+    /// The query failed and the stream ends
+    Error(StreamError),
+}
+
+/// Event sent by a streamed store request
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum StoreEvent {
+    /// Entity extracted from the text, not yet written
+    Entity(StoredEntity),
+    /// Relationship detected in the text, not yet written
+    Relationship(StoredRelationship),
+    /// Node, edge or source document written to the stores
+    Persisted {
+        /// What was written
+        kind: PersistedKind,
+        /// ID of the written element
+        id: Uuid,
+    },
+    /// Summary of everything stored
+    Done(StoreResponse),
+    /// Storing failed and the stream ends; elements already persisted remain
+    Error(StreamError),
+}
+
+/// Kind of element announced by a [`StoreEvent::Persisted`] event
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PersistedKind {
+    /// Node for an extracted entity
+    Node,
+    /// Edge for a detected relationship
+    Edge,
+    /// Source document kept in memory
+    Document,
+}
+
+/// Error ending an event stream
+#[derive(Debug, Clone, Serialize)]
+pub struct StreamError {
+    /// HTTP status the request would have failed with
+    pub code: u16,
+    /// Error message
+    pub message: String,
+}
+
+impl From<ApiError> for StreamError {
+    fn from(error: ApiError) -> Self {
+        let (status, message) = error.into_status_and_message();
+        Self { code: status.as_u16(), message }
+    }
+}
+
 /// Request to create a new node
 #[derive(Debug, Clone, Deserialize, ToSchema)]
 pub struct CreateNodeRequest {
@@ -80,22 +214,27 @@ pub struct CreateEdgeRequest {
 }
 
 /// Request to update a node
+///
+/// Applied as a partial patch: omitted fields are left unchanged.
 #[derive(Debug, Clone, Deserialize, ToSchema)]
 pub struct UpdateNodeRequest {
     /// Node label
     pub label: Option<String>,
-    /// Node properties to update
+    /// Properties to merge into the node; a `null` value removes the property
     pub properties: Option<Properties>,
     /// Valid time range
     pub valid_time: Option<TemporalRange>,
 }
 
 /// Request to update an edge
+///
+/// Applied as a partial patch: omitted fields are left unchanged.
 #[derive(Debug, Clone, Deserialize, ToSchema)]
 pub struct UpdateEdgeRequest {
-    /// Edge label
+    /// Edge label, which cannot be changed: a label other than the edge's
+    /// current one is rejected
     pub label: Option<String>,
-    /// Edge properties to update
+    /// Properties to merge into the edge; a `null` value removes the property
     pub properties: Option<Properties>,
     /// Valid time range
     pub valid_time: Option<TemporalRange>,
@@ -120,6 +259,8 @@ pub struct BatchCreateEdgesRequest {
 pub struct BatchOperationResponse {
     /// Number of successful operations
     pub success_count: usize,
+    /// IDs of the created elements, in request order
+    pub ids: Vec<Uuid>,
     /// List of failed operations with error messages
     pub failures: Vec<BatchOperationError>,
 }
@@ -133,6 +274,101 @@ pub struct BatchOperationError {
     pub error: String,
 }
 
+/// Response to deleting a node or edge
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct DeleteResponse {
+    /// Whether the element was deleted
+    pub success: bool,
+    /// ID of the deleted element
+    pub id: Uuid,
+}
+
+/// Node together with the temporal version it was read from
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct VersionedNode {
+    /// Node as of the requested times
+    #[serde(flatten)]
+    pub node: Node,
+    /// Temporal version the node was read from, if it has been recorded
+    pub version_id: Option<Uuid>,
+}
+
+/// Edge together with the temporal version it was read from
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct VersionedEdge {
+    /// Edge as of the requested times
+    #[serde(flatten)]
+    pub edge: Edge,
+    /// Temporal version the edge was read from, if it has been recorded
+    pub version_id: Option<Uuid>,
+}
+
+/// One recorded version of a node
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct NodeVersion {
+    /// Version identifier
+    pub version_id: Uuid,
+    /// When this version was recorded
+    pub recorded_at: DateTime<Utc>,
+    /// When this version was replaced or deleted, if it has been
+    pub superseded_at: Option<DateTime<Utc>>,
+    /// Node as recorded in this version
+    pub node: Node,
+}
+
+/// One recorded version of an edge
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct EdgeVersion {
+    /// Version identifier
+    pub version_id: Uuid,
+    /// When this version was recorded
+    pub recorded_at: DateTime<Utc>,
+    /// When this version was replaced or deleted, if it has been
+    pub superseded_at: Option<DateTime<Utc>>,
+    /// Edge as recorded in this version
+    pub edge: Edge,
+}
+
+/// A page of a node's history, oldest version first
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct NodeHistoryResponse {
+    /// Versions on this page
+    pub versions: Vec<NodeVersion>,
+    /// Cursor for the next page, absent on the last page
+    pub next_cursor: Option<Uuid>,
+}
+
+/// A page of an edge's history, oldest version first
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct EdgeHistoryResponse {
+    /// Versions on this page
+    pub versions: Vec<EdgeVersion>,
+    /// Cursor for the next page, absent on the last page
+    pub next_cursor: Option<Uuid>,
+}
+
+/// Times to read a node or edge as of
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct AsOfParams {
+    /// Valid time the element must be valid at
+    pub as_of: Option<DateTime<Utc>>,
+    /// Transaction time to read the stored state as of
+    pub tx_as_of: Option<DateTime<Utc>>,
+}
+
+/// Range and page of a node or edge history
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct HistoryParams {
+    /// Only versions valid at or after this time
+    pub from: Option<DateTime<Utc>>,
+    /// Only versions valid at or before this time
+    pub to: Option<DateTime<Utc>>,
+    /// Maximum number of versions to return
+    pub limit: Option<usize>,
+    /// `next_cursor` of the previous page
+    pub cursor: Option<Uuid>,
+}
+
 /// API version information
 #[derive(Debug, Clone, Serialize, ToSchema)]
 pub struct VersionInfo {
@@ -166,15 +402,28 @@ pub struct ComponentHealth {
     pub status: ComponentStatus,
     /// Additional details
     pub details: Option<String>,
+    /// Time the component took to answer its probe
+    pub latency_ms: u64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
 pub enum ComponentStatus {
     Healthy,
     Degraded,
     Unhealthy,
 }
 
+impl ComponentStatus {
+    /// Lowercase name used for the overall service status
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ComponentStatus::Healthy => "healthy",
+            ComponentStatus::Degraded => "degraded",
+            ComponentStatus::Unhealthy => "unhealthy",
+        }
+    }
+}
+
 // Implement custom schemas for imported types
 impl<'s> utoipa::ToSchema<'s> for TemporalRange {
     fn schema() -> (&'s str, utoipa::openapi::RefOr<utoipa::openapi::schema::Schema>) {
@@ -1,193 +0,0 @@
-use async_trait::async_trait;
-use std::sync::Arc;
-use std::time::{Duration, Instant};
-use tokio::sync::RwLock;
-use aws_sdk_dynamodb::Client as DynamoClient;
-use aws_sdk_neptune::Client as NeptuneClient;
-use aws_config::Region;
-use opensearch::OpenSearch;
-use chrono::{DateTime, Utc};
-use uuid::Uuid;
-use crate::{
-    config::Config,
-    error::{Error, Result},
-    memory::{MemorySystem, Memory},
-    temporal::{DynamoDBTemporal, graph::TemporalGraph},
-    rag::{RAGSystem, RAGConfig},
-    types::{Node, Edge, NodeId, EdgeId, EntityId, TemporalRange},
-};
-
-/// Mock implementation of TemporalGraph for testing
-struct MockTemporalGraph {
-    // Add any fields needed for mocking
-}
-
-impl MockTemporalGraph {
-    fn new() -> Self {
-        Self {}
-    }
-}
-
-#[async_trait]
-impl TemporalGraph for MockTemporalGraph {
-    async fn get_nodes_at(&self, _timestamp: DateTime<Utc>, _node_type: Option<crate::types::EntityType>) -> Result<Vec<Node>> {
-        Ok(Vec::new())
-    }
-
-    async fn get_edges_at(&self, _timestamp: DateTime<Utc>, _source_id: Option<Uuid>, _target_id: Option<Uuid>) -> Result<Vec<Edge>> {
-        Ok(Vec::new())
-    }
-
-    async fn get_nodes_between(&self, _start: DateTime<Utc>, _end: DateTime<Utc>, _node_type: Option<crate::types::EntityType>) -> Result<Vec<Node>> {
-        Ok(Vec::new())
-    }
-
-    async fn get_edges_between(&self, _start: DateTime<Utc>, _end: DateTime<Utc>) -> Result<Vec<Edge>> {
-        Ok(Vec::new())
-    }
-
-    async fn store(&self, _entity_id: EntityId, _data: Box<dyn crate::temporal::graph::StorableData>, _valid_time: TemporalRange) -> Result<()> {
-        Ok(())
-    }
-
-    async fn get_node_evolution(&self, _node_id: NodeId, _time_range: &TemporalRange) -> Result<Vec<Node>> {
-        Ok(Vec::new())
-    }
-
-    async fn get_edge_evolution(&self, _edge_id: EdgeId, _time_range: &TemporalRange) -> Result<Vec<Edge>> {
-        Ok(Vec::new())
-    }
-}
-
-/// Health status of a system component
-#[derive(Debug, Clone)]
-pub struct ComponentHealth {
-    /// Component name
-    pub name: String,
-    /// Component status
-    pub status: ComponentStatus,
-    /// Additional details
-    pub details: Option<String>,
-}
-
-/// Component health status
-#[derive(Debug, Clone)]
-pub enum ComponentStatus {
-    Healthy,
-    Degraded,
-    Unhealthy,
-}
-
-/// Shared application state
-pub struct ApiState {
-    /// Time when the service started
-    startup_time: Instant,
-    /// DynamoDB client for temporal storage
-    dynamo_client: Arc<DynamoClient>,
-    /// Neptune client for graph operations
-    neptune_client: Arc<NeptuneClient>,
-    /// Temporal query system for historical data
-    pub temporal: Arc<DynamoDBTemporal<Node, DynamoClient>>,
-    /// RAG system for knowledge processing
-    pub rag: Arc<RAGSystem>,
-    /// Memory system for context tracking
-    memory: Arc<dyn Memory>,
-}
-
-impl ApiState {
-    /// Initialize the API state from configuration
-    pub async fn initialize(config: &Config) -> Result<Self> {
-        // Create AWS SDK clients
-        let aws_config = aws_config::from_env()
-            .region(aws_config::Region::new(config.aws_region.clone()))
-            .load()
-            .await;
-
-        let dynamo_client = Arc::new(DynamoClient::new(&aws_config));
-        let neptune_client = Arc::new(NeptuneClient::new(&aws_config));
-
-        // Initialize temporal system for nodes
-        let temporal = Arc::new(DynamoDBTemporal::<Node, DynamoClient>::new(
-            dynamo_client.clone(),
-            config.dynamodb_table.clone(),
-        ));
-        
-        // Create a temporal graph - temporarily skip the TemporalGraphImpl
-        // since we need to fix Edge/Node type compatibility
-        // This is a temporary fix to get the code to compile
-        let temporal_graph = Arc::new(MockTemporalGraph::new());
-
-        // Initialize memory system
-        let memory_client = MemorySystem::create_client(config).await?;
-        let memory = Arc::new(MemorySystem::new(
-            memory_client, 
-            "memory".to_string(), 
-            config.memory_size
-        ).await?);
-
-        // Initialize RAG system
-        let rag_config = RAGConfig {
-            entity_confidence_threshold: config.entity_extraction_confidence,
-            relationship_confidence_threshold: config.relationship_detection_confidence,
-            max_context_window: config.max_context_window,
-            batch_size: config.batch_size,
-            custom_entity_patterns: Vec::new(),
-            custom_relationship_patterns: Vec::new(),
-        };
-
-        let rag = Arc::new(RAGSystem::new(
-            rag_config,
-            memory.clone(),
-            temporal_graph,
-        ).await?);
-
-        Ok(Self {
-            startup_time: Instant::now(),
-            dynamo_client,
-            neptune_client,
-            temporal,
-            rag,
-            memory,
-        })
-    }
-
-    /// Get the uptime of the service
-    pub fn uptime(&self) -> Duration {
-        self.startup_time.elapsed()
-    }
-
-    /// Run a basic health check
-    pub async fn health_check(&self) -> Result<Vec<ComponentHealth>> {
-        let mut components = Vec::new();
-        
-        // Check DynamoDB connection
-        let dynamo_status = match self
-            .dynamo_client
-            .list_tables()
-            .limit(1)
-            .send()
-            .await
-        {
-            Ok(_) => ComponentStatus::Healthy,
-            Err(_) => ComponentStatus::Unhealthy,
-        };
-        
-        components.push(ComponentHealth {
-            name: "dynamodb".to_string(),
-            status: dynamo_status,
-            details: None,
-        });
-        
-        // Check Neptune connection
-        components.push(ComponentHealth {
-            name: "neptune".to_string(),
-            status: ComponentStatus::Healthy, // Simplified check for now
-            details: None,
-        });
-        
-        Ok(components)
-    }
-}
-
-unsafe impl Send for ApiState {}
-unsafe impl Sync for ApiState {}
\ No newline at end of file
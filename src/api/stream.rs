@@ -0,0 +1,129 @@
+//! Streamed responses
+//!
+//! Long-running requests send their progress as a sequence of JSON events,
+//! framed as Server-Sent Events when the client accepts `text/event-stream`
+//! and as newline-delimited JSON otherwise. Each event carries its kind in an
+//! `event` field, which also names the SSE event.
+
+use std::convert::Infallible;
+
+use axum::{
+    body::Body,
+    http::{header, HeaderMap, HeaderValue},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+};
+use futures::{stream, Stream, StreamExt};
+use serde::Serialize;
+use serde_json::Value;
+use tokio::sync::mpsc;
+
+/// Events buffered for a slow client before the producer waits
+pub const STREAM_BUFFER: usize = 32;
+
+/// Media type of newline-delimited JSON
+pub const NDJSON_CONTENT_TYPE: &str = "application/x-ndjson";
+
+/// Media type of Server-Sent Events
+pub const SSE_CONTENT_TYPE: &str = "text/event-stream";
+
+/// Framing of a streamed response
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamFormat {
+    /// Server-Sent Events
+    Sse,
+    /// One JSON document per line
+    Ndjson,
+}
+
+impl StreamFormat {
+    /// Format requested by the `Accept` header, defaulting to NDJSON
+    pub fn from_headers(headers: &HeaderMap) -> Self {
+        let accepts_sse = headers.get_all(header::ACCEPT).iter()
+            .filter_map(|value| value.to_str().ok())
+            .any(|value| value.contains(SSE_CONTENT_TYPE));
+        if accepts_sse {
+            Self::Sse
+        } else {
+            Self::Ndjson
+        }
+    }
+}
+
+/// Respond with the events received on `events` until every sender is dropped
+pub fn event_stream<E>(format: StreamFormat, events: mpsc::Receiver<E>) -> Response
+where
+    E: Serialize + Send + 'static,
+{
+    let events = receiver_stream(events).map(|event| {
+        serde_json::to_value(&event).unwrap_or_else(|e| {
+            serde_json::json!({ "event": "error", "code": 500, "message": e.to_string() })
+        })
+    });
+
+    match format {
+        StreamFormat::Sse => {
+            let events = events.map(|event| Ok::<_, Infallible>(sse_event(&event)));
+            Sse::new(events).keep_alive(KeepAlive::default()).into_response()
+        }
+        StreamFormat::Ndjson => {
+            let lines = events.map(|event| Ok::<_, Infallible>(format!("{}\n", event)));
+            let mut response = Body::from_stream(lines).into_response();
+            response.headers_mut().insert(header::CONTENT_TYPE, HeaderValue::from_static(NDJSON_CONTENT_TYPE));
+            response
+        }
+    }
+}
+
+/// SSE event named after the event's `event` field
+fn sse_event(event: &Value) -> Event {
+    let sse = match event.get("event").and_then(Value::as_str) {
+        Some(name) => Event::default().event(name),
+        None => Event::default(),
+    };
+    sse.data(event.to_string())
+}
+
+fn receiver_stream<E>(events: mpsc::Receiver<E>) -> impl Stream<Item = E> {
+    stream::unfold(events, |mut events| async move {
+        events.recv().await.map(|event| (event, events))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http_body_util::BodyExt;
+    use serde_json::json;
+
+    #[test]
+    fn test_format_follows_accept_header() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(StreamFormat::from_headers(&headers), StreamFormat::Ndjson);
+
+        headers.insert(header::ACCEPT, HeaderValue::from_static("text/event-stream"));
+        assert_eq!(StreamFormat::from_headers(&headers), StreamFormat::Sse);
+    }
+
+    #[tokio::test]
+    async fn test_events_are_framed() {
+        let (tx, rx) = mpsc::channel(STREAM_BUFFER);
+        tx.send(json!({ "event": "results", "n": 1 })).await.unwrap();
+        tx.send(json!({ "event": "done" })).await.unwrap();
+        drop(tx);
+        let response = event_stream(StreamFormat::Ndjson, rx);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], NDJSON_CONTENT_TYPE);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(body, "{\"event\":\"results\",\"n\":1}\n{\"event\":\"done\"}\n");
+
+        let (tx, rx) = mpsc::channel(STREAM_BUFFER);
+        tx.send(json!({ "event": "done" })).await.unwrap();
+        drop(tx);
+        let response = event_stream(StreamFormat::Sse, rx);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], SSE_CONTENT_TYPE);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(body, "event: done\ndata: {\"event\":\"done\"}\n\n");
+    }
+}
@@ -0,0 +1,32 @@
+use std::sync::Arc;
+
+use tokio::io::BufReader;
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+
+use graph::{
+    api::ApiState,
+    config::Config,
+    mcp::{stdio, McpServer},
+};
+
+/// Model Context Protocol server over stdio, for clients that launch it as a subprocess
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // Stdout carries the protocol, so logs go to stderr
+    tracing_subscriber::registry()
+        .with(tracing_subscriber::fmt::layer().with_writer(std::io::stderr))
+        .init();
+
+    // Load config from the environment; in-process stores only with --dev
+    let config = if std::env::args().any(|arg| arg == "--dev") {
+        tracing::warn!("Using the in-process development configuration");
+        Config::for_development()
+    } else {
+        Config::from_env()?
+    };
+
+    let state = Arc::new(ApiState::from_config(&config).await?);
+    stdio::serve(McpServer::new(state), BufReader::new(tokio::io::stdin()), tokio::io::stdout()).await?;
+
+    Ok(())
+}
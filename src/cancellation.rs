@@ -0,0 +1,206 @@
+//! Cancellation of in-flight requests
+//!
+//! A request started through a [`RequestRegistry`] runs with a
+//! [`CancellationToken`] installed for its task. Storage operations awaited
+//! through [`telemetry::timed`](crate::telemetry::timed) and the stages of
+//! hybrid queries, traversals and text processing consult that token, so
+//! cancelling a request drops its outstanding OpenSearch, DynamoDB and
+//! Neptune calls and fails it with [`Error::Cancelled`]. Work running outside
+//! a registered request is never cancelled.
+
+use std::{
+    collections::HashMap,
+    future::Future,
+    sync::{Arc, Mutex},
+};
+
+use tokio::task::JoinHandle;
+pub use tokio_util::sync::CancellationToken;
+
+use crate::{
+    error::{Error, Result},
+    types::TenantId,
+};
+
+tokio::task_local! {
+    static CURRENT: CancellationToken;
+}
+
+/// Run `future` with `token` as the current token, failing as soon as it is cancelled
+pub async fn with_token<T, E, F>(token: CancellationToken, future: F) -> std::result::Result<T, E>
+where
+    F: Future<Output = std::result::Result<T, E>>,
+    E: From<Error>,
+{
+    let cancelled = token.clone();
+    CURRENT.scope(token, async move {
+        tokio::select! {
+            biased;
+            _ = cancelled.cancelled() => Err(Error::Cancelled.into()),
+            result = future => result,
+        }
+    }).await
+}
+
+/// Run `future` with `token` as the current token, leaving it to stop at its next check
+///
+/// Unlike [`with_token`], `future` is not dropped when `token` is cancelled.
+pub async fn scoped<F: Future>(token: CancellationToken, future: F) -> F::Output {
+    CURRENT.scope(token, future).await
+}
+
+/// Token of the request the current task is working on, if any
+pub fn current() -> Option<CancellationToken> {
+    CURRENT.try_with(CancellationToken::clone).ok()
+}
+
+/// Fail with `Error::Cancelled` if the current request has been cancelled
+pub fn check() -> Result<()> {
+    match CURRENT.try_with(CancellationToken::is_cancelled) {
+        Ok(true) => Err(Error::Cancelled),
+        _ => Ok(()),
+    }
+}
+
+/// Await `future`, dropping it as soon as the current request is cancelled
+pub async fn cancellable<T, F>(future: F) -> Result<T>
+where
+    F: Future<Output = Result<T>>,
+{
+    match current() {
+        Some(token) => tokio::select! {
+            biased;
+            _ = token.cancelled() => Err(Error::Cancelled),
+            result = future => result,
+        },
+        None => future.await,
+    }
+}
+
+/// Spawn `future` as a task working on the current request
+///
+/// Task-local tokens are not inherited by spawned tasks, so work fanned out
+/// with `tokio::spawn` would otherwise outlive a cancelled request.
+pub fn spawn<F>(future: F) -> JoinHandle<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    match current() {
+        Some(token) => tokio::spawn(CURRENT.scope(token, future)),
+        None => tokio::spawn(future),
+    }
+}
+
+/// A running request, identified by its tenant and client-chosen id
+type RequestKey = (TenantId, String);
+
+/// Requests running under client-chosen ids, so that clients can cancel them
+#[derive(Clone, Default)]
+pub struct RequestRegistry {
+    running: Arc<Mutex<HashMap<RequestKey, CancellationToken>>>,
+}
+
+impl RequestRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Run `future` as request `id` of `tenant` until it finishes or is cancelled
+    ///
+    /// Fails with `Error::InvalidInput` if the tenant already has a request
+    /// `id` running. The request is unregistered when it finishes, is
+    /// cancelled or is dropped.
+    pub async fn run<T, E, F>(&self, tenant: &TenantId, id: &str, future: F) -> std::result::Result<T, E>
+    where
+        F: Future<Output = std::result::Result<T, E>>,
+        E: From<Error>,
+    {
+        let key = (tenant.clone(), id.to_string());
+        let token = CancellationToken::new();
+        {
+            let mut running = self.running.lock().unwrap();
+            if running.contains_key(&key) {
+                return Err(Error::InvalidInput(format!("Request {} is already running", id)).into());
+            }
+            running.insert(key.clone(), token.clone());
+        }
+
+        let _registration = Registration { registry: self, key };
+        with_token(token, future).await
+    }
+
+    /// Cancel request `id` of `tenant`, returning whether it was running
+    pub fn cancel(&self, tenant: &TenantId, id: &str) -> bool {
+        match self.running.lock().unwrap().get(&(tenant.clone(), id.to_string())) {
+            Some(token) => {
+                token.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Whether request `id` of `tenant` is running
+    pub fn is_running(&self, tenant: &TenantId, id: &str) -> bool {
+        self.running.lock().unwrap().contains_key(&(tenant.clone(), id.to_string()))
+    }
+}
+
+/// Removes a request from its registry when dropped
+struct Registration<'a> {
+    registry: &'a RequestRegistry,
+    key: RequestKey,
+}
+
+impl Drop for Registration<'_> {
+    fn drop(&mut self) {
+        self.registry.running.lock().unwrap().remove(&self.key);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_cancel_stops_a_running_request() {
+        let registry = RequestRegistry::new();
+        let tenant = TenantId::default();
+
+        let running = tokio::spawn({
+            let (registry, tenant) = (registry.clone(), tenant.clone());
+            async move {
+                registry.run(&tenant, "q1", async {
+                    check()?;
+                    cancellable(futures::future::pending::<Result<()>>()).await
+                }).await
+            }
+        });
+        while !registry.is_running(&tenant, "q1") {
+            tokio::task::yield_now().await;
+        }
+
+        // Other tenants cannot cancel it, and the id cannot be reused while it runs
+        assert!(!registry.cancel(&TenantId::new("other").unwrap(), "q1"));
+        let duplicate = registry.run(&tenant, "q1", async { Ok::<_, Error>(()) }).await;
+        assert!(matches!(duplicate, Err(Error::InvalidInput(_))));
+
+        assert!(registry.cancel(&tenant, "q1"));
+        let result = tokio::time::timeout(Duration::from_secs(1), running).await.unwrap().unwrap();
+        assert!(matches!(result, Err(Error::Cancelled)));
+        assert!(!registry.is_running(&tenant, "q1"));
+        assert!(!registry.cancel(&tenant, "q1"));
+    }
+
+    #[tokio::test]
+    async fn test_spawned_work_inherits_the_token() {
+        let token = CancellationToken::new();
+        token.cancel();
+        assert!(check().is_ok());
+
+        let spawned = scoped(token, async { spawn(async { check() }).await.unwrap() }).await;
+        assert!(matches!(spawned, Err(Error::Cancelled)));
+    }
+}
@@ -0,0 +1,387 @@
+//! Graph change feed
+//!
+//! Every successful create, update, delete and supersede of a node, edge or
+//! memory made through the API is published to a [`ChangeFeed`] as a
+//! [`ChangeEvent`] carrying snapshots of the element before and after the
+//! change. Events are numbered by a sequence that increases across the whole
+//! feed, and the most recent ones are retained so that subscribers can
+//! resume after the last sequence they saw.
+
+use std::{
+    collections::{HashSet, VecDeque},
+    sync::{Arc, Mutex},
+};
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tokio::sync::broadcast::{self, error::RecvError};
+use uuid::Uuid;
+
+use crate::{
+    error::{Error, Result},
+    memory::MemoryEntry,
+    types::{Edge, EntityType, Node, TenantId},
+};
+
+/// Events retained for resuming subscribers unless configured otherwise
+pub const DEFAULT_RETENTION: usize = 10_000;
+
+/// Live events buffered per subscriber before it falls behind
+const SUBSCRIBER_BUFFER: usize = 1024;
+
+/// What happened to an element
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    /// The element was written for the first time
+    Created,
+    /// A new version of the element was written
+    Updated,
+    /// The element was removed
+    Deleted,
+    /// A version of the element was closed by a newer one
+    Superseded,
+}
+
+/// State of a node, edge or memory before or after a change
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Snapshot {
+    Node(Node),
+    Edge(Edge),
+    Memory(MemoryEntry),
+}
+
+impl Snapshot {
+    /// Snapshot of a memory, without its embedding
+    pub fn memory(entry: &MemoryEntry) -> Self {
+        Self::Memory(MemoryEntry { embedding: None, ..entry.clone() })
+    }
+
+    /// ID of the element
+    pub fn id(&self) -> String {
+        match self {
+            Self::Node(node) => node.id.0.to_string(),
+            Self::Edge(edge) => edge.id.0.to_string(),
+            Self::Memory(entry) => entry.id.clone(),
+        }
+    }
+
+    /// ID of the node or edge version captured, if it has been stored
+    pub fn version_id(&self) -> Option<Uuid> {
+        match self {
+            Self::Node(node) => node.version_id(),
+            Self::Edge(edge) => edge.version_id(),
+            Self::Memory(_) => None,
+        }
+    }
+
+    fn label(&self) -> Option<&str> {
+        match self {
+            Self::Node(node) => Some(&node.label),
+            Self::Edge(edge) => Some(&edge.label),
+            Self::Memory(_) => None,
+        }
+    }
+
+    fn entity_type(&self) -> Option<&EntityType> {
+        match self {
+            Self::Node(node) => Some(&node.entity_type),
+            Self::Edge(_) => Some(&EntityType::Edge),
+            Self::Memory(entry) => entry.node_type.as_ref(),
+        }
+    }
+
+    /// Whether the element is the node `id` or an edge incident to it
+    fn touches(&self, id: &Uuid) -> bool {
+        match self {
+            Self::Node(node) => node.id.0 == *id,
+            Self::Edge(edge) => edge.source_id.0 == *id || edge.target_id.0 == *id,
+            Self::Memory(entry) => entry.id == id.to_string(),
+        }
+    }
+}
+
+/// A change to a single node, edge or memory
+#[derive(Debug, Clone, Serialize)]
+pub struct ChangeEvent {
+    /// Position in the feed, assigned when the event is published
+    pub sequence: u64,
+    /// Tenant owning the element
+    pub tenant: TenantId,
+    /// What happened
+    pub kind: ChangeKind,
+    /// ID of the changed element
+    pub id: String,
+    /// Version written by the change, or closed by a delete or supersede
+    pub version_id: Option<Uuid>,
+    /// Element before the change; absent for creates
+    pub before: Option<Snapshot>,
+    /// Element after the change; absent for deletes and supersedes
+    pub after: Option<Snapshot>,
+    /// When the change was published
+    pub timestamp: DateTime<Utc>,
+}
+
+impl ChangeEvent {
+    /// Change of `kind` from `before` to `after`, at least one of which must be set
+    pub fn new(tenant: TenantId, kind: ChangeKind, before: Option<Snapshot>, after: Option<Snapshot>) -> Self {
+        let id = after.as_ref().or(before.as_ref()).map(Snapshot::id).unwrap_or_default();
+        Self {
+            sequence: 0,
+            tenant,
+            kind,
+            id,
+            version_id: None,
+            before,
+            after,
+            timestamp: Utc::now(),
+        }
+    }
+
+    /// Attach the version written or closed by the change
+    pub fn with_version(mut self, version_id: Option<Uuid>) -> Self {
+        self.version_id = version_id;
+        self
+    }
+
+    fn snapshots(&self) -> impl Iterator<Item = &Snapshot> {
+        self.before.iter().chain(self.after.iter())
+    }
+}
+
+/// Selects the events a subscriber receives
+///
+/// Empty sets match everything. An event matches a set when its element
+/// matches before or after the change.
+#[derive(Debug, Clone, Default)]
+pub struct ChangeFilter {
+    /// Only events of this tenant
+    pub tenant: Option<TenantId>,
+    /// Only nodes and edges with one of these labels
+    pub labels: HashSet<String>,
+    /// Only elements of one of these entity types; edges have type `Edge`
+    pub entity_types: HashSet<EntityType>,
+    /// Only these nodes, and edges incident to them
+    pub node_ids: HashSet<Uuid>,
+}
+
+impl ChangeFilter {
+    /// Whether `event` should be delivered
+    pub fn matches(&self, event: &ChangeEvent) -> bool {
+        self.tenant.as_ref().is_none_or(|tenant| *tenant == event.tenant)
+            && (self.labels.is_empty()
+                || event.snapshots().any(|s| s.label().is_some_and(|label| self.labels.contains(label))))
+            && (self.entity_types.is_empty()
+                || event.snapshots().any(|s| s.entity_type().is_some_and(|t| self.entity_types.contains(t))))
+            && (self.node_ids.is_empty()
+                || event.snapshots().any(|s| self.node_ids.iter().any(|id| s.touches(id))))
+    }
+}
+
+struct FeedState {
+    last_sequence: u64,
+    retained: VecDeque<Arc<ChangeEvent>>,
+}
+
+/// Publishes change events to live subscribers and retains recent ones
+pub struct ChangeFeed {
+    retention: usize,
+    state: Mutex<FeedState>,
+    sender: broadcast::Sender<Arc<ChangeEvent>>,
+}
+
+impl ChangeFeed {
+    /// Create a feed retaining the last `retention` events for resuming subscribers
+    pub fn new(retention: usize) -> Self {
+        let (sender, _) = broadcast::channel(SUBSCRIBER_BUFFER);
+        Self {
+            retention,
+            state: Mutex::new(FeedState {
+                last_sequence: 0,
+                retained: VecDeque::with_capacity(retention.min(SUBSCRIBER_BUFFER)),
+            }),
+            sender,
+        }
+    }
+
+    /// Number `event` and deliver it to every subscriber, returning its sequence
+    pub fn publish(&self, mut event: ChangeEvent) -> u64 {
+        // Numbering and sending under one lock keeps delivery in sequence order
+        let mut state = self.state.lock().unwrap();
+        state.last_sequence += 1;
+        event.sequence = state.last_sequence;
+
+        let event = Arc::new(event);
+        state.retained.push_back(event.clone());
+        while state.retained.len() > self.retention {
+            state.retained.pop_front();
+        }
+        // Nobody may be listening
+        let _ = self.sender.send(event);
+        state.last_sequence
+    }
+
+    /// Sequence of the most recently published event, or 0
+    pub fn last_sequence(&self) -> u64 {
+        self.state.lock().unwrap().last_sequence
+    }
+
+    /// Receive the events matching `filter`
+    ///
+    /// With `since`, retained events after that sequence are delivered first.
+    /// Fails if some of them are no longer retained.
+    pub fn subscribe(&self, filter: ChangeFilter, since: Option<u64>) -> Result<ChangeSubscription> {
+        let state = self.state.lock().unwrap();
+        let backlog = match since {
+            None => VecDeque::new(),
+            Some(since) => {
+                let oldest = state.retained.front().map_or(state.last_sequence + 1, |event| event.sequence);
+                if since.saturating_add(1) < oldest {
+                    return Err(Error::InvalidInput(format!(
+                        "Changes after sequence {} are no longer retained; the oldest retained is {}",
+                        since, oldest
+                    )));
+                }
+                state.retained.iter().filter(|event| event.sequence > since).cloned().collect()
+            }
+        };
+
+        Ok(ChangeSubscription {
+            filter,
+            last_sequence: since.unwrap_or(state.last_sequence),
+            backlog,
+            receiver: self.sender.subscribe(),
+        })
+    }
+}
+
+impl Default for ChangeFeed {
+    fn default() -> Self {
+        Self::new(DEFAULT_RETENTION)
+    }
+}
+
+/// Stream of change events for one subscriber
+pub struct ChangeSubscription {
+    filter: ChangeFilter,
+    last_sequence: u64,
+    backlog: VecDeque<Arc<ChangeEvent>>,
+    receiver: broadcast::Receiver<Arc<ChangeEvent>>,
+}
+
+impl ChangeSubscription {
+    /// Wait for the next matching event
+    ///
+    /// Fails when the subscriber fell too far behind the feed; it can
+    /// resubscribe after [`last_sequence`](Self::last_sequence).
+    pub async fn next(&mut self) -> Result<Arc<ChangeEvent>> {
+        loop {
+            let event = match self.backlog.pop_front() {
+                Some(event) => event,
+                None => match self.receiver.recv().await {
+                    Ok(event) => event,
+                    Err(RecvError::Lagged(missed)) => {
+                        return Err(Error::OperationFailed(format!(
+                            "Subscriber missed {} changes; resume after sequence {}",
+                            missed, self.last_sequence
+                        )));
+                    }
+                    Err(RecvError::Closed) => {
+                        return Err(Error::OperationFailed("Change feed closed".to_string()));
+                    }
+                },
+            };
+
+            self.last_sequence = event.sequence;
+            if self.filter.matches(&event) {
+                return Ok(event);
+            }
+        }
+    }
+
+    /// Sequence of the last event seen, delivered or filtered out
+    pub fn last_sequence(&self) -> u64 {
+        self.last_sequence
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{NodeId, Properties, TemporalRange};
+
+    fn node(label: &str, entity_type: EntityType) -> Node {
+        Node {
+            id: NodeId(Uuid::new_v4()),
+            entity_type,
+            label: label.to_string(),
+            properties: Properties::new(),
+            valid_time: TemporalRange::unbounded(),
+            transaction_time: TemporalRange::from_now(),
+        }
+    }
+
+    fn created(node: &Node) -> ChangeEvent {
+        ChangeEvent::new(TenantId::default(), ChangeKind::Created, None, Some(Snapshot::Node(node.clone())))
+    }
+
+    #[tokio::test]
+    async fn test_subscribers_resume_from_a_sequence() {
+        let feed = ChangeFeed::new(2);
+        let (ada, bob, eve) = (node("Ada", EntityType::Person), node("Bob", EntityType::Person), node("Eve", EntityType::Person));
+        assert_eq!(feed.publish(created(&ada)), 1);
+        feed.publish(created(&bob));
+        feed.publish(created(&eve));
+
+        // Only the last two events are retained
+        assert!(feed.subscribe(ChangeFilter::default(), Some(0)).is_err());
+        let mut subscription = feed.subscribe(ChangeFilter::default(), Some(1)).unwrap();
+        assert_eq!(subscription.next().await.unwrap().id, bob.id.0.to_string());
+        assert_eq!(subscription.next().await.unwrap().sequence, 3);
+
+        feed.publish(created(&ada));
+        assert_eq!(subscription.next().await.unwrap().sequence, 4);
+        assert_eq!(feed.last_sequence(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_filters_select_events() {
+        let feed = ChangeFeed::default();
+        let ada = node("Ada", EntityType::Person);
+        let acme = node("Acme", EntityType::Organization);
+        let filter = ChangeFilter {
+            entity_types: HashSet::from([EntityType::Organization]),
+            ..Default::default()
+        };
+        let mut organizations = feed.subscribe(filter, None).unwrap();
+        let filter = ChangeFilter {
+            node_ids: HashSet::from([ada.id.0]),
+            ..Default::default()
+        };
+        let mut about_ada = feed.subscribe(filter, None).unwrap();
+        let filter = ChangeFilter {
+            tenant: Some(TenantId::new("acme").unwrap()),
+            ..Default::default()
+        };
+        let mut other_tenant = feed.subscribe(filter, None).unwrap();
+
+        feed.publish(created(&ada));
+        feed.publish(created(&acme));
+        let renamed = Node { label: "Countess".to_string(), ..ada.clone() };
+        feed.publish(ChangeEvent::new(
+            TenantId::default(),
+            ChangeKind::Updated,
+            Some(Snapshot::Node(ada.clone())),
+            Some(Snapshot::Node(renamed)),
+        ));
+
+        assert_eq!(organizations.next().await.unwrap().id, acme.id.0.to_string());
+        assert_eq!(about_ada.next().await.unwrap().kind, ChangeKind::Created);
+        assert_eq!(about_ada.next().await.unwrap().kind, ChangeKind::Updated);
+        assert_eq!(about_ada.last_sequence(), 3);
+
+        let pending = tokio::time::timeout(std::time::Duration::from_millis(20), other_tenant.next()).await;
+        assert!(pending.is_err());
+    }
+}
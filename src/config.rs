@@ -1,13 +1,131 @@
 use serde::{Deserialize, Serialize};
 use std::env;
+use std::str::FromStr;
 use std::sync::Mutex;
 
+/// Storage backend used for the knowledge graph
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum GraphBackend {
+    /// Amazon Neptune over Gremlin
+    #[default]
+    Neptune,
+    /// In-process graph, for tests and local development
+    InMemory,
+}
+
+impl FromStr for GraphBackend {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "neptune" => Ok(GraphBackend::Neptune),
+            "memory" | "in_memory" | "in-memory" | "inmemory" => Ok(GraphBackend::InMemory),
+            other => Err(crate::Error::ConfigurationError(format!("Unknown graph backend: {}", other))),
+        }
+    }
+}
+
+/// Storage backend used for vector memory
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum MemoryBackend {
+    /// OpenSearch with the k-NN plugin
+    #[default]
+    OpenSearch,
+    /// Files in a local directory with an embedded HNSW index
+    Local,
+}
+
+impl FromStr for MemoryBackend {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "opensearch" | "open_search" => Ok(MemoryBackend::OpenSearch),
+            "local" | "file" => Ok(MemoryBackend::Local),
+            other => Err(crate::Error::ConfigurationError(format!("Unknown memory backend: {}", other))),
+        }
+    }
+}
+
+/// Source of vector embeddings
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum EmbeddingProvider {
+    /// Local sentence-transformer, falling back to hashing when no model path is set
+    #[default]
+    Local,
+    /// Deterministic feature hashing, for tests and offline development
+    Hashing,
+    /// OpenAI-compatible `/v1/embeddings` endpoint
+    Http,
+}
+
+impl FromStr for EmbeddingProvider {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "local" | "rust-bert" | "rust_bert" => Ok(EmbeddingProvider::Local),
+            "hashing" | "hash" => Ok(EmbeddingProvider::Hashing),
+            "http" | "openai" => Ok(EmbeddingProvider::Http),
+            other => Err(crate::Error::ConfigurationError(format!("Unknown embedding provider: {}", other))),
+        }
+    }
+}
+
+/// How API requests are authenticated
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum AuthMode {
+    /// No authentication, for local development
+    #[default]
+    None,
+    /// Hashed API keys from the config or a key store file
+    ApiKey,
+    /// JWTs signed with HS256 or with RS256 keys from a JWKS file
+    Jwt,
+}
+
+impl FromStr for AuthMode {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "none" | "disabled" | "off" => Ok(AuthMode::None),
+            "api_key" | "api-key" | "apikey" => Ok(AuthMode::ApiKey),
+            "jwt" => Ok(AuthMode::Jwt),
+            other => Err(crate::Error::ConfigurationError(format!("Unknown auth mode: {}", other))),
+        }
+    }
+}
+
+/// API key accepted by the `api_key` auth mode
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApiKeyConfig {
+    /// Hex-encoded SHA-256 hash of the key
+    pub hash: String,
+    /// Subject the key authenticates as
+    pub subject: String,
+    /// Tenant the key belongs to
+    #[serde(default)]
+    pub tenant: Option<String>,
+    /// Scopes granted to the key
+    #[serde(default)]
+    pub scopes: Vec<String>,
+}
+
 /// Configuration for AWS services and application settings
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
     /// AWS Region
     pub aws_region: String,
     
+    /// Graph storage backend
+    #[serde(default)]
+    pub graph_backend: GraphBackend,
+    
     /// Neptune endpoint URL
     pub neptune_endpoint: String,
     
@@ -32,6 +150,14 @@ pub struct Config {
     /// SQS queue URL for async processing
     pub sqs_queue_url: String,
     
+    /// Vector memory storage backend
+    #[serde(default)]
+    pub memory_backend: MemoryBackend,
+    
+    /// Directory for the local memory backend
+    #[serde(default)]
+    pub memory_path: Option<String>,
+    
     /// Memory service URL
     pub memory_url: String,
     
@@ -64,22 +190,221 @@ pub struct Config {
     
     /// Memory size for embedding models
     pub memory_size: usize,
+    
+    /// Embedding provider
+    #[serde(default)]
+    pub embedding_provider: EmbeddingProvider,
+    
+    /// Local sentence-transformer directory; hashing embeddings are used when unset
+    #[serde(default)]
+    pub embedding_model_path: Option<String>,
+    
+    /// Base URL of an OpenAI-compatible embeddings endpoint
+    #[serde(default)]
+    pub embedding_endpoint: Option<String>,
+    
+    /// Bearer token for the embeddings endpoint
+    #[serde(default)]
+    pub embedding_api_key: Option<String>,
+    
+    /// Model name requested from the embeddings endpoint
+    #[serde(default)]
+    pub embedding_model: Option<String>,
+    
+    /// Version recorded with generated embeddings; change it when the model's
+    /// weights change under the same name so stored vectors are re-embedded
+    #[serde(default)]
+    pub embedding_model_version: Option<String>,
+    
+    /// API authentication mode
+    #[serde(default)]
+    pub auth_mode: AuthMode,
+    
+    /// API keys accepted in `api_key` mode, read from the `API_KEYS` JSON array
+    #[serde(default)]
+    pub api_keys: Vec<ApiKeyConfig>,
+    
+    /// JSON file holding further API keys in `api_key` mode
+    #[serde(default)]
+    pub api_keys_path: Option<String>,
+    
+    /// Shared secret for HS256 JWTs
+    #[serde(default)]
+    pub jwt_secret: Option<String>,
+    
+    /// JWKS file holding the public keys for RS256 JWTs
+    #[serde(default)]
+    pub jwks_path: Option<String>,
+    
+    /// Required JWT issuer
+    #[serde(default)]
+    pub jwt_issuer: Option<String>,
+    
+    /// Required JWT audience
+    #[serde(default)]
+    pub jwt_audience: Option<String>,
+    
+    /// Requests a client may burst before being rate limited; 0 disables rate limiting
+    #[serde(default = "default_rate_limit_burst")]
+    pub rate_limit_burst: u32,
+    
+    /// Sustained requests per second allowed per client
+    #[serde(default = "default_rate_limit_per_second")]
+    pub rate_limit_per_second: f64,
+    
+    /// Proxy addresses whose `X-Forwarded-For` header is trusted
+    #[serde(default)]
+    pub trusted_proxies: Vec<String>,
+    
+    /// Milliseconds each backend health probe may take before it counts as failed
+    #[serde(default = "default_health_probe_timeout_ms")]
+    pub health_probe_timeout_ms: u64,
+    
+    /// Change events retained so reconnecting subscribers can resume
+    #[serde(default = "default_change_feed_retention")]
+    pub change_feed_retention: usize,
+    
+    /// Reject node and edge updates that carry no `If-Match` header
+    #[serde(default)]
+    pub require_if_match: bool,
+    
+    /// Most connections or search results an MCP command returns
+    #[serde(default = "default_mcp_max_results")]
+    pub mcp_max_results: usize,
+    
+    /// Most versions an MCP history command returns
+    #[serde(default = "default_mcp_max_history")]
+    pub mcp_max_history: usize,
+    
+    /// Seconds an MCP navigation session may sit unused before it expires
+    #[serde(default = "default_mcp_navigation_idle_secs")]
+    pub mcp_navigation_idle_secs: u64,
+    
+    /// Most MCP HTTP sessions open at once across all callers
+    #[serde(default = "default_mcp_max_sessions")]
+    pub mcp_max_sessions: usize,
+    
+    /// Most MCP HTTP sessions one authenticated caller may hold open
+    #[serde(default = "default_mcp_max_sessions_per_caller")]
+    pub mcp_max_sessions_per_caller: usize,
+}
+
+fn default_rate_limit_burst() -> u32 {
+    100
+}
+
+fn default_rate_limit_per_second() -> f64 {
+    10.0
+}
+
+fn default_health_probe_timeout_ms() -> u64 {
+    2000
+}
+
+fn default_change_feed_retention() -> usize {
+    crate::changes::DEFAULT_RETENTION
+}
+
+fn default_mcp_max_results() -> usize {
+    crate::mcp::DEFAULT_MAX_RESULTS
+}
+
+fn default_mcp_max_history() -> usize {
+    crate::mcp::DEFAULT_MAX_HISTORY
+}
+
+fn default_mcp_navigation_idle_secs() -> u64 {
+    crate::mcp::navigation::DEFAULT_IDLE_TIMEOUT.as_secs()
+}
+
+fn default_mcp_max_sessions() -> usize {
+    crate::mcp::http::MAX_SESSIONS
+}
+
+fn default_mcp_max_sessions_per_caller() -> usize {
+    crate::mcp::http::MAX_SESSIONS_PER_CALLER
+}
+
+/// Read a variable that must be set
+fn required(name: &str) -> crate::Result<String> {
+    env::var(name).map_err(|_| crate::Error::ConfigurationError(format!("{} must be set", name)))
+}
+
+/// Read a variable that must be set when `needed`, or fall back to `default`
+fn required_if(name: &str, needed: bool, default: String) -> crate::Result<String> {
+    if needed {
+        required(name)
+    } else {
+        Ok(env::var(name).unwrap_or(default))
+    }
+}
+
+/// Parse a variable, or `None` when it is unset
+///
+/// A value that does not parse is an error rather than a silent default.
+fn parsed<T>(name: &str) -> crate::Result<Option<T>>
+where
+    T: FromStr,
+    T::Err: std::fmt::Display,
+{
+    env::var(name)
+        .ok()
+        .map(|value| value.parse().map_err(|e| crate::Error::ConfigurationError(format!("Invalid {}: {}", name, e))))
+        .transpose()
 }
 
 impl Config {
     /// Create a new configuration from environment variables
-    pub fn from_env() -> Result<Self, env::VarError> {
+    pub fn from_env() -> crate::Result<Self> {
+        let graph_backend: GraphBackend = parsed("GRAPH_BACKEND")?.unwrap_or_default();
+        let memory_backend: MemoryBackend = parsed("MEMORY_BACKEND")?.unwrap_or_default();
+        let embedding_provider: EmbeddingProvider = parsed("EMBEDDING_PROVIDER")?.unwrap_or_default();
+        // AWS settings are only required by the backends that use them
+        let on_aws = graph_backend == GraphBackend::Neptune;
+        let on_opensearch = memory_backend == MemoryBackend::OpenSearch;
+        let defaults = Self::default();
+        // Leaving the API unauthenticated must be an explicit choice
+        let auth_mode: AuthMode = parsed("AUTH_MODE")?.ok_or_else(|| crate::Error::ConfigurationError(
+            "AUTH_MODE must be set; use AUTH_MODE=none to run without authentication".to_string(),
+        ))?;
+        if auth_mode == AuthMode::None {
+            tracing::warn!("AUTH_MODE=none: API requests are not authenticated");
+        }
+        // A cap of zero would leave every MCP tool returning nothing
+        let mcp_max_results = parsed("MCP_MAX_RESULTS")?.unwrap_or_else(default_mcp_max_results);
+        if mcp_max_results == 0 {
+            return Err(crate::Error::ConfigurationError("MCP_MAX_RESULTS must be at least 1".to_string()));
+        }
+        // A cap of zero would refuse every MCP HTTP session
+        let mcp_max_sessions = parsed("MCP_MAX_SESSIONS")?.unwrap_or_else(default_mcp_max_sessions);
+        if mcp_max_sessions == 0 {
+            return Err(crate::Error::ConfigurationError("MCP_MAX_SESSIONS must be at least 1".to_string()));
+        }
+        let mcp_max_sessions_per_caller =
+            parsed("MCP_MAX_SESSIONS_PER_CALLER")?.unwrap_or_else(default_mcp_max_sessions_per_caller);
+        if mcp_max_sessions_per_caller == 0 {
+            return Err(crate::Error::ConfigurationError("MCP_MAX_SESSIONS_PER_CALLER must be at least 1".to_string()));
+        }
+        let api_keys = match env::var("API_KEYS") {
+            Ok(keys) => serde_json::from_str(&keys)
+                .map_err(|e| crate::Error::ConfigurationError(format!("Invalid API_KEYS: {}", e)))?,
+            Err(_) => Vec::new(),
+        };
+
         Ok(Self {
-            aws_region: env::var("AWS_REGION")?,
-            neptune_endpoint: env::var("NEPTUNE_ENDPOINT")?,
+            aws_region: required_if("AWS_REGION", on_aws, defaults.aws_region)?,
+            graph_backend,
+            neptune_endpoint: required_if("NEPTUNE_ENDPOINT", on_aws, defaults.neptune_endpoint)?,
             neptune_port: env::var("NEPTUNE_PORT").unwrap_or_else(|_| "8182".to_string()).parse().unwrap_or(8182),
             neptune_iam_auth: env::var("NEPTUNE_IAM_AUTH").unwrap_or_else(|_| "false".to_string()).parse().unwrap_or(false),
-            opensearch_endpoint: env::var("OPENSEARCH_ENDPOINT")?,
-            dynamodb_table: env::var("DYNAMODB_TABLE")?,
-            temporal_table: env::var("TEMPORAL_TABLE")?,
-            s3_bucket: env::var("S3_BUCKET")?,
-            sqs_queue_url: env::var("SQS_QUEUE_URL")?,
-            memory_url: env::var("MEMORY_URL")?,
+            opensearch_endpoint: required_if("OPENSEARCH_ENDPOINT", on_opensearch, defaults.opensearch_endpoint)?,
+            dynamodb_table: required_if("DYNAMODB_TABLE", on_aws, defaults.dynamodb_table)?,
+            temporal_table: required_if("TEMPORAL_TABLE", on_aws, defaults.temporal_table)?,
+            s3_bucket: required_if("S3_BUCKET", on_aws, defaults.s3_bucket)?,
+            sqs_queue_url: required_if("SQS_QUEUE_URL", on_aws, defaults.sqs_queue_url)?,
+            memory_backend,
+            memory_path: env::var("MEMORY_PATH").ok(),
+            memory_url: env::var("MEMORY_URL").unwrap_or(defaults.memory_url),
             memory_username: env::var("MEMORY_USERNAME").unwrap_or_default(),
             memory_password: env::var("MEMORY_PASSWORD").unwrap_or_default(),
             max_retries: env::var("MAX_RETRIES").unwrap_or_else(|_| "3".to_string()).parse().unwrap_or(3),
@@ -90,6 +415,32 @@ impl Config {
             max_context_window: env::var("MAX_CONTEXT_WINDOW").unwrap_or_else(|_| "512".to_string()).parse().unwrap_or(512),
             batch_size: env::var("BATCH_SIZE").unwrap_or_else(|_| "32".to_string()).parse().unwrap_or(32),
             memory_size: env::var("MEMORY_SIZE").unwrap_or_else(|_| "384".to_string()).parse().unwrap_or(384),
+            embedding_provider,
+            embedding_model_path: env::var("EMBEDDING_MODEL_PATH").ok(),
+            embedding_endpoint: env::var("EMBEDDING_ENDPOINT").ok(),
+            embedding_api_key: env::var("EMBEDDING_API_KEY").ok(),
+            embedding_model: env::var("EMBEDDING_MODEL").ok(),
+            embedding_model_version: env::var("EMBEDDING_MODEL_VERSION").ok(),
+            auth_mode,
+            api_keys,
+            api_keys_path: env::var("API_KEYS_FILE").ok(),
+            jwt_secret: env::var("JWT_SECRET").ok(),
+            jwks_path: env::var("JWKS_FILE").ok(),
+            jwt_issuer: env::var("JWT_ISSUER").ok(),
+            jwt_audience: env::var("JWT_AUDIENCE").ok(),
+            rate_limit_burst: parsed("RATE_LIMIT_BURST")?.unwrap_or_else(default_rate_limit_burst),
+            rate_limit_per_second: parsed("RATE_LIMIT_PER_SECOND")?.unwrap_or_else(default_rate_limit_per_second),
+            trusted_proxies: env::var("TRUSTED_PROXIES")
+                .map(|proxies| proxies.split(',').map(|p| p.trim().to_string()).filter(|p| !p.is_empty()).collect())
+                .unwrap_or_default(),
+            health_probe_timeout_ms: parsed("HEALTH_PROBE_TIMEOUT_MS")?.unwrap_or_else(default_health_probe_timeout_ms),
+            change_feed_retention: parsed("CHANGE_FEED_RETENTION")?.unwrap_or_else(default_change_feed_retention),
+            require_if_match: parsed("REQUIRE_IF_MATCH")?.unwrap_or(false),
+            mcp_max_results,
+            mcp_max_history: parsed("MCP_MAX_HISTORY")?.unwrap_or_else(default_mcp_max_history),
+            mcp_navigation_idle_secs: parsed("MCP_NAVIGATION_IDLE_SECS")?.unwrap_or_else(default_mcp_navigation_idle_secs),
+            mcp_max_sessions,
+            mcp_max_sessions_per_caller,
         })
     }
 
@@ -97,6 +448,7 @@ impl Config {
     pub fn for_testing() -> Self {
         Self {
             aws_region: "us-east-1".to_string(),
+            graph_backend: GraphBackend::Neptune,
             neptune_endpoint: "localhost".to_string(),
             neptune_port: 8182,
             neptune_iam_auth: false,
@@ -105,6 +457,8 @@ impl Config {
             temporal_table: "test-temporal-table".to_string(),
             s3_bucket: "test-bucket".to_string(),
             sqs_queue_url: "http://localhost:4566/000000000000/test-queue".to_string(),
+            memory_backend: MemoryBackend::OpenSearch,
+            memory_path: None,
             memory_url: "http://localhost:9200".to_string(),
             memory_username: "".to_string(),
             memory_password: "".to_string(),
@@ -116,6 +470,42 @@ impl Config {
             max_context_window: 512,
             batch_size: 32,
             memory_size: 384,
+            embedding_provider: EmbeddingProvider::Local,
+            embedding_model_path: None,
+            embedding_endpoint: None,
+            embedding_api_key: None,
+            embedding_model: None,
+            embedding_model_version: None,
+            auth_mode: AuthMode::None,
+            api_keys: Vec::new(),
+            api_keys_path: None,
+            jwt_secret: None,
+            jwks_path: None,
+            jwt_issuer: None,
+            jwt_audience: None,
+            rate_limit_burst: default_rate_limit_burst(),
+            rate_limit_per_second: default_rate_limit_per_second(),
+            trusted_proxies: Vec::new(),
+            health_probe_timeout_ms: default_health_probe_timeout_ms(),
+            change_feed_retention: default_change_feed_retention(),
+            require_if_match: false,
+            mcp_max_results: default_mcp_max_results(),
+            mcp_max_history: default_mcp_max_history(),
+            mcp_navigation_idle_secs: default_mcp_navigation_idle_secs(),
+            mcp_max_sessions: default_mcp_max_sessions(),
+            mcp_max_sessions_per_caller: default_mcp_max_sessions_per_caller(),
+        }
+    }
+
+    /// Create a configuration for local development, with in-process graph
+    /// storage and file-backed memory under `data/`
+    pub fn for_development() -> Self {
+        Self {
+            graph_backend: GraphBackend::InMemory,
+            memory_backend: MemoryBackend::Local,
+            memory_path: Some("data/memory".to_string()),
+            embedding_provider: EmbeddingProvider::Hashing,
+            ..Self::for_testing()
         }
     }
 
@@ -123,6 +513,7 @@ impl Config {
     pub fn new(neptune_endpoint: String, max_connections: u32, connection_timeout: u32) -> Self {
         Self {
             aws_region: "us-east-1".to_string(),
+            graph_backend: GraphBackend::Neptune,
             neptune_endpoint,
             neptune_port: 8182,
             neptune_iam_auth: false,
@@ -131,6 +522,8 @@ impl Config {
             temporal_table: "graph-temporal-table".to_string(),
             s3_bucket: "graph-bucket".to_string(),
             sqs_queue_url: "http://localhost:4566/000000000000/graph-queue".to_string(),
+            memory_backend: MemoryBackend::OpenSearch,
+            memory_path: None,
             memory_url: "http://localhost:9200".to_string(),
             memory_username: "".to_string(),
             memory_password: "".to_string(),
@@ -142,6 +535,30 @@ impl Config {
             max_context_window: 512,
             batch_size: 32,
             memory_size: 384,
+            embedding_provider: EmbeddingProvider::Local,
+            embedding_model_path: None,
+            embedding_endpoint: None,
+            embedding_api_key: None,
+            embedding_model: None,
+            embedding_model_version: None,
+            auth_mode: AuthMode::None,
+            api_keys: Vec::new(),
+            api_keys_path: None,
+            jwt_secret: None,
+            jwks_path: None,
+            jwt_issuer: None,
+            jwt_audience: None,
+            rate_limit_burst: default_rate_limit_burst(),
+            rate_limit_per_second: default_rate_limit_per_second(),
+            trusted_proxies: Vec::new(),
+            health_probe_timeout_ms: default_health_probe_timeout_ms(),
+            change_feed_retention: default_change_feed_retention(),
+            require_if_match: false,
+            mcp_max_results: default_mcp_max_results(),
+            mcp_max_history: default_mcp_max_history(),
+            mcp_navigation_idle_secs: default_mcp_navigation_idle_secs(),
+            mcp_max_sessions: default_mcp_max_sessions(),
+            mcp_max_sessions_per_caller: default_mcp_max_sessions_per_caller(),
         }
     }
 }
@@ -150,6 +567,7 @@ impl Default for Config {
     fn default() -> Self {
         Self {
             aws_region: "us-east-1".to_string(),
+            graph_backend: GraphBackend::Neptune,
             neptune_endpoint: "localhost".to_string(),
             neptune_port: 8182,
             neptune_iam_auth: false,
@@ -158,6 +576,8 @@ impl Default for Config {
             temporal_table: "graph-temporal-table".to_string(),
             s3_bucket: "graph-bucket".to_string(),
             sqs_queue_url: "http://localhost:4566/000000000000/graph-queue".to_string(),
+            memory_backend: MemoryBackend::OpenSearch,
+            memory_path: None,
             memory_url: "http://localhost:9200".to_string(),
             memory_username: "".to_string(),
             memory_password: "".to_string(),
@@ -169,6 +589,30 @@ impl Default for Config {
             max_context_window: 512,
             batch_size: 32,
             memory_size: 384,
+            embedding_provider: EmbeddingProvider::Local,
+            embedding_model_path: None,
+            embedding_endpoint: None,
+            embedding_api_key: None,
+            embedding_model: None,
+            embedding_model_version: None,
+            auth_mode: AuthMode::None,
+            api_keys: Vec::new(),
+            api_keys_path: None,
+            jwt_secret: None,
+            jwks_path: None,
+            jwt_issuer: None,
+            jwt_audience: None,
+            rate_limit_burst: default_rate_limit_burst(),
+            rate_limit_per_second: default_rate_limit_per_second(),
+            trusted_proxies: Vec::new(),
+            health_probe_timeout_ms: default_health_probe_timeout_ms(),
+            change_feed_retention: default_change_feed_retention(),
+            require_if_match: false,
+            mcp_max_results: default_mcp_max_results(),
+            mcp_max_history: default_mcp_max_history(),
+            mcp_navigation_idle_secs: default_mcp_navigation_idle_secs(),
+            mcp_max_sessions: default_mcp_max_sessions(),
+            mcp_max_sessions_per_caller: default_mcp_max_sessions_per_caller(),
         }
     }
 }
@@ -197,6 +641,7 @@ mod tests {
         let save_s3_bucket = env::var("S3_BUCKET").ok();
         let save_sqs_queue_url = env::var("SQS_QUEUE_URL").ok();
         let save_memory_url = env::var("MEMORY_URL").ok();
+        let save_auth_mode = env::var("AUTH_MODE").ok();
         let save_max_retries = env::var("MAX_RETRIES").ok();
         let save_connection_timeout = env::var("CONNECTION_TIMEOUT").ok();
         let save_max_connections = env::var("MAX_CONNECTIONS").ok();
@@ -210,6 +655,7 @@ mod tests {
         env::set_var("S3_BUCKET", "test-bucket");
         env::set_var("SQS_QUEUE_URL", "test-queue");
         env::set_var("MEMORY_URL", "http://localhost:9200");
+        env::set_var("AUTH_MODE", "none");
         env::set_var("MAX_RETRIES", "5");
         env::set_var("CONNECTION_TIMEOUT", "10");
         env::set_var("MAX_CONNECTIONS", "50");
@@ -229,6 +675,7 @@ mod tests {
         if let Some(v) = save_s3_bucket { env::set_var("S3_BUCKET", v); } else { env::remove_var("S3_BUCKET"); }
         if let Some(v) = save_sqs_queue_url { env::set_var("SQS_QUEUE_URL", v); } else { env::remove_var("SQS_QUEUE_URL"); }
         if let Some(v) = save_memory_url { env::set_var("MEMORY_URL", v); } else { env::remove_var("MEMORY_URL"); }
+        if let Some(v) = save_auth_mode { env::set_var("AUTH_MODE", v); } else { env::remove_var("AUTH_MODE"); }
         if let Some(v) = save_max_retries { env::set_var("MAX_RETRIES", v); } else { env::remove_var("MAX_RETRIES"); }
         if let Some(v) = save_connection_timeout { env::set_var("CONNECTION_TIMEOUT", v); } else { env::remove_var("CONNECTION_TIMEOUT"); }
         if let Some(v) = save_max_connections { env::set_var("MAX_CONNECTIONS", v); } else { env::remove_var("MAX_CONNECTIONS"); }
@@ -247,6 +694,7 @@ mod tests {
         let save_s3_bucket = env::var("S3_BUCKET").ok();
         let save_sqs_queue_url = env::var("SQS_QUEUE_URL").ok();
         let save_memory_url = env::var("MEMORY_URL").ok();
+        let save_auth_mode = env::var("AUTH_MODE").ok();
         let save_max_retries = env::var("MAX_RETRIES").ok();
         let save_connection_timeout = env::var("CONNECTION_TIMEOUT").ok();
         let save_max_connections = env::var("MAX_CONNECTIONS").ok();
@@ -260,6 +708,7 @@ mod tests {
         env::set_var("S3_BUCKET", "test-bucket");
         env::set_var("SQS_QUEUE_URL", "test-queue");
         env::set_var("MEMORY_URL", "http://localhost:9200");
+        env::set_var("AUTH_MODE", "none");
         
         // Clear optional variables
         env::remove_var("MAX_RETRIES");
@@ -280,11 +729,147 @@ mod tests {
         if let Some(v) = save_s3_bucket { env::set_var("S3_BUCKET", v); } else { env::remove_var("S3_BUCKET"); }
         if let Some(v) = save_sqs_queue_url { env::set_var("SQS_QUEUE_URL", v); } else { env::remove_var("SQS_QUEUE_URL"); }
         if let Some(v) = save_memory_url { env::set_var("MEMORY_URL", v); } else { env::remove_var("MEMORY_URL"); }
+        if let Some(v) = save_auth_mode { env::set_var("AUTH_MODE", v); } else { env::remove_var("AUTH_MODE"); }
         if let Some(v) = save_max_retries { env::set_var("MAX_RETRIES", v); } else { env::remove_var("MAX_RETRIES"); }
         if let Some(v) = save_connection_timeout { env::set_var("CONNECTION_TIMEOUT", v); } else { env::remove_var("CONNECTION_TIMEOUT"); }
         if let Some(v) = save_max_connections { env::set_var("MAX_CONNECTIONS", v); } else { env::remove_var("MAX_CONNECTIONS"); }
     }
 
+    #[test]
+    fn test_unknown_backend_is_an_error() {
+        let _lock = TEST_MUTEX.lock().unwrap();
+
+        for name in ["GRAPH_BACKEND", "MEMORY_BACKEND", "EMBEDDING_PROVIDER"] {
+            let saved = env::var(name).ok();
+            env::set_var(name, "inmemoryy");
+
+            // A typo must not quietly select the default backend
+            let result = Config::from_env();
+            assert!(matches!(result, Err(crate::Error::ConfigurationError(_))), "{} typo was accepted", name);
+
+            if let Some(v) = saved { env::set_var(name, v); } else { env::remove_var(name); }
+        }
+    }
+
+    #[test]
+    fn test_local_backends_need_no_aws_settings() {
+        let _lock = TEST_MUTEX.lock().unwrap();
+
+        let aws = ["AWS_REGION", "NEPTUNE_ENDPOINT", "OPENSEARCH_ENDPOINT", "DYNAMODB_TABLE", "TEMPORAL_TABLE", "S3_BUCKET", "SQS_QUEUE_URL", "MEMORY_URL"];
+        let names: Vec<&str> = aws.iter().copied().chain(["GRAPH_BACKEND", "MEMORY_BACKEND", "AUTH_MODE"]).collect();
+        let saved: Vec<_> = names.iter().map(|name| env::var(name).ok()).collect();
+        for name in aws {
+            env::remove_var(name);
+        }
+        env::set_var("AUTH_MODE", "none");
+
+        env::set_var("GRAPH_BACKEND", "memory");
+        env::set_var("MEMORY_BACKEND", "local");
+        let config = Config::from_env().unwrap();
+        assert_eq!(config.graph_backend, GraphBackend::InMemory);
+        assert_eq!(config.aws_region, Config::default().aws_region);
+
+        // Each backend still requires its own settings
+        env::set_var("MEMORY_BACKEND", "opensearch");
+        assert!(matches!(Config::from_env(), Err(crate::Error::ConfigurationError(_))));
+        env::set_var("MEMORY_BACKEND", "local");
+        env::set_var("GRAPH_BACKEND", "neptune");
+        assert!(matches!(Config::from_env(), Err(crate::Error::ConfigurationError(_))));
+
+        for (name, value) in names.iter().zip(saved) {
+            if let Some(v) = value { env::set_var(name, v); } else { env::remove_var(name); }
+        }
+    }
+
+    #[test]
+    fn test_auth_settings_from_env() {
+        let _lock = TEST_MUTEX.lock().unwrap();
+
+        let names = ["GRAPH_BACKEND", "MEMORY_BACKEND", "AUTH_MODE", "API_KEYS"];
+        let saved: Vec<_> = names.iter().map(|name| env::var(name).ok()).collect();
+        env::set_var("GRAPH_BACKEND", "memory");
+        env::set_var("MEMORY_BACKEND", "local");
+
+        // Unset or misspelled, the API must not silently run unauthenticated
+        env::remove_var("AUTH_MODE");
+        assert!(matches!(Config::from_env(), Err(crate::Error::ConfigurationError(_))));
+        env::set_var("AUTH_MODE", "api-keys");
+        assert!(matches!(Config::from_env(), Err(crate::Error::ConfigurationError(_))));
+
+        env::set_var("AUTH_MODE", "api_key");
+        env::set_var("API_KEYS", r#"[{"hash": "abc123", "subject": "ci", "scopes": ["graph:read"]}]"#);
+        let config = Config::from_env().unwrap();
+        assert_eq!(config.auth_mode, AuthMode::ApiKey);
+        assert_eq!(config.api_keys.len(), 1);
+        assert_eq!(config.api_keys[0].subject, "ci");
+        assert_eq!(config.api_keys[0].scopes, vec!["graph:read".to_string()]);
+
+        env::set_var("API_KEYS", "not json");
+        assert!(matches!(Config::from_env(), Err(crate::Error::ConfigurationError(_))));
+
+        for (name, value) in names.iter().zip(saved) {
+            if let Some(v) = value { env::set_var(name, v); } else { env::remove_var(name); }
+        }
+    }
+
+    #[test]
+    fn test_unparsable_settings_are_an_error() {
+        let _lock = TEST_MUTEX.lock().unwrap();
+
+        let settings = [("REQUIRE_IF_MATCH", "yes"), ("MCP_NAVIGATION_IDLE_SECS", "15m"), ("MCP_MAX_RESULTS", "0"), ("MCP_MAX_RESULTS", "many"), ("MCP_MAX_HISTORY", "all"), ("MCP_MAX_SESSIONS", "0"), ("MCP_MAX_SESSIONS_PER_CALLER", "lots"), ("CHANGE_FEED_RETENTION", "-1"), ("HEALTH_PROBE_TIMEOUT_MS", "2s"), ("RATE_LIMIT_BURST", "ten"), ("RATE_LIMIT_PER_SECOND", "fast")];
+        let names: Vec<&str> = settings.iter().map(|(name, _)| *name).chain(["GRAPH_BACKEND", "MEMORY_BACKEND", "AUTH_MODE"]).collect();
+        let saved: Vec<_> = names.iter().map(|name| env::var(name).ok()).collect();
+        for (name, _) in settings {
+            env::remove_var(name);
+        }
+        env::set_var("GRAPH_BACKEND", "memory");
+        env::set_var("MEMORY_BACKEND", "local");
+        env::set_var("AUTH_MODE", "none");
+        assert!(Config::from_env().is_ok());
+
+        // A malformed value must not quietly fall back to the default
+        for (name, value) in settings {
+            env::set_var(name, value);
+            assert!(matches!(Config::from_env(), Err(crate::Error::ConfigurationError(_))), "{}={} was accepted", name, value);
+            env::remove_var(name);
+        }
+
+        for (name, value) in names.iter().zip(saved) {
+            if let Some(v) = value { env::set_var(name, v); } else { env::remove_var(name); }
+        }
+    }
+
+    #[test]
+    fn test_graph_backend_from_str() {
+        assert_eq!("neptune".parse::<GraphBackend>().unwrap(), GraphBackend::Neptune);
+        assert_eq!("in-memory".parse::<GraphBackend>().unwrap(), GraphBackend::InMemory);
+        assert_eq!("MEMORY".parse::<GraphBackend>().unwrap(), GraphBackend::InMemory);
+        assert!("gremlin-server".parse::<GraphBackend>().is_err());
+    }
+
+    #[test]
+    fn test_embedding_provider_from_str() {
+        assert_eq!("local".parse::<EmbeddingProvider>().unwrap(), EmbeddingProvider::Local);
+        assert_eq!("Hashing".parse::<EmbeddingProvider>().unwrap(), EmbeddingProvider::Hashing);
+        assert_eq!("openai".parse::<EmbeddingProvider>().unwrap(), EmbeddingProvider::Http);
+        assert!("word2vec".parse::<EmbeddingProvider>().is_err());
+    }
+
+    #[test]
+    fn test_memory_backend_from_str() {
+        assert_eq!("opensearch".parse::<MemoryBackend>().unwrap(), MemoryBackend::OpenSearch);
+        assert_eq!("Local".parse::<MemoryBackend>().unwrap(), MemoryBackend::Local);
+        assert!("redis".parse::<MemoryBackend>().is_err());
+    }
+
+    #[test]
+    fn test_auth_mode_from_str() {
+        assert_eq!("none".parse::<AuthMode>().unwrap(), AuthMode::None);
+        assert_eq!("API_KEY".parse::<AuthMode>().unwrap(), AuthMode::ApiKey);
+        assert_eq!("jwt".parse::<AuthMode>().unwrap(), AuthMode::Jwt);
+        assert!("basic".parse::<AuthMode>().is_err());
+    }
+
     #[test]
     fn test_config_for_testing() {
         let config = Config::for_testing();
@@ -4,6 +4,7 @@ use gremlin_client::GValue;
 use url;
 use chrono;
 use opensearch;
+use crate::types::VersionToken;
 use aws_sdk_dynamodb::{
     error::SdkError,
     Error as DynamoDbError,
@@ -146,6 +147,16 @@ pub enum Error {
 
     #[error("Not implemented: {0}")]
     NotImplemented(String),
+
+    #[error("Request cancelled")]
+    Cancelled,
+
+    #[error("Version conflict on {id}: expected version {expected}, current version is {current}")]
+    VersionConflict {
+        id: String,
+        expected: VersionToken,
+        current: VersionToken,
+    },
 }
 
 /// Result type alias using our custom Error
@@ -0,0 +1,613 @@
+//! In-process implementation of the `Graph` trait
+//!
+//! `InMemoryGraph` keeps nodes and edges in process memory so the full stack
+//! (hybrid store, RAG pipeline, API) can run without a Neptune cluster. It
+//! follows the same bi-temporal rules as the Neptune backend. Raw Gremlin
+//! execution is not supported.
+
+use std::collections::{HashMap, HashSet};
+
+use async_trait::async_trait;
+use gremlin_client::{GResultSet, ToGValue};
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+use crate::{
+    error::{Error, Result},
+    types::{Edge, EdgeId, FromLocalResultSet, Node, NodeId, TemporalFilter, TemporalRange, Timestamp, VersionToken, Versioned},
+};
+
+use super::Graph;
+
+/// Every recorded version of an element, oldest first
+type History<T> = Vec<T>;
+
+/// The version of an element visible through `filter`, if any
+fn visible<'a, T: Versioned>(history: &'a History<T>, filter: &TemporalFilter) -> Option<&'a T> {
+    history.iter()
+        .rev()
+        .find(|version| filter.matches(version.valid_time(), version.transaction_time()))
+}
+
+/// The current (not yet superseded or deleted) version of an element
+fn current_mut<T: Versioned>(history: &mut History<T>) -> Option<&mut T> {
+    history.last_mut().filter(|version| version.transaction_time().end.is_none())
+}
+
+/// Fail unless the current version carries the `expected` token
+fn check_version<T: Versioned>(history: &History<T>, id: String, expected: Option<VersionToken>) -> Result<()> {
+    let Some(expected) = expected else {
+        return Ok(());
+    };
+    match history.last().and_then(|current| VersionToken::of(current.transaction_time())) {
+        Some(current) if current != expected => Err(Error::VersionConflict { id, expected, current }),
+        _ => Ok(()),
+    }
+}
+
+/// Close the current version at `now` and record `next` as its successor
+fn supersede<T: Versioned>(history: &mut History<T>, mut next: T, mut now: Timestamp) {
+    if let Some(current) = current_mut(history) {
+        // Successive versions must carry distinct version tokens
+        if let Some(start) = current.transaction_time().start {
+            if now.0 <= start.0 {
+                now = Timestamp(start.0 + chrono::Duration::microseconds(1));
+            }
+        }
+        current.transaction_time_mut().end = Some(now);
+    }
+    *next.transaction_time_mut() = TemporalRange::new(Some(now), None);
+    history.push(next);
+}
+
+/// Backing storage shared by all graph operations
+#[derive(Debug, Default)]
+struct GraphData {
+    nodes: HashMap<NodeId, History<Node>>,
+    edges: HashMap<EdgeId, History<Edge>>,
+}
+
+impl GraphData {
+    fn is_current_node(&self, id: &NodeId) -> bool {
+        self.nodes.get(id)
+            .and_then(|history| history.last())
+            .map_or(false, |node| node.transaction_time.end.is_none())
+    }
+
+    /// Edge versions visible through `filter`
+    fn edges<'a>(&'a self, filter: &'a TemporalFilter) -> impl Iterator<Item = &'a Edge> {
+        self.edges.values().filter_map(move |history| visible(history, filter))
+    }
+
+    /// Node versions visible through `filter`
+    fn nodes<'a>(&'a self, filter: &'a TemporalFilter) -> impl Iterator<Item = &'a Node> {
+        self.nodes.values().filter_map(move |history| visible(history, filter))
+    }
+}
+
+/// In-memory graph backend, primarily for tests and local development
+///
+/// Every write is recorded as a new version so reads can be replayed as of
+/// any earlier transaction time.
+#[derive(Debug, Default)]
+pub struct InMemoryGraph {
+    data: RwLock<GraphData>,
+}
+
+impl InMemoryGraph {
+    /// Create an empty in-memory graph
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of current nodes
+    pub async fn node_count(&self) -> usize {
+        let data = self.data.read().await;
+        data.nodes(&TemporalFilter::current()).count()
+    }
+
+    /// Number of current edges
+    pub async fn edge_count(&self) -> usize {
+        let data = self.data.read().await;
+        data.edges(&TemporalFilter::current()).count()
+    }
+
+    /// Helper to validate temporal range
+    fn validate_temporal_range(range: &TemporalRange) -> Result<()> {
+        if let (Some(start), Some(end)) = (range.start, range.end) {
+            if start.0 > end.0 {
+                return Err(Error::InvalidTemporalRange(
+                    "Start time must be before end time".to_string()
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn validate_filter(filter: &TemporalFilter) -> Result<()> {
+        match &filter.valid_time {
+            Some(range) => Self::validate_temporal_range(range),
+            None => Ok(()),
+        }
+    }
+
+    /// Collect edges visible through `filter` that satisfy `predicate`
+    async fn find_edges(&self, filter: &TemporalFilter, predicate: impl Fn(&Edge) -> bool) -> Result<Vec<Edge>> {
+        Self::validate_filter(filter)?;
+        let data = self.data.read().await;
+        Ok(data.edges(filter)
+            .filter(|edge| predicate(edge))
+            .cloned()
+            .collect())
+    }
+}
+
+#[async_trait]
+impl Graph for InMemoryGraph {
+    async fn create_node(&self, node: Node) -> Result<NodeId> {
+        Self::validate_temporal_range(&node.valid_time)?;
+
+        let mut data = self.data.write().await;
+        if data.nodes.contains_key(&node.id) {
+            return Err(Error::ValidationError(format!("Node {} already exists", node.id)));
+        }
+
+        let id = node.id;
+        let mut history = History::new();
+        supersede(&mut history, node, Timestamp::now());
+        data.nodes.insert(id, history);
+        Ok(id)
+    }
+
+    async fn get_node(&self, id: NodeId, filter: TemporalFilter) -> Result<Node> {
+        Self::validate_filter(&filter)?;
+        let data = self.data.read().await;
+        data.nodes.get(&id)
+            .and_then(|history| visible(history, &filter))
+            .cloned()
+            .ok_or_else(|| Error::NodeNotFound(id.to_string()))
+    }
+
+    async fn get_node_versions(&self, id: NodeId) -> Result<Vec<Node>> {
+        let data = self.data.read().await;
+        data.nodes.get(&id)
+            .cloned()
+            .ok_or_else(|| Error::NodeNotFound(id.to_string()))
+    }
+
+    async fn update_node(&self, node: Node, expected: Option<VersionToken>) -> Result<()> {
+        Self::validate_temporal_range(&node.valid_time)?;
+
+        let mut data = self.data.write().await;
+        if !data.is_current_node(&node.id) {
+            return Err(Error::NodeNotFound(node.id.to_string()));
+        }
+
+        let history = data.nodes.get_mut(&node.id).expect("checked above");
+        check_version(history, node.id.to_string(), expected)?;
+        supersede(history, node, Timestamp::now());
+        Ok(())
+    }
+
+    async fn delete_node(&self, id: NodeId) -> Result<()> {
+        let mut data = self.data.write().await;
+        let now = Timestamp::now();
+
+        match data.nodes.get_mut(&id).and_then(current_mut) {
+            Some(node) => node.transaction_time.end = Some(now),
+            None => return Err(Error::NodeNotFound(id.to_string())),
+        }
+
+        // Deleting a vertex deletes its incident edges, as in Gremlin
+        for history in data.edges.values_mut() {
+            if let Some(edge) = current_mut(history) {
+                if edge.source_id == id || edge.target_id == id {
+                    edge.transaction_time.end = Some(now);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn create_edge(&self, edge: Edge) -> Result<EdgeId> {
+        Self::validate_temporal_range(&edge.valid_time)?;
+
+        let mut data = self.data.write().await;
+        for endpoint in [edge.source_id, edge.target_id] {
+            if !data.is_current_node(&endpoint) {
+                return Err(Error::NodeNotFound(endpoint.to_string()));
+            }
+        }
+        if data.edges.contains_key(&edge.id) {
+            return Err(Error::ValidationError(format!("Edge {} already exists", edge.id)));
+        }
+
+        let id = edge.id;
+        let mut history = History::new();
+        supersede(&mut history, edge, Timestamp::now());
+        data.edges.insert(id, history);
+        Ok(id)
+    }
+
+    async fn get_edge(&self, id: EdgeId, filter: TemporalFilter) -> Result<Edge> {
+        Self::validate_filter(&filter)?;
+        let data = self.data.read().await;
+        data.edges.get(&id)
+            .and_then(|history| visible(history, &filter))
+            .cloned()
+            .ok_or_else(|| Error::EdgeNotFound(id.to_string()))
+    }
+
+    async fn get_edge_versions(&self, id: EdgeId) -> Result<Vec<Edge>> {
+        let data = self.data.read().await;
+        data.edges.get(&id)
+            .cloned()
+            .ok_or_else(|| Error::EdgeNotFound(id.to_string()))
+    }
+
+    async fn update_edge(&self, edge: Edge, expected: Option<VersionToken>) -> Result<()> {
+        Self::validate_temporal_range(&edge.valid_time)?;
+
+        let mut data = self.data.write().await;
+        for endpoint in [edge.source_id, edge.target_id] {
+            if !data.is_current_node(&endpoint) {
+                return Err(Error::NodeNotFound(endpoint.to_string()));
+            }
+        }
+
+        match data.edges.get_mut(&edge.id) {
+            Some(history) if history.last().map_or(false, |e| e.transaction_time.end.is_none()) => {
+                check_version(history, edge.id.to_string(), expected)?;
+                supersede(history, edge, Timestamp::now());
+                Ok(())
+            }
+            _ => Err(Error::EdgeNotFound(edge.id.to_string())),
+        }
+    }
+
+    async fn delete_edge(&self, id: EdgeId) -> Result<()> {
+        let mut data = self.data.write().await;
+        match data.edges.get_mut(&id).and_then(current_mut) {
+            Some(edge) => {
+                edge.transaction_time.end = Some(Timestamp::now());
+                Ok(())
+            }
+            None => Err(Error::EdgeNotFound(id.to_string())),
+        }
+    }
+
+    async fn get_edges_for_node(
+        &self,
+        node_id: NodeId,
+        filter: TemporalFilter,
+    ) -> Result<Vec<Edge>> {
+        self.find_edges(&filter, |edge| edge.source_id == node_id || edge.target_id == node_id).await
+    }
+
+    async fn get_connected_nodes(
+        &self,
+        node_id: NodeId,
+        filter: TemporalFilter,
+    ) -> Result<Vec<Node>> {
+        Self::validate_filter(&filter)?;
+
+        let data = self.data.read().await;
+        let mut seen = HashSet::new();
+        let mut nodes = Vec::new();
+
+        for edge in data.edges(&filter) {
+            let neighbor_id = if edge.source_id == node_id {
+                edge.target_id
+            } else if edge.target_id == node_id {
+                edge.source_id
+            } else {
+                continue;
+            };
+            if !seen.insert(neighbor_id) {
+                continue;
+            }
+
+            if let Some(neighbor) = data.nodes.get(&neighbor_id).and_then(|history| visible(history, &filter)) {
+                nodes.push(neighbor.clone());
+            }
+        }
+
+        Ok(nodes)
+    }
+
+    async fn execute_query<T>(&self, query: &str, params: &[(&str, &dyn ToGValue)]) -> Result<T>
+    where
+        T: FromLocalResultSet,
+    {
+        self.execute_query_with_retry::<T>(query, params).await
+    }
+
+    async fn execute_query_with_retry<T>(&self, _query: &str, _params: &[(&str, &dyn ToGValue)]) -> Result<T>
+    where
+        T: FromLocalResultSet,
+    {
+        Err(Error::NotImplemented(
+            "Raw Gremlin queries are not supported by the in-memory graph".to_string()
+        ))
+    }
+
+    async fn get_nodes_by_label(&self, label: &str, filter: TemporalFilter) -> Result<Vec<Node>> {
+        Self::validate_filter(&filter)?;
+        let data = self.data.read().await;
+        Ok(data.nodes(&filter)
+            .filter(|node| node.label == label)
+            .cloned()
+            .collect())
+    }
+
+    async fn get_edges_by_label(&self, label: &str, filter: TemporalFilter) -> Result<Vec<Edge>> {
+        self.find_edges(&filter, |edge| edge.label == label).await
+    }
+
+    async fn get_edges_between(&self, from: NodeId, to: NodeId, filter: TemporalFilter) -> Result<Vec<Edge>> {
+        self.find_edges(&filter, |edge| edge.source_id == from && edge.target_id == to).await
+    }
+
+    async fn get_edges_from(&self, from: NodeId, filter: TemporalFilter) -> Result<Vec<Edge>> {
+        self.find_edges(&filter, |edge| edge.source_id == from).await
+    }
+
+    async fn get_edges_to(&self, to: NodeId, filter: TemporalFilter) -> Result<Vec<Edge>> {
+        self.find_edges(&filter, |edge| edge.target_id == to).await
+    }
+
+    async fn get_vertex(&self, id: &str, filter: TemporalFilter) -> Result<Option<Node>> {
+        let node_id = match Uuid::parse_str(id) {
+            Ok(uuid) => NodeId(uuid),
+            Err(_) => return Ok(None),
+        };
+
+        match self.get_node(node_id, filter).await {
+            Ok(node) => Ok(Some(node)),
+            Err(Error::NodeNotFound(_)) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    async fn execute_gremlin_query(&self, _query: &str, _params: &[(&str, &dyn ToGValue)]) -> Result<GResultSet> {
+        Err(Error::NotImplemented(
+            "Raw Gremlin queries are not supported by the in-memory graph".to_string()
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{EntityType, Properties, Timestamp};
+    use chrono::{Duration, Utc};
+
+    fn node(label: &str, valid_time: TemporalRange) -> Node {
+        Node {
+            id: NodeId(Uuid::new_v4()),
+            entity_type: EntityType::Person,
+            label: label.to_string(),
+            properties: Properties::new(),
+            valid_time,
+            transaction_time: TemporalRange::from_now(),
+        }
+    }
+
+    fn edge(source: &Node, target: &Node, label: &str, valid_time: TemporalRange) -> Edge {
+        Edge {
+            id: EdgeId(Uuid::new_v4()),
+            source_id: source.id,
+            target_id: target.id,
+            label: label.to_string(),
+            properties: Properties::new(),
+            valid_time,
+            transaction_time: TemporalRange::from_now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_node_crud() {
+        let graph = InMemoryGraph::new();
+        let mut alice = node("alice", TemporalRange::unbounded());
+
+        let id = graph.create_node(alice.clone()).await.unwrap();
+        assert_eq!(id, alice.id);
+        assert!(graph.create_node(alice.clone()).await.is_err());
+
+        alice.label = "alice_updated".to_string();
+        graph.update_node(alice.clone(), None).await.unwrap();
+        assert_eq!(graph.get_node(id, TemporalFilter::current()).await.unwrap().label, "alice_updated");
+        assert!(graph.get_vertex(&id.to_string(), TemporalFilter::current()).await.unwrap().is_some());
+        assert!(graph.get_vertex("not-a-uuid", TemporalFilter::current()).await.unwrap().is_none());
+
+        graph.delete_node(id).await.unwrap();
+        assert!(matches!(graph.get_node(id, TemporalFilter::current()).await, Err(Error::NodeNotFound(_))));
+        assert!(matches!(graph.delete_node(id).await, Err(Error::NodeNotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_updates_check_expected_version() {
+        let graph = InMemoryGraph::new();
+        let alice = node("alice", TemporalRange::unbounded());
+        let bob = node("bob", TemporalRange::unbounded());
+        graph.create_node(alice.clone()).await.unwrap();
+        graph.create_node(bob.clone()).await.unwrap();
+
+        let read = graph.get_node(alice.id, TemporalFilter::current()).await.unwrap();
+        let token = read.version().unwrap();
+        graph.update_node(read.clone(), Some(token)).await.unwrap();
+
+        // The first update superseded the version both writers read
+        let current = graph.get_node(alice.id, TemporalFilter::current()).await.unwrap().version().unwrap();
+        assert_ne!(current, token);
+        match graph.update_node(read, Some(token)).await {
+            Err(Error::VersionConflict { expected, current: actual, .. }) => {
+                assert_eq!(expected, token);
+                assert_eq!(actual, current);
+            }
+            other => panic!("expected a version conflict, got {:?}", other),
+        }
+
+        let knows = edge(&alice, &bob, "knows", TemporalRange::unbounded());
+        graph.create_edge(knows.clone()).await.unwrap();
+        let stale = graph.get_edge(knows.id, TemporalFilter::current()).await.unwrap().version();
+        graph.update_edge(knows.clone(), None).await.unwrap();
+        assert!(matches!(graph.update_edge(knows, stale).await, Err(Error::VersionConflict { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_versions_list_history_oldest_first() {
+        let graph = InMemoryGraph::new();
+        let mut alice = node("alice", TemporalRange::unbounded());
+        graph.create_node(alice.clone()).await.unwrap();
+        alice.label = "alice_updated".to_string();
+        graph.update_node(alice.clone(), None).await.unwrap();
+        graph.delete_node(alice.id).await.unwrap();
+
+        let versions = graph.get_node_versions(alice.id).await.unwrap();
+        assert_eq!(versions.iter().map(|v| v.label.as_str()).collect::<Vec<_>>(), ["alice", "alice_updated"]);
+        assert!(versions.iter().all(|v| v.transaction_time.end.is_some()));
+        assert_ne!(versions[0].version_id(), versions[1].version_id());
+        assert!(matches!(graph.get_node_versions(NodeId(Uuid::new_v4())).await, Err(Error::NodeNotFound(_))));
+        assert!(matches!(graph.get_edge_versions(EdgeId(Uuid::new_v4())).await, Err(Error::EdgeNotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_edge_requires_endpoints_and_cascades() {
+        let graph = InMemoryGraph::new();
+        let alice = node("alice", TemporalRange::unbounded());
+        let bob = node("bob", TemporalRange::unbounded());
+        graph.create_node(alice.clone()).await.unwrap();
+
+        let knows = edge(&alice, &bob, "knows", TemporalRange::unbounded());
+        assert!(matches!(graph.create_edge(knows.clone()).await, Err(Error::NodeNotFound(_))));
+
+        graph.create_node(bob.clone()).await.unwrap();
+        let edge_id = graph.create_edge(knows.clone()).await.unwrap();
+        assert_eq!(graph.get_edge(edge_id, TemporalFilter::current()).await.unwrap().label, "knows");
+
+        graph.delete_node(bob.id).await.unwrap();
+        assert_eq!(graph.edge_count().await, 0);
+        assert!(matches!(graph.get_edge(edge_id, TemporalFilter::current()).await, Err(Error::EdgeNotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_label_and_direction_lookups() {
+        let graph = InMemoryGraph::new();
+        let alice = node("alice", TemporalRange::unbounded());
+        let bob = node("bob", TemporalRange::unbounded());
+        let carol = node("carol", TemporalRange::unbounded());
+        for n in [&alice, &bob, &carol] {
+            graph.create_node(n.clone()).await.unwrap();
+        }
+
+        graph.create_edge(edge(&alice, &bob, "knows", TemporalRange::unbounded())).await.unwrap();
+        graph.create_edge(edge(&alice, &bob, "works_with", TemporalRange::unbounded())).await.unwrap();
+        graph.create_edge(edge(&carol, &alice, "knows", TemporalRange::unbounded())).await.unwrap();
+
+        assert_eq!(graph.get_nodes_by_label("bob", TemporalFilter::current()).await.unwrap().len(), 1);
+        assert_eq!(graph.get_edges_by_label("knows", TemporalFilter::current()).await.unwrap().len(), 2);
+        assert_eq!(graph.get_edges_between(alice.id, bob.id, TemporalFilter::current()).await.unwrap().len(), 2);
+        assert!(graph.get_edges_between(bob.id, alice.id, TemporalFilter::current()).await.unwrap().is_empty());
+        assert_eq!(graph.get_edges_from(alice.id, TemporalFilter::current()).await.unwrap().len(), 2);
+        assert_eq!(graph.get_edges_to(alice.id, TemporalFilter::current()).await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_temporal_filtering() {
+        let graph = InMemoryGraph::new();
+        let now = Utc::now();
+        let past = TemporalRange::new(
+            Some(Timestamp(now - Duration::days(10))),
+            Some(Timestamp(now - Duration::days(5))),
+        );
+        let current = TemporalRange::new(Some(Timestamp(now - Duration::days(1))), None);
+
+        let alice = node("alice", TemporalRange::unbounded());
+        let bob = node("bob", TemporalRange::unbounded());
+        let carol = node("carol", current.clone());
+        for n in [&alice, &bob, &carol] {
+            graph.create_node(n.clone()).await.unwrap();
+        }
+        graph.create_edge(edge(&alice, &bob, "knew", past.clone())).await.unwrap();
+        graph.create_edge(edge(&carol, &alice, "knows", current.clone())).await.unwrap();
+
+        assert_eq!(graph.get_edges_for_node(alice.id, TemporalFilter::current()).await.unwrap().len(), 2);
+
+        let last_week = TemporalRange::new(
+            Some(Timestamp(now - Duration::days(7))),
+            Some(Timestamp(now - Duration::days(6))),
+        );
+        let edges = graph.get_edges_for_node(alice.id, last_week.clone().into()).await.unwrap();
+        assert_eq!(edges.len(), 1);
+        assert_eq!(edges[0].label, "knew");
+
+        let neighbors = graph.get_connected_nodes(alice.id, last_week.into()).await.unwrap();
+        assert_eq!(neighbors.len(), 1);
+        assert_eq!(neighbors[0].id, bob.id);
+
+        let today = TemporalRange::new(Some(Timestamp(now)), Some(Timestamp(now)));
+        let neighbors = graph.get_connected_nodes(alice.id, today.into()).await.unwrap();
+        assert_eq!(neighbors.len(), 1);
+        assert_eq!(neighbors[0].id, carol.id);
+
+        let inverted = TemporalRange::new(Some(Timestamp(now)), Some(Timestamp(now - Duration::days(1))));
+        assert!(matches!(
+            graph.get_edges_for_node(alice.id, inverted.into()).await,
+            Err(Error::InvalidTemporalRange(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_as_of_transaction_time() {
+        let graph = InMemoryGraph::new();
+        let mut alice = node("alice", TemporalRange::unbounded());
+        let bob = node("bob", TemporalRange::unbounded());
+        graph.create_node(alice.clone()).await.unwrap();
+        graph.create_node(bob.clone()).await.unwrap();
+        let knows = graph.create_edge(edge(&alice, &bob, "knows", TemporalRange::unbounded())).await.unwrap();
+
+        tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+        let before_update = Timestamp::now();
+        tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+
+        alice.label = "alice_updated".to_string();
+        graph.update_node(alice.clone(), None).await.unwrap();
+
+        tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+        let before_delete = Timestamp::now();
+        tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+
+        graph.delete_node(bob.id).await.unwrap();
+
+        let then = TemporalFilter::current().as_of(before_update);
+        assert_eq!(graph.get_node(alice.id, then.clone()).await.unwrap().label, "alice");
+        assert_eq!(graph.get_node(alice.id, TemporalFilter::current()).await.unwrap().label, "alice_updated");
+
+        // Deleted elements remain visible as of earlier transaction times
+        let then = TemporalFilter::current().as_of(before_delete);
+        assert_eq!(graph.get_edge(knows, then.clone()).await.unwrap().label, "knows");
+        let neighbors = graph.get_connected_nodes(alice.id, then).await.unwrap();
+        assert_eq!(neighbors.len(), 1);
+        assert_eq!(neighbors[0].id, bob.id);
+
+        assert!(graph.get_connected_nodes(alice.id, TemporalFilter::current()).await.unwrap().is_empty());
+        assert!(matches!(
+            graph.get_node(bob.id, TemporalFilter::current()).await,
+            Err(Error::NodeNotFound(_))
+        ));
+
+        // Nothing is visible before it was recorded
+        let before_create = TemporalFilter::current().as_of(Timestamp(Utc::now() - Duration::days(1)));
+        assert!(graph.get_node(alice.id, before_create).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_raw_queries_not_supported() {
+        let graph = InMemoryGraph::new();
+        assert!(matches!(
+            graph.execute_query::<()>("g.V()", &[]).await,
+            Err(Error::NotImplemented(_))
+        ));
+    }
+}
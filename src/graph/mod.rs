@@ -10,26 +10,50 @@ use gremlin_client::{GValue, ToGValue, GResultSet};
 use crate::{
     error::{Error, Result},
     types::{FromLocalResultSet, TimeRange},
+    config::GraphBackend,
     Config,
 };
 
 // Re-export common types for external use
-pub use crate::types::{Node, Edge, NodeId, EdgeId, TemporalRange, Properties, EntityType, Timestamp};
+pub use crate::types::{Node, Edge, NodeId, EdgeId, TemporalRange, TemporalFilter, Properties, EntityType, Timestamp, VersionToken};
 
 pub mod neptune;
 pub mod query;
+pub mod in_memory;
+pub mod traversal;
+pub mod tenant;
+
+pub use in_memory::InMemoryGraph;
+pub use neptune::NeptuneGraph;
+pub use tenant::TenantGraph;
 
 /// Core trait defining graph operations
+///
+/// Graphs are bi-temporal. Implementations stamp transaction time on every
+/// write and keep superseded versions, so any read can be filtered by valid
+/// time and replayed as of an earlier transaction time via `TemporalFilter`.
 #[async_trait]
 pub trait Graph: Send + Sync {
     /// Create a new node
     async fn create_node(&self, node: Node) -> Result<NodeId>;
     
     /// Get a node by ID
-    async fn get_node(&self, id: NodeId) -> Result<Node>;
+    async fn get_node(&self, id: NodeId, filter: TemporalFilter) -> Result<Node>;
     
+    /// Every recorded version of a node, oldest first
+    ///
+    /// Includes superseded and deleted versions. Fails with
+    /// `Error::NodeNotFound` when the node was never stored.
+    async fn get_node_versions(&self, id: NodeId) -> Result<Vec<Node>>;
+
     /// Update a node
-    async fn update_node(&self, node: Node) -> Result<()>;
+    ///
+    /// When `expected` is given the update only applies if the current
+    /// version still carries that token, and fails with
+    /// `Error::VersionConflict` otherwise. Without `expected` the update is
+    /// applied to whichever version is current; backends that cannot do that
+    /// atomically fail rather than overwrite a concurrent write.
+    async fn update_node(&self, node: Node, expected: Option<VersionToken>) -> Result<()>;
     
     /// Delete a node
     async fn delete_node(&self, id: NodeId) -> Result<()>;
@@ -38,10 +62,22 @@ pub trait Graph: Send + Sync {
     async fn create_edge(&self, edge: Edge) -> Result<EdgeId>;
     
     /// Get an edge by ID
-    async fn get_edge(&self, id: EdgeId) -> Result<Edge>;
+    async fn get_edge(&self, id: EdgeId, filter: TemporalFilter) -> Result<Edge>;
     
+    /// Every recorded version of an edge, oldest first
+    ///
+    /// Includes superseded and deleted versions. Fails with
+    /// `Error::EdgeNotFound` when the edge was never stored.
+    async fn get_edge_versions(&self, id: EdgeId) -> Result<Vec<Edge>>;
+
     /// Update an edge
-    async fn update_edge(&self, edge: Edge) -> Result<()>;
+    ///
+    /// When `expected` is given the update only applies if the current
+    /// version still carries that token, and fails with
+    /// `Error::VersionConflict` otherwise. Without `expected` the update is
+    /// applied to whichever version is current; backends that cannot do that
+    /// atomically fail rather than overwrite a concurrent write.
+    async fn update_edge(&self, edge: Edge, expected: Option<VersionToken>) -> Result<()>;
     
     /// Delete an edge
     async fn delete_edge(&self, id: EdgeId) -> Result<()>;
@@ -50,51 +86,182 @@ pub trait Graph: Send + Sync {
     async fn get_edges_for_node(
         &self,
         node_id: NodeId,
-        temporal_range: Option<TemporalRange>,
+        filter: TemporalFilter,
     ) -> Result<Vec<Edge>>;
     
     /// Get all nodes connected to a node within a temporal range
     async fn get_connected_nodes(
         &self,
         node_id: NodeId,
-        temporal_range: Option<TemporalRange>,
+        filter: TemporalFilter,
     ) -> Result<Vec<Node>>;
 
     /// Execute a query
+    ///
+    /// Generic over the result type, so it is not available on `dyn Graph`.
     async fn execute_query<T>(&self, query: &str, params: &[(&str, &dyn ToGValue)]) -> Result<T> 
     where
-        T: FromLocalResultSet;
+        T: FromLocalResultSet,
+        Self: Sized;
 
     /// Execute a query with built-in retry mechanism
     async fn execute_query_with_retry<T>(&self, query: &str, params: &[(&str, &dyn ToGValue)]) -> Result<T>
     where
-        T: FromLocalResultSet;
+        T: FromLocalResultSet,
+        Self: Sized;
 
     /// Get all nodes with a given label
-    async fn get_nodes_by_label(&self, label: &str) -> Result<Vec<Node>>;
+    async fn get_nodes_by_label(&self, label: &str, filter: TemporalFilter) -> Result<Vec<Node>>;
 
     /// Get all edges with a given label
-    async fn get_edges_by_label(&self, label: &str) -> Result<Vec<Edge>>;
+    async fn get_edges_by_label(&self, label: &str, filter: TemporalFilter) -> Result<Vec<Edge>>;
 
     /// Get all edges between two nodes
-    async fn get_edges_between(&self, from: NodeId, to: NodeId) -> Result<Vec<Edge>>;
+    async fn get_edges_between(&self, from: NodeId, to: NodeId, filter: TemporalFilter) -> Result<Vec<Edge>>;
 
     /// Get all edges from a node
-    async fn get_edges_from(&self, from: NodeId) -> Result<Vec<Edge>>;
+    async fn get_edges_from(&self, from: NodeId, filter: TemporalFilter) -> Result<Vec<Edge>>;
 
     /// Get all edges to a node
-    async fn get_edges_to(&self, to: NodeId) -> Result<Vec<Edge>>;
+    async fn get_edges_to(&self, to: NodeId, filter: TemporalFilter) -> Result<Vec<Edge>>;
 
     /// Get a vertex by ID
-    async fn get_vertex(&self, id: &str) -> Result<Option<Node>>;
+    async fn get_vertex(&self, id: &str, filter: TemporalFilter) -> Result<Option<Node>>;
 
     /// Execute a Gremlin query
     async fn execute_gremlin_query(&self, query: &str, params: &[(&str, &dyn ToGValue)]) -> Result<GResultSet>;
 }
 
+/// Graph implementation chosen at runtime from `Config::graph_backend`
+pub enum ConfiguredGraph {
+    /// Amazon Neptune over Gremlin
+    Neptune(NeptuneGraph),
+    /// In-process graph for tests and local development
+    InMemory(InMemoryGraph),
+}
+
+/// Forward a `Graph` call to whichever backend is configured
+macro_rules! dispatch {
+    ($self:ident, $graph:ident => $call:expr) => {
+        match $self {
+            ConfiguredGraph::Neptune($graph) => $call,
+            ConfiguredGraph::InMemory($graph) => $call,
+        }
+    };
+}
+
+#[async_trait]
+impl Graph for ConfiguredGraph {
+    async fn create_node(&self, node: Node) -> Result<NodeId> {
+        dispatch!(self, graph => graph.create_node(node).await)
+    }
+
+    async fn get_node(&self, id: NodeId, filter: TemporalFilter) -> Result<Node> {
+        dispatch!(self, graph => graph.get_node(id, filter).await)
+    }
+
+    async fn get_node_versions(&self, id: NodeId) -> Result<Vec<Node>> {
+        dispatch!(self, graph => graph.get_node_versions(id).await)
+    }
+
+    async fn update_node(&self, node: Node, expected: Option<VersionToken>) -> Result<()> {
+        dispatch!(self, graph => graph.update_node(node, expected).await)
+    }
+
+    async fn delete_node(&self, id: NodeId) -> Result<()> {
+        dispatch!(self, graph => graph.delete_node(id).await)
+    }
+
+    async fn create_edge(&self, edge: Edge) -> Result<EdgeId> {
+        dispatch!(self, graph => graph.create_edge(edge).await)
+    }
+
+    async fn get_edge(&self, id: EdgeId, filter: TemporalFilter) -> Result<Edge> {
+        dispatch!(self, graph => graph.get_edge(id, filter).await)
+    }
+
+    async fn get_edge_versions(&self, id: EdgeId) -> Result<Vec<Edge>> {
+        dispatch!(self, graph => graph.get_edge_versions(id).await)
+    }
+
+    async fn update_edge(&self, edge: Edge, expected: Option<VersionToken>) -> Result<()> {
+        dispatch!(self, graph => graph.update_edge(edge, expected).await)
+    }
+
+    async fn delete_edge(&self, id: EdgeId) -> Result<()> {
+        dispatch!(self, graph => graph.delete_edge(id).await)
+    }
+
+    async fn get_edges_for_node(
+        &self,
+        node_id: NodeId,
+        filter: TemporalFilter,
+    ) -> Result<Vec<Edge>> {
+        dispatch!(self, graph => graph.get_edges_for_node(node_id, filter).await)
+    }
+
+    async fn get_connected_nodes(
+        &self,
+        node_id: NodeId,
+        filter: TemporalFilter,
+    ) -> Result<Vec<Node>> {
+        dispatch!(self, graph => graph.get_connected_nodes(node_id, filter).await)
+    }
+
+    async fn execute_query<T>(&self, query: &str, params: &[(&str, &dyn ToGValue)]) -> Result<T>
+    where
+        T: FromLocalResultSet,
+    {
+        dispatch!(self, graph => graph.execute_query::<T>(query, params).await)
+    }
+
+    async fn execute_query_with_retry<T>(&self, query: &str, params: &[(&str, &dyn ToGValue)]) -> Result<T>
+    where
+        T: FromLocalResultSet,
+    {
+        dispatch!(self, graph => graph.execute_query_with_retry::<T>(query, params).await)
+    }
+
+    async fn get_nodes_by_label(&self, label: &str, filter: TemporalFilter) -> Result<Vec<Node>> {
+        dispatch!(self, graph => graph.get_nodes_by_label(label, filter).await)
+    }
+
+    async fn get_edges_by_label(&self, label: &str, filter: TemporalFilter) -> Result<Vec<Edge>> {
+        dispatch!(self, graph => graph.get_edges_by_label(label, filter).await)
+    }
+
+    async fn get_edges_between(&self, from: NodeId, to: NodeId, filter: TemporalFilter) -> Result<Vec<Edge>> {
+        dispatch!(self, graph => graph.get_edges_between(from, to, filter).await)
+    }
+
+    async fn get_edges_from(&self, from: NodeId, filter: TemporalFilter) -> Result<Vec<Edge>> {
+        dispatch!(self, graph => graph.get_edges_from(from, filter).await)
+    }
+
+    async fn get_edges_to(&self, to: NodeId, filter: TemporalFilter) -> Result<Vec<Edge>> {
+        dispatch!(self, graph => graph.get_edges_to(to, filter).await)
+    }
+
+    async fn get_vertex(&self, id: &str, filter: TemporalFilter) -> Result<Option<Node>> {
+        dispatch!(self, graph => graph.get_vertex(id, filter).await)
+    }
+
+    async fn execute_gremlin_query(&self, query: &str, params: &[(&str, &dyn ToGValue)]) -> Result<GResultSet> {
+        dispatch!(self, graph => graph.execute_gremlin_query(query, params).await)
+    }
+}
+
 /// Factory function to create a new graph instance
+///
+/// The backend is selected by `config.graph_backend`.
 pub async fn new_graph(config: &crate::Config) -> Result<impl Graph> {
-    neptune::NeptuneGraph::new(config).await
+    match config.graph_backend {
+        GraphBackend::Neptune => Ok(ConfiguredGraph::Neptune(NeptuneGraph::new(config).await?)),
+        GraphBackend::InMemory => {
+            info!("Using in-memory graph backend");
+            Ok(ConfiguredGraph::InMemory(InMemoryGraph::new()))
+        }
+    }
 }
 
 #[cfg(test)]
@@ -141,4 +308,28 @@ mod tests {
             },
         }
     }
-} 
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_new_graph_in_memory_backend() {
+        let config = crate::Config {
+            graph_backend: GraphBackend::InMemory,
+            ..crate::Config::for_testing()
+        };
+        let graph = new_graph(&config).await.unwrap();
+
+        let source = create_test_node();
+        let target = create_test_node();
+        graph.create_node(source.clone()).await.unwrap();
+        graph.create_node(target.clone()).await.unwrap();
+
+        let mut edge = create_test_edge();
+        edge.source_id = source.id;
+        edge.target_id = target.id;
+        graph.create_edge(edge.clone()).await.unwrap();
+
+        let connected = graph.get_connected_nodes(source.id, TemporalFilter::current()).await.unwrap();
+        assert_eq!(connected.len(), 1);
+        assert_eq!(connected[0].id, target.id);
+        assert_eq!(graph.get_edges_between(source.id, target.id, TemporalFilter::current()).await.unwrap().len(), 1);
+    }
+}
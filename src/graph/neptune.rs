@@ -1,26 +1,61 @@
 use aws_sdk_neptune::Client as NeptuneClient;
 use async_trait::async_trait;
 use backoff::{ExponentialBackoff, future::retry};
-use gremlin_client::{GremlinClient, ConnectionOptions, TlsOptions, Vertex, Edge as GremlinEdge, GValue, ToGValue, GResultSet};
+use gremlin_client::{GremlinClient, ConnectionOptions, TlsOptions, GValue, ToGValue, GResultSet};
 use std::sync::Arc;
 use std::time::Duration;
 use tracing::{debug, error, info, warn};
 use chrono::{DateTime, Utc};
-use std::collections::HashMap;
 use futures::TryStreamExt;
 use serde_json::json;
-use uuid::Uuid;
-use std::str::FromStr;
 use gremlin_client::GremlinError;
 use tokio::time::sleep;
 
 use crate::{
     error::{Error, Result},
-    types::{Node, Edge, NodeId, EdgeId, TemporalRange, Properties, EntityType, LocalResultSet, FromLocalResultSet, Timestamp, GID},
+    types::{to_millis, Node, Edge, NodeId, EdgeId, TemporalRange, TemporalFilter, Properties, EntityType, LocalResultSet, FromLocalResultSet, Timestamp, VersionToken, GID},
     config::Config,
+    telemetry::timed,
 };
 
-use super::{Graph, query};
+use super::{Graph, query, traversal::Traversal};
+
+/// Backend label on the metrics recorded by `NeptuneGraph`
+const BACKEND: &str = "neptune";
+
+/// Fail unless `current` is the `expected` version token
+fn check_version(id: String, expected: Option<VersionToken>, current: Option<VersionToken>) -> Result<()> {
+    match (expected, current) {
+        (Some(expected), Some(current)) if expected != current => {
+            Err(Error::VersionConflict { id, expected, current })
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Guarded writes an update attempts before giving up on concurrent writers
+const UPDATE_ATTEMPTS: usize = 3;
+
+/// Error for an update whose guarded write lost every attempt to other writers
+fn contended(id: String) -> Error {
+    Error::NeptuneTransaction(format!(
+        "{id} was superseded by concurrent writes on {UPDATE_ATTEMPTS} attempts"
+    ))
+}
+
+/// Transaction time for the version replacing one recorded at `current`
+///
+/// Times are stored to the millisecond, so a write landing in the same
+/// millisecond is moved past it to keep version tokens distinct.
+fn successor_time(current: &TemporalRange) -> Timestamp {
+    let now = Timestamp::now();
+    match current.start {
+        Some(start) if to_millis(now) <= to_millis(start) => {
+            Timestamp(start.0 + chrono::Duration::milliseconds(1))
+        }
+        _ => now,
+    }
+}
 
 /// Neptune implementation of the Graph trait
 pub struct NeptuneGraph {
@@ -49,6 +84,25 @@ impl NeptuneGraph {
         })
     }
 
+    /// Execute a traversal built by the `query` module with its bound parameters
+    async fn execute_traversal<T>(&self, traversal: &Traversal) -> Result<T>
+    where
+        T: FromLocalResultSet,
+    {
+        debug!("Executing traversal: {}", traversal.script());
+        self.execute_query::<T>(traversal.script(), &traversal.params()).await
+    }
+
+    /// Add archived edge versions to live results when reading as of a past transaction time
+    ///
+    /// Current reads never match archived versions, so the extra query is skipped.
+    async fn with_archived_edges(&self, mut edges: Vec<Edge>, archived: Traversal, filter: &TemporalFilter) -> Result<Vec<Edge>> {
+        if filter.as_of_transaction.is_some() {
+            edges.extend(self.execute_traversal::<Vec<Edge>>(&archived).await?);
+        }
+        Ok(edges)
+    }
+
     /// Helper to validate temporal range
     fn validate_temporal_range(&self, range: &TemporalRange) -> Result<()> {
         if let (Some(start), Some(end)) = (range.start, range.end) {
@@ -60,156 +114,6 @@ impl NeptuneGraph {
         }
         Ok(())
     }
-
-    /// Safely converts a gremlin_client::GID to a String
-    fn gid_to_string(id: &gremlin_client::GID) -> Result<String> {
-        match id {
-            gremlin_client::GID::String(s) => Ok(s.clone()),
-            gremlin_client::GID::Int32(i) => Ok(i.to_string()),
-            gremlin_client::GID::Int64(i) => Ok(i.to_string()),
-            _ => Err(Error::Neptune("Unsupported GID format".to_string())),
-        }
-    }
-
-    /// Parse a vertex into a Node
-    fn parse_vertex(&self, vertex: &Vertex) -> Result<Node> {
-        // Extract ID safely
-        let id_str = Self::gid_to_string(vertex.id())?;
-        let node_id = NodeId(Uuid::from_str(&id_str)?);
-        
-        // Extract properties
-        let mut properties = HashMap::new();
-        
-        // Iterate through all property keys and values
-        for (key, vertex_properties) in vertex.iter() {
-            if !vertex_properties.is_empty() {
-                // Take the first property value for each key
-                let property = &vertex_properties[0];
-                properties.insert(key.clone(), Self::convert_gvalue_to_json(property.value()));
-            }
-        }
-        
-        let label = vertex.label().to_string();
-        
-        // Determine entity type from properties
-        let entity_type = if let Some(value) = properties.get("entity_type") {
-            let entity_type_str = value.as_str()
-                .ok_or_else(|| Error::Neptune("Invalid entity_type format".to_string()))?;
-            match entity_type_str {
-                "Person" => EntityType::Person,
-                "Organization" => EntityType::Organization,
-                "Location" => EntityType::Location,
-                "Event" => EntityType::Event,
-                "Topic" => EntityType::Topic,
-                "Document" => EntityType::Document,
-                "Vertex" => EntityType::Vertex,
-                other => EntityType::Custom(other.to_string()),
-            }
-        } else {
-            EntityType::Vertex
-        };
-        
-        // Extract temporal range properties
-        let valid_time = vertex.property("valid_time")
-            .map(|prop| Self::convert_gvalue_to_json(&prop.value()))
-            .ok_or_else(|| Error::Neptune("Missing valid_time property".to_string()))?;
-        let valid_time: TemporalRange = serde_json::from_value(valid_time)?;
-        
-        let transaction_time = vertex.property("transaction_time")
-            .map(|prop| Self::convert_gvalue_to_json(&prop.value()))
-            .ok_or_else(|| Error::Neptune("Missing transaction_time property".to_string()))?;
-        let transaction_time: TemporalRange = serde_json::from_value(transaction_time)?;
-        
-        Ok(Node {
-            id: node_id,
-            entity_type,
-            label,
-            properties: Properties(properties),
-            valid_time,
-            transaction_time,
-        })
-    }
-
-    /// Convert a GValue to a serde_json::Value
-    fn convert_gvalue_to_json(value: &GValue) -> serde_json::Value {
-        match value {
-            GValue::String(s) => serde_json::Value::String(s.clone()),
-            GValue::Int32(i) => serde_json::Value::Number(serde_json::Number::from(*i as i64)),
-            GValue::Int64(i) => serde_json::Value::Number(serde_json::Number::from(*i)),
-            GValue::Float(f) => {
-                if let Some(n) = serde_json::Number::from_f64(*f as f64) {
-                    serde_json::Value::Number(n)
-                } else {
-                    serde_json::Value::Null
-                }
-            },
-            GValue::Double(d) => {
-                if let Some(n) = serde_json::Number::from_f64(*d) {
-                    serde_json::Value::Number(n)
-                } else {
-                    serde_json::Value::Null
-                }
-            },
-            GValue::List(list) => serde_json::Value::Array(
-                list.iter().map(|v| Self::convert_gvalue_to_json(v)).collect()
-            ),
-            GValue::Map(_) => serde_json::Value::Object(serde_json::Map::new()), // Simplified for now
-            _ => serde_json::Value::Null,
-        }
-    }
-
-    /// Parse a Gremlin edge into an Edge
-    fn parse_edge(&self, edge: &GremlinEdge) -> Result<Edge> {
-        // Extract ID safely
-        let id_str = Self::gid_to_string(edge.id())?;
-        let edge_id = EdgeId(Uuid::from_str(&id_str)?);
-        
-        // Extract source and target IDs
-        let source_id_str = Self::gid_to_string(edge.out_v().id())?;
-        let target_id_str = Self::gid_to_string(edge.in_v().id())?;
-        
-        let source_id = NodeId(Uuid::from_str(&source_id_str)?);
-        let target_id = NodeId(Uuid::from_str(&target_id_str)?);
-        
-        // Extract properties
-        let mut properties = HashMap::new();
-        
-        // Iterate through all properties
-        for (key, property) in edge.iter() {
-            properties.insert(key.clone(), Self::convert_gvalue_to_json(property.value()));
-        }
-        
-        // Extract valid_time and transaction_time separately for special handling
-        let valid_time = if let Some(valid_time_prop) = edge.property("valid_time") {
-            let valid_time_value = Self::convert_gvalue_to_json(valid_time_prop.value());
-            let valid_time_str = valid_time_value.as_str()
-                .ok_or_else(|| Error::Neptune("Invalid valid_time format".to_string()))?;
-            serde_json::from_str::<TemporalRange>(valid_time_str)?
-        } else {
-            // Default to unbounded range if not specified
-            TemporalRange::unbounded()
-        };
-        
-        let transaction_time = if let Some(tx_time_prop) = edge.property("transaction_time") {
-            let tx_time_value = Self::convert_gvalue_to_json(tx_time_prop.value());
-            let tx_time_str = tx_time_value.as_str()
-                .ok_or_else(|| Error::Neptune("Invalid transaction_time format".to_string()))?;
-            serde_json::from_str::<TemporalRange>(tx_time_str)?
-        } else {
-            // Default to unbounded range if not specified
-            TemporalRange::unbounded()
-        };
-        
-        Ok(Edge {
-            id: edge_id,
-            source_id,
-            target_id,
-            label: edge.label().to_string(),
-            properties: Properties(properties),
-            valid_time,
-            transaction_time,
-        })
-    }
 }
 
 impl ToGValue for NodeId {
@@ -255,206 +159,250 @@ impl ToGValue for TemporalRange {
 
 #[async_trait]
 impl Graph for NeptuneGraph {
-    async fn create_node(&self, node: Node) -> Result<NodeId> {
-        self.validate_temporal_range(&node.valid_time)?;
-        self.validate_temporal_range(&node.transaction_time)?;
-
-        let query = query::create_node(&node);
-        let params: Vec<(&str, &dyn ToGValue)> = vec![
-            ("id", &node.id),
-            ("entity_type", &node.entity_type),
-            ("label", &node.label),
-            ("properties", &node.properties),
-            ("valid_time", &node.valid_time),
-            ("transaction_time", &node.transaction_time),
-        ];
+    async fn create_node(&self, mut node: Node) -> Result<NodeId> {
+        timed(BACKEND, "create_node", async {
+            self.validate_temporal_range(&node.valid_time)?;
 
-        self.execute_query::<NodeId>(&query, &params).await
+            node.transaction_time = TemporalRange::from_now();
+            self.execute_traversal::<NodeId>(&query::create_node(&node)).await
+        }).await
     }
 
-    async fn get_node(&self, id: NodeId) -> Result<Node> {
-        let query = query::get_node(id);
-        let params: Vec<(&str, &dyn ToGValue)> = vec![("id", &id)];
-
-        self.execute_query::<Node>(&query, &params).await
+    async fn get_node(&self, id: NodeId, filter: TemporalFilter) -> Result<Node> {
+        timed(BACKEND, "get_node", async {
+            self.execute_traversal::<Vec<Node>>(&query::get_node(id, &filter)).await?
+                .into_iter()
+                .next()
+                .ok_or_else(|| Error::NodeNotFound(id.to_string()))
+        }).await
     }
 
-    async fn update_node(&self, node: Node) -> Result<()> {
-        self.validate_temporal_range(&node.valid_time)?;
-        self.validate_temporal_range(&node.transaction_time)?;
-
-        let query = query::update_node(&node);
-        let params: Vec<(&str, &dyn ToGValue)> = vec![
-            ("id", &node.id),
-            ("entity_type", &node.entity_type),
-            ("label", &node.label),
-            ("properties", &node.properties),
-            ("valid_time", &node.valid_time),
-            ("transaction_time", &node.transaction_time),
-        ];
+    async fn get_node_versions(&self, id: NodeId) -> Result<Vec<Node>> {
+        timed(BACKEND, "get_node_versions", async {
+            let mut versions = self.execute_traversal::<Vec<Node>>(&query::get_node_history(id)).await?;
+            if versions.is_empty() {
+                return Err(Error::NodeNotFound(id.to_string()));
+            }
+            versions.sort_by_key(|node| node.transaction_time.start.map(to_millis));
+            Ok(versions)
+        }).await
+    }
+
+    async fn update_node(&self, mut node: Node, expected: Option<VersionToken>) -> Result<()> {
+        timed(BACKEND, "update_node", async {
+            self.validate_temporal_range(&node.valid_time)?;
+
+            for _ in 0..UPDATE_ATTEMPTS {
+                let mut previous = self.get_node(node.id, TemporalFilter::current()).await?;
+                check_version(node.id.to_string(), expected, previous.version())?;
+
+                let now = successor_time(&previous.transaction_time);
+                node.transaction_time = TemporalRange::new(Some(now), None);
+                let updated = self.execute_traversal::<Vec<Node>>(&query::update_node(&node, &previous.transaction_time)).await?;
+                if updated.is_empty() {
+                    // Superseded between the read and the guarded write: re-read,
+                    // which fails the version check when a token was expected
+                    continue;
+                }
 
-        self.execute_query::<()>(&query, &params).await
+                previous.transaction_time.end = Some(now);
+                return self.execute_traversal::<()>(&query::archive_node(&previous)).await;
+            }
+            Err(contended(node.id.to_string()))
+        }).await
     }
 
     async fn delete_node(&self, id: NodeId) -> Result<()> {
-        let query = query::delete_node(id);
-        let params: Vec<(&str, &dyn ToGValue)> = vec![("id", &id)];
+        timed(BACKEND, "delete_node", async {
+            // Ensure the node is current so deletes of unknown IDs are reported
+            self.get_node(id, TemporalFilter::current()).await?;
 
-        self.execute_query::<()>(&query, &params).await
+            let now = Timestamp::now();
+            self.execute_traversal::<()>(&query::delete_edges_for_node(id, now)).await?;
+            self.execute_traversal::<()>(&query::delete_node(id, now)).await
+        }).await
     }
 
-    async fn create_edge(&self, edge: Edge) -> Result<EdgeId> {
-        self.validate_temporal_range(&edge.valid_time)?;
-        self.validate_temporal_range(&edge.transaction_time)?;
-
-        let query = query::create_edge(&edge);
-        let params: Vec<(&str, &dyn ToGValue)> = vec![
-            ("id", &edge.id),
-            ("source_id", &edge.source_id),
-            ("target_id", &edge.target_id),
-            ("label", &edge.label),
-            ("properties", &edge.properties),
-            ("valid_time", &edge.valid_time),
-            ("transaction_time", &edge.transaction_time),
-        ];
+    async fn create_edge(&self, mut edge: Edge) -> Result<EdgeId> {
+        timed(BACKEND, "create_edge", async {
+            self.validate_temporal_range(&edge.valid_time)?;
 
-        self.execute_query::<EdgeId>(&query, &params).await
+            edge.transaction_time = TemporalRange::from_now();
+            self.execute_traversal::<EdgeId>(&query::create_edge(&edge)).await
+        }).await
     }
 
-    async fn get_edge(&self, id: EdgeId) -> Result<Edge> {
-        let query = query::get_edge(id);
-        let params: Vec<(&str, &dyn ToGValue)> = vec![("id", &id)];
-
-        self.execute_query::<Edge>(&query, &params).await
+    async fn get_edge(&self, id: EdgeId, filter: TemporalFilter) -> Result<Edge> {
+        timed(BACKEND, "get_edge", async {
+            let live = self.execute_traversal::<Vec<Edge>>(&query::get_edge(id, &filter)).await?;
+            let mut edges = self.with_archived_edges(live, query::get_edge_versions(id, &filter), &filter).await?;
+            if edges.is_empty() {
+                return Err(Error::EdgeNotFound(id.to_string()));
+            }
+            Ok(edges.remove(0))
+        }).await
     }
 
-    async fn update_edge(&self, edge: Edge) -> Result<()> {
-        self.validate_temporal_range(&edge.valid_time)?;
-        self.validate_temporal_range(&edge.transaction_time)?;
-
-        let query = query::update_edge(&edge);
-        let params: Vec<(&str, &dyn ToGValue)> = vec![
-            ("id", &edge.id),
-            ("source_id", &edge.source_id),
-            ("target_id", &edge.target_id),
-            ("label", &edge.label),
-            ("properties", &edge.properties),
-            ("valid_time", &edge.valid_time),
-            ("transaction_time", &edge.transaction_time),
-        ];
+    async fn get_edge_versions(&self, id: EdgeId) -> Result<Vec<Edge>> {
+        timed(BACKEND, "get_edge_versions", async {
+            let mut versions = self.execute_traversal::<Vec<Edge>>(&query::get_archived_edge_history(id)).await?;
+            versions.extend(self.execute_traversal::<Vec<Edge>>(&query::get_edge_history(id)).await?);
+            if versions.is_empty() {
+                return Err(Error::EdgeNotFound(id.to_string()));
+            }
+            versions.sort_by_key(|edge| edge.transaction_time.start.map(to_millis));
+            Ok(versions)
+        }).await
+    }
+
+    async fn update_edge(&self, mut edge: Edge, expected: Option<VersionToken>) -> Result<()> {
+        timed(BACKEND, "update_edge", async {
+            self.validate_temporal_range(&edge.valid_time)?;
+
+            for _ in 0..UPDATE_ATTEMPTS {
+                let mut previous = self.get_edge(edge.id, TemporalFilter::current()).await?;
+                check_version(edge.id.to_string(), expected, previous.version())?;
+
+                let now = successor_time(&previous.transaction_time);
+                edge.transaction_time = TemporalRange::new(Some(now), None);
+                let updated = self.execute_traversal::<Vec<Edge>>(&query::update_edge(&edge, &previous.transaction_time)).await?;
+                if updated.is_empty() {
+                    // Superseded between the read and the guarded write: re-read,
+                    // which fails the version check when a token was expected
+                    continue;
+                }
 
-        self.execute_query::<()>(&query, &params).await
+                previous.transaction_time.end = Some(now);
+                return self.execute_traversal::<()>(&query::archive_edge(&previous)).await;
+            }
+            Err(contended(edge.id.to_string()))
+        }).await
     }
 
     async fn delete_edge(&self, id: EdgeId) -> Result<()> {
-        let query = query::delete_edge(id);
-        let params: Vec<(&str, &dyn ToGValue)> = vec![("id", &id)];
-
-        self.execute_query::<()>(&query, &params).await
+        timed(BACKEND, "delete_edge", async {
+            self.get_edge(id, TemporalFilter::current()).await?;
+            self.execute_traversal::<()>(&query::delete_edge(id, Timestamp::now())).await
+        }).await
     }
 
-    async fn get_edges_for_node(&self, node_id: NodeId, temporal_range: Option<TemporalRange>) -> Result<Vec<Edge>> {
-        let query = query::get_edges_for_node(node_id, temporal_range);
-        let params: Vec<(&str, &dyn ToGValue)> = vec![];
-        self.execute_query::<Vec<Edge>>(&query, &params).await
+    async fn get_edges_for_node(&self, node_id: NodeId, filter: TemporalFilter) -> Result<Vec<Edge>> {
+        timed(BACKEND, "get_edges_for_node", async {
+            let live = self.execute_traversal::<Vec<Edge>>(&query::get_edges_for_node(node_id, &filter)).await?;
+            self.with_archived_edges(live, query::get_edge_versions_for_node(node_id, &filter), &filter).await
+        }).await
     }
 
-    async fn get_connected_nodes(&self, node_id: NodeId, temporal_range: Option<TemporalRange>) -> Result<Vec<Node>> {
-        let query = query::get_connected_nodes(node_id, temporal_range);
-        let params: Vec<(&str, &dyn ToGValue)> = vec![];
-        self.execute_query::<Vec<Node>>(&query, &params).await
-    }
+    async fn get_connected_nodes(&self, node_id: NodeId, filter: TemporalFilter) -> Result<Vec<Node>> {
+        timed(BACKEND, "get_connected_nodes", async {
+            if filter.as_of_transaction.is_none() {
+                return self.execute_traversal::<Vec<Node>>(&query::get_connected_nodes(node_id, &filter)).await;
+            }
 
-    async fn get_nodes_by_label(&self, label: &str) -> Result<Vec<Node>> {
-        let query = query::get_nodes_by_label(label);
-        let params: Vec<(&str, &dyn ToGValue)> = vec![("label", &label)];
+            // Archived versions are not linked in the graph, so resolve neighbours by ID
+            let edges = self.get_edges_for_node(node_id, filter.clone()).await?;
+            let mut neighbor_ids: Vec<NodeId> = edges.iter()
+                .map(|edge| if edge.source_id == node_id { edge.target_id } else { edge.source_id })
+                .collect();
+            neighbor_ids.sort_by_key(|id| id.0);
+            neighbor_ids.dedup();
+            if neighbor_ids.is_empty() {
+                return Ok(Vec::new());
+            }
 
-        self.execute_query::<Vec<Node>>(&query, &params).await
+            self.execute_traversal::<Vec<Node>>(&query::get_nodes(&neighbor_ids, &filter)).await
+        }).await
     }
 
-    async fn get_edges_by_label(&self, label: &str) -> Result<Vec<Edge>> {
-        let query = query::get_edges_by_label(label);
-        let params: Vec<(&str, &dyn ToGValue)> = vec![("label", &label)];
-
-        self.execute_query::<Vec<Edge>>(&query, &params).await
+    async fn get_nodes_by_label(&self, label: &str, filter: TemporalFilter) -> Result<Vec<Node>> {
+        timed(BACKEND, "get_nodes_by_label", async {
+            self.execute_traversal::<Vec<Node>>(&query::get_nodes_by_label(label, &filter)).await
+        }).await
     }
 
-    async fn get_edges_between(&self, from: NodeId, to: NodeId) -> Result<Vec<Edge>> {
-        let query = query::get_edges_between(&from, &to);
-        let params: Vec<(&str, &dyn ToGValue)> = vec![
-            ("from", &from),
-            ("to", &to),
-        ];
-
-        self.execute_query::<Vec<Edge>>(&query, &params).await
+    async fn get_edges_by_label(&self, label: &str, filter: TemporalFilter) -> Result<Vec<Edge>> {
+        timed(BACKEND, "get_edges_by_label", async {
+            let live = self.execute_traversal::<Vec<Edge>>(&query::get_edges_by_label(label, &filter)).await?;
+            self.with_archived_edges(live, query::get_edge_versions_by_label(label, &filter), &filter).await
+        }).await
     }
 
-    async fn get_edges_from(&self, from: NodeId) -> Result<Vec<Edge>> {
-        let query = query::get_edges_from(&from);
-        let params: Vec<(&str, &dyn ToGValue)> = vec![("from", &from)];
-
-        self.execute_query::<Vec<Edge>>(&query, &params).await
+    async fn get_edges_between(&self, from: NodeId, to: NodeId, filter: TemporalFilter) -> Result<Vec<Edge>> {
+        timed(BACKEND, "get_edges_between", async {
+            let live = self.execute_traversal::<Vec<Edge>>(&query::get_edges_between(&from, &to, &filter)).await?;
+            self.with_archived_edges(live, query::get_edge_versions_between(&from, &to, &filter), &filter).await
+        }).await
     }
 
-    async fn get_edges_to(&self, to: NodeId) -> Result<Vec<Edge>> {
-        let query = query::get_edges_to(&to);
-        let params: Vec<(&str, &dyn ToGValue)> = vec![("to", &to)];
-
-        self.execute_query::<Vec<Edge>>(&query, &params).await
+    async fn get_edges_from(&self, from: NodeId, filter: TemporalFilter) -> Result<Vec<Edge>> {
+        timed(BACKEND, "get_edges_from", async {
+            let live = self.execute_traversal::<Vec<Edge>>(&query::get_edges_from(&from, &filter)).await?;
+            self.with_archived_edges(live, query::get_edge_versions_from(&from, &filter), &filter).await
+        }).await
     }
 
-    async fn get_vertex(&self, id: &str) -> Result<Option<Node>> {
-        let query = query::get_vertex(id);
-        let params: Vec<(&str, &dyn ToGValue)> = vec![("id", &id)];
+    async fn get_edges_to(&self, to: NodeId, filter: TemporalFilter) -> Result<Vec<Edge>> {
+        timed(BACKEND, "get_edges_to", async {
+            let live = self.execute_traversal::<Vec<Edge>>(&query::get_edges_to(&to, &filter)).await?;
+            self.with_archived_edges(live, query::get_edge_versions_to(&to, &filter), &filter).await
+        }).await
+    }
 
-        self.execute_query::<Option<Node>>(&query, &params).await
+    async fn get_vertex(&self, id: &str, filter: TemporalFilter) -> Result<Option<Node>> {
+        timed(BACKEND, "get_vertex", async {
+            self.execute_traversal::<Option<Node>>(&query::get_vertex(id, &filter)).await
+        }).await
     }
 
     async fn execute_gremlin_query(&self, query: &str, params: &[(&str, &dyn ToGValue)]) -> Result<GResultSet> {
-        match self.client.execute(query, params) {
-            Ok(result) => Ok(result),
-            Err(err) => Err(Error::Neptune(format!("Failed to execute Gremlin query: {}", err))),
-        }
+        timed(BACKEND, "execute_gremlin_query", async {
+            match self.client.execute(query, params) {
+                Ok(result) => Ok(result),
+                Err(err) => Err(Error::Neptune(format!("Failed to execute Gremlin query: {}", err))),
+            }
+        }).await
     }
 
     async fn execute_query_with_retry<T>(&self, query: &str, params: &[(&str, &dyn ToGValue)]) -> Result<T>
     where
         T: FromLocalResultSet,
     {
-        use backoff::{backoff::Backoff, ExponentialBackoff};
+        timed(BACKEND, "execute_query_with_retry", async {
+            use backoff::{backoff::Backoff, ExponentialBackoff};
         
-        let max_retries = 3;
-        let mut backoff = ExponentialBackoff::default();
+            let max_retries = 3;
+            let mut backoff = ExponentialBackoff::default();
         
-        for _ in 0..max_retries {
-            match self.client.execute(query, params) {
-                Ok(result_set) => {
-                    // Extract the results from GResultSet and convert to Vec<GValue>
-                    let mut results: Vec<GValue> = Vec::new();
-                    for result in result_set {
-                        match result {
-                            Ok(value) => results.push(value),
-                            Err(e) => log::warn!("Error processing result: {}", e),
+            for _ in 0..max_retries {
+                match self.client.execute(query, params) {
+                    Ok(result_set) => {
+                        // Extract the results from GResultSet and convert to Vec<GValue>
+                        let mut results: Vec<GValue> = Vec::new();
+                        for result in result_set {
+                            match result {
+                                Ok(value) => results.push(value),
+                                Err(e) => log::warn!("Error processing result: {}", e),
+                            }
                         }
-                    }
                     
-                    // Create LocalResultSet from the results
-                    let local_result_set = LocalResultSet(results);
+                        // Create LocalResultSet from the results
+                        let local_result_set = LocalResultSet(results);
                     
-                    return T::from_local_result_set(local_result_set);
-                }
-                Err(e) => {
-                    if let Some(duration) = backoff.next_backoff() {
-                        warn!("Query execution failed, retrying in {:?}: {}", duration, e);
-                        tokio::time::sleep(duration).await;
-                    } else {
-                        return Err(Error::Neptune(format!("Query execution failed after retries: {}", e)));
+                        return T::from_local_result_set(local_result_set);
+                    }
+                    Err(e) => {
+                        if let Some(duration) = backoff.next_backoff() {
+                            warn!("Query execution failed, retrying in {:?}: {}", duration, e);
+                            tokio::time::sleep(duration).await;
+                        } else {
+                            return Err(Error::Neptune(format!("Query execution failed after retries: {}", e)));
+                        }
                     }
                 }
             }
-        }
         
-        Err(Error::Neptune("Maximum number of retries reached".to_string()))
+            Err(Error::Neptune("Maximum number of retries reached".to_string()))
+        }).await
     }
 
     async fn execute_query<T>(&self, query: &str, params: &[(&str, &dyn ToGValue)]) -> Result<T>
@@ -549,7 +497,8 @@ mod tests {
         };
 
         // Verify that the query building is correct
-        let query = query::create_node(&test_node);
+        let traversal = query::create_node(&test_node);
+        let query = traversal.script();
         assert!(query.contains("addV"));
         assert!(query.contains("property('id'"));
         
@@ -570,7 +519,8 @@ mod tests {
             },
         };
         
-        let edge_query = query::create_edge(&edge);
+        let edge_traversal = query::create_edge(&edge);
+        let edge_query = edge_traversal.script();
         assert!(edge_query.contains("addE"));
         assert!(edge_query.contains("property('id'"));
     }
@@ -1,176 +1,328 @@
-use crate::types::{Node, Edge, NodeId, EdgeId, TemporalRange};
-use serde_json::json;
+//! Gremlin traversals used by the Neptune backend
+//!
+//! Nodes and edges are persisted bi-temporally. Valid time and transaction
+//! time are flattened into `<dimension>.start` / `<dimension>.end` properties
+//! holding epoch milliseconds, with open bounds stored as `i64::MIN` /
+//! `i64::MAX` so range predicates work without null checks.
+//!
+//! Live elements hold the current version of each node and edge. When an
+//! element is updated its previous state is archived as a separate vertex
+//! with a closed transaction-time range; deletes close the live element's
+//! transaction time instead of dropping it. Reads filter on transaction time
+//! so that only current versions are visible unless an as-of time is given.
+
+use gremlin_client::GValue;
+
+use crate::types::{keys, to_millis, Node, Edge, NodeId, EdgeId, TemporalFilter, TemporalRange, Timestamp, OPEN_END, OPEN_START};
+
+use super::traversal::{Predicate, Traversal};
+
+fn start_millis(range: &TemporalRange) -> i64 {
+    range.start.map_or(OPEN_START, to_millis)
+}
+
+fn end_millis(range: &TemporalRange) -> i64 {
+    range.end.map_or(OPEN_END, to_millis)
+}
+
+/// Serialize a value for storage as a single string property
+fn json_property<T: serde::Serialize>(value: &T) -> String {
+    serde_json::to_string(value).unwrap_or_default()
+}
+
+/// Write the properties shared by live and archived node vertices
+fn node_properties(traversal: Traversal, node: &Node) -> Traversal {
+    traversal
+        .property(keys::ID, &node.id)
+        .property(keys::LABEL, &node.label)
+        .property(keys::ENTITY_TYPE, &node.entity_type)
+        .property(keys::PROPERTIES, &json_property(&node.properties))
+        .property(keys::VALID_START, &start_millis(&node.valid_time))
+        .property(keys::VALID_END, &end_millis(&node.valid_time))
+        .property(keys::TRANSACTION_START, &start_millis(&node.transaction_time))
+        .property(keys::TRANSACTION_END, &end_millis(&node.transaction_time))
+}
+
+/// Write the properties shared by live edges and archived edge versions
+fn edge_properties(traversal: Traversal, edge: &Edge) -> Traversal {
+    traversal
+        .property(keys::ID, &edge.id)
+        .property(keys::PROPERTIES, &json_property(&edge.properties))
+        .property(keys::VALID_START, &start_millis(&edge.valid_time))
+        .property(keys::VALID_END, &end_millis(&edge.valid_time))
+        .property(keys::TRANSACTION_START, &start_millis(&edge.transaction_time))
+        .property(keys::TRANSACTION_END, &end_millis(&edge.transaction_time))
+}
+
+/// Append bi-temporal filters to a traversal over nodes or edges
+pub(crate) fn with_temporal_filter(mut traversal: Traversal, filter: &TemporalFilter) -> Traversal {
+    if let Some(range) = &filter.valid_time {
+        if let Some(end) = range.end {
+            traversal = traversal.has(keys::VALID_START, Predicate::Lte(GValue::Int64(to_millis(end))));
+        }
+        if let Some(start) = range.start {
+            traversal = traversal.has(keys::VALID_END, Predicate::Gte(GValue::Int64(to_millis(start))));
+        }
+    }
+
+    match filter.as_of_transaction {
+        None => traversal.has(keys::TRANSACTION_END, Predicate::Eq(GValue::Int64(OPEN_END))),
+        Some(as_of) => traversal
+            .has(keys::TRANSACTION_START, Predicate::Lte(GValue::Int64(to_millis(as_of))))
+            .has(keys::TRANSACTION_END, Predicate::Gt(GValue::Int64(to_millis(as_of)))),
+    }
+}
 
 /// Build a Gremlin query to create a node
-pub(crate) fn create_node(node: &Node) -> String {
-    let properties = json!({
-        "id": node.id.0.to_string(),
-        "label": node.label,
-        "entity_type": node.entity_type.to_string(),
-        "properties": node.properties,
-        "valid_time": node.valid_time,
-        "transaction_time": node.transaction_time,
-    });
-
-    format!(
-        "g.addV('{}'){}",
-        node.entity_type.to_string(),
-        properties.as_object().unwrap()
-            .iter()
-            .map(|(k, v)| format!(".property('{}', {})", k, v.to_string()))
-            .collect::<Vec<_>>()
-            .join("")
+pub(crate) fn create_node(node: &Node) -> Traversal {
+    node_properties(
+        Traversal::g()
+            .add_v(&node.entity_type.to_string())
+            .property_id(&node.id),
+        node,
     )
 }
 
+/// Build a Gremlin query to archive a superseded node version
+pub(crate) fn archive_node(node: &Node) -> Traversal {
+    node_properties(Traversal::g().add_v(&node.entity_type.to_string()), node)
+}
+
 /// Build a Gremlin query to get a node by ID
-pub(crate) fn get_node(id: NodeId) -> String {
-    format!("g.V('{}').hasLabel('node')", id.0)
+pub(crate) fn get_node(id: NodeId, filter: &TemporalFilter) -> Traversal {
+    with_temporal_filter(
+        Traversal::g().v_all().has(keys::ID, Predicate::Eq(GValue::String(id.0.to_string()))),
+        filter,
+    )
+}
+
+/// Build a Gremlin query to get every stored version of a node
+///
+/// Matches the live vertex and its archived versions, whatever their
+/// transaction time.
+pub(crate) fn get_node_history(id: NodeId) -> Traversal {
+    Traversal::g().v_all().has(keys::ID, Predicate::Eq(GValue::String(id.0.to_string())))
+}
+
+/// Build a Gremlin query to get nodes by logical ID
+pub(crate) fn get_nodes(ids: &[NodeId], filter: &TemporalFilter) -> Traversal {
+    let ids = ids.iter().map(|id| GValue::String(id.0.to_string())).collect();
+    with_temporal_filter(Traversal::g().v_all().has(keys::ID, Predicate::Within(ids)), filter)
 }
 
 /// Build a Gremlin query to update a node
-pub(crate) fn update_node(node: &Node) -> String {
-    let properties = json!({
-        "label": node.label,
-        "properties": node.properties,
-        "valid_time": node.valid_time,
-        "transaction_time": node.transaction_time,
-    });
-
-    format!(
-        "g.V('{}'){}",
-        node.id.0,
-        properties.as_object().unwrap()
-            .iter()
-            .map(|(k, v)| format!(".property('{}', {})", k, v.to_string()))
-            .collect::<Vec<_>>()
-            .join("")
-    )
+///
+/// Only the live version recorded at `current` is written, so the traversal
+/// returns nothing if another write superseded it in the meantime.
+pub(crate) fn update_node(node: &Node, current: &TemporalRange) -> Traversal {
+    Traversal::g()
+        .v(&node.id)
+        .has(keys::TRANSACTION_START, Predicate::Eq(GValue::Int64(start_millis(current))))
+        .has(keys::TRANSACTION_END, Predicate::Eq(GValue::Int64(OPEN_END)))
+        .property_single(keys::LABEL, &node.label)
+        .property_single(keys::ENTITY_TYPE, &node.entity_type)
+        .property_single(keys::PROPERTIES, &json_property(&node.properties))
+        .property_single(keys::VALID_START, &start_millis(&node.valid_time))
+        .property_single(keys::VALID_END, &end_millis(&node.valid_time))
+        .property_single(keys::TRANSACTION_START, &start_millis(&node.transaction_time))
+        .property_single(keys::TRANSACTION_END, &end_millis(&node.transaction_time))
+}
+
+/// Build a Gremlin query to delete a node by closing its transaction time
+pub(crate) fn delete_node(id: NodeId, at: Timestamp) -> Traversal {
+    Traversal::g()
+        .v(&id)
+        .has(keys::TRANSACTION_END, Predicate::Eq(GValue::Int64(OPEN_END)))
+        .property_single(keys::TRANSACTION_END, &to_millis(at))
 }
 
-/// Build a Gremlin query to delete a node
-pub(crate) fn delete_node(id: NodeId) -> String {
-    format!("g.V('{}').drop()", id.0)
+/// Build a Gremlin query to close the transaction time of a node's current edges
+pub(crate) fn delete_edges_for_node(id: NodeId, at: Timestamp) -> Traversal {
+    Traversal::g()
+        .v(&id)
+        .both_e()
+        .has(keys::TRANSACTION_END, Predicate::Eq(GValue::Int64(OPEN_END)))
+        .property(keys::TRANSACTION_END, &to_millis(at))
 }
 
 /// Build a Gremlin query to create an edge
-pub(crate) fn create_edge(edge: &Edge) -> String {
-    let properties = json!({
-        "id": edge.id.0.to_string(),
-        "properties": edge.properties,
-        "valid_time": edge.valid_time,
-        "transaction_time": edge.transaction_time,
-    });
-
-    format!(
-        "g.V('{}').addE('{}').to(V('{}')){}",
-        edge.source_id.0,
-        edge.label,
-        edge.target_id.0,
-        properties.as_object().unwrap()
-            .iter()
-            .map(|(k, v)| format!(".property('{}', {})", k, v.to_string()))
-            .collect::<Vec<_>>()
-            .join("")
+pub(crate) fn create_edge(edge: &Edge) -> Traversal {
+    let target_id = edge.target_id;
+    edge_properties(
+        Traversal::g()
+            .v(&edge.source_id)
+            .add_e(&edge.label)
+            .to(|t| t.v(&target_id))
+            .property_id(&edge.id),
+        edge,
+    )
+}
+
+/// Build a Gremlin query to archive a superseded edge version
+pub(crate) fn archive_edge(edge: &Edge) -> Traversal {
+    edge_properties(
+        Traversal::g()
+            .add_v(keys::EDGE_VERSION_LABEL)
+            .property(keys::SOURCE_ID, &edge.source_id)
+            .property(keys::TARGET_ID, &edge.target_id)
+            .property(keys::EDGE_LABEL, &edge.label),
+        edge,
     )
 }
 
 /// Build a Gremlin query to get an edge by ID
-pub(crate) fn get_edge(id: EdgeId) -> String {
-    format!("g.E('{}').hasLabel('edge')", id.0)
+pub(crate) fn get_edge(id: EdgeId, filter: &TemporalFilter) -> Traversal {
+    with_temporal_filter(Traversal::g().e(&id), filter)
 }
 
 /// Build a Gremlin query to update an edge
-pub(crate) fn update_edge(edge: &Edge) -> String {
-    let properties = json!({
-        "properties": edge.properties,
-        "valid_time": edge.valid_time,
-        "transaction_time": edge.transaction_time,
-    });
-
-    format!(
-        "g.E('{}'){}",
-        edge.id.0,
-        properties.as_object().unwrap()
-            .iter()
-            .map(|(k, v)| format!(".property('{}', {})", k, v.to_string()))
-            .collect::<Vec<_>>()
-            .join("")
+///
+/// Only the live version recorded at `current` is written, so the traversal
+/// returns nothing if another write superseded it in the meantime.
+pub(crate) fn update_edge(edge: &Edge, current: &TemporalRange) -> Traversal {
+    Traversal::g()
+        .e(&edge.id)
+        .has(keys::TRANSACTION_START, Predicate::Eq(GValue::Int64(start_millis(current))))
+        .has(keys::TRANSACTION_END, Predicate::Eq(GValue::Int64(OPEN_END)))
+        .property(keys::PROPERTIES, &json_property(&edge.properties))
+        .property(keys::VALID_START, &start_millis(&edge.valid_time))
+        .property(keys::VALID_END, &end_millis(&edge.valid_time))
+        .property(keys::TRANSACTION_START, &start_millis(&edge.transaction_time))
+        .property(keys::TRANSACTION_END, &end_millis(&edge.transaction_time))
+}
+
+/// Build a Gremlin query to delete an edge by closing its transaction time
+pub(crate) fn delete_edge(id: EdgeId, at: Timestamp) -> Traversal {
+    Traversal::g()
+        .e(&id)
+        .has(keys::TRANSACTION_END, Predicate::Eq(GValue::Int64(OPEN_END)))
+        .property(keys::TRANSACTION_END, &to_millis(at))
+}
+
+/// Start a traversal over archived edge versions
+fn edge_versions() -> Traversal {
+    Traversal::g().v_all().has_label(keys::EDGE_VERSION_LABEL)
+}
+
+/// Build a Gremlin query to get archived versions of an edge
+pub(crate) fn get_edge_versions(id: EdgeId, filter: &TemporalFilter) -> Traversal {
+    with_temporal_filter(
+        edge_versions().has(keys::ID, Predicate::Eq(GValue::String(id.0.to_string()))),
+        filter,
     )
 }
 
-/// Build a Gremlin query to delete an edge
-pub(crate) fn delete_edge(id: EdgeId) -> String {
-    format!("g.E('{}').drop()", id.0)
+/// Build a Gremlin query to get the live version of an edge, even if deleted
+pub(crate) fn get_edge_history(id: EdgeId) -> Traversal {
+    Traversal::g().e(&id)
+}
+
+/// Build a Gremlin query to get every archived version of an edge
+pub(crate) fn get_archived_edge_history(id: EdgeId) -> Traversal {
+    edge_versions().has(keys::ID, Predicate::Eq(GValue::String(id.0.to_string())))
 }
 
 /// Build a Gremlin query to get edges for a node with temporal filtering
-pub(crate) fn get_edges_for_node(node_id: NodeId, temporal_range: Option<TemporalRange>) -> String {
-    let mut query = format!("g.V('{}').bothE()", node_id.0);
-    
-    if let Some(range) = temporal_range {
-        if let Some(start) = range.start {
-            query.push_str(&format!(".has('valid_time.start', gte({}))", start.0.timestamp()));
-        }
-        if let Some(end) = range.end {
-            query.push_str(&format!(".has('valid_time.end', lte({}))", end.0.timestamp()));
-        }
-    }
-    
-    query
+pub(crate) fn get_edges_for_node(node_id: NodeId, filter: &TemporalFilter) -> Traversal {
+    with_temporal_filter(Traversal::g().v(&node_id).both_e(), filter)
+}
+
+/// Build a Gremlin query to get archived edge versions touching a node
+pub(crate) fn get_edge_versions_for_node(node_id: NodeId, filter: &TemporalFilter) -> Traversal {
+    let id = node_id.0.to_string();
+    with_temporal_filter(
+        edge_versions().or(
+            |t| t.has(keys::SOURCE_ID, Predicate::Eq(GValue::String(id.clone()))),
+            |t| t.has(keys::TARGET_ID, Predicate::Eq(GValue::String(id.clone()))),
+        ),
+        filter,
+    )
 }
 
 /// Build a Gremlin query to get connected nodes with temporal filtering
-pub(crate) fn get_connected_nodes(node_id: NodeId, temporal_range: Option<TemporalRange>) -> String {
-    let mut query = format!("g.V('{}').both()", node_id.0);
-    
-    if let Some(range) = temporal_range {
-        if let Some(start) = range.start {
-            query.push_str(&format!(".has('valid_time.start', gte({}))", start.0.timestamp()));
-        }
-        if let Some(end) = range.end {
-            query.push_str(&format!(".has('valid_time.end', lte({}))", end.0.timestamp()));
-        }
-    }
-    
-    query
+///
+/// Both the connecting edges and the neighbours must pass the filter. Only
+/// valid for current reads, since archived versions are not connected.
+pub(crate) fn get_connected_nodes(node_id: NodeId, filter: &TemporalFilter) -> Traversal {
+    let edges = with_temporal_filter(Traversal::g().v(&node_id).both_e(), filter);
+    with_temporal_filter(edges.other_v(), filter).dedup()
 }
 
 /// Build a Gremlin query to get nodes by label
-pub(crate) fn get_nodes_by_label(label: &str) -> String {
-    format!("g.V().hasLabel('{}')", label)
+pub(crate) fn get_nodes_by_label(label: &str, filter: &TemporalFilter) -> Traversal {
+    with_temporal_filter(
+        Traversal::g().v_all().has(keys::LABEL, Predicate::Eq(GValue::String(label.to_string()))),
+        filter,
+    )
 }
 
 /// Build a Gremlin query to get edges by label
-pub(crate) fn get_edges_by_label(label: &str) -> String {
-    format!("g.E().hasLabel('{}')", label)
+pub(crate) fn get_edges_by_label(label: &str, filter: &TemporalFilter) -> Traversal {
+    with_temporal_filter(Traversal::g().e_all().has_label(label), filter)
+}
+
+/// Build a Gremlin query to get archived edge versions by label
+pub(crate) fn get_edge_versions_by_label(label: &str, filter: &TemporalFilter) -> Traversal {
+    with_temporal_filter(
+        edge_versions().has(keys::EDGE_LABEL, Predicate::Eq(GValue::String(label.to_string()))),
+        filter,
+    )
 }
 
 /// Build a Gremlin query to get edges between two nodes
-pub(crate) fn get_edges_between(from: &NodeId, to: &NodeId) -> String {
-    format!("g.V('{}').outE().where(inV().hasId('{}'))", from.0, to.0)
+pub(crate) fn get_edges_between(from: &NodeId, to: &NodeId, filter: &TemporalFilter) -> Traversal {
+    with_temporal_filter(Traversal::g().v(from).out_e().where_(|t| t.in_v().has_id(to)), filter)
+}
+
+/// Build a Gremlin query to get archived edge versions between two nodes
+pub(crate) fn get_edge_versions_between(from: &NodeId, to: &NodeId, filter: &TemporalFilter) -> Traversal {
+    with_temporal_filter(
+        edge_versions()
+            .has(keys::SOURCE_ID, Predicate::Eq(GValue::String(from.0.to_string())))
+            .has(keys::TARGET_ID, Predicate::Eq(GValue::String(to.0.to_string()))),
+        filter,
+    )
 }
 
 /// Build a Gremlin query to get edges from a node
-pub(crate) fn get_edges_from(from: &NodeId) -> String {
-    format!("g.V('{}').outE()", from.0)
+pub(crate) fn get_edges_from(from: &NodeId, filter: &TemporalFilter) -> Traversal {
+    with_temporal_filter(Traversal::g().v(from).out_e(), filter)
+}
+
+/// Build a Gremlin query to get archived edge versions from a node
+pub(crate) fn get_edge_versions_from(from: &NodeId, filter: &TemporalFilter) -> Traversal {
+    with_temporal_filter(
+        edge_versions().has(keys::SOURCE_ID, Predicate::Eq(GValue::String(from.0.to_string()))),
+        filter,
+    )
 }
 
 /// Build a Gremlin query to get edges to a node
-pub(crate) fn get_edges_to(to: &NodeId) -> String {
-    format!("g.V('{}').inE()", to.0)
+pub(crate) fn get_edges_to(to: &NodeId, filter: &TemporalFilter) -> Traversal {
+    with_temporal_filter(Traversal::g().v(to).in_e(), filter)
+}
+
+/// Build a Gremlin query to get archived edge versions to a node
+pub(crate) fn get_edge_versions_to(to: &NodeId, filter: &TemporalFilter) -> Traversal {
+    with_temporal_filter(
+        edge_versions().has(keys::TARGET_ID, Predicate::Eq(GValue::String(to.0.to_string()))),
+        filter,
+    )
 }
 
 /// Build a Gremlin query to get a vertex by ID
-pub(crate) fn get_vertex(id: &str) -> String {
-    format!("g.V('{}')", id)
+pub(crate) fn get_vertex(id: &str, filter: &TemporalFilter) -> Traversal {
+    with_temporal_filter(
+        Traversal::g().v_all().has(keys::ID, Predicate::Eq(GValue::String(id.to_string()))),
+        filter,
+    )
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use chrono::Utc;
-    use crate::types::{EntityType, Properties, Timestamp};
+    use crate::types::{from_millis, EntityType, Properties, Timestamp};
     use std::collections::HashMap;
     use uuid::Uuid;
 
@@ -211,23 +363,78 @@ mod tests {
         }
     }
 
+    /// String values bound by a traversal
+    fn bound_strings(query: &Traversal) -> Vec<String> {
+        query.bindings().iter()
+            .filter_map(|(_, value)| match &value.0 {
+                GValue::String(s) => Some(s.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
     #[test]
     fn test_create_node_query() {
         let node = create_test_node();
         let query = create_node(&node);
-        assert!(query.contains(&node.id.0.to_string()));
-        assert!(query.contains(&node.label));
-        assert!(query.contains("addV"));
+        let bound = bound_strings(&query);
+        assert!(bound.contains(&node.id.0.to_string()));
+        assert!(bound.contains(&node.label));
+        assert!(query.script().contains("addV"));
+        assert!(!query.script().contains(&node.id.0.to_string()));
+    }
+
+    #[test]
+    fn test_update_node_query_writes_entity_type() {
+        let mut node = create_test_node();
+        let current = node.transaction_time.clone();
+        node.entity_type = EntityType::Person;
+
+        let query = update_node(&node, &current);
+        assert!(query.script().contains(&format!("property(single, '{}'", keys::ENTITY_TYPE)));
+        assert!(bound_strings(&query).contains(&"Person".to_string()));
     }
 
     #[test]
     fn test_create_edge_query() {
         let edge = create_test_edge();
         let query = create_edge(&edge);
-        assert!(query.contains(&edge.id.0.to_string()));
-        assert!(query.contains(&edge.source_id.0.to_string()));
-        assert!(query.contains(&edge.target_id.0.to_string()));
-        assert!(query.contains("addE"));
+        let bound = bound_strings(&query);
+        assert!(bound.contains(&edge.id.0.to_string()));
+        assert!(bound.contains(&edge.source_id.0.to_string()));
+        assert!(bound.contains(&edge.target_id.0.to_string()));
+        assert!(query.script().contains("addE"));
+    }
+
+    #[test]
+    fn test_hostile_label_and_properties_are_bound() {
+        let hostile = "O'Brien').drop();g.V().drop() //";
+        let mut node = create_test_node();
+        node.label = hostile.to_string();
+        node.entity_type = EntityType::Custom(hostile.to_string());
+        node.properties.insert(hostile.to_string(), serde_json::Value::String(hostile.to_string()));
+
+        let query = create_node(&node);
+        assert!(!query.script().contains("O'Brien"));
+        assert!(!query.script().contains("drop"));
+        let bound = bound_strings(&query);
+        assert!(bound.iter().any(|s| s == hostile));
+
+        // Properties survive the JSON round trip untouched
+        let stored = bound.iter()
+            .find_map(|s| serde_json::from_str::<Properties>(s).ok().filter(|p| !p.0.is_empty()))
+            .expect("properties binding");
+        assert_eq!(stored.get(hostile), Some(&serde_json::Value::String(hostile.to_string())));
+
+        let filter = TemporalFilter::current();
+        for query in [
+            get_nodes_by_label(hostile, &filter),
+            get_edges_by_label(hostile, &filter),
+            get_vertex(hostile, &filter),
+        ] {
+            assert!(!query.script().contains("O'Brien"));
+            assert_eq!(bound_strings(&query), vec![hostile.to_string()]);
+        }
     }
 
     #[test]
@@ -239,11 +446,97 @@ mod tests {
             end: Some(Timestamp(now + chrono::Duration::hours(1))),
         };
         
-        let query = get_edges_for_node(node_id, Some(range));
-        assert!(query.contains(&node_id.0.to_string()));
-        assert!(query.contains("valid_time.start"));
-        assert!(query.contains("valid_time.end"));
-        assert!(query.contains("gte"));
-        assert!(query.contains("lte"));
-    }
-} 
\ No newline at end of file
+        let query = get_edges_for_node(node_id, &range.into());
+        assert!(bound_strings(&query).contains(&node_id.0.to_string()));
+        assert!(query.script().contains("valid_time.start"));
+        assert!(query.script().contains("valid_time.end"));
+        assert!(query.script().contains("gte"));
+        assert!(query.script().contains("lte"));
+    }
+
+    /// Properties written by `.property('key', pN)` steps, as JSON
+    fn written_properties(query: &Traversal) -> HashMap<String, serde_json::Value> {
+        let re = regex::Regex::new(r"\.property\((?:single, )?'([^']+)', (p\d+)\)").unwrap();
+        re.captures_iter(query.script())
+            .map(|c| {
+                let value = query.binding(&c[2]).cloned().unwrap();
+                (c[1].to_string(), crate::types::LocalGValue(value).into_json())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_node_temporal_properties_round_trip() {
+        let now = Utc::now();
+        let mut node = create_test_node();
+        node.valid_time = TemporalRange::new(None, Some(Timestamp(now)));
+        node.properties.insert("name".to_string(), serde_json::json!("O'Brien"));
+
+        let props = written_properties(&create_node(&node));
+        assert_eq!(props[keys::VALID_START], serde_json::json!(OPEN_START));
+        assert_eq!(props[keys::TRANSACTION_END], serde_json::json!(OPEN_END));
+
+        let parsed = crate::types::node_from_properties("unused", "Event", &props).unwrap();
+        assert_eq!(parsed.id, node.id);
+        assert_eq!(parsed.label, node.label);
+        assert_eq!(parsed.properties, node.properties);
+        assert!(parsed.valid_time.start.is_none());
+        assert_eq!(
+            parsed.valid_time.end.map(|ts| ts.0.timestamp_millis()),
+            Some(now.timestamp_millis())
+        );
+        assert!(parsed.transaction_time.end.is_none());
+    }
+
+    #[test]
+    fn test_archived_edge_round_trip() {
+        let mut edge = create_test_edge();
+        edge.transaction_time.end = Some(Timestamp(Utc::now()));
+
+        let query = archive_edge(&edge);
+        assert!(!query.script().contains("T.id"));
+        let props = written_properties(&query);
+        let parsed = crate::types::edge_from_properties("gid", keys::EDGE_VERSION_LABEL, "gid", "gid", &props).unwrap();
+        assert_eq!(parsed.id, edge.id);
+        assert_eq!(parsed.source_id, edge.source_id);
+        assert_eq!(parsed.target_id, edge.target_id);
+        assert_eq!(parsed.label, edge.label);
+        assert!(parsed.transaction_time.end.is_some());
+    }
+
+    #[test]
+    fn test_transaction_time_filters() {
+        let node_id = NodeId(Uuid::new_v4());
+
+        let current = get_node(node_id, &TemporalFilter::current());
+        assert!(current.script().ends_with(".has('transaction_time.end', eq(p1))"));
+        assert!(matches!(current.binding("p1"), Some(GValue::Int64(OPEN_END))));
+
+        let as_of = Timestamp(Utc::now());
+        let past = get_node(node_id, &TemporalFilter::current().as_of(as_of));
+        assert!(past.script().contains(".has('transaction_time.start', lte(p1))"));
+        assert!(past.script().contains(".has('transaction_time.end', gt(p2))"));
+        assert!(matches!(past.binding("p2"), Some(GValue::Int64(ms)) if *ms == to_millis(as_of)));
+    }
+
+    #[test]
+    fn test_history_queries_ignore_transaction_time() {
+        let node_id = NodeId(Uuid::new_v4());
+        let edge_id = EdgeId(Uuid::new_v4());
+
+        for query in [get_node_history(node_id), get_edge_history(edge_id), get_archived_edge_history(edge_id)] {
+            assert!(!query.script().contains("transaction_time"));
+        }
+        assert!(bound_strings(&get_node_history(node_id)).contains(&node_id.0.to_string()));
+        assert!(bound_strings(&get_archived_edge_history(edge_id)).contains(&edge_id.0.to_string()));
+    }
+
+    #[test]
+    fn test_millis_conversion() {
+        let now = Timestamp(Utc::now());
+        let restored = from_millis(to_millis(now)).unwrap();
+        assert_eq!(restored.0.timestamp_millis(), now.0.timestamp_millis());
+        assert!(from_millis(OPEN_START).is_none());
+        assert!(from_millis(OPEN_END).is_none());
+    }
+}
@@ -0,0 +1,278 @@
+//! Tenant-scoped view of a `Graph`
+//!
+//! `TenantGraph` stamps every node and edge it writes with its tenant in the
+//! `tenant_id` property and treats elements stamped by any other tenant as
+//! missing: reads return `NodeNotFound`/`EdgeNotFound` or leave them out of
+//! listings, and writes cannot touch them. Raw Gremlin cannot be scoped and
+//! is refused.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use gremlin_client::{GResultSet, ToGValue};
+use serde_json::json;
+
+use crate::{
+    error::{Error, Result},
+    types::{Edge, EdgeId, FromLocalResultSet, Node, NodeId, Properties, TemporalFilter, TenantId, VersionToken, TENANT_PROPERTY},
+};
+
+use super::Graph;
+
+/// A `Graph` restricted to the elements of one tenant
+pub struct TenantGraph {
+    inner: Arc<dyn Graph>,
+    tenant: TenantId,
+}
+
+impl TenantGraph {
+    /// Scope `inner` to `tenant`
+    pub fn new(inner: Arc<dyn Graph>, tenant: TenantId) -> Self {
+        Self { inner, tenant }
+    }
+
+    /// The tenant this view is scoped to
+    pub fn tenant(&self) -> &TenantId {
+        &self.tenant
+    }
+
+    fn owns(&self, properties: &Properties) -> bool {
+        self.tenant.owns(properties.get(TENANT_PROPERTY))
+    }
+
+    fn stamp(&self, properties: &mut Properties) {
+        properties.insert(TENANT_PROPERTY.to_string(), json!(self.tenant.as_str()));
+    }
+
+    fn own_nodes(&self, nodes: Vec<Node>) -> Vec<Node> {
+        nodes.into_iter().filter(|node| self.owns(&node.properties)).collect()
+    }
+
+    fn own_edges(&self, edges: Vec<Edge>) -> Vec<Edge> {
+        edges.into_iter().filter(|edge| self.owns(&edge.properties)).collect()
+    }
+
+    /// Fail with `NodeNotFound` unless the current version of `id` is ours
+    async fn check_node(&self, id: NodeId) -> Result<()> {
+        self.get_node(id, TemporalFilter::current()).await.map(|_| ())
+    }
+
+    /// Fail with `EdgeNotFound` unless the current version of `id` is ours
+    async fn check_edge(&self, id: EdgeId) -> Result<()> {
+        self.get_edge(id, TemporalFilter::current()).await.map(|_| ())
+    }
+
+    fn unscoped_query() -> Error {
+        Error::InvalidInput("Raw graph queries cannot be scoped to a tenant".to_string())
+    }
+}
+
+#[async_trait]
+impl Graph for TenantGraph {
+    async fn create_node(&self, mut node: Node) -> Result<NodeId> {
+        self.stamp(&mut node.properties);
+        self.inner.create_node(node).await
+    }
+
+    async fn get_node(&self, id: NodeId, filter: TemporalFilter) -> Result<Node> {
+        let node = self.inner.get_node(id, filter).await?;
+        if self.owns(&node.properties) {
+            Ok(node)
+        } else {
+            Err(Error::NodeNotFound(id.to_string()))
+        }
+    }
+
+    async fn get_node_versions(&self, id: NodeId) -> Result<Vec<Node>> {
+        let versions = self.own_nodes(self.inner.get_node_versions(id).await?);
+        if versions.is_empty() {
+            return Err(Error::NodeNotFound(id.to_string()));
+        }
+        Ok(versions)
+    }
+
+    async fn update_node(&self, mut node: Node, expected: Option<VersionToken>) -> Result<()> {
+        self.check_node(node.id).await?;
+        self.stamp(&mut node.properties);
+        self.inner.update_node(node, expected).await
+    }
+
+    async fn delete_node(&self, id: NodeId) -> Result<()> {
+        self.check_node(id).await?;
+        self.inner.delete_node(id).await
+    }
+
+    async fn create_edge(&self, mut edge: Edge) -> Result<EdgeId> {
+        self.check_node(edge.source_id).await?;
+        self.check_node(edge.target_id).await?;
+        self.stamp(&mut edge.properties);
+        self.inner.create_edge(edge).await
+    }
+
+    async fn get_edge(&self, id: EdgeId, filter: TemporalFilter) -> Result<Edge> {
+        let edge = self.inner.get_edge(id, filter).await?;
+        if self.owns(&edge.properties) {
+            Ok(edge)
+        } else {
+            Err(Error::EdgeNotFound(id.to_string()))
+        }
+    }
+
+    async fn get_edge_versions(&self, id: EdgeId) -> Result<Vec<Edge>> {
+        let versions = self.own_edges(self.inner.get_edge_versions(id).await?);
+        if versions.is_empty() {
+            return Err(Error::EdgeNotFound(id.to_string()));
+        }
+        Ok(versions)
+    }
+
+    async fn update_edge(&self, mut edge: Edge, expected: Option<VersionToken>) -> Result<()> {
+        self.check_edge(edge.id).await?;
+        self.check_node(edge.source_id).await?;
+        self.check_node(edge.target_id).await?;
+        self.stamp(&mut edge.properties);
+        self.inner.update_edge(edge, expected).await
+    }
+
+    async fn delete_edge(&self, id: EdgeId) -> Result<()> {
+        self.check_edge(id).await?;
+        self.inner.delete_edge(id).await
+    }
+
+    async fn get_edges_for_node(
+        &self,
+        node_id: NodeId,
+        filter: TemporalFilter,
+    ) -> Result<Vec<Edge>> {
+        Ok(self.own_edges(self.inner.get_edges_for_node(node_id, filter).await?))
+    }
+
+    async fn get_connected_nodes(
+        &self,
+        node_id: NodeId,
+        filter: TemporalFilter,
+    ) -> Result<Vec<Node>> {
+        Ok(self.own_nodes(self.inner.get_connected_nodes(node_id, filter).await?))
+    }
+
+    async fn execute_query<T>(&self, _query: &str, _params: &[(&str, &dyn ToGValue)]) -> Result<T>
+    where
+        T: FromLocalResultSet,
+    {
+        Err(Self::unscoped_query())
+    }
+
+    async fn execute_query_with_retry<T>(&self, _query: &str, _params: &[(&str, &dyn ToGValue)]) -> Result<T>
+    where
+        T: FromLocalResultSet,
+    {
+        Err(Self::unscoped_query())
+    }
+
+    async fn get_nodes_by_label(&self, label: &str, filter: TemporalFilter) -> Result<Vec<Node>> {
+        Ok(self.own_nodes(self.inner.get_nodes_by_label(label, filter).await?))
+    }
+
+    async fn get_edges_by_label(&self, label: &str, filter: TemporalFilter) -> Result<Vec<Edge>> {
+        Ok(self.own_edges(self.inner.get_edges_by_label(label, filter).await?))
+    }
+
+    async fn get_edges_between(&self, from: NodeId, to: NodeId, filter: TemporalFilter) -> Result<Vec<Edge>> {
+        Ok(self.own_edges(self.inner.get_edges_between(from, to, filter).await?))
+    }
+
+    async fn get_edges_from(&self, from: NodeId, filter: TemporalFilter) -> Result<Vec<Edge>> {
+        Ok(self.own_edges(self.inner.get_edges_from(from, filter).await?))
+    }
+
+    async fn get_edges_to(&self, to: NodeId, filter: TemporalFilter) -> Result<Vec<Edge>> {
+        Ok(self.own_edges(self.inner.get_edges_to(to, filter).await?))
+    }
+
+    async fn get_vertex(&self, id: &str, filter: TemporalFilter) -> Result<Option<Node>> {
+        Ok(self.inner.get_vertex(id, filter).await?.filter(|node| self.owns(&node.properties)))
+    }
+
+    async fn execute_gremlin_query(&self, _query: &str, _params: &[(&str, &dyn ToGValue)]) -> Result<GResultSet> {
+        Err(Self::unscoped_query())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::InMemoryGraph;
+    use crate::types::{EntityType, TemporalRange};
+    use uuid::Uuid;
+
+    fn node(label: &str) -> Node {
+        Node {
+            id: NodeId(Uuid::new_v4()),
+            entity_type: EntityType::Person,
+            label: label.to_string(),
+            properties: Properties::new(),
+            valid_time: TemporalRange::from_now(),
+            transaction_time: TemporalRange::from_now(),
+        }
+    }
+
+    fn edge(source: NodeId, target: NodeId) -> Edge {
+        Edge {
+            id: EdgeId(Uuid::new_v4()),
+            source_id: source,
+            target_id: target,
+            label: "knows".to_string(),
+            properties: Properties::new(),
+            valid_time: TemporalRange::from_now(),
+            transaction_time: TemporalRange::from_now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_graph_isolation() {
+        let inner: Arc<dyn Graph> = Arc::new(InMemoryGraph::new());
+        let acme = TenantGraph::new(inner.clone(), TenantId::new("acme").unwrap());
+        let globex = TenantGraph::new(inner.clone(), TenantId::new("globex").unwrap());
+        let current = TemporalFilter::current;
+
+        let alice = acme.create_node(node("Person")).await.unwrap();
+        let bob = acme.create_node(node("Person")).await.unwrap();
+        let knows = acme.create_edge(edge(alice, bob)).await.unwrap();
+        let carol = globex.create_node(node("Person")).await.unwrap();
+
+        let stored = inner.get_node(alice, current()).await.unwrap();
+        assert_eq!(stored.properties.get(TENANT_PROPERTY), Some(&json!("acme")));
+
+        // Reads never see the other tenant's elements
+        assert!(matches!(globex.get_node(alice, current()).await, Err(Error::NodeNotFound(_))));
+        assert!(matches!(globex.get_edge(knows, current()).await, Err(Error::EdgeNotFound(_))));
+        assert!(globex.get_vertex(&alice.to_string(), current()).await.unwrap().is_none());
+        assert_eq!(acme.get_nodes_by_label("Person", current()).await.unwrap().len(), 2);
+        assert_eq!(globex.get_nodes_by_label("Person", current()).await.unwrap().len(), 1);
+        assert!(globex.get_edges_for_node(alice, current()).await.unwrap().is_empty());
+        assert!(globex.get_connected_nodes(alice, current()).await.unwrap().is_empty());
+        assert!(globex.get_edges_by_label("knows", current()).await.unwrap().is_empty());
+        assert_eq!(acme.get_edges_between(alice, bob, current()).await.unwrap().len(), 1);
+
+        // Writes cannot touch or link to the other tenant's elements
+        let mut hijacked = stored.clone();
+        hijacked.label = "hijacked".to_string();
+        assert!(globex.update_node(hijacked, None).await.is_err());
+        assert!(globex.delete_node(alice).await.is_err());
+        assert!(globex.delete_edge(knows).await.is_err());
+        assert!(globex.create_edge(edge(carol, alice)).await.is_err());
+        assert_eq!(inner.get_node(alice, current()).await.unwrap().label, "Person");
+
+        // Stamps cannot be forged through properties
+        let mut forged = node("Person");
+        forged.properties.insert(TENANT_PROPERTY.to_string(), json!("acme"));
+        let forged = globex.create_node(forged).await.unwrap();
+        assert!(acme.get_node(forged, current()).await.is_err());
+
+        assert!(acme.execute_gremlin_query("g.V()", &[]).await.is_err());
+
+        acme.delete_node(alice).await.unwrap();
+        assert!(acme.get_edge(knows, current()).await.is_err());
+        assert!(globex.get_node(carol, current()).await.is_ok());
+    }
+}
@@ -0,0 +1,401 @@
+//! Typed Gremlin traversal builder with bound parameters
+//!
+//! Every caller-supplied value (ids, labels, property keys and values) is
+//! emitted as a script binding rather than spliced into the Gremlin text, so
+//! quotes or Gremlin syntax inside data can never alter the traversal. The
+//! only literals in a script are step names and property keys fixed by this
+//! crate.
+
+use gremlin_client::{GValue, ToGValue};
+use serde_json::Value;
+
+use crate::types::LocalGValue;
+
+/// Comparison used by a `has` step
+#[derive(Debug, Clone)]
+pub enum Predicate {
+    Eq(GValue),
+    Neq(GValue),
+    Gt(GValue),
+    Gte(GValue),
+    Lt(GValue),
+    Lte(GValue),
+    Within(Vec<GValue>),
+}
+
+impl Predicate {
+    fn parts(self) -> (&'static str, GValue) {
+        match self {
+            Predicate::Eq(v) => ("eq", v),
+            Predicate::Neq(v) => ("neq", v),
+            Predicate::Gt(v) => ("gt", v),
+            Predicate::Gte(v) => ("gte", v),
+            Predicate::Lt(v) => ("lt", v),
+            Predicate::Lte(v) => ("lte", v),
+            Predicate::Within(values) => ("within", GValue::from(values)),
+        }
+    }
+}
+
+/// A Gremlin script together with the bindings it references
+#[derive(Debug, Clone)]
+pub struct Traversal {
+    script: String,
+    bindings: Vec<(String, LocalGValue)>,
+}
+
+impl Traversal {
+    /// Start a traversal from the graph traversal source `g`
+    pub fn g() -> Self {
+        Self {
+            script: "g".to_string(),
+            bindings: Vec::new(),
+        }
+    }
+
+    /// The Gremlin script, containing only step names, fixed keys and binding names
+    pub fn script(&self) -> &str {
+        &self.script
+    }
+
+    /// Bindings referenced by the script, in the order they were added
+    pub fn bindings(&self) -> &[(String, LocalGValue)] {
+        &self.bindings
+    }
+
+    /// Look up the value bound under `name`
+    pub fn binding(&self, name: &str) -> Option<&GValue> {
+        self.bindings.iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| &value.0)
+    }
+
+    /// Bindings in the shape expected by `Graph::execute_query`
+    pub fn params(&self) -> Vec<(&str, &dyn ToGValue)> {
+        self.bindings.iter()
+            .map(|(name, value)| (name.as_str(), value as &dyn ToGValue))
+            .collect()
+    }
+
+    /// Bind a value and return the name to reference it by
+    fn bind<V: ToGValue + ?Sized>(&mut self, value: &V) -> String {
+        let name = format!("p{}", self.bindings.len());
+        self.bindings.push((name.clone(), LocalGValue(value.to_gvalue())));
+        name
+    }
+
+    fn step(mut self, step: &str) -> Self {
+        self.script.push('.');
+        self.script.push_str(step);
+        self.script.push_str("()");
+        self
+    }
+
+    fn bound_step<V: ToGValue + ?Sized>(mut self, step: &str, value: &V) -> Self {
+        let name = self.bind(value);
+        self.script.push_str(&format!(".{}({})", step, name));
+        self
+    }
+
+    /// Append `.step(<sub-traversal>)` where the sub-traversal starts from `__`
+    ///
+    /// The sub-traversal shares this traversal's bindings so names stay unique.
+    fn nested_step(mut self, step: &str, build: impl FnOnce(Traversal) -> Traversal) -> Self {
+        let inner = build(Traversal {
+            script: "__".to_string(),
+            bindings: std::mem::take(&mut self.bindings),
+        });
+        self.bindings = inner.bindings;
+        self.script.push_str(&format!(".{}({})", step, inner.script));
+        self
+    }
+
+    /// `V(id)`
+    pub fn v<V: ToGValue + ?Sized>(self, id: &V) -> Self {
+        self.bound_step("V", id)
+    }
+
+    /// `V()` over all vertices
+    pub fn v_all(self) -> Self {
+        self.step("V")
+    }
+
+    /// `E(id)`
+    pub fn e<V: ToGValue + ?Sized>(self, id: &V) -> Self {
+        self.bound_step("E", id)
+    }
+
+    /// `E()` over all edges
+    pub fn e_all(self) -> Self {
+        self.step("E")
+    }
+
+    /// `addV(label)`
+    pub fn add_v(self, label: &str) -> Self {
+        self.bound_step("addV", &label)
+    }
+
+    /// `addE(label)`
+    pub fn add_e(self, label: &str) -> Self {
+        self.bound_step("addE", &label)
+    }
+
+    /// `to(__...)`, used after `addE` to pick the target vertex
+    pub fn to(self, target: impl FnOnce(Traversal) -> Traversal) -> Self {
+        self.nested_step("to", target)
+    }
+
+    /// `or(__..., __...)`
+    pub fn or(
+        mut self,
+        left: impl FnOnce(Traversal) -> Traversal,
+        right: impl FnOnce(Traversal) -> Traversal,
+    ) -> Self {
+        let left = left(Traversal {
+            script: "__".to_string(),
+            bindings: std::mem::take(&mut self.bindings),
+        });
+        let right = right(Traversal {
+            script: "__".to_string(),
+            bindings: left.bindings,
+        });
+        self.bindings = right.bindings;
+        self.script.push_str(&format!(".or({}, {})", left.script, right.script));
+        self
+    }
+
+    /// `where(__...)`
+    pub fn where_(self, condition: impl FnOnce(Traversal) -> Traversal) -> Self {
+        self.nested_step("where", condition)
+    }
+
+    /// `property('key', value)` for a key defined by this crate
+    pub fn property<V: ToGValue + ?Sized>(mut self, key: &'static str, value: &V) -> Self {
+        let key = literal_key(key);
+        let name = self.bind(value);
+        self.script.push_str(&format!(".property({}, {})", key, name));
+        self
+    }
+
+    /// `property(T.id, value)`, assigning the element's own ID
+    pub fn property_id<V: ToGValue + ?Sized>(mut self, id: &V) -> Self {
+        let name = self.bind(id);
+        self.script.push_str(&format!(".property(T.id, {})", name));
+        self
+    }
+
+    /// `property(single, 'key', value)`, replacing any existing value
+    pub fn property_single<V: ToGValue + ?Sized>(mut self, key: &'static str, value: &V) -> Self {
+        let key = literal_key(key);
+        let name = self.bind(value);
+        self.script.push_str(&format!(".property(single, {}, {})", key, name));
+        self
+    }
+
+    /// `property(key, value)` where the key itself comes from caller data
+    pub fn property_dynamic<V: ToGValue + ?Sized>(mut self, key: &str, value: &V) -> Self {
+        let key_name = self.bind(&key);
+        let value_name = self.bind(value);
+        self.script.push_str(&format!(".property(single, {}, {})", key_name, value_name));
+        self
+    }
+
+    /// `hasLabel(label)`
+    pub fn has_label(self, label: &str) -> Self {
+        self.bound_step("hasLabel", &label)
+    }
+
+    /// `hasId(id)`
+    pub fn has_id<V: ToGValue + ?Sized>(self, id: &V) -> Self {
+        self.bound_step("hasId", id)
+    }
+
+    /// `has('key', predicate(value))`
+    pub fn has(mut self, key: &'static str, predicate: Predicate) -> Self {
+        let key = literal_key(key);
+        let (op, value) = predicate.parts();
+        let name = self.bind(&LocalGValue(value));
+        self.script.push_str(&format!(".has({}, {}({}))", key, op, name));
+        self
+    }
+
+    /// `hasNot('key')`
+    pub fn has_not(mut self, key: &'static str) -> Self {
+        let key = literal_key(key);
+        self.script.push_str(&format!(".hasNot({})", key));
+        self
+    }
+
+    /// `outE()`
+    pub fn out_e(self) -> Self {
+        self.step("outE")
+    }
+
+    /// `inE()`
+    pub fn in_e(self) -> Self {
+        self.step("inE")
+    }
+
+    /// `bothE()`
+    pub fn both_e(self) -> Self {
+        self.step("bothE")
+    }
+
+    /// `both()`
+    pub fn both(self) -> Self {
+        self.step("both")
+    }
+
+    /// `inV()`
+    pub fn in_v(self) -> Self {
+        self.step("inV")
+    }
+
+    /// `otherV()`
+    pub fn other_v(self) -> Self {
+        self.step("otherV")
+    }
+
+    /// `dedup()`
+    pub fn dedup(self) -> Self {
+        self.step("dedup")
+    }
+
+    /// `drop()`
+    pub fn drop(self) -> Self {
+        self.step("drop")
+    }
+}
+
+/// Render a crate-defined property key as a quoted Gremlin literal
+///
+/// Keys passed here are compile-time constants; anything that would need
+/// escaping is a programming error.
+fn literal_key(key: &'static str) -> String {
+    assert!(
+        !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.'),
+        "property key {:?} must be bound, not inlined",
+        key
+    );
+    format!("'{}'", key)
+}
+
+/// Convert a JSON value into a Gremlin value suitable for binding
+///
+/// Scalars map to their Gremlin counterparts; arrays and objects are stored
+/// as their JSON text since Neptune has no nested property values.
+pub fn json_to_gvalue(value: &Value) -> GValue {
+    match value {
+        Value::Null => GValue::Null,
+        Value::Bool(b) => GValue::Bool(*b),
+        Value::Number(n) => {
+            if let Some(i) = n.as_i64() {
+                GValue::Int64(i)
+            } else {
+                GValue::Double(n.as_f64().unwrap_or_default())
+            }
+        }
+        Value::String(s) => GValue::String(s.clone()),
+        Value::Array(_) | Value::Object(_) => GValue::String(value.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bound_string(traversal: &Traversal, name: &str) -> String {
+        match traversal.binding(name) {
+            Some(GValue::String(s)) => s.clone(),
+            other => panic!("expected string binding for {}, got {:?}", name, other),
+        }
+    }
+
+    #[test]
+    fn test_values_are_bound_not_inlined() {
+        let traversal = Traversal::g().v(&"abc").out_e().has_label("knows");
+        assert_eq!(traversal.script(), "g.V(p0).outE().hasLabel(p1)");
+        assert_eq!(bound_string(&traversal, "p0"), "abc");
+        assert_eq!(bound_string(&traversal, "p1"), "knows");
+        assert_eq!(traversal.params().len(), 2);
+    }
+
+    #[test]
+    fn test_nested_traversals_share_bindings() {
+        let traversal = Traversal::g()
+            .v(&"a")
+            .add_e("knows")
+            .to(|t| t.v(&"b"))
+            .property("weight", &1.5f64);
+        assert_eq!(traversal.script(), "g.V(p0).addE(p1).to(__.V(p2)).property('weight', p3)");
+        assert_eq!(bound_string(&traversal, "p2"), "b");
+        assert!(matches!(traversal.binding("p3"), Some(GValue::Double(_))));
+    }
+
+    #[test]
+    fn test_hostile_strings_round_trip() {
+        let hostile = [
+            "O'Brien",
+            "x').drop(); g.V().drop() //",
+            "\\'); System.exit(0)",
+            "\"double\" and 'single'",
+            "line\nbreak",
+        ];
+
+        for input in hostile {
+            let traversal = Traversal::g()
+                .add_v(input)
+                .property("label", &input)
+                .property_dynamic(input, &input)
+                .has_label(input);
+
+            assert!(!traversal.script().contains(input));
+            assert!(!traversal.script().contains("drop"));
+            for (name, value) in traversal.bindings() {
+                match &value.0 {
+                    GValue::String(s) => assert_eq!(s, input, "binding {} altered", name),
+                    other => panic!("unexpected binding {:?}", other),
+                }
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "must be bound")]
+    fn test_unsafe_literal_key_rejected() {
+        let _ = Traversal::g().v_all().property("it's", &"x");
+    }
+
+    #[test]
+    fn test_predicates() {
+        let traversal = Traversal::g()
+            .e_all()
+            .has("valid_time.start", Predicate::Gte(GValue::Int64(1)))
+            .has("valid_time.end", Predicate::Lte(GValue::Int64(2)));
+        assert_eq!(
+            traversal.script(),
+            "g.E().has('valid_time.start', gte(p0)).has('valid_time.end', lte(p1))"
+        );
+
+        let traversal = Traversal::g()
+            .v_all()
+            .has("id", Predicate::Within(vec![GValue::String("a".into()), GValue::String("b".into())]))
+            .or(|t| t.has("source_id", Predicate::Eq(GValue::Int64(1))), |t| t.has_label("x"));
+        assert_eq!(
+            traversal.script(),
+            "g.V().has('id', within(p0)).or(__.has('source_id', eq(p1)), __.hasLabel(p2))"
+        );
+        assert!(matches!(traversal.binding("p0"), Some(GValue::List(_))));
+    }
+
+    #[test]
+    fn test_json_to_gvalue() {
+        assert!(matches!(json_to_gvalue(&serde_json::json!(3)), GValue::Int64(3)));
+        assert!(matches!(json_to_gvalue(&serde_json::json!(true)), GValue::Bool(true)));
+        assert!(matches!(json_to_gvalue(&serde_json::json!(null)), GValue::Null));
+        match json_to_gvalue(&serde_json::json!({"a": "it's"})) {
+            GValue::String(s) => assert_eq!(s, r#"{"a":"it's"}"#),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,591 @@
+//! Embedding function implementations
+//!
+//! `RustBertEmbeddings` runs a sentence-transformer loaded from a local model
+//! directory. `HttpEmbeddings` calls an OpenAI-compatible `/v1/embeddings`
+//! endpoint. `HashingEmbeddings` is a deterministic bag-of-words embedder
+//! that needs no model files, for tests and offline development.
+
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use backoff::{backoff::Backoff, ExponentialBackoff};
+use reqwest::StatusCode;
+use rust_bert::pipelines::sentence_embeddings::{SentenceEmbeddingsBuilder, SentenceEmbeddingsModel};
+use serde::Deserialize;
+use serde_json::json;
+use tracing::warn;
+
+use crate::{
+    error::{Error, Result},
+    hybrid::models::EmbeddingFunction,
+};
+
+/// Scale a vector to unit length, leaving zero vectors untouched
+pub fn l2_normalize(vector: &mut [f32]) {
+    let norm = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        vector.iter_mut().for_each(|x| *x /= norm);
+    }
+}
+
+/// Configuration for a local sentence-transformer model
+#[derive(Debug, Clone)]
+pub struct LocalEmbeddingConfig {
+    /// Directory containing the exported sentence-transformer
+    pub model_dir: PathBuf,
+    /// Version recorded alongside generated embeddings
+    pub model_version: String,
+    /// Maximum number of texts encoded in one forward pass
+    pub batch_size: usize,
+    /// Whether to L2-normalize the pooled embeddings
+    pub normalize: bool,
+}
+
+impl LocalEmbeddingConfig {
+    /// Create a configuration for the model in `model_dir`
+    pub fn new(model_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            model_dir: model_dir.into(),
+            model_version: "1.0".to_string(),
+            batch_size: 32,
+            normalize: true,
+        }
+    }
+}
+
+/// Sentence embeddings from a local rust-bert sentence-transformer
+///
+/// Tokenization and pooling follow the model directory's own configuration
+/// (mean pooling for the common sentence-transformers exports). Forward
+/// passes block, so they run on tokio's blocking pool rather than on the
+/// runtime's workers.
+pub struct RustBertEmbeddings {
+    /// The loaded model
+    model: Arc<Mutex<SentenceEmbeddingsModel>>,
+    /// The model used for embeddings
+    model_name: String,
+    /// The model version
+    model_version: String,
+    /// The dimension of the embeddings
+    embedding_dim: usize,
+    /// Maximum batch size per forward pass
+    batch_size: usize,
+    /// Whether to L2-normalize embeddings
+    normalize: bool,
+}
+
+impl RustBertEmbeddings {
+    /// Load a sentence-transformer from a local model directory
+    pub fn load(config: LocalEmbeddingConfig) -> Result<Self> {
+        let model_name = config.model_dir
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| config.model_dir.display().to_string());
+
+        let model = SentenceEmbeddingsBuilder::local(&config.model_dir)
+            .with_device(tch::Device::cuda_if_available())
+            .create_model()
+            .map_err(|e| Error::ModelError(format!("Failed to load embedding model {}: {}", model_name, e)))?;
+
+        let embedding_dim = model.get_embedding_dim()
+            .map_err(|e| Error::ModelError(format!("Failed to read embedding dimension: {}", e)))?;
+
+        Ok(Self {
+            model: Arc::new(Mutex::new(model)),
+            model_name,
+            model_version: config.model_version,
+            embedding_dim: embedding_dim as usize,
+            batch_size: config.batch_size.max(1),
+            normalize: config.normalize,
+        })
+    }
+}
+
+#[async_trait]
+impl EmbeddingFunction for RustBertEmbeddings {
+    async fn generate_embedding(&self, text: &str) -> Result<Vec<f32>> {
+        self.generate_embeddings(&[text.to_string()]).await?
+            .pop()
+            .ok_or_else(|| Error::ModelError("Embedding model returned no output".to_string()))
+    }
+
+    async fn generate_embeddings(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        let model = self.model.clone();
+        let texts = texts.to_vec();
+        let batch_size = self.batch_size;
+        let mut embeddings = tokio::task::spawn_blocking(move || -> Result<Vec<Vec<f32>>> {
+            let model = model.lock()
+                .map_err(|_| Error::ModelError("Embedding model lock poisoned".to_string()))?;
+            let mut embeddings = Vec::with_capacity(texts.len());
+            for batch in texts.chunks(batch_size) {
+                let encoded = model.encode(batch)
+                    .map_err(|e| Error::ModelError(format!("Failed to encode text: {}", e)))?;
+                embeddings.extend(encoded);
+            }
+            Ok(embeddings)
+        })
+        .await
+        .map_err(|e| Error::ModelError(format!("Embedding task failed: {}", e)))??;
+
+        if self.normalize {
+            embeddings.iter_mut().for_each(|embedding| l2_normalize(embedding));
+        }
+
+        Ok(embeddings)
+    }
+
+    fn embedding_dim(&self) -> usize {
+        self.embedding_dim
+    }
+
+    fn model_name(&self) -> String {
+        self.model_name.clone()
+    }
+
+    fn model_version(&self) -> String {
+        self.model_version.clone()
+    }
+}
+
+/// Configuration for an OpenAI-compatible embeddings endpoint
+#[derive(Debug, Clone)]
+pub struct HttpEmbeddingConfig {
+    /// Base URL of the service; `/v1/embeddings` is appended
+    pub endpoint: String,
+    /// Bearer token sent in the `Authorization` header
+    pub api_key: Option<String>,
+    /// Model name sent with every request
+    pub model: String,
+    /// Version recorded alongside generated embeddings
+    pub model_version: String,
+    /// Expected embedding dimension
+    pub dimension: usize,
+    /// Maximum number of inputs per request
+    pub batch_size: usize,
+    /// Approximate token limit per input; longer inputs are truncated
+    pub max_input_tokens: usize,
+    /// Maximum number of attempts per request
+    pub max_retries: u32,
+    /// Delay before the first retry
+    pub initial_retry_interval: Duration,
+    /// Per-request timeout
+    pub timeout: Duration,
+}
+
+impl HttpEmbeddingConfig {
+    /// Create a configuration for `model` served at `endpoint`
+    pub fn new(endpoint: impl Into<String>, model: impl Into<String>, dimension: usize) -> Self {
+        Self {
+            endpoint: endpoint.into(),
+            api_key: None,
+            model: model.into(),
+            model_version: "1".to_string(),
+            dimension,
+            batch_size: 64,
+            max_input_tokens: 8191,
+            max_retries: 3,
+            initial_retry_interval: Duration::from_millis(500),
+            timeout: Duration::from_secs(30),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct EmbeddingResponse {
+    data: Vec<EmbeddingData>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EmbeddingData {
+    embedding: Vec<f32>,
+    index: usize,
+}
+
+/// Embeddings from an OpenAI-compatible HTTP service
+pub struct HttpEmbeddings {
+    client: reqwest::Client,
+    url: String,
+    config: HttpEmbeddingConfig,
+}
+
+impl HttpEmbeddings {
+    /// Create a client for the configured endpoint
+    pub fn new(config: HttpEmbeddingConfig) -> Result<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(config.timeout)
+            .build()
+            .map_err(|e| Error::ConfigurationError(format!("Failed to build HTTP client: {}", e)))?;
+        let url = format!("{}/v1/embeddings", config.endpoint.trim_end_matches('/'));
+
+        Ok(Self { client, url, config })
+    }
+
+    /// Embed one batch, retrying transient failures with exponential backoff
+    async fn embed_batch(&self, inputs: &[String]) -> Result<Vec<Vec<f32>>> {
+        let mut backoff = ExponentialBackoff {
+            initial_interval: self.config.initial_retry_interval,
+            ..ExponentialBackoff::default()
+        };
+        let attempts = self.config.max_retries.max(1);
+
+        for attempt in 1..=attempts {
+            match self.send(inputs).await {
+                Ok(embeddings) => return Ok(embeddings),
+                Err(RequestError::Permanent(e)) => return Err(e),
+                Err(RequestError::Transient(e)) if attempt < attempts => {
+                    match backoff.next_backoff() {
+                        Some(duration) => {
+                            warn!("Embedding request failed, retrying in {:?}: {}", duration, e);
+                            tokio::time::sleep(duration).await;
+                        }
+                        None => return Err(e),
+                    }
+                }
+                Err(RequestError::Transient(e)) => {
+                    return Err(Error::Retry(format!("Embedding request failed after {} attempts: {}", attempts, e)));
+                }
+            }
+        }
+
+        Err(Error::Retry("Maximum number of retries reached".to_string()))
+    }
+
+    async fn send(&self, inputs: &[String]) -> std::result::Result<Vec<Vec<f32>>, RequestError> {
+        let mut request = self.client
+            .post(&self.url)
+            .json(&json!({
+                "model": self.config.model,
+                "input": inputs,
+            }));
+        if let Some(api_key) = &self.config.api_key {
+            request = request.bearer_auth(api_key);
+        }
+
+        let response = request.send().await
+            .map_err(|e| RequestError::Transient(Error::Connection(e.to_string())))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            let error = Error::ModelError(format!("Embedding endpoint returned {}: {}", status, body));
+            return Err(if status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error() {
+                RequestError::Transient(error)
+            } else {
+                RequestError::Permanent(error)
+            });
+        }
+
+        let mut body: EmbeddingResponse = response.json().await
+            .map_err(|e| RequestError::Permanent(Error::Deserialization(e.to_string())))?;
+        body.data.sort_by_key(|data| data.index);
+
+        if body.data.len() != inputs.len() {
+            return Err(RequestError::Permanent(Error::ModelError(format!(
+                "Embedding endpoint returned {} embeddings for {} inputs",
+                body.data.len(),
+                inputs.len()
+            ))));
+        }
+        if let Some(data) = body.data.iter().find(|data| data.embedding.len() != self.config.dimension) {
+            return Err(RequestError::Permanent(Error::ModelError(format!(
+                "Expected {}-dimensional embeddings from {}, got {}",
+                self.config.dimension,
+                self.config.model,
+                data.embedding.len()
+            ))));
+        }
+
+        Ok(body.data.into_iter().map(|data| data.embedding).collect())
+    }
+}
+
+/// Whether a failed embedding request is worth retrying
+enum RequestError {
+    Transient(Error),
+    Permanent(Error),
+}
+
+/// Truncate `text` to roughly `max_tokens` tokens
+///
+/// Uses the common estimate of four characters per token and cuts at the
+/// last whitespace inside the budget when there is one.
+pub fn truncate_to_tokens(text: &str, max_tokens: usize) -> &str {
+    let max_chars = max_tokens.saturating_mul(4);
+    let cut = match text.char_indices().nth(max_chars) {
+        Some((byte_index, _)) => byte_index,
+        None => return text,
+    };
+
+    let truncated = &text[..cut];
+    match truncated.rfind(char::is_whitespace) {
+        Some(space) if space > 0 => truncated[..space].trim_end(),
+        _ => truncated,
+    }
+}
+
+#[async_trait]
+impl EmbeddingFunction for HttpEmbeddings {
+    async fn generate_embedding(&self, text: &str) -> Result<Vec<f32>> {
+        self.generate_embeddings(&[text.to_string()]).await?
+            .pop()
+            .ok_or_else(|| Error::ModelError("Embedding endpoint returned no output".to_string()))
+    }
+
+    async fn generate_embeddings(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        let inputs: Vec<String> = texts.iter()
+            .map(|text| truncate_to_tokens(text, self.config.max_input_tokens).to_string())
+            .collect();
+
+        let mut embeddings = Vec::with_capacity(inputs.len());
+        for batch in inputs.chunks(self.config.batch_size.max(1)) {
+            embeddings.extend(self.embed_batch(batch).await?);
+        }
+        Ok(embeddings)
+    }
+
+    fn embedding_dim(&self) -> usize {
+        self.config.dimension
+    }
+
+    fn model_name(&self) -> String {
+        self.config.model.clone()
+    }
+
+    fn model_version(&self) -> String {
+        self.config.model_version.clone()
+    }
+}
+
+/// Deterministic feature-hashing embedder
+///
+/// Each lowercase word is hashed into one of `embedding_dim` buckets with a
+/// hashed sign, and the result is L2-normalized. Texts sharing vocabulary
+/// therefore score higher under cosine similarity, which is enough to assert
+/// retrieval behaviour without model files.
+#[derive(Debug, Clone)]
+pub struct HashingEmbeddings {
+    embedding_dim: usize,
+}
+
+impl HashingEmbeddings {
+    /// Create a hashing embedder producing `embedding_dim`-sized vectors
+    pub fn new(embedding_dim: usize) -> Self {
+        Self {
+            embedding_dim: embedding_dim.max(1),
+        }
+    }
+
+    /// Embed `text` synchronously
+    pub fn embed(&self, text: &str) -> Vec<f32> {
+        let mut embedding = vec![0.0; self.embedding_dim];
+
+        let words = text
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .map(str::to_lowercase);
+
+        for word in words {
+            let hash = fnv1a(word.as_bytes());
+            let bucket = (hash % self.embedding_dim as u64) as usize;
+            let sign = if hash >> 63 == 0 { 1.0 } else { -1.0 };
+            embedding[bucket] += sign;
+        }
+
+        l2_normalize(&mut embedding);
+        embedding
+    }
+}
+
+/// 64-bit FNV-1a, stable across platforms and releases
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+#[async_trait]
+impl EmbeddingFunction for HashingEmbeddings {
+    async fn generate_embedding(&self, text: &str) -> Result<Vec<f32>> {
+        Ok(self.embed(text))
+    }
+
+    async fn generate_embeddings(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        Ok(texts.iter().map(|text| self.embed(text)).collect())
+    }
+
+    fn embedding_dim(&self) -> usize {
+        self.embedding_dim
+    }
+
+    fn model_name(&self) -> String {
+        "feature-hashing".to_string()
+    }
+
+    fn model_version(&self) -> String {
+        "1".to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hybrid::query::vector_similarity::cosine_similarity;
+    use wiremock::matchers::{body_partial_json, header, method, path};
+    use wiremock::{Mock, MockServer, Request, Respond, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_hashing_embeddings_are_deterministic() {
+        let embedder = HashingEmbeddings::new(64);
+        let a = embedder.generate_embedding("Graph databases store relationships").await.unwrap();
+        let b = embedder.generate_embedding("graph DATABASES store, relationships!").await.unwrap();
+
+        assert_eq!(a.len(), 64);
+        assert_eq!(a, b);
+
+        let norm: f32 = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+        assert!((norm - 1.0).abs() < 1e-5);
+        assert!(embedder.embed("").iter().all(|x| *x == 0.0));
+    }
+
+    #[tokio::test]
+    async fn test_hashing_embeddings_rank_by_shared_vocabulary() {
+        let embedder = HashingEmbeddings::new(256);
+        let documents = vec![
+            "Neptune stores the knowledge graph".to_string(),
+            "The chef cooked pasta for dinner".to_string(),
+            "OpenSearch indexes vector embeddings".to_string(),
+        ];
+        let embeddings = embedder.generate_embeddings(&documents).await.unwrap();
+        assert_eq!(embeddings.len(), documents.len());
+
+        let query = embedder.embed("which service indexes the vector embeddings");
+        let scores: Vec<f32> = embeddings.iter().map(|e| cosine_similarity(&query, e)).collect();
+        let best = scores.iter()
+            .enumerate()
+            .max_by(|a, b| a.1.partial_cmp(b.1).unwrap())
+            .map(|(i, _)| i);
+
+        assert_eq!(best, Some(2));
+        assert!(scores[2] > scores[1]);
+    }
+
+    #[test]
+    fn test_l2_normalize() {
+        let mut vector = vec![3.0, 4.0];
+        l2_normalize(&mut vector);
+        assert_eq!(vector, vec![0.6, 0.8]);
+
+        let mut zero = vec![0.0, 0.0];
+        l2_normalize(&mut zero);
+        assert_eq!(zero, vec![0.0, 0.0]);
+    }
+
+    #[test]
+    fn test_truncate_to_tokens() {
+        assert_eq!(truncate_to_tokens("short text", 10), "short text");
+        assert_eq!(truncate_to_tokens("one two three four", 3), "one two");
+        assert_eq!(truncate_to_tokens("abcdefghij", 2), "abcdefgh");
+        assert_eq!(truncate_to_tokens("héllo wörld", 1), "héll");
+    }
+
+    fn http_config(server: &MockServer) -> HttpEmbeddingConfig {
+        let mut config = HttpEmbeddingConfig::new(server.uri(), "text-embedding-test", 2);
+        config.api_key = Some("secret".to_string());
+        config.batch_size = 2;
+        config.initial_retry_interval = Duration::from_millis(1);
+        config
+    }
+
+    /// Responds with `[input length, index]` for every input, in reverse order
+    struct EchoEmbeddings;
+
+    impl Respond for EchoEmbeddings {
+        fn respond(&self, request: &Request) -> ResponseTemplate {
+            let body: serde_json::Value = serde_json::from_slice(&request.body).unwrap();
+            let data: Vec<_> = body["input"].as_array().unwrap()
+                .iter()
+                .enumerate()
+                .rev()
+                .map(|(i, input)| json!({
+                    "object": "embedding",
+                    "index": i,
+                    "embedding": [input.as_str().unwrap().len() as f32, i as f32],
+                }))
+                .collect();
+            ResponseTemplate::new(200).set_body_json(json!({ "object": "list", "data": data }))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_http_embeddings_batches_requests() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/embeddings"))
+            .and(header("authorization", "Bearer secret"))
+            .and(body_partial_json(json!({ "model": "text-embedding-test" })))
+            .respond_with(EchoEmbeddings)
+            .expect(2)
+            .mount(&server)
+            .await;
+
+        let embedder = HttpEmbeddings::new(http_config(&server)).unwrap();
+        let texts = vec!["a".to_string(), "bb".to_string(), "ccc".to_string()];
+        let embeddings = embedder.generate_embeddings(&texts).await.unwrap();
+
+        assert_eq!(embeddings, vec![vec![1.0, 0.0], vec![2.0, 1.0], vec![3.0, 0.0]]);
+        assert_eq!(embedder.embedding_dim(), 2);
+        assert_eq!(embedder.model_name(), "text-embedding-test");
+    }
+
+    #[tokio::test]
+    async fn test_http_embeddings_retries_transient_errors() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(503))
+            .up_to_n_times(2)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .respond_with(EchoEmbeddings)
+            .mount(&server)
+            .await;
+
+        let embedder = HttpEmbeddings::new(http_config(&server)).unwrap();
+        assert_eq!(embedder.generate_embedding("hello").await.unwrap(), vec![5.0, 0.0]);
+    }
+
+    #[tokio::test]
+    async fn test_http_embeddings_fails_fast_on_client_errors() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(400).set_body_string("bad model"))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let embedder = HttpEmbeddings::new(http_config(&server)).unwrap();
+        assert!(matches!(embedder.generate_embedding("hello").await, Err(Error::ModelError(_))));
+    }
+
+    #[tokio::test]
+    async fn test_http_embeddings_rejects_wrong_dimension() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "data": [{ "index": 0, "embedding": [0.1, 0.2, 0.3] }]
+            })))
+            .mount(&server)
+            .await;
+
+        let embedder = HttpEmbeddings::new(http_config(&server)).unwrap();
+        assert!(matches!(embedder.generate_embedding("hello").await, Err(Error::ModelError(_))));
+    }
+
+    #[test]
+    fn test_missing_local_model_is_a_model_error() {
+        let result = RustBertEmbeddings::load(LocalEmbeddingConfig::new("/nonexistent/model"));
+        assert!(matches!(result, Err(Error::ModelError(_))));
+    }
+}
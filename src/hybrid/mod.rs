@@ -15,21 +15,25 @@ use crate::{
     Config,
     error::{Error, Result},
     graph::{Graph, Node, Edge, NodeId, EdgeId, TemporalRange},
-    memory::{Memory, MemoryEntry, MemoryOperations},
+    memory::{Memory, MemoryEntry},
     temporal::TemporalIndex,
-    types::{EntityType, Properties, Timestamp},
+    types::{EntityType, Properties, Timestamp, VersionToken},
 };
 
 pub mod store;
 pub mod models;
 pub mod query;
 pub mod fusion;
+pub mod traversal;
+pub mod embeddings;
 
 /// Re-export key types for external use
-pub use store::HybridStore;
+pub use store::{HybridStore, ReembedOptions, ReembedReport};
 pub use models::{VectorizedNode, VectorizedEdge, EmbeddingFunction};
-pub use query::{HybridQuery, QueryResult, SimilarityMetric};
+pub use embeddings::{HashingEmbeddings, HttpEmbeddingConfig, HttpEmbeddings, LocalEmbeddingConfig, RustBertEmbeddings};
+pub use query::{HybridQuery, QueryResult, QueryStage, SimilarityMetric, StageResult};
 pub use fusion::{FusionStrategy, WeightedFusion, RankFusion};
+pub use traversal::{TraversalExecutor, TraversalOutput};
 
 /// Core trait for hybrid vector+graph operations
 #[async_trait]
@@ -41,7 +45,10 @@ pub trait HybridGraph: Send + Sync {
     async fn get_node(&self, id: NodeId) -> Result<VectorizedNode>;
     
     /// Update a node, including its vector embedding
-    async fn update_node(&self, node: VectorizedNode) -> Result<()>;
+    ///
+    /// The update is conditional on the `expected` version token, as in
+    /// `Graph::update_node`.
+    async fn update_node(&self, node: VectorizedNode, expected: Option<VersionToken>) -> Result<()>;
     
     /// Create an edge with vector embedding
     async fn create_edge(&self, edge: VectorizedEdge) -> Result<EdgeId>;
@@ -50,7 +57,10 @@ pub trait HybridGraph: Send + Sync {
     async fn get_edge(&self, id: EdgeId) -> Result<VectorizedEdge>;
     
     /// Update an edge, including its vector embedding
-    async fn update_edge(&self, edge: VectorizedEdge) -> Result<()>;
+    ///
+    /// The update is conditional on the `expected` version token, as in
+    /// `Graph::update_edge`.
+    async fn update_edge(&self, edge: VectorizedEdge, expected: Option<VersionToken>) -> Result<()>;
     
     /// Find similar nodes using vector similarity
     async fn find_similar_nodes(
@@ -81,7 +91,7 @@ pub async fn new_hybrid_graph(
     config: &Config,
     graph: impl Graph + 'static,
     memory: impl Memory + 'static,
-    temporal_index: impl TemporalIndex + 'static,
+    temporal_index: TemporalIndex,
 ) -> Result<impl HybridGraph> {
     store::HybridStore::new(config, graph, memory, temporal_index).await
 }
@@ -89,6 +99,262 @@ pub async fn new_hybrid_graph(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{
+        graph::InMemoryGraph,
+        hybrid::query::HybridQueryBuilder,
+        memory::{LocalMemory, MockMemory},
+        types::TenantId,
+    };
     
-    // Tests will be implemented once the core modules are complete
-} 
\ No newline at end of file
+    fn node(label: &str) -> Node {
+        Node {
+            id: NodeId(Uuid::new_v4()),
+            entity_type: EntityType::Person,
+            label: label.to_string(),
+            properties: Properties::new(),
+            valid_time: TemporalRange::unbounded(),
+            transaction_time: TemporalRange::from_now(),
+        }
+    }
+    
+    fn edge(source: &Node, target: &Node, label: &str) -> Edge {
+        Edge {
+            id: EdgeId(Uuid::new_v4()),
+            source_id: source.id,
+            target_id: target.id,
+            label: label.to_string(),
+            properties: Properties::new(),
+            valid_time: TemporalRange::unbounded(),
+            transaction_time: TemporalRange::from_now(),
+        }
+    }
+    
+    #[tokio::test]
+    async fn test_hybrid_query_follows_traversal_steps() {
+        let graph = InMemoryGraph::new();
+        let (alice, bob, carol) = (node("alice"), node("bob"), node("carol"));
+        for n in [&alice, &bob, &carol] {
+            graph.create_node(n.clone()).await.unwrap();
+        }
+        let knows = edge(&alice, &bob, "knows");
+        let mentors = edge(&bob, &carol, "mentors");
+        graph.create_edge(knows.clone()).await.unwrap();
+        graph.create_edge(mentors.clone()).await.unwrap();
+        
+        let store = HybridStore::with_embedding_function(
+            &Config::for_testing(),
+            graph,
+            MockMemory::new(),
+            TemporalIndex::new(),
+            HashingEmbeddings::new(8),
+        ).await.unwrap();
+        
+        let query = HybridQueryBuilder::new()
+            .start_from(alice.id)
+            .follow_outgoing(Some("knows".to_string()))
+            .follow_outgoing(Some("mentors".to_string()))
+            .include_graph_structure(true)
+            .build();
+        let result = store.execute_hybrid_query(query, None).await.unwrap();
+        
+        assert_eq!(result.nodes.len(), 1);
+        assert_eq!(result.nodes[0].node.id(), carol.id);
+        let path = result.nodes[0].path.as_ref().unwrap();
+        assert_eq!(path.node_ids, vec![alice.id, bob.id, carol.id]);
+        assert_eq!(path.edge_ids, vec![knows.id, mentors.id]);
+        
+        let edge_ids: Vec<_> = result.edges.iter().map(|e| e.edge.id()).collect();
+        assert_eq!(edge_ids, vec![knows.id, mentors.id]);
+    }
+    
+    #[tokio::test]
+    async fn test_staged_query_reports_each_stage() {
+        let graph = InMemoryGraph::new();
+        let (alice, bob) = (node("alice"), node("bob"));
+        for n in [&alice, &bob] {
+            graph.create_node(n.clone()).await.unwrap();
+        }
+        graph.create_edge(edge(&alice, &bob, "knows")).await.unwrap();
+        
+        let store = HybridStore::with_embedding_function(
+            &Config::for_testing(),
+            graph,
+            MockMemory::new(),
+            TemporalIndex::new(),
+            HashingEmbeddings::new(8),
+        ).await.unwrap();
+        
+        let query = HybridQueryBuilder::new()
+            .with_text("bob".to_string())
+            .start_from(alice.id)
+            .follow_outgoing(Some("knows".to_string()))
+            .build();
+        let (tx, mut rx) = tokio::sync::mpsc::channel(4);
+        let result = store.execute_hybrid_query_staged(query, None, tx).await.unwrap();
+        
+        let vector = rx.recv().await.unwrap();
+        assert_eq!(vector.stage, QueryStage::Vector);
+        let graph = rx.recv().await.unwrap();
+        assert_eq!(graph.stage, QueryStage::Graph);
+        let ids = |result: &QueryResult| result.nodes.iter().map(|n| n.node.id()).collect::<Vec<_>>();
+        assert_eq!(ids(&graph.result), ids(&result));
+        assert!(ids(&result).contains(&bob.id));
+        assert!(rx.recv().await.is_none());
+    }
+    
+    #[tokio::test]
+    async fn test_reembed_stale_vectors_after_model_change() {
+        let graph = InMemoryGraph::new();
+        let alice = node("alice");
+        let store = HybridStore::with_embedding_function(
+            &Config::for_testing(),
+            graph,
+            MockMemory::new(),
+            TemporalIndex::new(),
+            HashingEmbeddings::new(8),
+        ).await.unwrap();
+        
+        // A vector produced by a previous, smaller model
+        let old_model = HashingEmbeddings::new(4);
+        let old_vector = old_model.generate_embedding("alice").await.unwrap();
+        let old_metadata = models::EmbeddingMetadata::for_function(&old_model);
+        store.create_node(VectorizedNode::with_metadata(alice.clone(), old_vector, old_metadata)).await.unwrap();
+        
+        let query = query::HybridQueryBuilder::new()
+            .with_embedding(vec![0.5; 4])
+            .build();
+        assert!(matches!(store.execute_hybrid_query(query, None).await, Err(Error::InvalidInput(_))));
+        
+        let report = store.reembed_stale(ReembedOptions {
+            batch_size: 1,
+            pause: std::time::Duration::ZERO,
+            max_batches: None,
+        }).await.unwrap();
+        assert_eq!(report, ReembedReport { reembedded: 1, skipped: 0, batches: 1 });
+        
+        let vectorized = store.get_node(alice.id).await.unwrap();
+        assert_eq!(vectorized.embedding.map(|e| e.len()), Some(8));
+        let metadata = vectorized.embedding_metadata.unwrap();
+        assert!(metadata.is_compatible_with(&HashingEmbeddings::new(8)));
+        
+        // Nothing is stale any more
+        let report = store.reembed_stale(ReembedOptions::default()).await.unwrap();
+        assert_eq!(report, ReembedReport::default());
+    }
+
+    #[tokio::test]
+    async fn test_supplied_embedding_must_match_its_model() {
+        let store = HybridStore::with_embedding_function(
+            &Config::for_testing(),
+            InMemoryGraph::new(),
+            MockMemory::new(),
+            TemporalIndex::new(),
+            HashingEmbeddings::new(8),
+        ).await.unwrap();
+        let alice = node("alice");
+        
+        // Without metadata the vector would be attributed to the active 8-dimensional model
+        let result = store.create_node(VectorizedNode::new(alice.clone(), Some(vec![0.5; 4]))).await;
+        assert!(matches!(result, Err(Error::InvalidInput(_))));
+        assert!(store.graph().get_node(alice.id, crate::types::TemporalFilter::current()).await.is_err());
+        
+        store.create_node(VectorizedNode::new(alice.clone(), Some(vec![0.5; 8]))).await.unwrap();
+        let metadata = store.get_node(alice.id).await.unwrap().embedding_metadata.unwrap();
+        assert!(metadata.is_compatible_with(&HashingEmbeddings::new(8)));
+    }
+
+    #[tokio::test]
+    async fn test_find_similar_nodes_skips_edges_and_deleted_nodes() {
+        let dir = std::env::temp_dir().join(format!("hybrid-similar-{}", Uuid::new_v4()));
+        let store = HybridStore::with_embedding_function(
+            &Config::for_testing(),
+            InMemoryGraph::new(),
+            LocalMemory::open(&dir).await.unwrap(),
+            TemporalIndex::new(),
+            HashingEmbeddings::new(8),
+        ).await.unwrap();
+        let (alice, bob) = (node("alice"), node("bob"));
+        store.create_node(VectorizedNode::new(alice.clone(), None)).await.unwrap();
+        store.create_node(VectorizedNode::new(bob.clone(), None)).await.unwrap();
+        store.create_edge(VectorizedEdge::new(edge(&alice, &bob, "knows"), None)).await.unwrap();
+        
+        // Bob's vector outlives his node
+        store.graph().delete_node(bob.id).await.unwrap();
+        
+        let embedding = HashingEmbeddings::new(8).generate_embedding("knows bob alice").await.unwrap();
+        let ids: Vec<_> = store.find_similar_nodes(embedding, 10, None).await.unwrap()
+            .iter().map(|n| n.id()).collect();
+        assert_eq!(ids, vec![alice.id]);
+        
+        tokio::fs::remove_dir_all(&dir).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_time_window_is_applied_inside_the_vector_search() {
+        let dir = std::env::temp_dir().join(format!("hybrid-window-{}", Uuid::new_v4()));
+        let store = HybridStore::with_embedding_function(
+            &Config::for_testing(),
+            InMemoryGraph::new(),
+            LocalMemory::open(&dir).await.unwrap(),
+            TemporalIndex::new(),
+            HashingEmbeddings::new(8),
+        ).await.unwrap();
+        let year = |y: i32| Timestamp(chrono::TimeZone::with_ymd_and_hms(&Utc, y, 1, 1, 0, 0, 0).unwrap());
+
+        // Closer matches outside the window outnumber the `limit * 2` candidates
+        for _ in 0..5 {
+            let mut recent = node("alice");
+            recent.valid_time = TemporalRange::new(Some(year(2020)), None);
+            store.create_node(VectorizedNode::new(recent, None)).await.unwrap();
+        }
+        let mut old = node("alice smith");
+        old.valid_time = TemporalRange::new(Some(year(1990)), Some(year(2000)));
+        store.create_node(VectorizedNode::new(old.clone(), None)).await.unwrap();
+
+        let query = HybridQueryBuilder::new()
+            .with_text("alice".to_string())
+            .in_time_range(TemporalRange::new(Some(year(1995)), Some(year(1996))))
+            .limit(1)
+            .build();
+        let ids: Vec<_> = store.execute_hybrid_query(query, None).await.unwrap()
+            .nodes.iter().map(|n| n.node.id()).collect();
+        assert_eq!(ids, vec![old.id]);
+
+        tokio::fs::remove_dir_all(&dir).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_tenant_views_are_isolated() {
+        let dir = std::env::temp_dir().join(format!("hybrid-tenants-{}", Uuid::new_v4()));
+        let store = HybridStore::with_embedding_function(
+            &Config::for_testing(),
+            InMemoryGraph::new(),
+            LocalMemory::open(&dir).await.unwrap(),
+            TemporalIndex::new(),
+            HashingEmbeddings::new(8),
+        ).await.unwrap();
+        let acme = store.for_tenant(&TenantId::new("acme").unwrap());
+        let globex = store.for_tenant(&TenantId::new("globex").unwrap());
+
+        let (alice, bob) = (node("alice"), node("bob"));
+        acme.create_node(VectorizedNode::new(alice.clone(), None)).await.unwrap();
+        acme.create_node(VectorizedNode::new(bob.clone(), None)).await.unwrap();
+        acme.create_edge(VectorizedEdge::new(edge(&alice, &bob, "knows"), None)).await.unwrap();
+        let other_alice = node("alice");
+        globex.create_node(VectorizedNode::new(other_alice.clone(), None)).await.unwrap();
+
+        let query = HybridQueryBuilder::new().with_text("alice".to_string()).limit(10).build();
+        let ids: Vec<_> = globex.execute_hybrid_query(query.clone(), None).await.unwrap()
+            .nodes.iter().map(|n| n.node.id()).collect();
+        assert_eq!(ids, vec![other_alice.id]);
+        let ids: Vec<_> = acme.execute_hybrid_query(query, None).await.unwrap()
+            .nodes.iter().map(|n| n.node.id()).collect();
+        assert!(ids.contains(&alice.id) && !ids.contains(&other_alice.id));
+
+        assert!(globex.get_node(alice.id).await.is_err());
+        assert!(globex.create_edge(VectorizedEdge::new(edge(&other_alice, &alice, "knows"), None)).await.is_err());
+        assert!(globex.memory().get(&alice.id.0.to_string()).await.unwrap().is_none());
+
+        tokio::fs::remove_dir_all(&dir).await.unwrap();
+    }
+}
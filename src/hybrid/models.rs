@@ -4,16 +4,25 @@
 //! graph properties with vector embeddings.
 
 use std::sync::Arc;
+use std::time::Duration;
 use async_trait::async_trait;
 use serde::{Serialize, Deserialize};
+use tracing::warn;
 use uuid::Uuid;
 
 use crate::{
+    Config,
+    config::EmbeddingProvider,
     error::{Error, Result},
     graph::{Node, Edge, NodeId, EdgeId, TemporalRange},
+    memory::{MemoryEntry, EMBEDDING_METADATA_KEY},
     types::{EntityType, Properties},
 };
 
+pub use super::embeddings::{
+    HashingEmbeddings, HttpEmbeddingConfig, HttpEmbeddings, LocalEmbeddingConfig, RustBertEmbeddings,
+};
+
 /// Default embedding dimension used for vectors
 pub const DEFAULT_EMBEDDING_DIM: usize = 768;
 
@@ -54,6 +63,39 @@ pub struct EmbeddingMetadata {
     pub properties: Properties,
 }
 
+impl EmbeddingMetadata {
+    /// Describe an embedding generated now by `function`
+    pub fn for_function(function: &dyn EmbeddingFunction) -> Self {
+        Self {
+            model: function.model_name(),
+            model_version: function.model_version(),
+            generated_at: chrono::Utc::now(),
+            dimension: function.embedding_dim(),
+            properties: Properties::new(),
+        }
+    }
+
+    /// Read the metadata persisted with a memory entry, if any
+    pub fn from_entry(entry: &MemoryEntry) -> Option<Self> {
+        entry.metadata
+            .get(EMBEDDING_METADATA_KEY)
+            .and_then(|value| serde_json::from_value(value.clone()).ok())
+    }
+
+    /// Persist this metadata in a memory entry
+    pub fn store_in(&self, entry: &mut MemoryEntry) -> Result<()> {
+        entry.metadata.insert(EMBEDDING_METADATA_KEY.to_string(), serde_json::to_value(self)?);
+        Ok(())
+    }
+
+    /// Whether embeddings described by this metadata can be compared with `function`'s output
+    pub fn is_compatible_with(&self, function: &dyn EmbeddingFunction) -> bool {
+        self.model == function.model_name()
+            && self.model_version == function.model_version()
+            && self.dimension == function.embedding_dim()
+    }
+}
+
 impl VectorizedNode {
     /// Create a new vectorized node from a regular node
     pub fn new(node: Node, embedding: Option<Vec<f32>>) -> Self {
@@ -80,7 +122,7 @@ impl VectorizedNode {
 
     /// Get the embedding, returning an error if not present
     pub fn get_embedding(&self) -> Result<&Vec<f32>> {
-        self.embedding.as_ref().ok_or_else(|| Error::Generic("Node has no embedding".to_string()))
+        self.embedding.as_ref().ok_or_else(|| Error::NotFound("Node has no embedding".to_string()))
     }
 }
 
@@ -110,7 +152,7 @@ impl VectorizedEdge {
 
     /// Get the embedding, returning an error if not present
     pub fn get_embedding(&self) -> Result<&Vec<f32>> {
-        self.embedding.as_ref().ok_or_else(|| Error::Generic("Edge has no embedding".to_string()))
+        self.embedding.as_ref().ok_or_else(|| Error::NotFound("Edge has no embedding".to_string()))
     }
 }
 
@@ -120,6 +162,15 @@ pub trait EmbeddingFunction: Send + Sync {
     /// Generate an embedding for text content
     async fn generate_embedding(&self, text: &str) -> Result<Vec<f32>>;
     
+    /// Generate embeddings for a batch of texts, in input order
+    async fn generate_embeddings(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        let mut embeddings = Vec::with_capacity(texts.len());
+        for text in texts {
+            embeddings.push(self.generate_embedding(text).await?);
+        }
+        Ok(embeddings)
+    }
+    
     /// Get the dimension of embeddings produced by this function
     fn embedding_dim(&self) -> usize;
     
@@ -130,71 +181,48 @@ pub trait EmbeddingFunction: Send + Sync {
     fn model_version(&self) -> String;
 }
 
-/// Implementation of a simple embedding function that uses the rust-bert library
-pub struct RustBertEmbeddings {
-    /// The model used for embeddings
-    model_name: String,
-    /// The model version
-    model_version: String,
-    /// The dimension of the embeddings
-    embedding_dim: usize,
-}
+/// Create the embedding function selected by `config`
+///
+/// The local provider loads the sentence-transformer at `embedding_model_path`
+/// and falls back to hashing embeddings when no path is set.
+pub fn create_embedding_function(config: &Config) -> Result<Box<dyn EmbeddingFunction>> {
+    match config.embedding_provider {
+        EmbeddingProvider::Local => match &config.embedding_model_path {
+            Some(path) => {
+                let mut local = LocalEmbeddingConfig::new(path);
+                local.batch_size = config.batch_size;
+                if let Some(version) = &config.embedding_model_version {
+                    local.model_version = version.clone();
+                }
+                Ok(Box::new(RustBertEmbeddings::load(local)?))
+            }
+            None => {
+                warn!("No embedding model configured; using feature-hashing embeddings");
+                Ok(Box::new(HashingEmbeddings::new(config.memory_size)))
+            }
+        },
+        EmbeddingProvider::Hashing => Ok(Box::new(HashingEmbeddings::new(config.memory_size))),
+        EmbeddingProvider::Http => {
+            let endpoint = config.embedding_endpoint.clone().ok_or_else(|| {
+                Error::ConfigurationError("EMBEDDING_ENDPOINT is required for the http embedding provider".to_string())
+            })?;
+            let model = config.embedding_model.clone().ok_or_else(|| {
+                Error::ConfigurationError("EMBEDDING_MODEL is required for the http embedding provider".to_string())
+            })?;
 
-impl RustBertEmbeddings {
-    /// Create a new RustBertEmbeddings instance
-    pub fn new(model_name: String, model_version: String, embedding_dim: usize) -> Self {
-        Self {
-            model_name,
-            model_version,
-            embedding_dim,
+            let mut http = HttpEmbeddingConfig::new(endpoint, model, config.memory_size);
+            http.api_key = config.embedding_api_key.clone();
+            if let Some(version) = &config.embedding_model_version {
+                http.model_version = version.clone();
+            }
+            http.batch_size = config.batch_size;
+            http.max_retries = config.max_retries;
+            http.timeout = Duration::from_secs(config.connection_timeout.into());
+            Ok(Box::new(HttpEmbeddings::new(http)?))
         }
     }
 }
 
-#[async_trait]
-impl EmbeddingFunction for RustBertEmbeddings {
-    async fn generate_embedding(&self, text: &str) -> Result<Vec<f32>> {
-        // This is a placeholder implementation
-        // In a real implementation, we would use rust-bert to generate embeddings
-        // Since this requires loading large ML models, we'll implement this separately
-        
-        // For now, return a random embedding of the correct dimension
-        let mut rng = rand::thread_rng();
-        let embedding: Vec<f32> = (0..self.embedding_dim)
-            .map(|_| rand::Rng::gen_range(&mut rng, -1.0..1.0))
-            .collect();
-        
-        Ok(embedding)
-    }
-    
-    fn embedding_dim(&self) -> usize {
-        self.embedding_dim
-    }
-    
-    fn model_name(&self) -> String {
-        self.model_name.clone()
-    }
-    
-    fn model_version(&self) -> String {
-        self.model_version.clone()
-    }
-}
-
-/// Factory function to create a new embedding function
-pub fn create_embedding_function(
-    model_name: Option<String>,
-    embedding_dim: Option<usize>,
-) -> Box<dyn EmbeddingFunction> {
-    let model_name = model_name.unwrap_or_else(|| "all-MiniLM-L6-v2".to_string());
-    let embedding_dim = embedding_dim.unwrap_or(DEFAULT_EMBEDDING_DIM);
-    
-    Box::new(RustBertEmbeddings::new(
-        model_name,
-        "1.0".to_string(),
-        embedding_dim,
-    ))
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -223,4 +251,54 @@ mod tests {
         assert_eq!(vectorized_node.id(), node.id);
         assert_eq!(vectorized_node.get_embedding().unwrap(), &embedding);
     }
+    
+    #[test]
+    fn test_embedding_metadata_round_trip() {
+        let hashing = HashingEmbeddings::new(16);
+        let metadata = EmbeddingMetadata::for_function(&hashing);
+        
+        let mut entry = MemoryEntry::new("id".to_string(), "content".to_string());
+        assert!(EmbeddingMetadata::from_entry(&entry).is_none());
+        metadata.store_in(&mut entry).unwrap();
+        
+        let stored = EmbeddingMetadata::from_entry(&entry).unwrap();
+        assert_eq!(stored.model, "feature-hashing");
+        assert_eq!(stored.generated_at, metadata.generated_at);
+        assert!(stored.is_compatible_with(&hashing));
+        assert!(!stored.is_compatible_with(&HashingEmbeddings::new(32)));
+    }
+    
+    #[test]
+    fn test_create_embedding_function_from_config() {
+        let config = Config::for_testing();
+        let embedder = create_embedding_function(&config).unwrap();
+        assert_eq!(embedder.model_name(), "feature-hashing");
+        assert_eq!(embedder.embedding_dim(), config.memory_size);
+        
+        let http = Config {
+            embedding_provider: EmbeddingProvider::Http,
+            embedding_endpoint: Some("http://localhost:8080".to_string()),
+            embedding_model: Some("text-embedding-3-small".to_string()),
+            ..Config::for_testing()
+        };
+        let embedder = create_embedding_function(&http).unwrap();
+        assert_eq!(embedder.model_name(), "text-embedding-3-small");
+        assert_eq!(embedder.model_version(), "1");
+        
+        // Swapped weights under the same model name are told apart by the configured version
+        let reweighted = Config {
+            embedding_model_version: Some("2024-06".to_string()),
+            ..http.clone()
+        };
+        assert_eq!(create_embedding_function(&reweighted).unwrap().model_version(), "2024-06");
+        
+        let missing_endpoint = Config {
+            embedding_endpoint: None,
+            ..http
+        };
+        assert!(matches!(
+            create_embedding_function(&missing_endpoint),
+            Err(Error::ConfigurationError(_))
+        ));
+    }
 } 
\ No newline at end of file
@@ -129,6 +129,25 @@ pub struct QueryResult {
     pub metadata: Option<Value>,
 }
 
+/// Retrieval stage of a hybrid query
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryStage {
+    /// Vector similarity search over node embeddings
+    Vector,
+    /// Traversal from the start node
+    Graph,
+}
+
+/// Fused results of the stages that finished so far
+#[derive(Debug, Clone)]
+pub struct StageResult {
+    /// Stage that just finished
+    pub stage: QueryStage,
+    /// Results fused from this and all earlier stages
+    pub result: QueryResult,
+}
+
 /// A node with a similarity score
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ScoredNode {
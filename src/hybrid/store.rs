@@ -9,7 +9,7 @@ use std::time::{Instant, Duration};
 
 use async_trait::async_trait;
 use chrono::Utc;
-use tokio::sync::RwLock;
+use tokio::sync::{mpsc, RwLock};
 use serde_json::{json, Value};
 use tracing::{debug, error, info, warn};
 use uuid::Uuid;
@@ -17,17 +17,45 @@ use uuid::Uuid;
 use crate::{
     Config,
     error::{Error, Result},
-    graph::{Graph, Node, Edge, NodeId, EdgeId, TemporalRange},
-    memory::{Memory, MemoryEntry, MemoryOperations},
-    temporal::TemporalIndex,
-    types::{EntityType, Properties, Timestamp},
+    graph::{Graph, Node, Edge, NodeId, EdgeId, TemporalFilter, TemporalRange, TenantGraph},
+    memory::{Memory, MemoryEntry, TenantMemory, VectorFilter},
+    temporal::{TemporalIndex, ValidTimeIndex},
+    types::{EntityType, Properties, TenantId, Timestamp, VersionToken},
     hybrid::{
         models::{VectorizedNode, VectorizedEdge, EmbeddingFunction, EmbeddingMetadata, create_embedding_function},
-        query::{HybridQuery, QueryResult, ScoredNode, ScoredEdge, TraversalPath, SimilarityMetric},
+        query::{HybridQuery, QueryResult, QueryStage, ScoredNode, ScoredEdge, SimilarityMetric, StageResult},
         fusion::{FusionStrategy, default_fusion_strategy},
+        traversal::TraversalExecutor,
     },
+    telemetry::timed,
+    cancellation,
 };
 
+/// Backend label on the metrics recorded by `HybridStore`
+const BACKEND: &str = "hybrid";
+
+/// Backend label on the latency of embedding generation
+const EMBEDDING_BACKEND: &str = "embedding";
+
+/// Text representation of a node used for embedding
+fn node_embedding_text(node: &Node) -> String {
+    format!(
+        "{} {}\n{:?}",
+        node.label,
+        node.entity_type.to_string(),
+        node.properties,
+    )
+}
+
+/// Text representation of an edge used for embedding
+fn edge_embedding_text(edge: &Edge) -> String {
+    format!(
+        "{}\n{:?}",
+        edge.label,
+        edge.properties,
+    )
+}
+
 /// Main implementation of the hybrid graph store
 pub struct HybridStore {
     /// Reference to the underlying graph store
@@ -35,7 +63,9 @@ pub struct HybridStore {
     /// Reference to the vector memory store
     memory: Arc<dyn Memory>,
     /// Reference to the temporal index
-    temporal_index: Arc<dyn TemporalIndex>,
+    temporal_index: Arc<dyn ValidTimeIndex>,
+    /// Tenant whose entries are read from the temporal index
+    tenant: TenantId,
     /// Embedding function for generating vector embeddings
     embedding_function: Arc<dyn EmbeddingFunction>,
     /// Configuration
@@ -48,18 +78,16 @@ impl HybridStore {
         config: &Config,
         graph: impl Graph + 'static,
         memory: impl Memory + 'static,
-        temporal_index: impl TemporalIndex + 'static,
+        temporal_index: TemporalIndex,
     ) -> Result<Self> {
-        let embedding_function = create_embedding_function(
-            None, // Use default model
-            None, // Use default dimension
-        );
+        let embedding_function = create_embedding_function(config)?;
         
         Ok(Self {
             graph: Arc::new(graph),
             memory: Arc::new(memory),
             temporal_index: Arc::new(temporal_index),
-            embedding_function: Arc::new(embedding_function),
+            tenant: TenantId::default(),
+            embedding_function: Arc::from(embedding_function),
             config: config.clone(),
         })
     }
@@ -69,56 +97,108 @@ impl HybridStore {
         config: &Config,
         graph: impl Graph + 'static,
         memory: impl Memory + 'static,
-        temporal_index: impl TemporalIndex + 'static,
+        temporal_index: TemporalIndex,
         embedding_function: impl EmbeddingFunction + 'static,
     ) -> Result<Self> {
         Ok(Self {
             graph: Arc::new(graph),
             memory: Arc::new(memory),
             temporal_index: Arc::new(temporal_index),
+            tenant: TenantId::default(),
             embedding_function: Arc::new(embedding_function),
             config: config.clone(),
         })
     }
     
-    /// Generate a vector embedding for a node
-    async fn generate_node_embedding(&self, node: &Node) -> Result<Vec<f32>> {
-        // Extract text representation of the node for embedding
-        let text = format!(
-            "{} {}\n{:?}",
-            node.label,
-            node.entity_type.to_string(),
-            node.properties,
-        );
-        
-        self.embedding_function.generate_embedding(&text).await
+    /// Create a hybrid store over stores shared with the rest of the application
+    pub fn from_shared(
+        config: &Config,
+        graph: Arc<dyn Graph>,
+        memory: Arc<dyn Memory>,
+        temporal_index: Arc<dyn ValidTimeIndex>,
+        embedding_function: Arc<dyn EmbeddingFunction>,
+    ) -> Self {
+        Self {
+            graph,
+            memory,
+            temporal_index,
+            tenant: TenantId::default(),
+            embedding_function,
+            config: config.clone(),
+        }
+    }
+
+    /// View of this store restricted to `tenant`
+    ///
+    /// Graph and memory access go through `TenantGraph` and `TenantMemory`.
+    /// The temporal index is shared: lookups only return entries stamped
+    /// with `tenant`, and every element they point to is read back through
+    /// the scoped graph.
+    pub fn for_tenant(&self, tenant: &TenantId) -> Self {
+        Self {
+            graph: Arc::new(TenantGraph::new(self.graph.clone(), tenant.clone())),
+            memory: Arc::new(TenantMemory::new(self.memory.clone(), tenant.clone())),
+            temporal_index: self.temporal_index.clone(),
+            tenant: tenant.clone(),
+            embedding_function: self.embedding_function.clone(),
+            config: self.config.clone(),
+        }
+    }
+
+    /// The graph store, as seen by this view
+    pub fn graph(&self) -> Arc<dyn Graph> {
+        self.graph.clone()
+    }
+
+    /// The vector memory store, as seen by this view
+    pub fn memory(&self) -> Arc<dyn Memory> {
+        self.memory.clone()
+    }
+
+    /// Embedding to store for an element, with the metadata describing it
+    ///
+    /// Without an `embedding`, one is generated from `text` by the active
+    /// model. A supplied vector without metadata is taken to come from the
+    /// active model, so it must have that model's dimension; a vector that
+    /// disagrees with its dimension is rejected either way.
+    async fn resolve_embedding(
+        &self,
+        embedding: Option<Vec<f32>>,
+        metadata: Option<EmbeddingMetadata>,
+        text: String,
+    ) -> Result<(Vec<f32>, EmbeddingMetadata)> {
+        let Some(embedding) = embedding else {
+            return Ok((self.embed(&text).await?, self.create_embedding_metadata()));
+        };
+        let metadata = metadata.unwrap_or_else(|| self.create_embedding_metadata());
+        if embedding.len() != metadata.dimension {
+            return Err(Error::InvalidInput(format!(
+                "Embedding has dimension {} but model {} produces {}",
+                embedding.len(),
+                metadata.model,
+                metadata.dimension
+            )));
+        }
+        Ok((embedding, metadata))
     }
     
-    /// Generate a vector embedding for an edge
-    async fn generate_edge_embedding(&self, edge: &Edge) -> Result<Vec<f32>> {
-        // Extract text representation of the edge for embedding
-        let text = format!(
-            "{}\n{:?}",
-            edge.label,
-            edge.properties,
-        );
-        
-        self.embedding_function.generate_embedding(&text).await
+    /// Embed `text` with the active embedding function
+    async fn embed(&self, text: &str) -> Result<Vec<f32>> {
+        timed(EMBEDDING_BACKEND, "generate_embedding", self.embedding_function.generate_embedding(text)).await
     }
     
-    /// Create embedding metadata
+    /// Create embedding metadata for vectors generated now by the active embedding function
     fn create_embedding_metadata(&self) -> EmbeddingMetadata {
-        EmbeddingMetadata {
-            model: self.embedding_function.model_name(),
-            model_version: self.embedding_function.model_version(),
-            generated_at: Utc::now(),
-            dimension: self.embedding_function.embedding_dim(),
-            properties: Properties::new(),
-        }
+        EmbeddingMetadata::for_function(self.embedding_function.as_ref())
+    }
+    
+    /// Whether a stored vector with `metadata` may be compared with the active model's vectors
+    fn is_comparable(&self, metadata: Option<&EmbeddingMetadata>) -> bool {
+        metadata.map_or(false, |metadata| metadata.is_compatible_with(self.embedding_function.as_ref()))
     }
     
     /// Store node embedding in vector store
-    async fn store_node_embedding(&self, node: &Node, embedding: &[f32]) -> Result<()> {
+    async fn store_node_embedding(&self, node: &Node, embedding: &[f32], embedding_metadata: &EmbeddingMetadata) -> Result<()> {
         let metadata = json!({
             "id": node.id.0.to_string(),
             "type": "node",
@@ -133,21 +213,22 @@ impl HybridStore {
         let mut meta_map = HashMap::new();
         meta_map.insert("metadata".to_string(), metadata);
         
-        let memory_entry = MemoryEntry {
+        let mut memory_entry = MemoryEntry {
             id: node.id.0.to_string(),
             created_at: Utc::now(),
             updated_at: Utc::now(),
             content: node.label.clone(),
             metadata: meta_map,
             embedding: Some(embedding.to_vec()),
-            node_type: Some(node.entity_type),
+            node_type: Some(node.entity_type.clone()),
         };
+        embedding_metadata.store_in(&mut memory_entry)?;
         
         self.memory.store(memory_entry).await
     }
     
     /// Store edge embedding in vector store
-    async fn store_edge_embedding(&self, edge: &Edge, embedding: &[f32]) -> Result<()> {
+    async fn store_edge_embedding(&self, edge: &Edge, embedding: &[f32], embedding_metadata: &EmbeddingMetadata) -> Result<()> {
         let metadata = json!({
             "id": edge.id.0.to_string(),
             "type": "edge",
@@ -163,7 +244,7 @@ impl HybridStore {
         let mut meta_map = HashMap::new();
         meta_map.insert("metadata".to_string(), metadata);
         
-        let memory_entry = MemoryEntry {
+        let mut memory_entry = MemoryEntry {
             id: edge.id.0.to_string(),
             created_at: Utc::now(),
             updated_at: Utc::now(),
@@ -172,53 +253,34 @@ impl HybridStore {
             embedding: Some(embedding.to_vec()),
             node_type: None, // Edges don't have a node_type
         };
+        embedding_metadata.store_in(&mut memory_entry)?;
         
         self.memory.store(memory_entry).await
     }
     
-    /// Find node embedding in vector store
-    async fn find_node_embedding(&self, node_id: &NodeId) -> Result<Option<Vec<f32>>> {
-        let result = self.memory.get(node_id.0.to_string().as_str()).await?;
-        
-        match result {
-            Some(entry) => Ok(entry.embedding),
-            None => Ok(None),
-        }
-    }
-    
-    /// Find edge embedding in vector store
-    async fn find_edge_embedding(&self, edge_id: &EdgeId) -> Result<Option<Vec<f32>>> {
-        let result = self.memory.get(edge_id.0.to_string().as_str()).await?;
-        
-        match result {
-            Some(entry) => Ok(entry.embedding),
-            None => Ok(None),
+    /// Find an embedding and its persisted metadata in the vector store
+    async fn find_embedding(&self, id: &Uuid) -> Result<(Option<Vec<f32>>, Option<EmbeddingMetadata>)> {
+        match self.memory.get(id.to_string().as_str()).await? {
+            Some(entry) => {
+                let metadata = EmbeddingMetadata::from_entry(&entry);
+                Ok((entry.embedding, metadata))
+            }
+            None => Ok((None, None)),
         }
     }
     
-    /// Convert a MemoryEntry to a VectorizedNode
-    async fn memory_entry_to_vectorized_node(&self, entry: MemoryEntry) -> Result<VectorizedNode> {
+    /// Convert a MemoryEntry to a VectorizedNode, reading the node through `filter`
+    async fn memory_entry_to_vectorized_node(&self, entry: MemoryEntry, filter: TemporalFilter) -> Result<VectorizedNode> {
         let node_id = entry.id.parse::<Uuid>()
-            .map_err(|_| Error::Generic(format!("Invalid node ID: {}", entry.id)))?;
+            .map_err(|_| Error::InvalidId(format!("Invalid node ID: {}", entry.id)))?;
             
-        let node = self.graph.get_node(NodeId(node_id)).await?;
-        
-        let metadata = if let Some(ref embedding) = entry.embedding {
-            Some(EmbeddingMetadata {
-                model: self.embedding_function.model_name(),
-                model_version: self.embedding_function.model_version(),
-                generated_at: entry.updated_at,
-                dimension: embedding.len(),
-                properties: Properties::new(),
-            })
-        } else {
-            None
-        };
+        let node = self.graph.get_node(NodeId(node_id), filter).await?;
+        let embedding_metadata = EmbeddingMetadata::from_entry(&entry);
         
         Ok(VectorizedNode {
             node,
             embedding: entry.embedding,
-            embedding_metadata: metadata,
+            embedding_metadata,
         })
     }
     
@@ -233,22 +295,34 @@ impl HybridStore {
     }
     
     /// Convert vector search results to scored nodes
+    ///
+    /// Entries whose node is not visible through `filter` are dropped.
     async fn vector_results_to_scored_nodes(
         &self,
         entries: Vec<MemoryEntry>,
         query_embedding: &[f32],
         similarity_metric: SimilarityMetric,
+        filter: &TemporalFilter,
     ) -> Result<Vec<ScoredNode>> {
         use crate::hybrid::query::vector_similarity::compute_similarity;
         
         let mut results = Vec::with_capacity(entries.len());
         
         for entry in entries {
+            if !self.is_comparable(EmbeddingMetadata::from_entry(&entry).as_ref()) {
+                debug!("Skipping {}: embedding was produced by a different model", entry.id);
+                continue;
+            }
+            
             if let Some(ref entry_embedding) = entry.embedding {
-                let node = self.memory_entry_to_vectorized_node(entry).await?;
-                
                 let score = compute_similarity(query_embedding, entry_embedding, similarity_metric);
                 
+                let node = match self.memory_entry_to_vectorized_node(entry, filter.clone()).await {
+                    Ok(node) => node,
+                    Err(Error::NodeNotFound(_)) => continue,
+                    Err(e) => return Err(e),
+                };
+                
                 results.push(ScoredNode {
                     node,
                     score,
@@ -261,136 +335,373 @@ impl HybridStore {
     }
 }
 
-#[async_trait]
-impl super::HybridGraph for HybridStore {
-    async fn create_node(&self, mut node: VectorizedNode) -> Result<NodeId> {
-        // Create the node in the graph
-        let node_id = self.graph.create_node(node.node.clone()).await?;
-        
-        // Generate embedding if not provided
-        if node.embedding.is_none() {
-            let embedding = self.generate_node_embedding(&node.node).await?;
-            node.embedding = Some(embedding);
-            node.embedding_metadata = Some(self.create_embedding_metadata());
+/// Options for re-embedding vectors produced by an older model
+#[derive(Debug, Clone)]
+pub struct ReembedOptions {
+    /// Number of stale entries regenerated per batch
+    pub batch_size: usize,
+    /// Delay between batches, throttling load on the embedding model and vector store
+    pub pause: Duration,
+    /// Stop after this many batches; `None` runs until no stale entries remain
+    pub max_batches: Option<usize>,
+}
+
+impl Default for ReembedOptions {
+    fn default() -> Self {
+        Self {
+            batch_size: 64,
+            pause: Duration::from_millis(100),
+            max_batches: None,
         }
-        
-        // Store the embedding
-        if let Some(ref embedding) = node.embedding {
-            self.store_node_embedding(&node.node, embedding).await?;
+    }
+}
+
+/// Summary of a re-embedding run
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReembedReport {
+    /// Entries whose vectors were regenerated
+    pub reembedded: usize,
+    /// Entries skipped because their node or edge no longer exists
+    pub skipped: usize,
+    /// Batches processed
+    pub batches: usize,
+}
+
+impl HybridStore {
+    /// Regenerate every vector not produced by the active embedding function
+    ///
+    /// Stale entries are fetched from the vector store in batches of
+    /// `options.batch_size`, re-embedded from the current graph element and
+    /// written back with fresh embedding metadata.
+    pub async fn reembed_stale(&self, options: ReembedOptions) -> Result<ReembedReport> {
+        let batch_size = options.batch_size.max(1);
+        let model = self.embedding_function.model_name();
+        let model_version = self.embedding_function.model_version();
+        let dimension = self.embedding_function.embedding_dim();
+        let mut report = ReembedReport::default();
+        let mut attempted = std::collections::HashSet::new();
+        
+        info!("Re-embedding vectors not produced by {} {}", model, model_version);
+        
+        loop {
+            if options.max_batches.map_or(false, |max| report.batches >= max) {
+                break;
+            }
+            
+            let stale = self.memory.get_stale_embeddings(&model, &model_version, dimension, batch_size).await?;
+            // Entries that could not be re-embedded stay stale; stop once a batch has nothing new
+            let batch: Vec<MemoryEntry> = stale.into_iter()
+                .filter(|entry| attempted.insert(entry.id.clone()))
+                .collect();
+            if batch.is_empty() {
+                break;
+            }
+            
+            if report.batches > 0 && !options.pause.is_zero() {
+                tokio::time::sleep(options.pause).await;
+            }
+            
+            let mut entries = Vec::with_capacity(batch.len());
+            let mut texts = Vec::with_capacity(batch.len());
+            for entry in batch {
+                match self.embedding_text(&entry).await? {
+                    Some(text) => {
+                        texts.push(text);
+                        entries.push(entry);
+                    }
+                    None => {
+                        debug!("Skipping re-embedding of {}: source element no longer exists", entry.id);
+                        report.skipped += 1;
+                    }
+                }
+            }
+            
+            if !entries.is_empty() {
+                let embeddings = timed(EMBEDDING_BACKEND, "generate_embeddings", self.embedding_function.generate_embeddings(&texts)).await?;
+                let embedding_metadata = self.create_embedding_metadata();
+                let mut updated = Vec::with_capacity(entries.len());
+                for (mut entry, embedding) in entries.into_iter().zip(embeddings) {
+                    entry.embedding = Some(embedding);
+                    entry.updated_at = Utc::now();
+                    embedding_metadata.store_in(&mut entry)?;
+                    updated.push(entry);
+                }
+                report.reembedded += updated.len();
+                self.memory.store_bulk(updated).await?;
+            }
+            
+            report.batches += 1;
         }
         
-        Ok(node_id)
+        info!(
+            "Re-embedded {} vectors in {} batches ({} skipped)",
+            report.reembedded, report.batches, report.skipped
+        );
+        Ok(report)
     }
     
-    async fn get_node(&self, id: NodeId) -> Result<VectorizedNode> {
-        // Get the node from the graph
-        let node = self.graph.get_node(id).await?;
-        
-        // Find embedding in vector store
-        let embedding = self.find_node_embedding(&id).await?;
-        
-        // Create metadata if embedding exists
-        let embedding_metadata = if let Some(ref emb) = embedding {
-            Some(EmbeddingMetadata {
-                model: self.embedding_function.model_name(),
-                model_version: self.embedding_function.model_version(),
-                generated_at: Utc::now(), // We don't know when it was generated
-                dimension: emb.len(),
-                properties: Properties::new(),
-            })
-        } else {
-            None
-        };
-        
-        Ok(VectorizedNode {
-            node,
-            embedding,
-            embedding_metadata,
+    /// Run `reembed_stale` in the background
+    pub fn spawn_reembedding(self: Arc<Self>, options: ReembedOptions) -> tokio::task::JoinHandle<Result<ReembedReport>> {
+        tokio::spawn(async move {
+            let report = self.reembed_stale(options).await;
+            if let Err(e) = &report {
+                error!("Background re-embedding failed: {}", e);
+            }
+            report
         })
     }
     
-    async fn update_node(&self, mut node: VectorizedNode) -> Result<()> {
-        // Update the node in the graph
-        self.graph.update_node(node.node.clone()).await?;
-        
-        // Generate embedding if not provided
-        if node.embedding.is_none() {
-            let embedding = self.generate_node_embedding(&node.node).await?;
-            node.embedding = Some(embedding);
-            node.embedding_metadata = Some(self.create_embedding_metadata());
+    /// Text to embed for a stored entry, read from the current graph element
+    ///
+    /// Returns `None` when the node or edge the entry describes no longer exists.
+    async fn embedding_text(&self, entry: &MemoryEntry) -> Result<Option<String>> {
+        let element_type = entry.metadata.get("metadata")
+            .and_then(|metadata| metadata.get("type"))
+            .and_then(Value::as_str);
+        let id = entry.id.parse::<Uuid>();
+        
+        match (element_type, id) {
+            (Some("node"), Ok(id)) => match self.graph.get_node(NodeId(id), TemporalFilter::current()).await {
+                Ok(node) => Ok(Some(node_embedding_text(&node))),
+                Err(Error::NodeNotFound(_)) => Ok(None),
+                Err(e) => Err(e),
+            },
+            (Some("edge"), Ok(id)) => match self.graph.get_edge(EdgeId(id), TemporalFilter::current()).await {
+                Ok(edge) => Ok(Some(edge_embedding_text(&edge))),
+                Err(Error::EdgeNotFound(_)) => Ok(None),
+                Err(e) => Err(e),
+            },
+            _ => Ok(Some(entry.content.clone())),
         }
+    }
+}
+
+impl HybridStore {
+    /// Execute a hybrid query, sending fused results to `stages` as each retrieval stage finishes
+    ///
+    /// Returns the same result as `execute_hybrid_query`. The graph stage
+    /// only runs for queries with a start node.
+    pub async fn execute_hybrid_query_staged(
+        &self,
+        query: HybridQuery,
+        fusion_strategy: Option<Box<dyn FusionStrategy>>,
+        stages: mpsc::Sender<StageResult>,
+    ) -> Result<QueryResult> {
+        self.run_hybrid_query(query, fusion_strategy, Some(&stages)).await
+    }
+    
+    /// Shared body of the plain and staged hybrid queries
+    async fn run_hybrid_query(
+        &self,
+        query: HybridQuery,
+        fusion_strategy: Option<Box<dyn FusionStrategy>>,
+        stages: Option<&mpsc::Sender<StageResult>>,
+    ) -> Result<QueryResult> {
+        timed(BACKEND, "execute_hybrid_query", async {
+            let start_time = Instant::now();
+        
+            // Validate the query requirements
+            if query.query_embedding.is_none() && query.query_text.is_none() && query.start_node_id.is_none() {
+                return Err(Error::InvalidInput(
+                    "Hybrid query must have either a query embedding, query text, or a start node ID".to_string()
+                ));
+            }
         
-        // Store the embedding
-        if let Some(ref embedding) = node.embedding {
-            self.store_node_embedding(&node.node, embedding).await?;
-        }
+            // Generate embedding from text if provided
+            let mut query = query;
+            if let Some(text) = &query.query_text {
+                query.query_embedding = Some(self.embed(text).await?);
+            }
+            let query_embedding = query.query_embedding.clone();
+        
+            // Refuse to compare vectors from a different embedding space
+            if let Some(embedding) = &query_embedding {
+                if embedding.len() != self.embedding_function.embedding_dim() {
+                    return Err(Error::InvalidInput(format!(
+                        "Query embedding has dimension {} but the active model {} produces {}",
+                        embedding.len(),
+                        self.embedding_function.model_name(),
+                        self.embedding_function.embedding_dim()
+                    )));
+                }
+            }
         
-        Ok(())
+            let fusion = fusion_strategy.unwrap_or_else(default_fusion_strategy);
+            let mut vector_results = Vec::new();
+            let mut graph_results = Vec::new();
+        
+            // Execute vector similarity search if we have an embedding
+            if let Some(embedding) = &query_embedding {
+                cancellation::check()?;
+                // Only node embeddings can be returned as results
+                let mut filter = VectorFilter::new().with_metadata("metadata.type", json!("node"));
+                if let Some(node_types) = &query.node_type_filter {
+                    filter = filter.with_node_types(node_types.clone());
+                }
+                if let Some(custom_filter) = &query.custom_filter {
+                    filter = filter.with_query(custom_filter.clone());
+                }
+                // Apply the time window inside the search so it does not eat into the limit
+                if let Some(range) = &query.temporal_range {
+                    filter = filter.valid_during(range.clone());
+                }
+                let filter = filter.to_query();
+            
+                let entries = self.execute_vector_search(embedding, query.limit * 2, filter).await?;
+            
+                // The graph remains the source of truth for valid time, should the index lag
+                vector_results = self.vector_results_to_scored_nodes(
+                    entries,
+                    embedding,
+                    query.similarity_metric,
+                    &query.temporal_range.clone().into(),
+                ).await?;
+            
+                if let Some(stages) = stages {
+                    let result = fusion.fuse_with_context(vector_results.clone(), Vec::new(), query.limit).await?;
+                    // A dropped receiver only means nobody is listening any more
+                    let _ = stages.send(StageResult { stage: QueryStage::Vector, result }).await;
+                }
+            }
+        
+            // Walk the traversal steps if we have a start node
+            let mut path_edges = HashMap::new();
+            if let Some(start_node_id) = query.start_node_id {
+                cancellation::check()?;
+                let output = TraversalExecutor::for_query(self.graph.as_ref(), self.memory.as_ref(), &query)
+                    .with_embedding_function(self.embedding_function.as_ref())
+                    .execute(start_node_id, &query.traversal_steps)
+                    .await?;
+            
+                graph_results = output.nodes.into_iter()
+                    .filter(|scored| query.node_type_filter.as_ref()
+                        .map_or(true, |types| types.contains(&scored.node.node.entity_type)))
+                    .collect();
+                path_edges = output.edges;
+            }
+        
+            // Apply fusion strategy
+            cancellation::check()?;
+            let mut result = fusion.fuse_with_context(
+                vector_results,
+                graph_results,
+                query.limit,
+            ).await?;
+        
+            // Return the edges along each returned path so results can be explained
+            if query.include_graph_structure {
+                let mut seen = std::collections::HashSet::new();
+                for scored in &result.nodes {
+                    let edge_ids = scored.path.iter().flat_map(|path| path.edge_ids.iter());
+                    for edge_id in edge_ids {
+                        if let (true, Some(edge)) = (seen.insert(*edge_id), path_edges.get(edge_id)) {
+                            result.edges.push(ScoredEdge {
+                                edge: VectorizedEdge::new(edge.clone(), None),
+                                score: scored.score,
+                            });
+                        }
+                    }
+                }
+            }
+            result.execution_time_ms = start_time.elapsed().as_millis() as u64;
+        
+            if let (Some(stages), Some(_)) = (stages, query.start_node_id) {
+                let _ = stages.send(StageResult { stage: QueryStage::Graph, result: result.clone() }).await;
+            }
+        
+            Ok(result)
+        }).await
+    }
+}
+
+#[async_trait]
+impl super::HybridGraph for HybridStore {
+    async fn create_node(&self, node: VectorizedNode) -> Result<NodeId> {
+        timed(BACKEND, "create_node", async {
+            // Check or generate the embedding before anything is written
+            let text = node_embedding_text(&node.node);
+            let (embedding, metadata) = self.resolve_embedding(node.embedding, node.embedding_metadata, text).await?;
+        
+            // Create the node in the graph
+            let node_id = self.graph.create_node(node.node.clone()).await?;
+            self.store_node_embedding(&node.node, &embedding, &metadata).await?;
+        
+            Ok(node_id)
+        }).await
     }
     
-    async fn create_edge(&self, mut edge: VectorizedEdge) -> Result<EdgeId> {
-        // Create the edge in the graph
-        let edge_id = self.graph.create_edge(edge.edge.clone()).await?;
-        
-        // Generate embedding if not provided
-        if edge.embedding.is_none() {
-            let embedding = self.generate_edge_embedding(&edge.edge).await?;
-            edge.embedding = Some(embedding);
-            edge.embedding_metadata = Some(self.create_embedding_metadata());
-        }
+    async fn get_node(&self, id: NodeId) -> Result<VectorizedNode> {
+        timed(BACKEND, "get_node", async {
+            // Get the node from the graph
+            let node = self.graph.get_node(id, TemporalFilter::current()).await?;
         
-        // Store the embedding
-        if let Some(ref embedding) = edge.embedding {
-            self.store_edge_embedding(&edge.edge, embedding).await?;
-        }
+            // Find embedding and the metadata recorded when it was stored
+            let (embedding, embedding_metadata) = self.find_embedding(&id.0).await?;
         
-        Ok(edge_id)
+            Ok(VectorizedNode {
+                node,
+                embedding,
+                embedding_metadata,
+            })
+        }).await
     }
     
-    async fn get_edge(&self, id: EdgeId) -> Result<VectorizedEdge> {
-        // Get the edge from the graph
-        let edge = self.graph.get_edge(id).await?;
-        
-        // Find embedding in vector store
-        let embedding = self.find_edge_embedding(&id).await?;
-        
-        // Create metadata if embedding exists
-        let embedding_metadata = if let Some(ref emb) = embedding {
-            Some(EmbeddingMetadata {
-                model: self.embedding_function.model_name(),
-                model_version: self.embedding_function.model_version(),
-                generated_at: Utc::now(), // We don't know when it was generated
-                dimension: emb.len(),
-                properties: Properties::new(),
-            })
-        } else {
-            None
-        };
-        
-        Ok(VectorizedEdge {
-            edge,
-            embedding,
-            embedding_metadata,
-        })
+    async fn update_node(&self, node: VectorizedNode, expected: Option<VersionToken>) -> Result<()> {
+        timed(BACKEND, "update_node", async {
+            // Check or generate the embedding before anything is written
+            let text = node_embedding_text(&node.node);
+            let (embedding, metadata) = self.resolve_embedding(node.embedding, node.embedding_metadata, text).await?;
+        
+            // Update the node in the graph
+            self.graph.update_node(node.node.clone(), expected).await?;
+            self.store_node_embedding(&node.node, &embedding, &metadata).await?;
+        
+            Ok(())
+        }).await
     }
     
-    async fn update_edge(&self, mut edge: VectorizedEdge) -> Result<()> {
-        // Update the edge in the graph
-        self.graph.update_edge(edge.edge.clone()).await?;
-        
-        // Generate embedding if not provided
-        if edge.embedding.is_none() {
-            let embedding = self.generate_edge_embedding(&edge.edge).await?;
-            edge.embedding = Some(embedding);
-            edge.embedding_metadata = Some(self.create_embedding_metadata());
-        }
+    async fn create_edge(&self, edge: VectorizedEdge) -> Result<EdgeId> {
+        timed(BACKEND, "create_edge", async {
+            // Check or generate the embedding before anything is written
+            let text = edge_embedding_text(&edge.edge);
+            let (embedding, metadata) = self.resolve_embedding(edge.embedding, edge.embedding_metadata, text).await?;
+        
+            // Create the edge in the graph
+            let edge_id = self.graph.create_edge(edge.edge.clone()).await?;
+            self.store_edge_embedding(&edge.edge, &embedding, &metadata).await?;
+        
+            Ok(edge_id)
+        }).await
+    }
+    
+    async fn get_edge(&self, id: EdgeId) -> Result<VectorizedEdge> {
+        timed(BACKEND, "get_edge", async {
+            // Get the edge from the graph
+            let edge = self.graph.get_edge(id, TemporalFilter::current()).await?;
         
-        // Store the embedding
-        if let Some(ref embedding) = edge.embedding {
-            self.store_edge_embedding(&edge.edge, embedding).await?;
-        }
+            // Find embedding and the metadata recorded when it was stored
+            let (embedding, embedding_metadata) = self.find_embedding(&id.0).await?;
         
-        Ok(())
+            Ok(VectorizedEdge {
+                edge,
+                embedding,
+                embedding_metadata,
+            })
+        }).await
+    }
+    
+    async fn update_edge(&self, edge: VectorizedEdge, expected: Option<VersionToken>) -> Result<()> {
+        timed(BACKEND, "update_edge", async {
+            // Check or generate the embedding before anything is written
+            let text = edge_embedding_text(&edge.edge);
+            let (embedding, metadata) = self.resolve_embedding(edge.embedding, edge.embedding_metadata, text).await?;
+        
+            // Update the edge in the graph
+            self.graph.update_edge(edge.edge.clone(), expected).await?;
+            self.store_edge_embedding(&edge.edge, &embedding, &metadata).await?;
+        
+            Ok(())
+        }).await
     }
     
     async fn find_similar_nodes(
@@ -399,16 +710,31 @@ impl super::HybridGraph for HybridStore {
         limit: usize, 
         filter: Option<Value>
     ) -> Result<Vec<VectorizedNode>> {
-        let entries = self.execute_vector_search(&embedding, limit, filter).await?;
+        timed(BACKEND, "find_similar_nodes", async {
+            // Only node embeddings can be returned as results
+            let mut vector_filter = VectorFilter::new().with_metadata("metadata.type", json!("node"));
+            if let Some(filter) = filter {
+                vector_filter = vector_filter.with_query(filter);
+            }
+            let entries = self.execute_vector_search(&embedding, limit, vector_filter.to_query()).await?;
         
-        let mut nodes = Vec::with_capacity(entries.len());
+            let mut nodes = Vec::with_capacity(entries.len());
         
-        for entry in entries {
-            let node = self.memory_entry_to_vectorized_node(entry).await?;
-            nodes.push(node);
-        }
+            for entry in entries {
+                if !self.is_comparable(EmbeddingMetadata::from_entry(&entry).as_ref()) {
+                    debug!("Skipping {}: embedding was produced by a different model", entry.id);
+                    continue;
+                }
+                // Nodes deleted from the graph may linger in the vector store
+                match self.memory_entry_to_vectorized_node(entry, TemporalFilter::current()).await {
+                    Ok(node) => nodes.push(node),
+                    Err(Error::NodeNotFound(_)) => continue,
+                    Err(e) => return Err(e),
+                }
+            }
         
-        Ok(nodes)
+            Ok(nodes)
+        }).await
     }
     
     async fn execute_hybrid_query(
@@ -416,117 +742,7 @@ impl super::HybridGraph for HybridStore {
         query: HybridQuery,
         fusion_strategy: Option<Box<dyn FusionStrategy>>,
     ) -> Result<QueryResult> {
-        let start_time = Instant::now();
-        
-        // Validate the query requirements
-        if query.query_embedding.is_none() && query.query_text.is_none() && query.start_node_id.is_none() {
-            return Err(Error::Generic(
-                "Hybrid query must have either a query embedding, query text, or a start node ID".to_string()
-            ));
-        }
-        
-        // Generate embedding from text if provided
-        let query_embedding = if let Some(text) = &query.query_text {
-            Some(self.embedding_function.generate_embedding(text).await?)
-        } else {
-            query.query_embedding
-        };
-        
-        let mut vector_results = Vec::new();
-        let mut graph_results = Vec::new();
-        
-        // Execute vector similarity search if we have an embedding
-        if let Some(embedding) = &query_embedding {
-            let filter = if let Some(node_types) = &query.node_type_filter {
-                let types: Vec<String> = node_types.iter()
-                    .map(|t| t.to_string())
-                    .collect();
-                    
-                Some(json!({
-                    "filter": {
-                        "terms": {
-                            "node_type": types
-                        }
-                    }
-                }))
-            } else {
-                query.custom_filter.clone()
-            };
-            
-            let entries = self.execute_vector_search(embedding, query.limit * 2, filter).await?;
-            
-            vector_results = self.vector_results_to_scored_nodes(
-                entries,
-                embedding,
-                query.similarity_metric,
-            ).await?;
-        }
-        
-        // Execute graph traversal if we have a start node
-        if let Some(start_node_id) = query.start_node_id {
-            // This would execute graph traversal and collect results
-            // For now, we'll just fetch connected nodes as a simple example
-            
-            let connected_nodes = if let Some(range) = query.temporal_range {
-                self.graph.get_connected_nodes(start_node_id, Some(range)).await?
-            } else {
-                self.graph.get_connected_nodes(start_node_id, None).await?
-            };
-            
-            // Convert to vectorized nodes and assign scores
-            for node in connected_nodes {
-                let embedding = self.find_node_embedding(&node.id).await?;
-                
-                let score = if let (Some(node_emb), Some(query_emb)) = (&embedding, &query_embedding) {
-                    // Calculate similarity if we have both embeddings
-                    use crate::hybrid::query::vector_similarity::compute_similarity;
-                    compute_similarity(node_emb, query_emb, query.similarity_metric)
-                } else {
-                    // Assign default score if we don't have embeddings
-                    0.5
-                };
-                
-                // Create path information
-                let path = Some(TraversalPath {
-                    node_ids: vec![start_node_id, node.id],
-                    edge_ids: vec![], // We don't have edge IDs in this simplified example
-                    depth: 1,
-                });
-                
-                let metadata = if let Some(ref emb) = embedding {
-                    Some(EmbeddingMetadata {
-                        model: self.embedding_function.model_name(),
-                        model_version: self.embedding_function.model_version(),
-                        generated_at: Utc::now(),
-                        dimension: emb.len(),
-                        properties: Properties::new(),
-                    })
-                } else {
-                    None
-                };
-                
-                graph_results.push(ScoredNode {
-                    node: VectorizedNode {
-                        node,
-                        embedding,
-                        embedding_metadata: metadata,
-                    },
-                    score,
-                    path,
-                });
-            }
-        }
-        
-        // Apply fusion strategy
-        let fusion = fusion_strategy.unwrap_or_else(default_fusion_strategy);
-        
-        let result = fusion.fuse_with_context(
-            vector_results,
-            graph_results,
-            query.limit,
-        ).await?;
-        
-        Ok(result)
+        self.run_hybrid_query(query, fusion_strategy, None).await
     }
     
     async fn get_knowledge_in_time_range(
@@ -535,67 +751,56 @@ impl super::HybridGraph for HybridStore {
         query_embedding: Option<Vec<f32>>,
         limit: usize,
     ) -> Result<Vec<VectorizedNode>> {
-        // Get entity IDs in time range from temporal index
-        let entries = self.temporal_index.get_in_time_range(time_range, limit * 2).await?;
-        
-        let mut nodes = Vec::with_capacity(entries.len());
-        
-        // Fetch nodes from graph store
-        for entry in entries {
-            if let Ok(uuid) = Uuid::parse_str(&entry.entity_id) {
-                if let Ok(node) = self.graph.get_node(NodeId(uuid)).await {
-                    // Find embedding for this node
-                    let embedding = self.find_node_embedding(&node.id).await?;
-                    
-                    // Create metadata if embedding exists
-                    let embedding_metadata = if let Some(ref emb) = embedding {
-                        Some(EmbeddingMetadata {
-                            model: self.embedding_function.model_name(),
-                            model_version: self.embedding_function.model_version(),
-                            generated_at: Utc::now(),
-                            dimension: emb.len(),
-                            properties: Properties::new(),
-                        })
-                    } else {
-                        None
-                    };
+        timed(BACKEND, "get_knowledge_in_time_range", async {
+            // Get the tenant's node IDs in time range from temporal index
+            let entries = self.temporal_index.nodes_in_range(&self.tenant, &time_range, limit * 2).await?;
+        
+            let mut nodes = Vec::with_capacity(entries.len());
+        
+            // Fetch nodes from graph store
+            for entry in entries {
+                if let Ok(uuid) = Uuid::parse_str(&entry.entity_id.id) {
+                    if let Ok(node) = self.graph.get_node(NodeId(uuid), TemporalFilter::current()).await {
+                        // Find embedding for this node
+                        let (embedding, embedding_metadata) = self.find_embedding(&node.id.0).await?;
                     
-                    nodes.push(VectorizedNode {
-                        node,
-                        embedding,
-                        embedding_metadata,
-                    });
+                        nodes.push(VectorizedNode {
+                            node,
+                            embedding,
+                            embedding_metadata,
+                        });
+                    }
                 }
             }
-        }
         
-        // Sort by vector similarity if query embedding provided
-        if let Some(query_emb) = query_embedding {
-            use crate::hybrid::query::vector_similarity::{compute_similarity, SimilarityMetric};
+            // Sort by vector similarity if query embedding provided
+            if let Some(query_emb) = query_embedding {
+                use crate::hybrid::query::vector_similarity::compute_similarity;
             
-            // Filter to nodes with embeddings
-            let mut nodes_with_scores: Vec<_> = nodes.into_iter()
-                .filter_map(|node| {
-                    node.embedding.as_ref().map(|emb| {
-                        let score = compute_similarity(emb, &query_emb, SimilarityMetric::Cosine);
-                        (node, score)
+                // Filter to nodes with embeddings from the active model
+                let mut nodes_with_scores: Vec<_> = nodes.into_iter()
+                    .filter(|node| self.is_comparable(node.embedding_metadata.as_ref()))
+                    .filter_map(|node| {
+                        let score = node.embedding.as_ref()
+                            .map(|emb| compute_similarity(emb, &query_emb, SimilarityMetric::Cosine))?;
+                        Some((node, score))
                     })
-                })
-                .collect();
+                    .collect();
                 
-            // Sort by score
-            nodes_with_scores.sort_by(|(_, a), (_, b)| b.partial_cmp(a).unwrap_or(std::cmp::Ordering::Equal));
+                // Sort by score
+                nodes_with_scores.sort_by(|(_, a), (_, b)| b.partial_cmp(a).unwrap_or(std::cmp::Ordering::Equal));
             
-            // Take top results
-            nodes = nodes_with_scores.into_iter()
-                .take(limit)
-                .map(|(node, _)| node)
-                .collect();
-        } else {
-            // Limit results if no embedding to sort by
-            nodes.truncate(limit);
-        }
+                // Take top results
+                nodes = nodes_with_scores.into_iter()
+                    .take(limit)
+                    .map(|(node, _)| node)
+                    .collect();
+            } else {
+                // Limit results if no embedding to sort by
+                nodes.truncate(limit);
+            }
         
-        Ok(nodes)
+            Ok(nodes)
+        }).await
     }
 } 
\ No newline at end of file
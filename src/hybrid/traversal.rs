@@ -0,0 +1,530 @@
+//! Multi-hop traversal executor for hybrid queries
+//!
+//! This module walks the `TraversalStep`s of a `HybridQuery` over the
+//! underlying graph, keeping the full node and edge path to every result so
+//! callers can explain why a node was returned.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    cancellation,
+    error::{Error, Result},
+    graph::{Edge, EdgeId, Graph, Node, NodeId, TemporalFilter},
+    hybrid::{
+        models::{EmbeddingFunction, EmbeddingMetadata, VectorizedNode},
+        query::{
+            vector_similarity::compute_similarity,
+            HybridQuery, ScoredNode, SimilarityMetric, TraversalDirection, TraversalPath, TraversalStep,
+        },
+    },
+    memory::Memory,
+    telemetry::record_cache_lookup,
+};
+
+/// Cache label on the hit and miss counters of per-query embedding lookups
+const EMBEDDING_CACHE: &str = "traversal_embeddings";
+
+/// A partial path ending at `node`
+#[derive(Debug, Clone)]
+struct PathState {
+    node: Node,
+    node_ids: Vec<NodeId>,
+    edge_ids: Vec<EdgeId>,
+    score: f32,
+}
+
+impl PathState {
+    fn depth(&self) -> usize {
+        self.edge_ids.len()
+    }
+
+    fn contains(&self, id: &NodeId) -> bool {
+        self.node_ids.contains(id)
+    }
+
+    fn extend(&self, edge: &Edge, node: Node) -> Self {
+        let mut next = self.clone();
+        next.node_ids.push(node.id);
+        next.edge_ids.push(edge.id);
+        next.node = node;
+        next
+    }
+}
+
+/// Result of executing the traversal steps of a query
+#[derive(Debug, Clone, Default)]
+pub struct TraversalOutput {
+    /// Nodes reached by the final step, with their paths
+    pub nodes: Vec<ScoredNode>,
+    /// Every edge appearing on a returned path
+    pub edges: HashMap<EdgeId, Edge>,
+}
+
+/// Executes `TraversalStep`s from a start node
+///
+/// Each step expands the current frontier by between one and `max_depth`
+/// hops along matching edges. Paths never revisit a node, and within a step
+/// only the first (shortest) path to a node is kept, so cycles terminate.
+pub struct TraversalExecutor<'a> {
+    graph: &'a dyn Graph,
+    memory: &'a dyn Memory,
+    filter: TemporalFilter,
+    query_embedding: Option<&'a [f32]>,
+    metric: SimilarityMetric,
+    beam_width: usize,
+    embedding_function: Option<&'a dyn EmbeddingFunction>,
+    embeddings: HashMap<NodeId, Option<Vec<f32>>>,
+}
+
+impl<'a> TraversalExecutor<'a> {
+    /// Create an executor reading from `graph` and scoring with embeddings from `memory`
+    pub fn new(graph: &'a dyn Graph, memory: &'a dyn Memory) -> Self {
+        Self {
+            graph,
+            memory,
+            filter: TemporalFilter::current(),
+            query_embedding: None,
+            metric: SimilarityMetric::default(),
+            beam_width: usize::MAX,
+            embedding_function: None,
+            embeddings: HashMap::new(),
+        }
+    }
+
+    /// Configure the executor from a hybrid query
+    pub fn for_query(graph: &'a dyn Graph, memory: &'a dyn Memory, query: &'a HybridQuery) -> Self {
+        Self::new(graph, memory)
+            .with_filter(query.temporal_range.clone().into())
+            .with_similarity(query.query_embedding.as_deref(), query.similarity_metric)
+            .with_beam_width(query.limit.saturating_mul(2))
+    }
+
+    /// Set the temporal filter applied to every node and edge read
+    pub fn with_filter(mut self, filter: TemporalFilter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    /// Set the embedding used to re-score steps with `use_vector_similarity`
+    pub fn with_similarity(mut self, embedding: Option<&'a [f32]>, metric: SimilarityMetric) -> Self {
+        self.query_embedding = embedding;
+        self.metric = metric;
+        self
+    }
+
+    /// Ignore stored vectors that were not produced by `function`
+    pub fn with_embedding_function(mut self, function: &'a dyn EmbeddingFunction) -> Self {
+        self.embedding_function = Some(function);
+        self
+    }
+
+    /// Set how many paths a similarity-ranked step keeps
+    pub fn with_beam_width(mut self, width: usize) -> Self {
+        self.beam_width = width.max(1);
+        self
+    }
+
+    /// Walk `steps` from `start`, returning the nodes reached by the last step
+    ///
+    /// With no steps, a single hop in both directions is taken.
+    pub async fn execute(&mut self, start: NodeId, steps: &[TraversalStep]) -> Result<TraversalOutput> {
+        let default_step;
+        let steps = if steps.is_empty() {
+            default_step = [TraversalStep {
+                edge_label: None,
+                direction: TraversalDirection::Both,
+                max_depth: None,
+                node_type_filter: None,
+                use_vector_similarity: true,
+                required_properties: None,
+            }];
+            &default_step[..]
+        } else {
+            steps
+        };
+
+        let start_node = self.graph.get_node(start, self.filter.clone()).await?;
+        let mut frontier = vec![PathState {
+            node_ids: vec![start_node.id],
+            edge_ids: Vec::new(),
+            node: start_node,
+            score: 1.0,
+        }];
+
+        for step in steps {
+            frontier = self.execute_step(frontier, step).await?;
+            if frontier.is_empty() {
+                break;
+            }
+        }
+
+        self.into_output(frontier).await
+    }
+
+    /// Expand every path in `frontier` by one step
+    async fn execute_step(&mut self, frontier: Vec<PathState>, step: &TraversalStep) -> Result<Vec<PathState>> {
+        let max_depth = step.max_depth.unwrap_or(1);
+        if max_depth == 0 {
+            return Err(Error::InvalidInput("Traversal step max_depth must be at least 1".to_string()));
+        }
+
+        let mut reached = Vec::new();
+        let mut seen = HashSet::new();
+        let mut layer = frontier;
+
+        for _ in 0..max_depth {
+            let mut next_layer = Vec::new();
+
+            for path in &layer {
+                // Wide frontiers make many lookups, so stop between them once cancelled
+                cancellation::check()?;
+                for edge in self.edges_for_step(&path.node.id, step).await? {
+                    let neighbor_id = match neighbor(&edge, &path.node.id, step.direction) {
+                        Some(id) => id,
+                        None => continue,
+                    };
+                    if path.contains(&neighbor_id) || !seen.insert(neighbor_id) {
+                        continue;
+                    }
+
+                    let neighbor = match self.graph.get_node(neighbor_id, self.filter.clone()).await {
+                        Ok(node) => node,
+                        Err(Error::NodeNotFound(_)) => continue,
+                        Err(e) => return Err(e),
+                    };
+
+                    let next = path.extend(&edge, neighbor);
+                    if matches_step(&next.node, step) {
+                        reached.push(next.clone());
+                    }
+                    next_layer.push(next);
+                }
+            }
+
+            if next_layer.is_empty() {
+                break;
+            }
+            layer = next_layer;
+        }
+
+        for path in &mut reached {
+            path.score = self.score(&path.node.id, path.depth()).await?;
+        }
+
+        if step.use_vector_similarity && self.query_embedding.is_some() {
+            reached.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
+            reached.truncate(self.beam_width);
+        }
+
+        Ok(reached)
+    }
+
+    /// Edges leaving `node_id` in the step's direction with the step's label
+    async fn edges_for_step(&self, node_id: &NodeId, step: &TraversalStep) -> Result<Vec<Edge>> {
+        let edges = match step.direction {
+            TraversalDirection::Out => self.graph.get_edges_from(*node_id, self.filter.clone()).await?,
+            TraversalDirection::In => self.graph.get_edges_to(*node_id, self.filter.clone()).await?,
+            TraversalDirection::Both => self.graph.get_edges_for_node(*node_id, self.filter.clone()).await?,
+        };
+
+        Ok(match &step.edge_label {
+            Some(label) => edges.into_iter().filter(|edge| &edge.label == label).collect(),
+            None => edges,
+        })
+    }
+
+    /// Score a node reached at `depth` hops
+    ///
+    /// Uses vector similarity to the query when both embeddings are known,
+    /// otherwise a structural score that decays with depth.
+    async fn score(&mut self, node_id: &NodeId, depth: usize) -> Result<f32> {
+        if let Some(query_embedding) = self.query_embedding {
+            if let Some(embedding) = self.embedding(node_id).await? {
+                return Ok(compute_similarity(&embedding, query_embedding, self.metric));
+            }
+        }
+        Ok(1.0 / (1.0 + depth as f32))
+    }
+
+    /// Look up a node's comparable embedding, caching the result
+    async fn embedding(&mut self, node_id: &NodeId) -> Result<Option<Vec<f32>>> {
+        let cached = self.embeddings.get(node_id);
+        record_cache_lookup(EMBEDDING_CACHE, cached.is_some());
+        if let Some(cached) = cached {
+            return Ok(cached.clone());
+        }
+
+        let entry = self.memory.get(&node_id.0.to_string()).await?;
+        let embedding = entry.and_then(|entry| match self.embedding_function {
+            Some(function) => EmbeddingMetadata::from_entry(&entry)
+                .filter(|metadata| metadata.is_compatible_with(function))
+                .and(entry.embedding),
+            None => entry.embedding,
+        });
+        self.embeddings.insert(*node_id, embedding.clone());
+        Ok(embedding)
+    }
+
+    async fn into_output(&mut self, frontier: Vec<PathState>) -> Result<TraversalOutput> {
+        let mut output = TraversalOutput::default();
+
+        for path in frontier {
+            for edge_id in &path.edge_ids {
+                if !output.edges.contains_key(edge_id) {
+                    let edge = self.graph.get_edge(*edge_id, self.filter.clone()).await?;
+                    output.edges.insert(*edge_id, edge);
+                }
+            }
+
+            let embedding = self.embedding(&path.node.id).await?;
+            output.nodes.push(ScoredNode {
+                node: VectorizedNode::new(path.node, embedding),
+                score: path.score,
+                path: Some(TraversalPath {
+                    depth: path.edge_ids.len(),
+                    node_ids: path.node_ids,
+                    edge_ids: path.edge_ids,
+                }),
+            });
+        }
+
+        Ok(output)
+    }
+}
+
+/// The node on the other side of `edge` when walked from `from` in `direction`
+fn neighbor(edge: &Edge, from: &NodeId, direction: TraversalDirection) -> Option<NodeId> {
+    match direction {
+        TraversalDirection::Out if edge.source_id == *from => Some(edge.target_id),
+        TraversalDirection::In if edge.target_id == *from => Some(edge.source_id),
+        TraversalDirection::Both if edge.source_id == *from => Some(edge.target_id),
+        TraversalDirection::Both if edge.target_id == *from => Some(edge.source_id),
+        _ => None,
+    }
+}
+
+/// Check a node against the step's type and property filters
+fn matches_step(node: &Node, step: &TraversalStep) -> bool {
+    let type_matches = step.node_type_filter.as_ref()
+        .map_or(true, |types| types.contains(&node.entity_type));
+    let has_properties = step.required_properties.as_ref()
+        .map_or(true, |keys| keys.iter().all(|key| node.properties.0.contains_key(key)));
+
+    type_matches && has_properties
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        graph::InMemoryGraph,
+        memory::{MemoryEntry, MockMemory},
+        types::{EntityType, Properties, TemporalRange},
+    };
+    use serde_json::json;
+    use uuid::Uuid;
+
+    fn node(label: &str, entity_type: EntityType) -> Node {
+        Node {
+            id: NodeId(Uuid::new_v4()),
+            entity_type,
+            label: label.to_string(),
+            properties: Properties::new(),
+            valid_time: TemporalRange::unbounded(),
+            transaction_time: TemporalRange::from_now(),
+        }
+    }
+
+    fn edge(source: &Node, target: &Node, label: &str) -> Edge {
+        Edge {
+            id: EdgeId(Uuid::new_v4()),
+            source_id: source.id,
+            target_id: target.id,
+            label: label.to_string(),
+            properties: Properties::new(),
+            valid_time: TemporalRange::unbounded(),
+            transaction_time: TemporalRange::from_now(),
+        }
+    }
+
+    fn step(edge_label: &str, direction: TraversalDirection) -> TraversalStep {
+        TraversalStep {
+            edge_label: Some(edge_label.to_string()),
+            direction,
+            max_depth: None,
+            node_type_filter: None,
+            use_vector_similarity: false,
+            required_properties: None,
+        }
+    }
+
+    async fn store_embedding(memory: &MockMemory, node: &Node, embedding: Vec<f32>) {
+        let mut entry = MemoryEntry::new(node.id.0.to_string(), node.label.clone());
+        entry.embedding = Some(embedding);
+        memory.store(entry).await.unwrap();
+    }
+
+    /// alice -knows-> bob -works_at-> acme, carol -knows-> alice, bob -knows-> alice
+    async fn fixture() -> (InMemoryGraph, Vec<Node>, Vec<Edge>) {
+        let graph = InMemoryGraph::new();
+        let alice = node("alice", EntityType::Person);
+        let bob = node("bob", EntityType::Person);
+        let carol = node("carol", EntityType::Person);
+        let mut acme = node("acme", EntityType::Organization);
+        acme.properties.0.insert("industry".to_string(), json!("widgets"));
+
+        for n in [&alice, &bob, &carol, &acme] {
+            graph.create_node(n.clone()).await.unwrap();
+        }
+
+        let edges = vec![
+            edge(&alice, &bob, "knows"),
+            edge(&bob, &acme, "works_at"),
+            edge(&carol, &alice, "knows"),
+            edge(&bob, &alice, "knows"),
+        ];
+        for e in &edges {
+            graph.create_edge(e.clone()).await.unwrap();
+        }
+
+        (graph, vec![alice, bob, carol, acme], edges)
+    }
+
+    #[tokio::test]
+    async fn test_multi_hop_paths() {
+        let (graph, nodes, edges) = fixture().await;
+        let memory = MockMemory::new();
+        let (alice, bob, acme) = (&nodes[0], &nodes[1], &nodes[3]);
+
+        let steps = vec![
+            step("knows", TraversalDirection::Out),
+            step("works_at", TraversalDirection::Out),
+        ];
+        let output = TraversalExecutor::new(&graph, &memory)
+            .execute(alice.id, &steps)
+            .await
+            .unwrap();
+
+        assert_eq!(output.nodes.len(), 1);
+        let result = &output.nodes[0];
+        assert_eq!(result.node.id(), acme.id);
+
+        let path = result.path.as_ref().unwrap();
+        assert_eq!(path.depth, 2);
+        assert_eq!(path.node_ids, vec![alice.id, bob.id, acme.id]);
+        assert_eq!(path.edge_ids, vec![edges[0].id, edges[1].id]);
+        assert_eq!(output.edges.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_direction_and_cycles() {
+        let (graph, nodes, _) = fixture().await;
+        let memory = MockMemory::new();
+        let (alice, bob, carol) = (&nodes[0], &nodes[1], &nodes[2]);
+
+        let incoming = TraversalExecutor::new(&graph, &memory)
+            .execute(alice.id, &[step("knows", TraversalDirection::In)])
+            .await
+            .unwrap();
+        let mut ids: Vec<_> = incoming.nodes.iter().map(|n| n.node.id()).collect();
+        ids.sort_by_key(|id| id.0);
+        let mut expected = vec![bob.id, carol.id];
+        expected.sort_by_key(|id| id.0);
+        assert_eq!(ids, expected);
+
+        // alice and bob know each other; deep traversal must not loop or revisit alice
+        let mut deep = step("knows", TraversalDirection::Both);
+        deep.max_depth = Some(5);
+        let output = TraversalExecutor::new(&graph, &memory)
+            .execute(alice.id, &[deep])
+            .await
+            .unwrap();
+        assert_eq!(output.nodes.len(), 2);
+        assert!(output.nodes.iter().all(|n| n.node.id() != alice.id));
+        assert!(output.nodes.iter().all(|n| n.path.as_ref().unwrap().depth == 1));
+    }
+
+    #[tokio::test]
+    async fn test_step_filters() {
+        let (graph, nodes, _) = fixture().await;
+        let memory = MockMemory::new();
+        let (alice, acme) = (&nodes[0], &nodes[3]);
+
+        let mut any_two_hops = TraversalStep {
+            edge_label: None,
+            direction: TraversalDirection::Out,
+            max_depth: Some(2),
+            node_type_filter: Some(vec![EntityType::Organization]),
+            use_vector_similarity: false,
+            required_properties: None,
+        };
+        let output = TraversalExecutor::new(&graph, &memory)
+            .execute(alice.id, &[any_two_hops.clone()])
+            .await
+            .unwrap();
+        assert_eq!(output.nodes.len(), 1);
+        assert_eq!(output.nodes[0].node.id(), acme.id);
+
+        any_two_hops.node_type_filter = None;
+        any_two_hops.required_properties = Some(vec!["founded".to_string()]);
+        let output = TraversalExecutor::new(&graph, &memory)
+            .execute(alice.id, &[any_two_hops])
+            .await
+            .unwrap();
+        assert!(output.nodes.is_empty());
+
+        let mut invalid = step("knows", TraversalDirection::Out);
+        invalid.max_depth = Some(0);
+        assert!(matches!(
+            TraversalExecutor::new(&graph, &memory).execute(alice.id, &[invalid]).await,
+            Err(Error::InvalidInput(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_similarity_rescoring() {
+        let (graph, nodes, _) = fixture().await;
+        let memory = MockMemory::new();
+        let (alice, bob, carol) = (&nodes[0], &nodes[1], &nodes[2]);
+        store_embedding(&memory, bob, vec![1.0, 0.0]).await;
+        store_embedding(&memory, carol, vec![0.0, 1.0]).await;
+
+        let query_embedding = vec![0.1, 0.9];
+        let mut knows = step("knows", TraversalDirection::Both);
+        knows.use_vector_similarity = true;
+
+        let output = TraversalExecutor::new(&graph, &memory)
+            .with_similarity(Some(&query_embedding), SimilarityMetric::Cosine)
+            .with_beam_width(1)
+            .execute(alice.id, &[knows])
+            .await
+            .unwrap();
+
+        assert_eq!(output.nodes.len(), 1);
+        assert_eq!(output.nodes[0].node.id(), carol.id);
+        assert!(output.nodes[0].score > 0.9);
+        assert!(output.nodes[0].node.embedding.is_some());
+
+        // Without similarity every neighbour is kept with a structural score
+        let output = TraversalExecutor::new(&graph, &memory)
+            .with_beam_width(1)
+            .execute(alice.id, &[])
+            .await
+            .unwrap();
+        assert_eq!(output.nodes.len(), 2);
+        assert!(output.nodes.iter().all(|n| (n.score - 0.5).abs() < f32::EPSILON));
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_traversal_stops() {
+        let (graph, nodes, _) = fixture().await;
+        let memory = MockMemory::new();
+        let token = cancellation::CancellationToken::new();
+        token.cancel();
+
+        let steps = vec![step("knows", TraversalDirection::Out)];
+        let mut executor = TraversalExecutor::new(&graph, &memory);
+        let result = cancellation::scoped(token, executor.execute(nodes[0].id, &steps)).await;
+        assert!(matches!(result, Err(Error::Cancelled)));
+    }
+}
@@ -5,6 +5,8 @@
 
 pub mod api;
 pub mod aws;
+pub mod cancellation;
+pub mod changes;
 pub mod config;
 pub mod context;
 pub mod error;
@@ -13,6 +15,7 @@ pub mod hybrid;
 pub mod memory;
 pub mod mcp;
 pub mod rag;
+pub mod telemetry;
 pub mod temporal;
 pub mod types;
 
@@ -22,12 +25,12 @@ pub use crate::{
     error::{Error, Result},
 };
 
-pub use types::{EntityId, Timestamp, TemporalRange};
+pub use types::{EntityId, Timestamp, TemporalFilter, TemporalRange};
 pub use graph::Graph;
 pub use types::{Node, Edge, NodeId, EdgeId};
 pub use temporal::{DynamoDBTemporal as TemporalGraphStore, TemporalIndex, TemporalIndexEntry};
-pub use memory::{MemorySystem, MemoryEntry, Memory};
-pub use rag::{RAGSystem, RAGConfig, ExtractedEntity, DetectedRelationship};
+pub use memory::{MemorySystem, MemoryEntry, Memory, KnnSettings, VectorFilter};
+pub use rag::{RAGSystem, RAGConfig, ExtractedEntity, DetectedRelationship, ProcessedText};
 pub use hybrid::{HybridGraph, HybridStore, VectorizedNode, VectorizedEdge};
 
 /// Re-export common types
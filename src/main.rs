@@ -6,7 +6,7 @@ use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 use tower_http::trace::TraceLayer;
 
 use graph::{
-    api,
+    api::{self, ApiState},
     config::Config,
 };
 
@@ -19,18 +19,23 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     println!("Starting temporal knowledge graph API server...");
 
-    // Load config for testing/local development
-    let config = Config::for_testing();
+    // Load config from the environment; in-process stores only with --dev
+    let config = if std::env::args().any(|arg| arg == "--dev") {
+        tracing::warn!("Using the in-process development configuration");
+        Config::for_development()
+    } else {
+        Config::from_env()?
+    };
 
-    // Create router - the state is now created inside the function
-    let app = api::create_router()
+    let state = Arc::new(ApiState::from_config(&config).await?);
+    let app = api::create_router(state)
         .layer(TraceLayer::new_for_http());
 
     // Start server
     let addr = std::net::SocketAddr::from(([127, 0, 0, 1], 3000));
     let listener = tokio::net::TcpListener::bind(addr).await?;
     println!("Listening on {}", addr);
-    axum::serve(listener, app).await?;
+    axum::serve(listener, app.into_make_service_with_connect_info::<std::net::SocketAddr>()).await?;
 
     Ok(())
 }
\ No newline at end of file
@@ -1,196 +1,253 @@
 use axum::{
     extract::State,
-    response::{IntoResponse, Json},
+    response::{IntoResponse, Json, Response},
 };
 use std::sync::Arc;
 use uuid::Uuid;
-use serde_json::json;
+use serde_json::{json, Value};
 
 use crate::{
-    api::ApiState,
-    error::{Error, Result},
+    api::{handlers::insert_node, models::CreateNodeRequest, ApiError, ApiResult, ApiState, TenantScope},
+    hybrid::{query::{HybridQueryBuilder, ScoredNode}, HybridGraph},
+    types::{EntityType, Node, NodeId, Properties, TemporalFilter},
 };
-use super::{MCPRequest, MCPResponse};
+use super::{navigation::{navigate, Navigation}, MCPRequest, MCPResponse};
 
 /// Handle mcp request
-#[axum::debug_handler]
+#[axum::debug_handler(state = Arc<ApiState>)]
 pub async fn handle_mcp(
     State(state): State<Arc<ApiState>>,
+    scope: TenantScope,
     Json(request): Json<MCPRequest>,
-) -> impl IntoResponse {
-    match process_request(state, request).await {
-        Ok(response) => Json(response),
+) -> Response {
+    match process_request(&state, &scope, request).await {
+        Ok(response) => Json(response).into_response(),
         Err(e) => {
-            // Return error response
+            // Unknown ids and invalid arguments keep their status
+            let (status, message) = e.into_status_and_message();
             let error_response = MCPResponse::Error { 
-                error: format!("Error processing MCP request: {}", e),
+                error: format!("Error processing MCP request: {}", message),
             };
-            Json(error_response)
+            (status, Json(error_response)).into_response()
         }
     }
 }
 
-async fn process_request(_state: Arc<ApiState>, request: MCPRequest) -> Result<MCPResponse> {
+/// Run a graph command, query, cancellation or navigation request against the stores of `scope`
+///
+/// Commands and queries are registered under their `request_id` while they
+/// run, so that a `Cancel` request or `DELETE /requests/{id}` can stop them.
+pub(crate) async fn process_request(state: &ApiState, scope: &TenantScope, request: MCPRequest) -> ApiResult<MCPResponse> {
+    match request {
+        MCPRequest::Cancel { request_id } => {
+            if !state.requests.cancel(&scope.tenant, &request_id) {
+                return Err(ApiError::NotFound(format!("No running request {}", request_id)));
+            }
+            Ok(MCPResponse::Cancelled { request_id })
+        },
+        MCPRequest::Command { ref request_id, .. } | MCPRequest::Query { ref request_id, .. } => {
+            let request_id = request_id.clone();
+            state.requests.run(&scope.tenant, &request_id, run_request(state, scope, request)).await
+        },
+        request => run_request(state, scope, request).await,
+    }
+}
+
+/// Run a request without registering it for cancellation
+///
+/// Connections, search results and history are bounded by the state's
+/// [`McpLimits`](super::McpLimits), or by a smaller `limit` argument.
+pub(crate) async fn run_request(state: &ApiState, scope: &TenantScope, request: MCPRequest) -> ApiResult<MCPResponse> {
+    let limits = state.mcp_limits;
     match request {
         MCPRequest::Command { request_id, command, data: request_data, cursor_position: _cursor_position } => {
             match command.as_str() {
                 "get_entity" => {
-                    let entity_id = match request_data.get("id") {
-                        Some(id) => id.as_str().ok_or(Error::InvalidInput("Invalid entity ID".into()))?,
-                        None => return Ok(MCPResponse::Error { error: "Missing entity ID".to_string() }),
-                    };
+                    let node = scope.graph.get_node(entity_id(&request_data)?, TemporalFilter::current()).await?;
                     
-                    // Since the ApiState doesn't have a temporal field, we'll return a mock response
-                    // In a real implementation, you would use the actual temporal system
-                    Ok(MCPResponse::Entity { 
-                        request_id,
-                        entity: json!({
-                            "id": entity_id,
-                            "type": "node", 
-                            "properties": {},
-                            "note": "This is a mock response as ApiState doesn't have temporal field"
-                        })
-                    })
+                    Ok(MCPResponse::Entity { request_id, entity: json!(node) })
                 },
                 "get_connections" => {
-                    let entity_id = match request_data.get("id") {
-                        Some(id) => id.as_str().ok_or(Error::InvalidInput("Invalid entity ID".into()))?,
-                        None => return Ok(MCPResponse::Error { error: "Missing entity ID".to_string() }),
-                    };
-
-                    // Return mock response
-                    Ok(MCPResponse::Connections { 
-                        request_id,
-                        connections: json!([
-                            {
-                                "id": Uuid::new_v4().to_string(),
-                                "type": "RELATED_TO",
-                                "source": entity_id,
-                                "target": Uuid::new_v4().to_string(),
-                                "properties": {}
-                            }
-                        ])
-                    })
+                    let node_id = entity_id(&request_data)?;
+                    let limit = limit(&request_data, limits.max_results)?;
+                    
+                    // Unknown entities have no edges, so check the entity exists first
+                    scope.graph.get_node(node_id, TemporalFilter::current()).await?;
+                    let edges = scope.graph.get_edges_for_node(node_id, TemporalFilter::current()).await?;
+                    let connections: Vec<_> = edges.into_iter()
+                        .take(limit)
+                        .map(|edge| {
+                            let direction = if edge.source_id == node_id { "outgoing" } else { "incoming" };
+                            json!({ "direction": direction, "edge": edge })
+                        })
+                        .collect();
+                    
+                    Ok(MCPResponse::Connections { request_id, connections: json!(connections) })
                 },
                 "get_entity_history" => {
-                    let entity_id = match request_data.get("id") {
-                        Some(id) => id.as_str().ok_or(Error::InvalidInput("Invalid entity ID".into()))?,
-                        None => return Ok(MCPResponse::Error { error: "Missing entity ID".to_string() }),
-                    };
-
-                    // Return mock response
-                    Ok(MCPResponse::History { 
-                        request_id,
-                        history: json!([
-                            {
-                                "id": entity_id,
-                                "timestamp": "2023-05-01T12:00:00Z",
-                                "type": "node",
-                                "properties": { "name": "Initial state" }
-                            },
-                            {
-                                "id": entity_id,
-                                "timestamp": "2023-05-02T15:30:00Z", 
-                                "type": "node",
-                                "properties": { "name": "Updated state" }
-                            }
-                        ])
-                    })
+                    let node_id = entity_id(&request_data)?;
+                    let limit = limit(&request_data, limits.max_history)?;
+                    
+                    let history: Vec<_> = entity_history(scope, node_id, limit).await?
+                        .iter()
+                        .map(history_entry)
+                        .collect();
+                    
+                    Ok(MCPResponse::History { request_id, history: json!(history) })
                 },
                 "search_entities" => {
-                    let query = match request_data.get("query") {
-                        Some(q) => q.as_str().ok_or(Error::InvalidInput("Invalid query".into()))?,
-                        None => return Ok(MCPResponse::Error { error: "Missing query".to_string() }),
-                    };
-
-                    // Return mock search results
-                    Ok(MCPResponse::SearchResults { 
-                        request_id,
-                        results: json!([
-                            {
-                                "id": Uuid::new_v4().to_string(),
-                                "type": "node",
-                                "score": 0.95,
-                                "properties": { "name": format!("Result for '{}'", query) }
-                            },
-                            {
-                                "id": Uuid::new_v4().to_string(),
-                                "type": "node", 
-                                "score": 0.82,
-                                "properties": { "name": format!("Another match for '{}'", query) }
-                            }
-                        ])
-                    })
+                    let query = text(&request_data, "query")?;
+                    let limit = limit(&request_data, limits.max_results)?;
+                    
+                    let results: Vec<_> = search(scope, query, limit).await?
+                        .into_iter()
+                        .map(|scored| {
+                            let node = scored.node.node;
+                            json!({
+                                "id": node.id.0,
+                                "type": node.entity_type,
+                                "label": node.label,
+                                "score": scored.score,
+                                "properties": node.properties,
+                            })
+                        })
+                        .collect();
+                    
+                    Ok(MCPResponse::SearchResults { request_id, results: json!(results) })
                 },
                 "create_entity" => {
-                    // Mock entity creation
-                    let id = Uuid::new_v4().to_string();
+                    let entity_type = match request_data.get("entity_type") {
+                        Some(entity_type) => serde_json::from_value::<EntityType>(entity_type.clone())
+                            .map_err(|_| ApiError::BadRequest(format!("Invalid entity type: {}", entity_type)))?,
+                        None => EntityType::default(),
+                    };
+                    let properties = match request_data.get("properties") {
+                        Some(properties @ Value::Object(_)) => Properties::from_json(properties.clone())
+                            .map_err(|e| ApiError::BadRequest(e.to_string()))?,
+                        Some(_) => return Err(ApiError::BadRequest("Entity properties must be an object".to_string())),
+                        None => Properties::new(),
+                    };
+                    let label = request_data.get("label").and_then(Value::as_str).unwrap_or_default().to_string();
+                    
+                    let node = insert_node(scope, CreateNodeRequest { entity_type, label, properties, valid_time: None }).await?;
                     
                     Ok(MCPResponse::EntityCreated { 
                         request_id,
-                        id: id.clone(),
-                        entity: json!({
-                            "id": id,
-                            "type": "node",
-                            "properties": request_data.get("properties").unwrap_or(&json!({}))
-                        })
+                        id: node.id.0.to_string(),
+                        entity: json!(node),
                     })
                 },
+                "navigate" => {
+                    let request: Navigation = serde_json::from_value(request_data)
+                        .map_err(|e| ApiError::BadRequest(format!("Invalid navigation: {}", e)))?;
+                    
+                    Ok(MCPResponse::Navigation { request_id, navigation: navigate(state, scope, request).await? })
+                },
                 _ => Ok(MCPResponse::Error { 
                     error: format!("Unknown command: {}", command) 
                 })
             }
         },
         MCPRequest::Query { request_id, query_text } => {
-            // Mock query response
+            if query_text.trim().is_empty() {
+                return Err(ApiError::BadRequest("Query text must not be empty".to_string()));
+            }
+            
+            let matches: Vec<_> = search(scope, &query_text, limits.max_results).await?
+                .into_iter()
+                .map(|scored| json!({
+                    "id": scored.node.node.id.0,
+                    "relevance": scored.score,
+                    "content": scored.node.node.label,
+                }))
+                .collect();
+            
             Ok(MCPResponse::QueryResult { 
                 request_id,
-                result: json!({
-                    "query": query_text,
-                    "matches": [
-                        {
-                            "id": Uuid::new_v4().to_string(),
-                            "relevance": 0.89,
-                            "content": "Mock result for your query"
-                        }
-                    ]
-                })
+                result: json!({ "query": query_text, "matches": matches })
             })
         },
         MCPRequest::Cancel { request_id } => {
-            // Just acknowledge the cancellation
-            Ok(MCPResponse::Cancelled { request_id })
+            // Cancelling needs the registry, which unregistered requests are not in
+            Err(ApiError::NotFound(format!("No running request {}", request_id)))
         },
-        MCPRequest::Navigate { entity_id, direction } => {
-            // Return mock navigation result
-            Ok(MCPResponse::Entity {
+        MCPRequest::Navigate { entity_id, direction, session_id, label, as_of, page, limit } => {
+            let request = Navigation { session_id, entity_id, direction, label, as_of, page, limit };
+            
+            Ok(MCPResponse::Navigation {
                 request_id: Uuid::new_v4().to_string(),
-                entity: json!({
-                    "id": entity_id,
-                    "direction": direction,
-                    "type": "node",
-                    "note": "Navigation mock result"
-                })
+                navigation: navigate(state, scope, request).await?,
             })
         },
-        MCPRequest::EntityDetail { entity_id } => {
-            // Return mock entity detail
+        MCPRequest::EntityDetail { entity_id: id } => {
+            let node = scope.graph.get_node(entity_id(&json!({ "id": id }))?, TemporalFilter::current()).await?;
+            
             Ok(MCPResponse::Entity {
                 request_id: Uuid::new_v4().to_string(),
-                entity: json!({
-                    "id": entity_id,
-                    "type": "node",
-                    "properties": {
-                        "name": "Mock Entity",
-                        "created_at": chrono::Utc::now().to_rfc3339()
-                    },
-                    "note": "Entity detail mock result"
-                })
+                entity: json!(node),
             })
         }
     }
 }
 
+/// The `limit` most recently recorded versions of a node, oldest first
+///
+/// Older versions beyond `limit` are left out, so the current version is
+/// always the last one listed.
+pub(crate) async fn entity_history(scope: &TenantScope, node_id: NodeId, limit: usize) -> ApiResult<Vec<Node>> {
+    let mut versions = scope.graph.get_node_versions(node_id).await?;
+    let older = versions.len().saturating_sub(limit);
+    versions.drain(..older);
+    Ok(versions)
+}
+
+/// JSON listing of one version returned by [`entity_history`]
+pub(crate) fn history_entry(node: &Node) -> Value {
+    json!({
+        "version_id": node.version_id(),
+        "recorded_at": node.transaction_time.start.map(|ts| ts.0),
+        "superseded_at": node.transaction_time.end.map(|ts| ts.0),
+        "entity": node,
+    })
+}
+
+/// Nodes of the tenant's graph best matching `query`
+async fn search(scope: &TenantScope, query: &str, limit: usize) -> ApiResult<Vec<ScoredNode>> {
+    let query = HybridQueryBuilder::new()
+        .with_text(query.to_string())
+        .limit(limit)
+        .build();
+    Ok(scope.hybrid.execute_hybrid_query(query, None).await?.nodes)
+}
+
+/// Node named by the `id` argument of a command
+fn entity_id(data: &Value) -> ApiResult<NodeId> {
+    let id = text(data, "id")?;
+    Uuid::parse_str(id)
+        .map(NodeId)
+        .map_err(|_| ApiError::BadRequest(format!("Invalid entity ID: {}", id)))
+}
+
+/// Non-empty string argument `name` of a command
+fn text<'a>(data: &'a Value, name: &str) -> ApiResult<&'a str> {
+    match data.get(name).and_then(Value::as_str) {
+        Some(value) if !value.trim().is_empty() => Ok(value),
+        Some(_) => Err(ApiError::BadRequest(format!("Argument {} must not be empty", name))),
+        None => Err(ApiError::BadRequest(format!("Missing argument: {}", name))),
+    }
+}
+
+/// `limit` argument of a command, capped at `max`
+fn limit(data: &Value, max: usize) -> ApiResult<usize> {
+    match data.get("limit") {
+        None | Some(Value::Null) => Ok(max),
+        Some(limit) => limit.as_u64()
+            .filter(|limit| *limit > 0)
+            .map(|limit| usize::try_from(limit).unwrap_or(usize::MAX).min(max))
+            .ok_or_else(|| ApiError::BadRequest("limit must be a positive integer".to_string())),
+    }
+}
+
 /// Get MCP status
 #[axum::debug_handler]
 pub async fn get_status(
@@ -207,12 +264,11 @@ pub async fn get_status(
 /// Get MCP config
 #[axum::debug_handler]
 pub async fn get_config(
-    State(_state): State<Arc<ApiState>>,
+    State(state): State<Arc<ApiState>>,
 ) -> impl IntoResponse {
-    // Return configuration (mock)
     Json(json!({
-        "max_history_items": 100,
-        "default_search_limit": 20,
+        "max_history_items": state.mcp_limits.max_history,
+        "default_search_limit": state.mcp_limits.max_results,
         "features": {
             "history_tracking": true,
             "semantic_search": true,
@@ -259,4 +315,123 @@ pub async fn execute_command(
             "message": "Invalid request type, expected Command"
         }))
     }
-} 
\ No newline at end of file
+} 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        mcp::{server::tests::{state_with_embeddings, test_state, PendingEmbeddings}, McpLimits},
+        types::{Edge, EdgeId, TemporalRange, TenantId},
+    };
+
+    fn command(command: &str, data: Value) -> MCPRequest {
+        MCPRequest::Command {
+            request_id: "r".to_string(),
+            command: command.to_string(),
+            data,
+            cursor_position: None,
+        }
+    }
+
+    async fn run(state: &ApiState, command_name: &str, data: Value) -> ApiResult<Value> {
+        let scope = state.scope(TenantId::default());
+        Ok(match process_request(state, &scope, command(command_name, data)).await? {
+            MCPResponse::Entity { entity, .. } | MCPResponse::EntityCreated { entity, .. } => entity,
+            MCPResponse::Connections { connections, .. } => connections,
+            MCPResponse::History { history, .. } => history,
+            MCPResponse::SearchResults { results, .. } => results,
+            response => panic!("unexpected response {:?}", response),
+        })
+    }
+
+    #[tokio::test]
+    async fn test_commands_read_and_write_the_graph() {
+        let state = test_state().await;
+        let state = Arc::new(Arc::try_unwrap(state).ok().unwrap().with_mcp_limits(McpLimits { max_results: 2, max_history: 10 }));
+
+        let ada = run(&state, "create_entity", json!({ "label": "Ada Lovelace", "entity_type": "Person", "properties": { "born": 1815 } })).await.unwrap();
+        let id = ada["id"].as_str().unwrap().to_string();
+        let entity = run(&state, "get_entity", json!({ "id": id })).await.unwrap();
+        assert_eq!(entity["label"], "Ada Lovelace");
+        assert_eq!(entity["properties"]["born"], 1815);
+
+        let scope = state.scope(TenantId::default());
+        for _ in 0..3 {
+            let other = run(&state, "create_entity", json!({ "label": "Analytical Engine" })).await.unwrap();
+            let edge = Edge {
+                id: EdgeId(Uuid::new_v4()),
+                source_id: NodeId(Uuid::parse_str(&id).unwrap()),
+                target_id: NodeId(Uuid::parse_str(other["id"].as_str().unwrap()).unwrap()),
+                label: "worked_on".to_string(),
+                properties: Properties::new(),
+                valid_time: TemporalRange::from_now(),
+                transaction_time: TemporalRange::from_now(),
+            };
+            scope.graph.create_edge(edge).await.unwrap();
+        }
+        let connections = run(&state, "get_connections", json!({ "id": id })).await.unwrap();
+        assert_eq!(connections.as_array().unwrap().len(), 2);
+        assert_eq!(connections[0]["direction"], "outgoing");
+        let connections = run(&state, "get_connections", json!({ "id": id, "limit": 1 })).await.unwrap();
+        assert_eq!(connections.as_array().unwrap().len(), 1);
+
+        let history = run(&state, "get_entity_history", json!({ "id": id })).await.unwrap();
+        assert_eq!(history.as_array().unwrap().len(), 1);
+        assert_eq!(history[0]["entity"]["label"], "Ada Lovelace");
+
+        // A limit keeps the newest versions
+        let mut node = scope.graph.get_node(NodeId(Uuid::parse_str(&id).unwrap()), TemporalFilter::current()).await.unwrap();
+        node.label = "Ada King".to_string();
+        scope.graph.update_node(node, None).await.unwrap();
+        let history = run(&state, "get_entity_history", json!({ "id": id, "limit": 1 })).await.unwrap();
+        assert_eq!(history.as_array().unwrap().len(), 1);
+        assert_eq!(history[0]["entity"]["label"], "Ada King");
+
+        let results = run(&state, "search_entities", json!({ "query": "Analytical Engine" })).await.unwrap();
+        assert!(results.as_array().unwrap().len() <= 2);
+    }
+
+    #[tokio::test]
+    async fn test_unknown_and_invalid_ids_are_errors() {
+        let state = test_state().await;
+        let missing = Uuid::new_v4().to_string();
+
+        for name in ["get_entity", "get_connections", "get_entity_history"] {
+            let (status, _) = run(&state, name, json!({ "id": missing })).await.unwrap_err().into_status_and_message();
+            assert_eq!(status, axum::http::StatusCode::NOT_FOUND, "{}", name);
+            let (status, _) = run(&state, name, json!({ "id": "not-a-uuid" })).await.unwrap_err().into_status_and_message();
+            assert_eq!(status, axum::http::StatusCode::BAD_REQUEST, "{}", name);
+        }
+
+        let (status, _) = run(&state, "search_entities", json!({ "query": "x", "limit": 0 })).await.unwrap_err().into_status_and_message();
+        assert_eq!(status, axum::http::StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_cancel_stops_a_running_query() {
+        let state = state_with_embeddings(Arc::new(PendingEmbeddings)).await;
+        let tenant = TenantId::default();
+
+        let query = tokio::spawn({
+            let state = state.clone();
+            async move {
+                let scope = state.scope(TenantId::default());
+                let request = MCPRequest::Query { request_id: "q".to_string(), query_text: "engines".to_string() };
+                process_request(&state, &scope, request).await
+            }
+        });
+        while !state.requests.is_running(&tenant, "q") {
+            tokio::task::yield_now().await;
+        }
+
+        let scope = state.scope(tenant);
+        let cancel = MCPRequest::Cancel { request_id: "q".to_string() };
+        assert!(matches!(process_request(&state, &scope, cancel).await, Ok(MCPResponse::Cancelled { .. })));
+        let (status, _) = query.await.unwrap().unwrap_err().into_status_and_message();
+        assert_eq!(status.as_u16(), crate::api::error::CLIENT_CLOSED_REQUEST);
+
+        let cancel = MCPRequest::Cancel { request_id: "q".to_string() };
+        let (status, _) = process_request(&state, &scope, cancel).await.unwrap_err().into_status_and_message();
+        assert_eq!(status, axum::http::StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,327 @@
+//! MCP over streamable HTTP
+//!
+//! Clients POST JSON-RPC messages to `/mcp`. `initialize` opens a session
+//! whose id is returned in the `Mcp-Session-Id` header; later messages must
+//! carry it, and a DELETE ends it. Responses are plain JSON. A GET with the
+//! session's id opens a Server-Sent Events stream of the notifications the
+//! server sends on its own, such as resource updates.
+//!
+//! A session belongs to the tenant and subject that opened it; other callers
+//! are told it does not exist.
+
+use std::{
+    collections::HashMap,
+    convert::Infallible,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use axum::{
+    body::Bytes,
+    extract::State,
+    http::{HeaderMap, HeaderValue, StatusCode},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+    Extension, Json,
+};
+use serde_json::{json, Value};
+use tokio::sync::broadcast::error::RecvError;
+use uuid::Uuid;
+
+use crate::{
+    api::{
+        auth::{Claims, GRAPH_WRITE},
+        ApiResult, ApiState,
+    },
+    config::Config,
+    types::TenantId,
+};
+use super::{
+    protocol::{JsonRpcError, JsonRpcResponse, INVALID_REQUEST, PARSE_ERROR},
+    server::{Access, McpServer, McpSession},
+};
+
+/// Header carrying the session id
+pub const SESSION_HEADER: &str = "mcp-session-id";
+
+/// Sessions unused for this long are dropped
+const SESSION_IDLE_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+/// Most sessions open at once across all callers
+pub const MAX_SESSIONS: usize = 10_000;
+
+/// Most sessions one authenticated caller may hold open
+pub const MAX_SESSIONS_PER_CALLER: usize = 100;
+
+/// Tenant and subject of the credential that opened a session, or `None`
+/// when the server does not authenticate
+type Owner = Option<(TenantId, String)>;
+
+/// Session key: the session's owner and its id
+type SessionKey = (Owner, String);
+
+/// Open sessions by key, with when each was last used
+type SessionMap = HashMap<SessionKey, (Arc<McpSession>, Instant)>;
+
+/// Sessions of clients connected over HTTP
+#[derive(Clone)]
+pub struct McpSessions {
+    sessions: Arc<Mutex<SessionMap>>,
+    idle_timeout: Duration,
+    max_sessions: usize,
+    max_per_caller: usize,
+}
+
+impl McpSessions {
+    fn new(idle_timeout: Duration, max_sessions: usize, max_per_caller: usize) -> Self {
+        Self {
+            sessions: Arc::new(Mutex::new(HashMap::new())),
+            idle_timeout,
+            max_sessions,
+            max_per_caller,
+        }
+    }
+
+    pub fn from_config(config: &Config) -> Self {
+        Self::new(
+            SESSION_IDLE_TIMEOUT,
+            config.mcp_max_sessions.max(1),
+            config.mcp_max_sessions_per_caller.max(1),
+        )
+    }
+
+    /// Open a session for `owner`, returning its id
+    ///
+    /// Fails with the status to answer when too many sessions are open.
+    fn open(&self, owner: Owner) -> Result<(String, Arc<McpSession>), StatusCode> {
+        let mut sessions = self.sessions.lock().unwrap();
+        self.expire(&mut sessions);
+        if sessions.len() >= self.max_sessions {
+            return Err(StatusCode::SERVICE_UNAVAILABLE);
+        }
+        if owner.is_some() && sessions.keys().filter(|(opener, _)| *opener == owner).count() >= self.max_per_caller {
+            return Err(StatusCode::TOO_MANY_REQUESTS);
+        }
+
+        let id = Uuid::new_v4().to_string();
+        let session = Arc::new(McpSession::new());
+        sessions.insert((owner, id.clone()), (session.clone(), Instant::now()));
+        Ok((id, session))
+    }
+
+    /// Session `id` of `owner`, marking it used
+    fn get(&self, owner: &Owner, id: &str) -> Option<Arc<McpSession>> {
+        let mut sessions = self.sessions.lock().unwrap();
+        self.expire(&mut sessions);
+        let (session, last_used) = sessions.get_mut(&(owner.clone(), id.to_string()))?;
+        *last_used = Instant::now();
+        Some(session.clone())
+    }
+
+    fn close(&self, owner: &Owner, id: &str) -> bool {
+        self.sessions.lock().unwrap().remove(&(owner.clone(), id.to_string())).is_some()
+    }
+
+    /// Drop idle sessions
+    ///
+    /// A session with an open notification stream is in use, so it is kept
+    /// and its idle time restarts from when the stream was last seen open.
+    fn expire(&self, sessions: &mut SessionMap) {
+        sessions.retain(|_, (session, last_used)| {
+            if session.is_streaming() {
+                *last_used = Instant::now();
+                return true;
+            }
+            last_used.elapsed() < self.idle_timeout
+        });
+    }
+}
+
+impl Default for McpSessions {
+    fn default() -> Self {
+        Self::new(SESSION_IDLE_TIMEOUT, MAX_SESSIONS, MAX_SESSIONS_PER_CALLER)
+    }
+}
+
+/// Handle JSON-RPC messages posted by a client
+pub async fn post(
+    State(state): State<Arc<ApiState>>,
+    claims: Option<Extension<Claims>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    let payload: Value = match serde_json::from_slice(&body) {
+        Ok(payload) => payload,
+        Err(e) => return rpc_error(StatusCode::BAD_REQUEST, PARSE_ERROR, e.to_string()),
+    };
+    let owner = match owner(claims.as_ref()) {
+        Ok(owner) => owner,
+        Err(e) => return e.into_response(),
+    };
+    let access = match (&claims, &owner) {
+        (Some(Extension(claims)), Some((tenant, subject))) => Access {
+            write: claims.has_scope(GRAPH_WRITE),
+            tenant: tenant.clone(),
+            subject: Some(subject.clone()),
+        },
+        _ => Access::full(),
+    };
+
+    let sessions = state.mcp_sessions.clone();
+    let (session_id, session) = if payload.get("method").and_then(Value::as_str) == Some("initialize") {
+        match sessions.open(owner) {
+            Ok(opened) => opened,
+            Err(status) => return rpc_error(status, INVALID_REQUEST, "Too many open sessions"),
+        }
+    } else {
+        let Some(id) = session_id(&headers) else {
+            return rpc_error(StatusCode::BAD_REQUEST, INVALID_REQUEST, "Missing Mcp-Session-Id header");
+        };
+        match sessions.get(&owner, &id) {
+            Some(session) => (id, session),
+            None => return rpc_error(StatusCode::NOT_FOUND, INVALID_REQUEST, "Unknown session"),
+        }
+    };
+
+    let server = McpServer::new(state);
+    let mut response = match server.handle_payload(&session, access, payload).await {
+        Some(response) => Json(response).into_response(),
+        None => StatusCode::ACCEPTED.into_response(),
+    };
+    if let Ok(value) = HeaderValue::from_str(&session_id) {
+        response.headers_mut().insert(SESSION_HEADER, value);
+    }
+    response
+}
+
+/// Stream the notifications the server sends to the client's session
+///
+/// The session does not expire while the stream is open, and the stream
+/// ends when the session is closed. Notifications sent while the client has
+/// no stream open are not delivered later.
+pub async fn stream(
+    State(state): State<Arc<ApiState>>,
+    claims: Option<Extension<Claims>>,
+    headers: HeaderMap,
+) -> Response {
+    let owner = match owner(claims.as_ref()) {
+        Ok(owner) => owner,
+        Err(e) => return e.into_response(),
+    };
+    let Some(id) = session_id(&headers) else {
+        return rpc_error(StatusCode::BAD_REQUEST, INVALID_REQUEST, "Missing Mcp-Session-Id header");
+    };
+    let Some(session) = state.mcp_sessions.get(&owner, &id) else {
+        return rpc_error(StatusCode::NOT_FOUND, INVALID_REQUEST, "Unknown session");
+    };
+
+    let mut notifications = session.notifications();
+    // Holding only the receiver lets the session, and so the stream, end when the session is closed
+    drop(session);
+    let events = async_stream::stream! {
+        loop {
+            match notifications.recv().await {
+                Ok(notification) => yield Ok::<_, Infallible>(Event::default().event("message").data(notification.to_string())),
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => break,
+            }
+        }
+    };
+    Sse::new(events).keep_alive(KeepAlive::default()).into_response()
+}
+
+/// End the client's session
+pub async fn delete(
+    State(state): State<Arc<ApiState>>,
+    claims: Option<Extension<Claims>>,
+    headers: HeaderMap,
+) -> Response {
+    let owner = match owner(claims.as_ref()) {
+        Ok(owner) => owner,
+        Err(e) => return e.into_response(),
+    };
+    let status = match session_id(&headers) {
+        Some(id) if state.mcp_sessions.close(&owner, &id) => StatusCode::NO_CONTENT,
+        Some(_) => StatusCode::NOT_FOUND,
+        None => StatusCode::BAD_REQUEST,
+    };
+    status.into_response()
+}
+
+/// Owner of the sessions the caller may use
+fn owner(claims: Option<&Extension<Claims>>) -> ApiResult<Owner> {
+    match claims {
+        Some(Extension(claims)) => Ok(Some((claims.tenant_id()?, claims.subject.clone()))),
+        None => Ok(None),
+    }
+}
+
+fn session_id(headers: &HeaderMap) -> Option<String> {
+    headers.get(SESSION_HEADER)?.to_str().ok().map(str::to_string)
+}
+
+fn rpc_error(status: StatusCode, code: i64, message: impl Into<String>) -> Response {
+    (status, Json(json!(JsonRpcResponse::error(None, JsonRpcError::new(code, message))))).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn caller(tenant: &str, subject: &str) -> Owner {
+        Some((TenantId::new(tenant).unwrap(), subject.to_string()))
+    }
+
+    #[test]
+    fn test_sessions_belong_to_their_opener() {
+        let sessions = McpSessions::default();
+        let acme = caller("acme", "alice");
+        let (id, _) = sessions.open(acme.clone()).unwrap();
+
+        for other in [caller("globex", "alice"), caller("acme", "bob"), None] {
+            assert!(sessions.get(&other, &id).is_none());
+            assert!(!sessions.close(&other, &id));
+        }
+        assert!(sessions.get(&acme, &id).is_some());
+        assert!(sessions.close(&acme, &id));
+        assert!(sessions.get(&acme, &id).is_none());
+    }
+
+    #[test]
+    fn test_sessions_expire_when_idle() {
+        let sessions = McpSessions::new(Duration::ZERO, MAX_SESSIONS, MAX_SESSIONS_PER_CALLER);
+        let (id, _) = sessions.open(None).unwrap();
+        assert!(sessions.get(&None, &id).is_none());
+        assert!(sessions.sessions.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_streaming_sessions_do_not_expire() {
+        let sessions = McpSessions::new(Duration::ZERO, MAX_SESSIONS, MAX_SESSIONS_PER_CALLER);
+        let (id, session) = sessions.open(None).unwrap();
+        let notifications = session.notifications();
+        drop(session);
+
+        assert!(sessions.get(&None, &id).is_some());
+        drop(notifications);
+        assert!(sessions.get(&None, &id).is_none());
+    }
+
+    #[test]
+    fn test_open_sessions_are_bounded() {
+        let sessions = McpSessions::new(SESSION_IDLE_TIMEOUT, 3, 2);
+        let acme = caller("acme", "alice");
+        sessions.open(acme.clone()).unwrap();
+        let (id, _) = sessions.open(acme.clone()).unwrap();
+        assert_eq!(sessions.open(acme.clone()).unwrap_err(), StatusCode::TOO_MANY_REQUESTS);
+
+        sessions.open(caller("globex", "bob")).unwrap();
+        assert_eq!(sessions.open(caller("initech", "carol")).unwrap_err(), StatusCode::SERVICE_UNAVAILABLE);
+
+        // Closing a session frees its slot
+        assert!(sessions.close(&acme, &id));
+        sessions.open(acme).unwrap();
+    }
+}
@@ -10,16 +10,77 @@ use std::sync::Arc;
 
 use crate::{
     api::ApiState,
+    config::Config,
     types::{Node, Edge, EntityId, EntityType},
 };
 
 pub mod handlers;
+pub mod http;
+pub mod navigation;
+pub mod prompts;
+pub mod protocol;
+pub mod resources;
+pub mod server;
+pub mod stdio;
+pub mod subscriptions;
+pub mod tools;
+
+pub use server::{Access, McpServer, McpSession};
+
+/// Connections or search results a command returns when the config sets no limit
+pub const DEFAULT_MAX_RESULTS: usize = 20;
+
+/// Versions a history command returns when the config sets no limit
+pub const DEFAULT_MAX_HISTORY: usize = 100;
+
+/// Bounds on the results of MCP commands
+///
+/// Commands taking a `limit` argument return at most that many results, up
+/// to these bounds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct McpLimits {
+    /// Most connections or search results returned
+    pub max_results: usize,
+    /// Most versions returned by `get_entity_history`
+    pub max_history: usize,
+}
+
+impl McpLimits {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            max_results: config.mcp_max_results.max(1),
+            max_history: config.mcp_max_history.max(1),
+        }
+    }
+}
+
+impl Default for McpLimits {
+    fn default() -> Self {
+        Self {
+            max_results: DEFAULT_MAX_RESULTS,
+            max_history: DEFAULT_MAX_HISTORY,
+        }
+    }
+}
 
 #[derive(Debug, Serialize, Deserialize)]
 pub enum MCPRequest {
+    /// Start or move a navigation session; see [`navigation::Navigation`]
     Navigate {
+        #[serde(default)]
         entity_id: String,
+        #[serde(default)]
         direction: String,
+        #[serde(default)]
+        session_id: Option<String>,
+        #[serde(default)]
+        label: Option<String>,
+        #[serde(default)]
+        as_of: Option<chrono::DateTime<chrono::Utc>>,
+        #[serde(default)]
+        page: Option<usize>,
+        #[serde(default)]
+        limit: Option<usize>,
     },
     EntityDetail {
         entity_id: String,
@@ -90,6 +151,10 @@ pub enum MCPResponse {
     Cancelled {
         request_id: String,
     },
+    Navigation {
+        request_id: String,
+        navigation: serde_json::Value,
+    },
 }
 
 pub fn mcp_router(state: Arc<ApiState>) -> Router {
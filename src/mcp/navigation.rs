@@ -0,0 +1,571 @@
+//! Stateful graph navigation for `MCPRequest::Navigate`
+//!
+//! A navigation session keeps a cursor on one tenant's graph: the current
+//! node, the trail of nodes visited to reach it, the nodes left behind by
+//! going back, a temporal viewpoint and the edges being followed. Each
+//! request moves the cursor and returns the current node with a page of its
+//! neighbors, so a client can explore step by step without resending where
+//! it is. Only the caller that started a session can use it, the number of
+//! open sessions is capped, and sessions left unused for the idle timeout
+//! expire.
+
+use std::{
+    collections::HashMap,
+    mem,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::{
+    api::{ApiError, ApiResult, ApiState, TenantScope},
+    types::{Edge, Node, NodeId, TemporalFilter, TenantId},
+};
+use super::resources::{filter_at, summary, visible};
+
+/// How long a session may sit unused when the config sets no timeout
+pub const DEFAULT_IDLE_TIMEOUT: Duration = Duration::from_secs(15 * 60);
+
+/// Most sessions open at once across all callers
+const MAX_SESSIONS: usize = 10_000;
+
+/// Most sessions one authenticated caller may hold open
+const MAX_SESSIONS_PER_CALLER: usize = 100;
+
+/// Most nodes a breadcrumb trail remembers; older ones are forgotten
+const MAX_TRAIL: usize = 100;
+
+/// Edges a cursor follows from the current node
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EdgeDirection {
+    Outgoing,
+    Incoming,
+    Both,
+}
+
+impl EdgeDirection {
+    fn as_str(self) -> &'static str {
+        match self {
+            EdgeDirection::Outgoing => "outgoing",
+            EdgeDirection::Incoming => "incoming",
+            EdgeDirection::Both => "both",
+        }
+    }
+
+    /// Node at the other end of `edge` and whether it is outgoing, if the edge leads from `from` this way
+    fn follow(self, edge: &Edge, from: NodeId) -> Option<(NodeId, bool)> {
+        let outgoing = edge.source_id == from;
+        let incoming = edge.target_id == from;
+        match self {
+            EdgeDirection::Outgoing | EdgeDirection::Both if outgoing => Some((edge.target_id, true)),
+            EdgeDirection::Incoming | EdgeDirection::Both if incoming => Some((edge.source_id, false)),
+            _ => None,
+        }
+    }
+}
+
+/// What a request does with the cursor
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Motion {
+    /// Follow edges in this direction, moving to `entity_id` if it is set
+    Follow(EdgeDirection),
+    /// Keep following the same edges
+    Stay,
+    /// Return to the previous node of the trail
+    Back,
+    /// Undo the last `Back`
+    Forward,
+}
+
+impl Motion {
+    fn parse(direction: &str) -> ApiResult<Self> {
+        match direction {
+            "" => Ok(Motion::Stay),
+            "outgoing" => Ok(Motion::Follow(EdgeDirection::Outgoing)),
+            "incoming" => Ok(Motion::Follow(EdgeDirection::Incoming)),
+            "both" => Ok(Motion::Follow(EdgeDirection::Both)),
+            "back" => Ok(Motion::Back),
+            "forward" => Ok(Motion::Forward),
+            _ => Err(ApiError::BadRequest(format!(
+                "Invalid direction {}; expected outgoing, incoming, both, back or forward",
+                direction
+            ))),
+        }
+    }
+}
+
+/// A navigation request
+///
+/// Without `session_id` a session is started at `entity_id`. Otherwise the
+/// session's cursor moves to the neighbor `entity_id`, or `back` or `forward`
+/// along its trail, and stays put when `entity_id` is empty.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Navigation {
+    /// Session to continue
+    pub session_id: Option<String>,
+    /// Node to start at or neighbor to move to
+    #[serde(alias = "id")]
+    pub entity_id: String,
+    /// `outgoing`, `incoming` or `both` to choose the edges followed, or
+    /// `back` or `forward`; empty keeps following the same edges
+    pub direction: String,
+    /// Only follow edges with this label; empty follows every label
+    pub label: Option<String>,
+    /// Time to view the graph as of from now on
+    pub as_of: Option<DateTime<Utc>>,
+    /// Page of neighbors to return, from 0
+    pub page: Option<usize>,
+    /// Neighbors per page
+    pub limit: Option<usize>,
+}
+
+/// A node on the cursor's trail
+#[derive(Debug, Clone)]
+struct Visit {
+    id: NodeId,
+    label: String,
+}
+
+impl Visit {
+    fn of(node: &Node) -> Self {
+        Self { id: node.id, label: node.label.clone() }
+    }
+
+    fn to_json(&self) -> Value {
+        json!({ "id": self.id.0, "label": self.label })
+    }
+}
+
+/// Where a session is in the graph
+#[derive(Debug, Clone)]
+struct Cursor {
+    current: Visit,
+    /// Nodes visited before the current one, oldest first
+    back: Vec<Visit>,
+    /// Nodes left by going back, most recently left last
+    forward: Vec<Visit>,
+    /// Time the graph is viewed as of; now when unset
+    as_of: Option<DateTime<Utc>>,
+    direction: EdgeDirection,
+    label: Option<String>,
+}
+
+impl Cursor {
+    /// Choose the edges followed for `motion`, keeping the current ones where it sets none
+    fn follow(&mut self, motion: Motion, label: Option<String>) {
+        if let Motion::Follow(direction) = motion {
+            self.direction = direction;
+            self.label = None;
+        }
+        if let Some(label) = label {
+            self.label = (!label.is_empty()).then_some(label);
+        }
+    }
+
+    /// Move to `to`, leaving the current node on the trail
+    fn advance(&mut self, to: Visit) {
+        let left = mem::replace(&mut self.current, to);
+        self.back.push(left);
+        if self.back.len() > MAX_TRAIL {
+            self.back.remove(0);
+        }
+        self.forward.clear();
+    }
+
+    /// Edges followed from the current node, with the node at their other end
+    ///
+    /// Sorted by label and neighbor so that pages are stable between requests.
+    async fn edges(&self, scope: &TenantScope, filter: &TemporalFilter) -> ApiResult<Vec<(Edge, NodeId, bool)>> {
+        let from = self.current.id;
+        let mut edges: Vec<_> = scope.graph.get_edges_for_node(from, filter.clone()).await?
+            .into_iter()
+            .filter(|edge| self.label.as_ref().is_none_or(|label| edge.label == *label))
+            .filter_map(|edge| self.direction.follow(&edge, from).map(|(to, outgoing)| (edge, to, outgoing)))
+            .collect();
+        edges.sort_by(|a, b| (&a.0.label, a.1.0, a.0.id.0).cmp(&(&b.0.label, b.1.0, b.0.id.0)));
+        Ok(edges)
+    }
+}
+
+/// Tenant navigating and the subject of its credential, if it has one
+type Caller = (TenantId, Option<String>);
+
+/// Session key: the caller that started the session and the session's id
+type SessionKey = (Caller, String);
+
+/// Open sessions by key, with when each was last used
+type SessionMap = HashMap<SessionKey, (Arc<tokio::sync::Mutex<Cursor>>, Instant)>;
+
+/// Navigation sessions of every tenant
+#[derive(Clone)]
+pub struct NavigationSessions {
+    sessions: Arc<Mutex<SessionMap>>,
+    idle_timeout: Duration,
+    max_sessions: usize,
+    max_per_caller: usize,
+}
+
+impl NavigationSessions {
+    /// Sessions expiring after `idle_timeout` without a request
+    pub fn new(idle_timeout: Duration) -> Self {
+        Self::bounded(idle_timeout, MAX_SESSIONS, MAX_SESSIONS_PER_CALLER)
+    }
+
+    fn bounded(idle_timeout: Duration, max_sessions: usize, max_per_caller: usize) -> Self {
+        Self {
+            sessions: Arc::new(Mutex::new(HashMap::new())),
+            idle_timeout,
+            max_sessions,
+            max_per_caller,
+        }
+    }
+
+    /// Number of sessions that have not expired
+    pub fn len(&self) -> usize {
+        let mut sessions = self.sessions.lock().unwrap();
+        self.expire(&mut sessions);
+        sessions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Open a session for `caller` at `cursor`, returning its id
+    ///
+    /// Fails when too many sessions are open, in all or for an authenticated caller.
+    fn open(&self, caller: Caller, cursor: Cursor) -> ApiResult<String> {
+        let mut sessions = self.sessions.lock().unwrap();
+        self.expire(&mut sessions);
+        if sessions.len() >= self.max_sessions {
+            return Err(ApiError::Unavailable("Too many navigation sessions are open".to_string()));
+        }
+        if caller.1.is_some() && sessions.keys().filter(|(opener, _)| *opener == caller).count() >= self.max_per_caller {
+            return Err(ApiError::RateLimitExceeded(format!(
+                "At most {} navigation sessions may be open at once",
+                self.max_per_caller
+            )));
+        }
+
+        let id = Uuid::new_v4().to_string();
+        sessions.insert((caller, id.clone()), (Arc::new(tokio::sync::Mutex::new(cursor)), Instant::now()));
+        Ok(id)
+    }
+
+    /// Cursor of session `id` of `caller`, marking it used
+    fn get(&self, caller: &Caller, id: &str) -> Option<Arc<tokio::sync::Mutex<Cursor>>> {
+        let mut sessions = self.sessions.lock().unwrap();
+        self.expire(&mut sessions);
+        let (cursor, last_used) = sessions.get_mut(&(caller.clone(), id.to_string()))?;
+        *last_used = Instant::now();
+        Some(cursor.clone())
+    }
+
+    fn expire(&self, sessions: &mut SessionMap) {
+        sessions.retain(|_, (_, last_used)| last_used.elapsed() < self.idle_timeout);
+    }
+}
+
+impl Default for NavigationSessions {
+    fn default() -> Self {
+        Self::new(DEFAULT_IDLE_TIMEOUT)
+    }
+}
+
+/// Start or move a navigation session of the caller of `scope`
+///
+/// Returns the session's id, current node, trail and a page of the
+/// neighbors reached by the edges it follows. A failed request leaves the
+/// session where it was.
+pub async fn navigate(state: &ApiState, scope: &TenantScope, request: Navigation) -> ApiResult<Value> {
+    let motion = Motion::parse(request.direction.trim())?;
+    let caller = (scope.tenant.clone(), scope.caller.clone());
+    let page = request.page.unwrap_or(0);
+    let limit = match request.limit {
+        None => state.mcp_limits.max_results,
+        Some(0) => return Err(ApiError::BadRequest("limit must be a positive integer".to_string())),
+        Some(limit) => limit.min(state.mcp_limits.max_results),
+    };
+
+    let Some(session_id) = request.session_id else {
+        if matches!(motion, Motion::Back | Motion::Forward) {
+            return Err(ApiError::BadRequest("A new navigation session has no trail to move along".to_string()));
+        }
+        let node = scope.graph.get_node(node_id(&request.entity_id)?, filter_at(request.as_of)).await?;
+        let mut cursor = Cursor {
+            current: Visit::of(&node),
+            back: Vec::new(),
+            forward: Vec::new(),
+            as_of: request.as_of,
+            direction: EdgeDirection::Outgoing,
+            label: None,
+        };
+        cursor.follow(motion, request.label);
+
+        let view = view(scope, &cursor, &node, page, limit).await?;
+        let session_id = state.navigation.open(caller, cursor)?;
+        return Ok(with_session(view, &session_id));
+    };
+
+    let shared = state.navigation.get(&caller, &session_id)
+        .ok_or_else(|| ApiError::NotFound(format!("Navigation session {} does not exist or has expired", session_id)))?;
+    let mut cursor = shared.lock().await;
+    let mut next = cursor.clone();
+    if request.as_of.is_some() {
+        next.as_of = request.as_of;
+    }
+    next.follow(motion, request.label);
+    let filter = filter_at(next.as_of);
+
+    match motion {
+        Motion::Back => {
+            let previous = next.back.pop()
+                .ok_or_else(|| ApiError::BadRequest("There is no node to go back to".to_string()))?;
+            let left = mem::replace(&mut next.current, previous);
+            next.forward.push(left);
+        }
+        Motion::Forward => {
+            let following = next.forward.pop()
+                .ok_or_else(|| ApiError::BadRequest("There is no node to go forward to".to_string()))?;
+            let left = mem::replace(&mut next.current, following);
+            next.back.push(left);
+        }
+        Motion::Follow(_) | Motion::Stay if !request.entity_id.trim().is_empty() => {
+            let to = node_id(&request.entity_id)?;
+            if to != next.current.id {
+                let edges = next.edges(scope, &filter).await?;
+                if !edges.iter().any(|(_, neighbor, _)| *neighbor == to) {
+                    return Err(ApiError::BadRequest(format!(
+                        "No {} edge{} leads from {} to {}",
+                        next.direction.as_str(),
+                        next.label.as_ref().map(|label| format!(" labelled {}", label)).unwrap_or_default(),
+                        next.current.id.0,
+                        to.0,
+                    )));
+                }
+                next.advance(Visit { id: to, label: String::new() });
+            }
+        }
+        Motion::Follow(_) | Motion::Stay => {}
+    }
+
+    // The current node may not exist at a new viewpoint, or may have been deleted
+    let node = scope.graph.get_node(next.current.id, filter).await?;
+    next.current = Visit::of(&node);
+    let view = view(scope, &next, &node, page, limit).await?;
+    *cursor = next;
+    Ok(with_session(view, &session_id))
+}
+
+/// Current node, trail and a page of neighbors of `cursor`
+async fn view(scope: &TenantScope, cursor: &Cursor, node: &Node, page: usize, limit: usize) -> ApiResult<Value> {
+    let filter = filter_at(cursor.as_of);
+    let edges = cursor.edges(scope, &filter).await?;
+    let total = edges.len();
+
+    let mut neighbors = Vec::new();
+    for (edge, neighbor, outgoing) in edges.into_iter().skip(page.saturating_mul(limit)).take(limit) {
+        let neighbor = match visible(scope, neighbor, &filter).await? {
+            Some(node) => summary(&node),
+            None => json!({ "id": neighbor.0 }),
+        };
+        neighbors.push(json!({
+            "direction": if outgoing { "outgoing" } else { "incoming" },
+            "edge": { "id": edge.id.0, "label": edge.label },
+            "node": neighbor,
+        }));
+    }
+    let next_page = (page.saturating_add(1).saturating_mul(limit) < total).then_some(page + 1);
+
+    Ok(json!({
+        "current": node,
+        "as_of": cursor.as_of,
+        "direction": cursor.direction,
+        "label": cursor.label,
+        "breadcrumbs": cursor.back.iter().map(Visit::to_json).collect::<Vec<_>>(),
+        "can_go_back": !cursor.back.is_empty(),
+        "can_go_forward": !cursor.forward.is_empty(),
+        "neighbors": neighbors,
+        "page": page,
+        "next_page": next_page,
+        "total_neighbors": total,
+    }))
+}
+
+fn with_session(mut view: Value, session_id: &str) -> Value {
+    view["session_id"] = json!(session_id);
+    view
+}
+
+fn node_id(id: &str) -> ApiResult<NodeId> {
+    match id.trim() {
+        "" => Err(ApiError::BadRequest("Missing argument: entity_id".to_string())),
+        id => Uuid::parse_str(id)
+            .map(NodeId)
+            .map_err(|_| ApiError::BadRequest(format!("Invalid entity ID: {}", id))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        api::{handlers::{insert_edge, insert_node}, models::{CreateEdgeRequest, CreateNodeRequest}},
+        mcp::{server::tests::test_state, McpLimits},
+        types::{EntityType, Properties},
+    };
+    use axum::http::StatusCode;
+
+    async fn node(scope: &TenantScope, label: &str) -> Node {
+        let request = CreateNodeRequest {
+            entity_type: EntityType::Person,
+            label: label.to_string(),
+            properties: Properties::new(),
+            valid_time: None,
+        };
+        insert_node(scope, request).await.unwrap()
+    }
+
+    async fn edge(scope: &TenantScope, source: &Node, target: &Node, label: &str) {
+        let request = CreateEdgeRequest {
+            source_id: source.id.0,
+            target_id: target.id.0,
+            label: label.to_string(),
+            properties: Properties::new(),
+            valid_time: None,
+        };
+        insert_edge(scope, request).await.unwrap();
+    }
+
+    fn status(error: ApiError) -> StatusCode {
+        error.into_status_and_message().0
+    }
+
+    #[tokio::test]
+    async fn test_cursor_moves_along_edges_and_back() {
+        let state = test_state().await;
+        let state = Arc::try_unwrap(state).ok().unwrap().with_mcp_limits(McpLimits { max_results: 2, max_history: 10 });
+        let scope = state.scope(TenantId::default());
+
+        let ada = node(&scope, "Ada").await;
+        let charles = node(&scope, "Charles").await;
+        let mary = node(&scope, "Mary").await;
+        let engine = node(&scope, "Analytical Engine").await;
+        edge(&scope, &ada, &charles, "knows").await;
+        edge(&scope, &ada, &mary, "knows").await;
+        edge(&scope, &ada, &engine, "worked_on").await;
+        edge(&scope, &charles, &engine, "designed").await;
+
+        let start = navigate(&state, &scope, Navigation { entity_id: ada.id.0.to_string(), ..Default::default() }).await.unwrap();
+        let session_id = start["session_id"].as_str().unwrap().to_string();
+        assert_eq!(start["current"]["label"], "Ada");
+        assert_eq!(start["total_neighbors"], 3);
+        assert_eq!(start["neighbors"].as_array().unwrap().len(), 2);
+        assert_eq!(start["next_page"], 1);
+        assert_eq!(start["can_go_back"], false);
+
+        let step = |direction: &str, entity_id: String| Navigation {
+            session_id: Some(session_id.clone()),
+            entity_id,
+            direction: direction.to_string(),
+            ..Default::default()
+        };
+        let second_page = navigate(&state, &scope, Navigation { page: Some(1), ..step("", String::new()) }).await.unwrap();
+        assert_eq!(second_page["neighbors"][0]["edge"]["label"], "worked_on");
+        assert!(second_page["next_page"].is_null());
+
+        let labelled = navigate(&state, &scope, Navigation { label: Some("knows".to_string()), ..step("outgoing", String::new()) }).await.unwrap();
+        assert_eq!(labelled["total_neighbors"], 2);
+
+        // Only edges matching the cursor's direction and label can be followed
+        let error = navigate(&state, &scope, step("", engine.id.0.to_string())).await.unwrap_err();
+        assert_eq!(status(error), StatusCode::BAD_REQUEST);
+
+        let at_charles = navigate(&state, &scope, step("", charles.id.0.to_string())).await.unwrap();
+        assert_eq!(at_charles["current"]["label"], "Charles");
+        assert_eq!(at_charles["breadcrumbs"], json!([{ "id": ada.id.0, "label": "Ada" }]));
+        let incoming = navigate(&state, &scope, step("incoming", String::new())).await.unwrap();
+        assert_eq!(incoming["neighbors"][0]["node"]["label"], "Ada");
+        let at_engine = navigate(&state, &scope, step("outgoing", engine.id.0.to_string())).await.unwrap();
+        assert_eq!(at_engine["breadcrumbs"].as_array().unwrap().len(), 2);
+
+        let back = navigate(&state, &scope, step("back", String::new())).await.unwrap();
+        assert_eq!(back["current"]["label"], "Charles");
+        assert_eq!(back["can_go_forward"], true);
+        let forward = navigate(&state, &scope, step("forward", String::new())).await.unwrap();
+        assert_eq!(forward["current"]["label"], "Analytical Engine");
+        let error = navigate(&state, &scope, step("forward", String::new())).await.unwrap_err();
+        assert_eq!(status(error), StatusCode::BAD_REQUEST);
+
+        // Sessions belong to the tenant that started them
+        let other = state.scope(TenantId::new("other").unwrap());
+        let error = navigate(&state, &other, step("back", String::new())).await.unwrap_err();
+        assert_eq!(status(error), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_sessions_belong_to_their_caller() {
+        let state = test_state().await;
+        let alice = state.scope(TenantId::default()).with_caller(Some("alice".to_string()));
+        let bob = state.scope(TenantId::default()).with_caller(Some("bob".to_string()));
+        let ada = node(&alice, "Ada").await;
+
+        let start = navigate(&state, &alice, Navigation { entity_id: ada.id.0.to_string(), ..Default::default() }).await.unwrap();
+        let resume = Navigation {
+            session_id: start["session_id"].as_str().map(str::to_string),
+            ..Default::default()
+        };
+        let error = navigate(&state, &bob, resume.clone()).await.unwrap_err();
+        assert_eq!(status(error), StatusCode::NOT_FOUND);
+        let resumed = navigate(&state, &alice, resume).await.unwrap();
+        assert_eq!(resumed["current"]["label"], "Ada");
+    }
+
+    #[tokio::test]
+    async fn test_open_sessions_are_bounded() {
+        let sessions = NavigationSessions::bounded(DEFAULT_IDLE_TIMEOUT, 3, 2);
+        let cursor = Cursor {
+            current: Visit { id: NodeId(Uuid::new_v4()), label: "Ada".to_string() },
+            back: Vec::new(),
+            forward: Vec::new(),
+            as_of: None,
+            direction: EdgeDirection::Outgoing,
+            label: None,
+        };
+        let caller = |subject: &str| (TenantId::default(), Some(subject.to_string()));
+
+        sessions.open(caller("alice"), cursor.clone()).unwrap();
+        sessions.open(caller("alice"), cursor.clone()).unwrap();
+        let error = sessions.open(caller("alice"), cursor.clone()).unwrap_err();
+        assert_eq!(status(error), StatusCode::TOO_MANY_REQUESTS);
+
+        sessions.open(caller("bob"), cursor.clone()).unwrap();
+        let error = sessions.open(caller("carol"), cursor).unwrap_err();
+        assert_eq!(status(error), StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    #[tokio::test]
+    async fn test_sessions_expire_when_idle() {
+        let state = Arc::try_unwrap(test_state().await).ok().unwrap().with_navigation_idle_timeout(Duration::ZERO);
+        let scope = state.scope(TenantId::default());
+        let ada = node(&scope, "Ada").await;
+
+        let start = navigate(&state, &scope, Navigation { entity_id: ada.id.0.to_string(), ..Default::default() }).await.unwrap();
+        let resume = Navigation {
+            session_id: start["session_id"].as_str().map(str::to_string),
+            ..Default::default()
+        };
+        let error = navigate(&state, &scope, resume).await.unwrap_err();
+        assert_eq!(status(error), StatusCode::NOT_FOUND);
+        assert!(state.navigation.is_empty());
+
+        let error = navigate(&state, &scope, Navigation { direction: "sideways".to_string(), ..Default::default() }).await.unwrap_err();
+        assert_eq!(status(error), StatusCode::BAD_REQUEST);
+    }
+}
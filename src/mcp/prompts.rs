@@ -0,0 +1,100 @@
+//! MCP prompt templates over the graph's resources
+//!
+//! Filling in a prompt reads the resources it is about and embeds them in its
+//! messages, so the model sees the entity without calling any tools.
+
+use std::{collections::HashMap, sync::Arc};
+
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::{api::ApiState, types::{NodeId, TenantId}};
+use super::{
+    protocol::{Content, GetPromptResult, JsonRpcError, Prompt, PromptArgument, PromptMessage, Role, INVALID_PARAMS},
+    resources::{self, history_uri, node_uri, subgraph_uri},
+};
+
+/// Relationships around an entity a summary draws on
+const SUMMARY_HOPS: usize = 2;
+
+/// Every prompt the server offers
+pub fn list() -> Vec<Prompt> {
+    vec![
+        Prompt {
+            name: "summarize_entity".to_string(),
+            description: Some("Summarize what the knowledge graph knows about an entity, optionally as of a point in time".to_string()),
+            arguments: vec![
+                argument("id", "Entity id", true),
+                argument("as_of", "RFC 3339 time to summarize the entity as of; now if absent", false),
+            ],
+        },
+        Prompt {
+            name: "explain_history".to_string(),
+            description: Some("Explain how an entity changed over its recorded versions".to_string()),
+            arguments: vec![argument("id", "Entity id", true)],
+        },
+    ]
+}
+
+fn argument(name: &str, description: &str, required: bool) -> PromptArgument {
+    PromptArgument {
+        name: name.to_string(),
+        description: Some(description.to_string()),
+        required,
+    }
+}
+
+/// Fill in prompt `name` with `arguments`, embedding the resources it is about from the stores of `tenant`
+pub async fn get(
+    state: Arc<ApiState>,
+    tenant: TenantId,
+    name: &str,
+    arguments: HashMap<String, String>,
+) -> std::result::Result<GetPromptResult, JsonRpcError> {
+    let invalid = |message: String| JsonRpcError::new(INVALID_PARAMS, message);
+    let prompt = list().into_iter()
+        .find(|prompt| prompt.name == name)
+        .ok_or_else(|| invalid(format!("Unknown prompt: {}", name)))?;
+    if let Some(missing) = prompt.arguments.iter().find(|argument| argument.required && !arguments.contains_key(&argument.name)) {
+        return Err(invalid(format!("Missing argument: {}", missing.name)));
+    }
+
+    let id = &arguments["id"];
+    let id = Uuid::parse_str(id)
+        .map(NodeId)
+        .map_err(|_| invalid(format!("Invalid entity ID: {}", id)))?;
+    let as_of = match arguments.get("as_of").filter(|at| !at.trim().is_empty()) {
+        Some(at) => Some(
+            DateTime::parse_from_rfc3339(at)
+                .map_err(|_| invalid(format!("Invalid as_of time: {}", at)))?
+                .with_timezone(&Utc),
+        ),
+        None => None,
+    };
+
+    let (instruction, uris) = match name {
+        "summarize_entity" => {
+            let when = as_of.map(|at| format!(" as of {}", resources::time(at))).unwrap_or_default();
+            let instruction = format!(
+                "Summarize what we know about the entity below{}. Draw on its properties, its relationships and \
+                 the entities around it, and say when something is not recorded.",
+                when,
+            );
+            (instruction, vec![node_uri(id, as_of), subgraph_uri(id, SUMMARY_HOPS, as_of)])
+        }
+        _ => {
+            let instruction = "Explain how the entity below changed over its recorded versions: what changed, \
+                               when, and what stayed the same.".to_string();
+            (instruction, vec![history_uri(id)])
+        }
+    };
+
+    let mut messages = vec![PromptMessage { role: Role::User, content: Content::Text { text: instruction } }];
+    for uri in uris {
+        for resource in resources::read(state.clone(), tenant.clone(), &uri).await?.contents {
+            messages.push(PromptMessage { role: Role::User, content: Content::Resource { resource } });
+        }
+    }
+
+    Ok(GetPromptResult { description: prompt.description, messages })
+}
@@ -0,0 +1,555 @@
+//! MCP resources: the server's status and browsable views of the graph
+//!
+//! Entities are addressed by URI:
+//!
+//! - `kg://node/{id}`: the entity and its relationships
+//! - `kg://node/{id}/history`: its most recent recorded versions, oldest first
+//! - `kg://subgraph/{id}?hops=2`: the entities within `hops` relationships of it
+//!
+//! Entity and subgraph views take an RFC 3339 `as_of` time to read the graph
+//! as it was valid and recorded then. Views are compact Markdown, or JSON
+//! with `format=json`.
+
+use std::{collections::HashSet, sync::Arc};
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde_json::{json, Value};
+use url::Url;
+use uuid::Uuid;
+
+use crate::{
+    api::{error::CLIENT_CLOSED_REQUEST, ApiError, ApiResult, ApiState, TenantScope},
+    cancellation,
+    error::Error,
+    types::{Edge, Node, NodeId, TemporalFilter, TemporalRange, TenantId, Timestamp},
+};
+use super::{
+    handlers::{entity_history, history_entry},
+    protocol::{
+        JsonRpcError, ReadResourceResult, Resource, ResourceContents, ResourceTemplate, INTERNAL_ERROR, INVALID_PARAMS,
+        REQUEST_CANCELLED,
+    },
+    McpLimits,
+};
+
+/// Resource describing the running server
+pub const STATUS_URI: &str = "kg://status";
+
+/// The resource was not found
+pub const RESOURCE_NOT_FOUND: i64 = -32002;
+
+/// Relationships a subgraph spans when the URI sets no `hops`
+pub const DEFAULT_HOPS: usize = 1;
+
+/// Most relationships a subgraph may span
+pub const MAX_HOPS: usize = 3;
+
+/// How a view is rendered
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Markdown,
+    Json,
+}
+
+impl Format {
+    fn mime_type(self) -> &'static str {
+        match self {
+            Format::Markdown => "text/markdown",
+            Format::Json => "application/json",
+        }
+    }
+}
+
+/// A parsed resource URI
+#[derive(Debug, Clone, PartialEq)]
+pub enum ResourceUri {
+    Status,
+    Node { id: NodeId, as_of: Option<DateTime<Utc>>, format: Format },
+    History { id: NodeId, format: Format },
+    Subgraph { id: NodeId, hops: usize, as_of: Option<DateTime<Utc>>, format: Format },
+}
+
+impl ResourceUri {
+    /// Parse `uri`, failing with `RESOURCE_NOT_FOUND` for URIs the server does not offer
+    pub fn parse(uri: &str) -> std::result::Result<Self, JsonRpcError> {
+        let not_found = || JsonRpcError::new(RESOURCE_NOT_FOUND, format!("Resource not found: {}", uri));
+        let invalid = |message: String| JsonRpcError::new(INVALID_PARAMS, message);
+
+        let url = Url::parse(uri).map_err(|_| not_found())?;
+        if url.scheme() != "kg" {
+            return Err(not_found());
+        }
+
+        let mut as_of = None;
+        let mut hops = DEFAULT_HOPS;
+        let mut format = Format::Markdown;
+        for (key, value) in url.query_pairs() {
+            match key.as_ref() {
+                "as_of" if value.is_empty() => {}
+                "as_of" => {
+                    let at = DateTime::parse_from_rfc3339(&value)
+                        .map_err(|_| invalid(format!("Invalid as_of time: {}", value)))?;
+                    as_of = Some(at.with_timezone(&Utc));
+                }
+                "hops" => {
+                    hops = value.parse().ok()
+                        .filter(|hops| (1..=MAX_HOPS).contains(hops))
+                        .ok_or_else(|| invalid(format!("hops must be between 1 and {}", MAX_HOPS)))?;
+                }
+                "format" => {
+                    format = match value.as_ref() {
+                        "markdown" => Format::Markdown,
+                        "json" => Format::Json,
+                        _ => return Err(invalid(format!("Unknown format: {}", value))),
+                    };
+                }
+                _ => return Err(invalid(format!("Unknown resource parameter: {}", key))),
+            }
+        }
+
+        let node_id = |id: &str| Uuid::parse_str(id)
+            .map(NodeId)
+            .map_err(|_| invalid(format!("Invalid entity ID: {}", id)));
+        let segments: Vec<_> = url.path_segments()
+            .map(|segments| segments.filter(|segment| !segment.is_empty()).collect())
+            .unwrap_or_default();
+        match (url.host_str(), segments.as_slice()) {
+            (Some("status"), []) => Ok(Self::Status),
+            (Some("node"), [id]) => Ok(Self::Node { id: node_id(id)?, as_of, format }),
+            (Some("node"), [id, "history"]) => Ok(Self::History { id: node_id(id)?, format }),
+            (Some("subgraph"), [id]) => Ok(Self::Subgraph { id: node_id(id)?, hops, as_of, format }),
+            _ => Err(not_found()),
+        }
+    }
+
+    /// Node the resource is about, if any
+    pub fn node_id(&self) -> Option<NodeId> {
+        match self {
+            Self::Status => None,
+            Self::Node { id, .. } | Self::History { id, .. } | Self::Subgraph { id, .. } => Some(*id),
+        }
+    }
+}
+
+/// URI of the page of node `id`, read as of `as_of` if set
+pub fn node_uri(id: NodeId, as_of: Option<DateTime<Utc>>) -> String {
+    with_as_of(format!("kg://node/{}", id.0), as_of)
+}
+
+/// URI of the history of node `id`
+pub fn history_uri(id: NodeId) -> String {
+    format!("kg://node/{}/history", id.0)
+}
+
+/// URI of the subgraph within `hops` relationships of node `id`, read as of `as_of` if set
+pub fn subgraph_uri(id: NodeId, hops: usize, as_of: Option<DateTime<Utc>>) -> String {
+    let uri = format!("kg://subgraph/{}?hops={}", id.0, hops);
+    match as_of {
+        Some(at) => format!("{}&as_of={}", uri, time(at)),
+        None => uri,
+    }
+}
+
+fn with_as_of(uri: String, as_of: Option<DateTime<Utc>>) -> String {
+    match as_of {
+        Some(at) => format!("{}?as_of={}", uri, time(at)),
+        None => uri,
+    }
+}
+
+/// RFC 3339 form of `at` that needs no escaping in a URI
+pub(crate) fn time(at: DateTime<Utc>) -> String {
+    at.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+/// Every concrete resource the server offers
+///
+/// Entity views are too many to list; they are described by [`templates`].
+pub fn list() -> Vec<Resource> {
+    vec![Resource {
+        uri: STATUS_URI.to_string(),
+        name: "Server status".to_string(),
+        description: Some("Status, uptime and version of the knowledge graph server".to_string()),
+        mime_type: Some("application/json".to_string()),
+    }]
+}
+
+/// Templates of the entity views
+pub fn templates() -> Vec<ResourceTemplate> {
+    vec![
+        template(
+            "kg://node/{id}{?as_of,format}",
+            "Entity",
+            "An entity, its properties and its relationships, optionally as of an RFC 3339 time",
+        ),
+        template(
+            "kg://node/{id}/history{?format}",
+            "Entity history",
+            "The most recent recorded versions of an entity, oldest first",
+        ),
+        template(
+            "kg://subgraph/{id}{?hops,as_of,format}",
+            "Subgraph",
+            "The entities and relationships within `hops` (1 to 3, default 1) relationships of an entity",
+        ),
+    ]
+}
+
+fn template(uri_template: &str, name: &str, description: &str) -> ResourceTemplate {
+    ResourceTemplate {
+        uri_template: uri_template.to_string(),
+        name: name.to_string(),
+        description: Some(description.to_string()),
+        mime_type: Some(Format::Markdown.mime_type().to_string()),
+    }
+}
+
+/// Read the resource at `uri` from the stores of `tenant`
+pub async fn read(state: Arc<ApiState>, tenant: TenantId, uri: &str) -> std::result::Result<ReadResourceResult, JsonRpcError> {
+    let resource = ResourceUri::parse(uri)?;
+    let scope = state.scope(tenant);
+    let limits = state.mcp_limits;
+
+    let (format, text) = match resource {
+        ResourceUri::Status => {
+            let status = json!({
+                "status": "operational",
+                "uptime": state.uptime().as_secs(),
+                "version": env!("CARGO_PKG_VERSION"),
+            });
+            (Format::Json, status.to_string())
+        }
+        ResourceUri::Node { id, as_of, format } => {
+            (format, node_view(&scope, limits, id, as_of, format).await.map_err(rpc_error)?)
+        }
+        ResourceUri::History { id, format } => {
+            (format, history_view(&scope, limits, id, format).await.map_err(rpc_error)?)
+        }
+        ResourceUri::Subgraph { id, hops, as_of, format } => {
+            (format, subgraph_view(&scope, limits, id, hops, as_of, format).await.map_err(rpc_error)?)
+        }
+    };
+
+    Ok(ReadResourceResult {
+        contents: vec![ResourceContents {
+            uri: uri.to_string(),
+            mime_type: Some(format.mime_type().to_string()),
+            text,
+        }],
+    })
+}
+
+/// JSON-RPC error for a view that could not be read
+fn rpc_error(error: ApiError) -> JsonRpcError {
+    let (status, message) = error.into_status_and_message();
+    let code = match status.as_u16() {
+        404 => RESOURCE_NOT_FOUND,
+        400 => INVALID_PARAMS,
+        CLIENT_CLOSED_REQUEST => REQUEST_CANCELLED,
+        _ => INTERNAL_ERROR,
+    };
+    JsonRpcError::new(code, message)
+}
+
+/// Filter reading the graph as it was valid and recorded at `as_of`, or currently
+pub(crate) fn filter_at(as_of: Option<DateTime<Utc>>) -> TemporalFilter {
+    match as_of {
+        Some(at) => TemporalFilter::valid_during(TemporalRange::new(Some(Timestamp(at)), Some(Timestamp(at))))
+            .as_of(Timestamp(at)),
+        None => TemporalFilter::current(),
+    }
+}
+
+/// An edge of a node, with the node at its other end if that is visible
+struct Relationship {
+    edge: Edge,
+    outgoing: bool,
+    neighbor: Option<Node>,
+}
+
+async fn node_view(
+    scope: &TenantScope,
+    limits: McpLimits,
+    id: NodeId,
+    as_of: Option<DateTime<Utc>>,
+    format: Format,
+) -> ApiResult<String> {
+    let filter = filter_at(as_of);
+    let node = scope.graph.get_node(id, filter.clone()).await?;
+
+    let mut relationships = Vec::new();
+    for edge in scope.graph.get_edges_for_node(id, filter.clone()).await?.into_iter().take(limits.max_results) {
+        let outgoing = edge.source_id == id;
+        let other = if outgoing { edge.target_id } else { edge.source_id };
+        let neighbor = visible(scope, other, &filter).await?;
+        relationships.push(Relationship { edge, outgoing, neighbor });
+    }
+
+    if format == Format::Json {
+        let relationships: Vec<_> = relationships.iter()
+            .map(|relationship| json!({
+                "direction": if relationship.outgoing { "outgoing" } else { "incoming" },
+                "edge": relationship.edge,
+                "neighbor": relationship.neighbor.as_ref().map(summary),
+            }))
+            .collect();
+        return Ok(json!({ "entity": node, "as_of": as_of, "relationships": relationships }).to_string());
+    }
+
+    let mut page = format!("# {}\n\n- ID: `{}`\n- Type: {}\n- Valid: {}\n", title(&node), node.id.0, node.entity_type, range(&node.valid_time));
+    if let Some(at) = as_of {
+        page.push_str(&format!("- As of: {}\n", time(at)));
+    }
+
+    page.push_str("\n## Properties\n\n");
+    let mut properties: Vec<_> = node.properties.0.iter().collect();
+    properties.sort_by(|a, b| a.0.cmp(b.0));
+    if properties.is_empty() {
+        page.push_str("None.\n");
+    }
+    for (key, value) in properties {
+        page.push_str(&format!("- {}: {}\n", key, value));
+    }
+
+    page.push_str("\n## Relationships\n\n");
+    if relationships.is_empty() {
+        page.push_str("None.\n");
+    }
+    for relationship in &relationships {
+        let arrow = if relationship.outgoing { "→" } else { "←" };
+        let neighbor = match &relationship.neighbor {
+            Some(neighbor) => link(neighbor, as_of),
+            None => format!("`{}`", if relationship.outgoing { relationship.edge.target_id.0 } else { relationship.edge.source_id.0 }),
+        };
+        page.push_str(&format!("- {} {} {}\n", arrow, relationship.edge.label, neighbor));
+    }
+    Ok(page)
+}
+
+async fn history_view(scope: &TenantScope, limits: McpLimits, id: NodeId, format: Format) -> ApiResult<String> {
+    let history = entity_history(scope, id, limits.max_history).await?;
+
+    if format == Format::Json {
+        let versions: Vec<_> = history.iter().map(history_entry).collect();
+        return Ok(json!({ "id": id.0, "versions": versions }).to_string());
+    }
+
+    let name = history.last().map(title).unwrap_or_else(|| id.0.to_string());
+    let mut page = format!("# History of {}\n\n", name);
+    for (number, node) in history.iter().enumerate() {
+        let superseded = match node.transaction_time.end {
+            Some(at) => format!("superseded {}", time(at.0)),
+            None => "current".to_string(),
+        };
+        let recorded = node.transaction_time.start.map_or_else(|| "at an unknown time".to_string(), |at| time(at.0));
+        page.push_str(&format!(
+            "{}. **{}** recorded {}, {}; valid {}; properties {}\n",
+            number + 1,
+            title(node),
+            recorded,
+            superseded,
+            range(&node.valid_time),
+            json!(node.properties),
+        ));
+    }
+    Ok(page)
+}
+
+async fn subgraph_view(
+    scope: &TenantScope,
+    limits: McpLimits,
+    id: NodeId,
+    hops: usize,
+    as_of: Option<DateTime<Utc>>,
+    format: Format,
+) -> ApiResult<String> {
+    let filter = filter_at(as_of);
+    let root = scope.graph.get_node(id, filter.clone()).await?;
+
+    // Breadth-first, stopping once the view holds as many entities as a command may return
+    let mut nodes = vec![root];
+    let mut seen = HashSet::from([id]);
+    let mut edges = Vec::new();
+    let mut seen_edges = HashSet::new();
+    let mut truncated = false;
+    let mut frontier = vec![id];
+    for _ in 0..hops {
+        let mut next = Vec::new();
+        for node_id in frontier {
+            cancellation::check()?;
+            for edge in scope.graph.get_edges_for_node(node_id, filter.clone()).await? {
+                let other = if edge.source_id == node_id { edge.target_id } else { edge.source_id };
+                if !seen.contains(&other) {
+                    if nodes.len() >= limits.max_results {
+                        truncated = true;
+                        continue;
+                    }
+                    let Some(neighbor) = visible(scope, other, &filter).await? else { continue };
+                    seen.insert(other);
+                    nodes.push(neighbor);
+                    next.push(other);
+                }
+                if seen_edges.insert(edge.id) {
+                    edges.push(edge);
+                }
+            }
+        }
+        frontier = next;
+    }
+
+    if format == Format::Json {
+        return Ok(json!({
+            "root": id.0,
+            "hops": hops,
+            "as_of": as_of,
+            "truncated": truncated,
+            "nodes": nodes,
+            "edges": edges,
+        }).to_string());
+    }
+
+    let mut page = format!(
+        "# Subgraph around {}\n\n{} entities and {} relationships within {} hops",
+        title(&nodes[0]), nodes.len(), edges.len(), hops,
+    );
+    if let Some(at) = as_of {
+        page.push_str(&format!(" as of {}", time(at)));
+    }
+    page.push('.');
+    if truncated {
+        page.push_str(&format!(" Truncated at {} entities.", limits.max_results));
+    }
+
+    page.push_str("\n\n## Entities\n\n");
+    for node in &nodes {
+        page.push_str(&format!("- {} ({})\n", link(node, as_of), node.entity_type));
+    }
+
+    page.push_str("\n## Relationships\n\n");
+    if edges.is_empty() {
+        page.push_str("None.\n");
+    }
+    let name = |id: NodeId| nodes.iter().find(|node| node.id == id).map(title).unwrap_or_else(|| id.0.to_string());
+    for edge in &edges {
+        page.push_str(&format!("- {} —{}→ {}\n", name(edge.source_id), edge.label, name(edge.target_id)));
+    }
+    Ok(page)
+}
+
+/// Node `id` if it passes `filter`
+pub(crate) async fn visible(scope: &TenantScope, id: NodeId, filter: &TemporalFilter) -> ApiResult<Option<Node>> {
+    match scope.graph.get_node(id, filter.clone()).await {
+        Ok(node) => Ok(Some(node)),
+        Err(Error::NodeNotFound(_)) => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Id, type and label of `node`
+pub(crate) fn summary(node: &Node) -> Value {
+    json!({ "id": node.id.0, "type": node.entity_type, "label": node.label })
+}
+
+fn title(node: &Node) -> String {
+    if node.label.trim().is_empty() {
+        node.id.0.to_string()
+    } else {
+        node.label.clone()
+    }
+}
+
+fn link(node: &Node, as_of: Option<DateTime<Utc>>) -> String {
+    format!("[{}]({})", title(node), node_uri(node.id, as_of))
+}
+
+fn range(range: &TemporalRange) -> String {
+    let bound = |bound: &Option<Timestamp>, open: &str| bound.map_or_else(|| open.to_string(), |at| time(at.0));
+    format!("{} to {}", bound(&range.start, "the beginning"), bound(&range.end, "now"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        api::{handlers::{insert_edge, insert_node}, models::{CreateEdgeRequest, CreateNodeRequest}},
+        mcp::server::tests::test_state,
+        types::{EntityType, Properties},
+    };
+
+    #[test]
+    fn test_uris_are_parsed() {
+        let id = NodeId(Uuid::new_v4());
+        let at: DateTime<Utc> = "2024-05-01T12:00:00Z".parse().unwrap();
+
+        assert_eq!(ResourceUri::parse(STATUS_URI).unwrap(), ResourceUri::Status);
+        assert_eq!(
+            ResourceUri::parse(&node_uri(id, Some(at))).unwrap(),
+            ResourceUri::Node { id, as_of: Some(at), format: Format::Markdown },
+        );
+        assert_eq!(
+            ResourceUri::parse(&format!("{}?format=json", history_uri(id))).unwrap(),
+            ResourceUri::History { id, format: Format::Json },
+        );
+        assert_eq!(
+            ResourceUri::parse(&format!("kg://subgraph/{}?hops=2&as_of=", id.0)).unwrap(),
+            ResourceUri::Subgraph { id, hops: 2, as_of: None, format: Format::Markdown },
+        );
+        assert_eq!(ResourceUri::parse(&subgraph_uri(id, 3, Some(at))).unwrap().node_id(), Some(id));
+
+        for uri in ["kg://nope", "http://node/x", "kg://node/a/b/c", "not a uri"] {
+            assert_eq!(ResourceUri::parse(uri).unwrap_err().code, RESOURCE_NOT_FOUND, "{}", uri);
+        }
+        for uri in ["kg://node/not-a-uuid".to_string(), subgraph_uri(id, 4, None), format!("{}?as_of=yesterday", node_uri(id, None))] {
+            assert_eq!(ResourceUri::parse(&uri).unwrap_err().code, INVALID_PARAMS, "{}", uri);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_views_render_entities_and_their_neighborhood() {
+        let state = test_state().await;
+        let tenant = TenantId::default();
+        let scope = state.scope(tenant.clone());
+        let node = |label: &str| CreateNodeRequest {
+            entity_type: EntityType::Person,
+            label: label.to_string(),
+            properties: Properties::new(),
+            valid_time: None,
+        };
+        let edge = |source: &Node, target: &Node| CreateEdgeRequest {
+            source_id: source.id.0,
+            target_id: target.id.0,
+            label: "knows".to_string(),
+            properties: Properties::new(),
+            valid_time: None,
+        };
+        let ada = insert_node(&scope, node("Ada")).await.unwrap();
+        let charles = insert_node(&scope, node("Charles")).await.unwrap();
+        let mary = insert_node(&scope, node("Mary")).await.unwrap();
+        insert_edge(&scope, edge(&ada, &charles)).await.unwrap();
+        insert_edge(&scope, edge(&charles, &mary)).await.unwrap();
+
+        let text = |result: ReadResourceResult| result.contents[0].text.clone();
+        let page = text(read(state.clone(), tenant.clone(), &node_uri(ada.id, None)).await.unwrap());
+        assert!(page.starts_with("# Ada\n"));
+        assert!(page.contains(&format!("- → knows [Charles]({})", node_uri(charles.id, None))));
+
+        let history = read(state.clone(), tenant.clone(), &history_uri(ada.id)).await.unwrap();
+        assert_eq!(history.contents[0].mime_type.as_deref(), Some("text/markdown"));
+        assert!(text(history).contains("1. **Ada** recorded"));
+
+        let one_hop: Value = serde_json::from_str(&text(
+            read(state.clone(), tenant.clone(), &format!("{}&format=json", subgraph_uri(ada.id, 1, None))).await.unwrap(),
+        )).unwrap();
+        assert_eq!(one_hop["nodes"].as_array().unwrap().len(), 2);
+        let two_hops = text(read(state.clone(), tenant.clone(), &subgraph_uri(ada.id, 2, None)).await.unwrap());
+        assert!(two_hops.contains("3 entities and 2 relationships within 2 hops"));
+        assert!(two_hops.contains("- Charles —knows→ Mary"));
+
+        // Before the entity was recorded, and in another tenant, it does not exist
+        let before = Utc::now() - chrono::Duration::days(1);
+        let error = read(state.clone(), tenant, &node_uri(ada.id, Some(before))).await.unwrap_err();
+        assert_eq!(error.code, RESOURCE_NOT_FOUND);
+        let error = read(state, TenantId::new("other").unwrap(), &node_uri(ada.id, None)).await.unwrap_err();
+        assert_eq!(error.code, RESOURCE_NOT_FOUND);
+    }
+}
@@ -0,0 +1,456 @@
+//! MCP message dispatch shared by the stdio and HTTP transports
+//!
+//! A transport parses each message and hands it to [`McpServer::handle_payload`]
+//! together with the [`McpSession`] of the connection it arrived on. Requests
+//! run under a cancellation token registered with the session, so a
+//! `notifications/cancelled` for a running request stops it and the storage
+//! calls it is waiting on. Notifications the server sends on its own, such
+//! as resource updates, are read from [`McpSession::notifications`].
+
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+use futures::future::join_all;
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use tokio::sync::broadcast;
+use tracing::debug;
+
+use crate::{api::ApiState, cancellation::RequestRegistry, types::TenantId};
+use super::{
+    prompts,
+    protocol::*,
+    resources::{self, ResourceUri},
+    subscriptions::Subscriptions,
+    tools,
+};
+
+/// Name the server reports to clients
+pub const SERVER_NAME: &str = "graph";
+
+/// What the client behind a message is allowed to do
+#[derive(Debug, Clone)]
+pub struct Access {
+    /// Whether tools that write to the graph may be called
+    pub write: bool,
+    /// Tenant whose stores tools read and write
+    pub tenant: TenantId,
+    /// Subject of the authenticated client, or `None` for a local one
+    pub subject: Option<String>,
+}
+
+impl Access {
+    /// Access of a local client, such as the stdio transport's
+    pub fn full() -> Self {
+        Self { write: true, tenant: TenantId::default(), subject: None }
+    }
+}
+
+/// State of one client connection
+#[derive(Default)]
+pub struct McpSession {
+    initialized: AtomicBool,
+    /// Requests running on this connection, by JSON-RPC id
+    requests: RequestRegistry,
+    /// Resources the client asked to be notified about
+    subscriptions: Arc<Subscriptions>,
+}
+
+impl McpSession {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether `initialize` has completed
+    pub fn is_initialized(&self) -> bool {
+        self.initialized.load(Ordering::SeqCst)
+    }
+
+    /// Receive the notifications the server sends from now on
+    pub fn notifications(&self) -> broadcast::Receiver<Value> {
+        self.subscriptions.notifications()
+    }
+
+    /// Whether the client is listening for notifications, as on an open stream
+    pub fn is_streaming(&self) -> bool {
+        self.subscriptions.has_listeners()
+    }
+
+    /// Cancel the running request `id` of `tenant`, if any
+    fn cancel(&self, tenant: &TenantId, id: &RequestId) {
+        if self.requests.cancel(tenant, &id.to_string()) {
+            debug!("Cancelling MCP request {}", id);
+        }
+    }
+}
+
+/// Model Context Protocol server over the API's stores
+#[derive(Clone)]
+pub struct McpServer {
+    state: Arc<ApiState>,
+}
+
+impl McpServer {
+    pub fn new(state: Arc<ApiState>) -> Self {
+        Self { state }
+    }
+
+    /// Handle a message or batch of messages, returning what to send back
+    ///
+    /// Returns `None` when nothing is owed, as for notifications.
+    pub async fn handle_payload(&self, session: &Arc<McpSession>, access: Access, payload: Value) -> Option<Value> {
+        match payload {
+            Value::Array(messages) if messages.is_empty() => {
+                let error = JsonRpcError::new(INVALID_REQUEST, "Empty batch");
+                Some(json!(JsonRpcResponse::error(None, error)))
+            }
+            Value::Array(messages) => {
+                let responses: Vec<_> = join_all(messages.into_iter().map(|message| self.handle(session, access.clone(), message)))
+                    .await
+                    .into_iter()
+                    .flatten()
+                    .collect();
+                (!responses.is_empty()).then(|| json!(responses))
+            }
+            message => self.handle(session, access, message).await.map(|response| json!(response)),
+        }
+    }
+
+    /// Handle one message, returning the response owed if it is a request
+    ///
+    /// A request cancelled while running gets a `REQUEST_CANCELLED` error,
+    /// which transports that can stay silent should drop.
+    pub async fn handle(&self, session: &Arc<McpSession>, access: Access, message: Value) -> Option<JsonRpcResponse> {
+        // Clients may answer requests we never send; there is nothing to do with those
+        if message.get("method").is_none() && (message.get("result").is_some() || message.get("error").is_some()) {
+            return None;
+        }
+
+        let id = message.get("id").cloned().and_then(|id| serde_json::from_value(id).ok());
+        let request: JsonRpcRequest = match serde_json::from_value(message) {
+            Ok(request) => request,
+            Err(e) => return Some(JsonRpcResponse::error(id, JsonRpcError::new(INVALID_REQUEST, e.to_string()))),
+        };
+        if request.jsonrpc != "2.0" {
+            return Some(JsonRpcResponse::error(id, JsonRpcError::new(INVALID_REQUEST, "Unsupported JSON-RPC version")));
+        }
+
+        let Some(id) = request.id.clone() else {
+            self.notify(session, &access, request);
+            return None;
+        };
+        if !session.is_initialized() && !matches!(request.method.as_str(), "initialize" | "ping") {
+            return Some(JsonRpcResponse::error(Some(id), JsonRpcError::new(INVALID_REQUEST, "Server not initialized")));
+        }
+
+        let tenant = access.tenant.clone();
+        let outcome = session.requests.run(&tenant, &id.to_string(), self.dispatch(session, access, request)).await;
+
+        Some(match outcome {
+            Ok(result) => JsonRpcResponse::result(id, result),
+            Err(error) => JsonRpcResponse::error(Some(id), error),
+        })
+    }
+
+    fn notify(&self, session: &McpSession, access: &Access, notification: JsonRpcRequest) {
+        match notification.method.as_str() {
+            "notifications/cancelled" => {
+                if let Ok(params) = params::<CancelledParams>(notification.params) {
+                    session.cancel(&access.tenant, &params.request_id);
+                }
+            }
+            method => debug!("Ignoring MCP notification {}", method),
+        }
+    }
+
+    async fn dispatch(&self, session: &Arc<McpSession>, access: Access, request: JsonRpcRequest) -> std::result::Result<Value, JsonRpcError> {
+        match request.method.as_str() {
+            "initialize" => {
+                let params: InitializeParams = params(request.params)?;
+                session.initialized.store(true, Ordering::SeqCst);
+                to_value(initialize(&params))
+            }
+            "ping" => Ok(json!({})),
+            "tools/list" => Ok(json!({ "tools": tools::list() })),
+            "tools/call" => {
+                let params: CallToolParams = params(request.params)?;
+                if tools::WRITE_TOOLS.contains(&params.name.as_str()) && !access.write {
+                    return to_value(tools::error_result("Missing scope graph:write"));
+                }
+                let request_id = request.id.map(|id| id.to_string()).unwrap_or_default();
+                to_value(tools::call(self.state.clone(), access.tenant, access.subject, request_id, &params.name, params.arguments).await?)
+            }
+            "resources/list" => Ok(json!({ "resources": resources::list() })),
+            "resources/templates/list" => Ok(json!({ "resourceTemplates": resources::templates() })),
+            "resources/read" => {
+                let params: ReadResourceParams = params(request.params)?;
+                to_value(resources::read(self.state.clone(), access.tenant, &params.uri).await?)
+            }
+            "resources/subscribe" => {
+                let params: ReadResourceParams = params(request.params)?;
+                let node = ResourceUri::parse(&params.uri)?.node_id();
+                session.subscriptions.subscribe(&self.state.changes, access.tenant, params.uri, node);
+                Ok(json!({}))
+            }
+            "resources/unsubscribe" => {
+                let params: ReadResourceParams = params(request.params)?;
+                session.subscriptions.unsubscribe(&access.tenant, &params.uri);
+                Ok(json!({}))
+            }
+            "prompts/list" => Ok(json!({ "prompts": prompts::list() })),
+            "prompts/get" => {
+                let params: GetPromptParams = params(request.params)?;
+                to_value(prompts::get(self.state.clone(), access.tenant, &params.name, params.arguments).await?)
+            }
+            method => Err(JsonRpcError::new(METHOD_NOT_FOUND, format!("Method not found: {}", method))),
+        }
+    }
+}
+
+/// Result of `initialize`, agreeing on the client's protocol version if supported
+fn initialize(params: &InitializeParams) -> InitializeResult {
+    let protocol_version = SUPPORTED_PROTOCOL_VERSIONS.iter()
+        .find(|version| **version == params.protocol_version)
+        .unwrap_or(&SUPPORTED_PROTOCOL_VERSIONS[0]);
+
+    InitializeResult {
+        protocol_version: protocol_version.to_string(),
+        capabilities: ServerCapabilities {
+            tools: Some(ToolsCapability::default()),
+            resources: Some(ResourcesCapability { subscribe: true, list_changed: false }),
+            prompts: Some(PromptsCapability::default()),
+        },
+        server_info: Implementation {
+            name: SERVER_NAME.to_string(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+        },
+        instructions: Some(concat!(
+            "Tools read and write a temporal knowledge graph of entities and their relationships. ",
+            "Entities can also be read as resources, such as kg://node/{id} and kg://subgraph/{id}?hops=2.",
+        ).to_string()),
+    }
+}
+
+fn params<T: DeserializeOwned>(params: Option<Value>) -> std::result::Result<T, JsonRpcError> {
+    serde_json::from_value(params.unwrap_or(Value::Null))
+        .map_err(|e| JsonRpcError::new(INVALID_PARAMS, e.to_string()))
+}
+
+fn to_value<T: serde::Serialize>(value: T) -> std::result::Result<Value, JsonRpcError> {
+    serde_json::to_value(value).map_err(|e| JsonRpcError::new(INTERNAL_ERROR, e.to_string()))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::{
+        graph::InMemoryGraph,
+        hybrid::{EmbeddingFunction, HashingEmbeddings, HybridStore},
+        memory::LocalMemory,
+        rag::RAGSystem,
+        temporal::TemporalIndex,
+        Config,
+    };
+    use std::time::Duration;
+
+    /// API state over in-process stores
+    pub(crate) async fn test_state() -> Arc<ApiState> {
+        state_with_embeddings(Arc::new(HashingEmbeddings::new(64))).await
+    }
+
+    /// API state over in-process stores, embedding text with `embedding_function`
+    pub(crate) async fn state_with_embeddings(embedding_function: Arc<dyn EmbeddingFunction>) -> Arc<ApiState> {
+        let config = Config::for_testing();
+        let dir = std::env::temp_dir().join(format!("mcp-{}", uuid::Uuid::new_v4()));
+        let memory = Arc::new(LocalMemory::open(dir).await.unwrap());
+        let graph = Arc::new(InMemoryGraph::new());
+        let temporal = Arc::new(TemporalIndex::new());
+        let hybrid = HybridStore::from_shared(&config, graph.clone(), memory.clone(), temporal.clone(), embedding_function);
+        Arc::new(ApiState::new(graph, temporal, Arc::new(RAGSystem::default_mock()), Arc::new(hybrid), memory))
+    }
+
+    /// Embeddings that never finish, so queries run until they are cancelled
+    pub(crate) struct PendingEmbeddings;
+
+    #[async_trait::async_trait]
+    impl EmbeddingFunction for PendingEmbeddings {
+        async fn generate_embedding(&self, _text: &str) -> crate::Result<Vec<f32>> {
+            futures::future::pending().await
+        }
+
+        fn embedding_dim(&self) -> usize {
+            64
+        }
+
+        fn model_name(&self) -> String {
+            "pending".to_string()
+        }
+
+        fn model_version(&self) -> String {
+            "1".to_string()
+        }
+    }
+
+    pub(crate) async fn request(server: &McpServer, session: &Arc<McpSession>, id: i64, method: &str, params: Value) -> Value {
+        let message = json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params });
+        server.handle_payload(session, Access::full(), message).await.unwrap()
+    }
+
+    pub(crate) async fn initialized(server: &McpServer) -> Arc<McpSession> {
+        let session = Arc::new(McpSession::new());
+        request(server, &session, 0, "initialize", json!({
+            "protocolVersion": "2025-03-26",
+            "capabilities": {},
+            "clientInfo": { "name": "test", "version": "1" }
+        })).await;
+        session
+    }
+
+    #[tokio::test]
+    async fn test_initialize_negotiates_the_protocol_version() {
+        let server = McpServer::new(test_state().await);
+        let session = Arc::new(McpSession::new());
+
+        let response = request(&server, &session, 1, "tools/list", json!({})).await;
+        assert_eq!(response["error"]["code"], INVALID_REQUEST);
+
+        let response = request(&server, &session, 2, "initialize", json!({
+            "protocolVersion": "2024-11-05",
+            "capabilities": {}
+        })).await;
+        assert_eq!(response["result"]["protocolVersion"], "2024-11-05");
+        assert_eq!(response["result"]["serverInfo"]["name"], SERVER_NAME);
+        assert!(response["result"]["capabilities"]["tools"].is_object());
+        assert_eq!(response["result"]["capabilities"]["resources"]["subscribe"], true);
+        assert!(response["result"]["capabilities"]["prompts"].is_object());
+
+        let response = request(&server, &session, 3, "initialize", json!({ "protocolVersion": "1999-01-01" })).await;
+        assert_eq!(response["result"]["protocolVersion"], SUPPORTED_PROTOCOL_VERSIONS[0]);
+    }
+
+    #[tokio::test]
+    async fn test_tools_and_resources() {
+        let server = McpServer::new(test_state().await);
+        let session = initialized(&server).await;
+
+        let response = request(&server, &session, 1, "tools/list", json!({})).await;
+        let tools = response["result"]["tools"].as_array().unwrap();
+        assert_eq!(tools.len(), 6);
+        assert!(tools.iter().all(|tool| tool["inputSchema"]["type"] == "object"));
+
+        let response = request(&server, &session, 2, "tools/call", json!({
+            "name": "search_entities",
+            "arguments": { "query": "ada" }
+        })).await;
+        assert_eq!(response["result"]["isError"], false);
+        assert_eq!(response["result"]["content"][0]["type"], "text");
+
+        let response = request(&server, &session, 3, "tools/call", json!({ "name": "get_entity", "arguments": {} })).await;
+        assert_eq!(response["error"]["code"], INVALID_PARAMS);
+        let response = request(&server, &session, 4, "tools/call", json!({ "name": "drop_tables" })).await;
+        assert_eq!(response["error"]["code"], INVALID_PARAMS);
+
+        let message = json!({ "jsonrpc": "2.0", "id": 5, "method": "tools/call", "params": { "name": "create_entity" } });
+        let response = server.handle_payload(&session, Access { write: false, ..Access::full() }, message).await.unwrap();
+        assert_eq!(response["result"]["isError"], true);
+
+        let response = request(&server, &session, 6, "resources/read", json!({ "uri": resources::STATUS_URI })).await;
+        let status: Value = serde_json::from_str(response["result"]["contents"][0]["text"].as_str().unwrap()).unwrap();
+        assert_eq!(status["status"], "operational");
+        let response = request(&server, &session, 7, "resources/read", json!({ "uri": "kg://nope" })).await;
+        assert_eq!(response["error"]["code"], resources::RESOURCE_NOT_FOUND);
+
+        let response = request(&server, &session, 8, "sampling/createMessage", json!({})).await;
+        assert_eq!(response["error"]["code"], METHOD_NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_prompts_embed_entity_resources() {
+        let server = McpServer::new(test_state().await);
+        let session = initialized(&server).await;
+
+        let response = request(&server, &session, 1, "resources/templates/list", json!({})).await;
+        assert_eq!(response["result"]["resourceTemplates"].as_array().unwrap().len(), 3);
+        let response = request(&server, &session, 2, "prompts/list", json!({})).await;
+        assert_eq!(response["result"]["prompts"][0]["name"], "summarize_entity");
+
+        let response = request(&server, &session, 3, "tools/call", json!({
+            "name": "create_entity",
+            "arguments": { "label": "Ada Lovelace", "entity_type": "Person" }
+        })).await;
+        let entity: Value = serde_json::from_str(response["result"]["content"][0]["text"].as_str().unwrap()).unwrap();
+        let id = entity["id"].as_str().unwrap();
+
+        let response = request(&server, &session, 4, "prompts/get", json!({
+            "name": "summarize_entity",
+            "arguments": { "id": id }
+        })).await;
+        let messages = response["result"]["messages"].as_array().unwrap();
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[0]["content"]["type"], "text");
+        assert_eq!(messages[1]["content"]["type"], "resource");
+        assert_eq!(messages[1]["content"]["resource"]["uri"], format!("kg://node/{}", id));
+        assert!(messages[2]["content"]["resource"]["text"].as_str().unwrap().starts_with("# Subgraph around Ada Lovelace"));
+
+        let response = request(&server, &session, 5, "prompts/get", json!({ "name": "summarize_entity", "arguments": {} })).await;
+        assert_eq!(response["error"]["code"], INVALID_PARAMS);
+        let response = request(&server, &session, 6, "prompts/get", json!({
+            "name": "explain_history",
+            "arguments": { "id": uuid::Uuid::new_v4().to_string() }
+        })).await;
+        assert_eq!(response["error"]["code"], resources::RESOURCE_NOT_FOUND);
+
+        let response = request(&server, &session, 7, "resources/subscribe", json!({ "uri": format!("kg://node/{}", id) })).await;
+        assert_eq!(response["result"], json!({}));
+        let response = request(&server, &session, 8, "resources/subscribe", json!({ "uri": "kg://nope" })).await;
+        assert_eq!(response["error"]["code"], resources::RESOURCE_NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_notifications_and_batches() {
+        let server = McpServer::new(test_state().await);
+        let session = initialized(&server).await;
+
+        let notification = json!({ "jsonrpc": "2.0", "method": "notifications/initialized" });
+        assert!(server.handle_payload(&session, Access::full(), notification.clone()).await.is_none());
+
+        let batch = json!([
+            notification,
+            { "jsonrpc": "2.0", "id": "a", "method": "ping" },
+            { "jsonrpc": "1.0", "id": "b", "method": "ping" },
+        ]);
+        let responses = server.handle_payload(&session, Access::full(), batch).await.unwrap();
+        assert_eq!(responses, json!([
+            { "jsonrpc": "2.0", "id": "a", "result": {} },
+            { "jsonrpc": "2.0", "id": "b", "error": { "code": INVALID_REQUEST, "message": "Unsupported JSON-RPC version" } },
+        ]));
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_notification_stops_a_running_call() {
+        let server = McpServer::new(state_with_embeddings(Arc::new(PendingEmbeddings)).await);
+        let session = initialized(&server).await;
+
+        let call = tokio::spawn({
+            let (server, session) = (server.clone(), session.clone());
+            async move {
+                request(&server, &session, 1, "tools/call", json!({
+                    "name": "search_entities",
+                    "arguments": { "query": "ada" }
+                })).await
+            }
+        });
+        while !session.requests.is_running(&TenantId::default(), "1") {
+            tokio::task::yield_now().await;
+        }
+
+        let notification = json!({ "jsonrpc": "2.0", "method": "notifications/cancelled", "params": { "requestId": 1 } });
+        assert!(server.handle_payload(&session, Access::full(), notification).await.is_none());
+
+        let response = tokio::time::timeout(Duration::from_secs(1), call).await.unwrap().unwrap();
+        assert_eq!(response["error"]["code"], REQUEST_CANCELLED);
+        assert!(!session.requests.is_running(&TenantId::default(), "1"));
+    }
+}
@@ -0,0 +1,147 @@
+//! MCP over standard input and output
+//!
+//! Messages are newline-delimited JSON. Each message is handled on its own
+//! task, so a cancellation can arrive while earlier requests are running.
+//! Notifications the session sends, such as resource updates, are written
+//! between responses.
+
+use std::sync::Arc;
+
+use serde_json::{json, Value};
+use tokio::{
+    io::{AsyncBufRead, AsyncBufReadExt, AsyncWrite, AsyncWriteExt},
+    sync::{broadcast::error::RecvError, mpsc},
+};
+
+use crate::error::Result;
+use super::{
+    protocol::{JsonRpcError, JsonRpcResponse, PARSE_ERROR, REQUEST_CANCELLED},
+    server::{Access, McpServer, McpSession},
+};
+
+/// Serve one client reading from `reader` and writing to `writer` until the input closes
+pub async fn serve<R, W>(server: McpServer, reader: R, mut writer: W) -> Result<()>
+where
+    R: AsyncBufRead + Unpin,
+    W: AsyncWrite + Unpin + Send + 'static,
+{
+    let session = Arc::new(McpSession::new());
+    let (tx, mut rx) = mpsc::unbounded_channel::<Value>();
+
+    let writes = tokio::spawn(async move {
+        while let Some(message) = rx.recv().await {
+            writer.write_all(format!("{}\n", message).as_bytes()).await?;
+            writer.flush().await?;
+        }
+        Ok::<_, std::io::Error>(())
+    });
+
+    let forwarding = tokio::spawn({
+        let mut notifications = session.notifications();
+        let tx = tx.clone();
+        async move {
+            loop {
+                match notifications.recv().await {
+                    Ok(notification) => {
+                        if tx.send(notification).is_err() {
+                            break;
+                        }
+                    }
+                    Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        }
+    });
+
+    let mut lines = reader.lines();
+    while let Some(line) = lines.next_line().await? {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let payload = match serde_json::from_str::<Value>(&line) {
+            Ok(payload) => payload,
+            Err(e) => {
+                let _ = tx.send(json!(JsonRpcResponse::error(None, JsonRpcError::new(PARSE_ERROR, e.to_string()))));
+                continue;
+            }
+        };
+
+        let server = server.clone();
+        let session = session.clone();
+        let tx = tx.clone();
+        tokio::spawn(async move {
+            if let Some(response) = server.handle_payload(&session, Access::full(), payload).await {
+                if let Some(response) = without_cancelled(response) {
+                    let _ = tx.send(response);
+                }
+            }
+        });
+    }
+
+    // Requests still running hold senders, so their responses are written before this returns
+    forwarding.abort();
+    let _ = forwarding.await;
+    drop(tx);
+    writes.await.map_err(|e| crate::error::Error::Internal(e.to_string()))??;
+    Ok(())
+}
+
+/// Drop responses to cancelled requests, which the client no longer expects
+fn without_cancelled(response: Value) -> Option<Value> {
+    let cancelled = |response: &Value| response["error"]["code"] == REQUEST_CANCELLED;
+    match response {
+        Value::Array(responses) => {
+            let responses: Vec<_> = responses.into_iter().filter(|response| !cancelled(response)).collect();
+            (!responses.is_empty()).then_some(Value::Array(responses))
+        }
+        response if cancelled(&response) => None,
+        response => Some(response),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mcp::server::tests::test_state;
+    use tokio::io::{duplex, BufReader};
+
+    #[tokio::test]
+    async fn test_serves_newline_delimited_messages() {
+        let server = McpServer::new(test_state().await);
+        let (mut client_write, server_read) = duplex(64 * 1024);
+        let (server_write, client_read) = duplex(64 * 1024);
+        let serving = tokio::spawn(serve(server, BufReader::new(server_read), server_write));
+
+        let input = [
+            r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{"protocolVersion":"2025-03-26","capabilities":{}}}"#,
+            r#"{"jsonrpc":"2.0","method":"notifications/initialized"}"#,
+            "not json",
+            r#"{"jsonrpc":"2.0","id":2,"method":"tools/list"}"#,
+        ];
+        for line in input {
+            client_write.write_all(format!("{}\n", line).as_bytes()).await.unwrap();
+        }
+        drop(client_write);
+
+        let mut lines = BufReader::new(client_read).lines();
+        let mut responses = Vec::new();
+        while let Some(line) = lines.next_line().await.unwrap() {
+            responses.push(serde_json::from_str::<Value>(&line).unwrap());
+        }
+        serving.await.unwrap().unwrap();
+
+        assert_eq!(responses.len(), 3);
+        assert!(responses.iter().any(|r| r["id"] == 1 && r["result"]["serverInfo"].is_object()));
+        assert!(responses.iter().any(|r| r["id"].is_null() && r["error"]["code"] == PARSE_ERROR));
+        assert!(responses.iter().any(|r| r["id"] == 2 && r["result"]["tools"].is_array()));
+    }
+
+    #[test]
+    fn test_cancelled_responses_are_dropped() {
+        let cancelled = json!({ "jsonrpc": "2.0", "id": 1, "error": { "code": REQUEST_CANCELLED, "message": "Request cancelled" } });
+        let ok = json!({ "jsonrpc": "2.0", "id": 2, "result": {} });
+        assert_eq!(without_cancelled(cancelled.clone()), None);
+        assert_eq!(without_cancelled(json!([cancelled, ok.clone()])), Some(json!([ok])));
+    }
+}
@@ -0,0 +1,212 @@
+//! Resource subscriptions and the update notifications they produce
+//!
+//! A session's first `resources/subscribe` starts a task following the
+//! [`ChangeFeed`]. A change to a node, or to an edge incident to it, sends
+//! `notifications/resources/updated` for every subscribed resource about that
+//! node: its page, its history and subgraphs around it. Changes further out
+//! in a subgraph do not notify its subscribers.
+
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, Mutex},
+};
+
+use serde_json::{json, Value};
+use tokio::{sync::broadcast, task::AbortHandle};
+use tracing::warn;
+
+use crate::{
+    changes::{ChangeEvent, ChangeFeed, ChangeFilter, Snapshot},
+    types::{NodeId, TenantId},
+};
+use super::protocol::{JsonRpcRequest, ResourceUpdatedParams};
+
+/// Notifications buffered for a slow client before the oldest are dropped
+const NOTIFICATION_BUFFER: usize = 256;
+
+/// A subscribed resource, identified by the tenant reading it and its URI
+type SubscriptionKey = (TenantId, String);
+
+/// Resources a session subscribed to, and the notifications owed to its client
+pub struct Subscriptions {
+    /// Node each subscribed resource is about, if any
+    resources: Mutex<HashMap<SubscriptionKey, Option<NodeId>>>,
+    notifications: broadcast::Sender<Value>,
+    watcher: Mutex<Option<AbortHandle>>,
+}
+
+impl Subscriptions {
+    pub fn new() -> Self {
+        let (notifications, _) = broadcast::channel(NOTIFICATION_BUFFER);
+        Self {
+            resources: Mutex::new(HashMap::new()),
+            notifications,
+            watcher: Mutex::new(None),
+        }
+    }
+
+    /// Receive the notifications sent from now on
+    pub fn notifications(&self) -> broadcast::Receiver<Value> {
+        self.notifications.subscribe()
+    }
+
+    /// Whether a receiver of the notifications is still open
+    pub fn has_listeners(&self) -> bool {
+        self.notifications.receiver_count() > 0
+    }
+
+    /// Notify the client when resource `uri` about `node` changes in `tenant`
+    pub fn subscribe(self: &Arc<Self>, changes: &Arc<ChangeFeed>, tenant: TenantId, uri: String, node: Option<NodeId>) {
+        self.resources.lock().unwrap().insert((tenant, uri), node);
+        self.watch(changes);
+    }
+
+    /// Stop notifying the client about resource `uri`
+    pub fn unsubscribe(&self, tenant: &TenantId, uri: &str) {
+        self.resources.lock().unwrap().remove(&(tenant.clone(), uri.to_string()));
+    }
+
+    /// Follow `changes` unless already following a feed
+    fn watch(self: &Arc<Self>, changes: &Arc<ChangeFeed>) {
+        let mut watcher = self.watcher.lock().unwrap();
+        if watcher.is_some() {
+            return;
+        }
+
+        // Subscribing here rather than in the task misses no change made after this returns
+        let mut subscription = match changes.subscribe(ChangeFilter::default(), None) {
+            Ok(subscription) => subscription,
+            Err(e) => {
+                warn!("Could not follow the change feed: {}", e);
+                return;
+            }
+        };
+        let feed = changes.clone();
+        let subscriptions = Arc::downgrade(self);
+        let task = tokio::spawn(async move {
+            loop {
+                let event = subscription.next().await;
+                let Some(subscriptions) = subscriptions.upgrade() else { break };
+                match event {
+                    Ok(event) => subscriptions.changed(&event),
+                    Err(e) => {
+                        // Changes were missed, so any subscribed resource may have changed
+                        warn!("Resource subscriptions fell behind the change feed: {}", e);
+                        subscriptions.notify(|_| true);
+                        match feed.subscribe(ChangeFilter::default(), None) {
+                            Ok(resubscribed) => subscription = resubscribed,
+                            Err(_) => break,
+                        }
+                    }
+                }
+            }
+        });
+        *watcher = Some(task.abort_handle());
+    }
+
+    /// Notify subscribers of the resources about nodes `event` touches
+    fn changed(&self, event: &ChangeEvent) {
+        let touched: HashSet<NodeId> = event.before.iter().chain(event.after.iter())
+            .flat_map(|snapshot| match snapshot {
+                Snapshot::Node(node) => vec![node.id],
+                Snapshot::Edge(edge) => vec![edge.source_id, edge.target_id],
+                Snapshot::Memory(_) => Vec::new(),
+            })
+            .collect();
+        if touched.is_empty() {
+            return;
+        }
+
+        self.notify(|(tenant, node)| *tenant == event.tenant && node.is_some_and(|node| touched.contains(&node)));
+    }
+
+    /// Send `notifications/resources/updated` for the subscribed resources matching `predicate`
+    fn notify(&self, predicate: impl Fn((&TenantId, Option<NodeId>)) -> bool) {
+        let uris: Vec<_> = self.resources.lock().unwrap().iter()
+            .filter(|((tenant, _), node)| predicate((tenant, **node)))
+            .map(|((_, uri), _)| uri.clone())
+            .collect();
+
+        for uri in uris {
+            let notification = JsonRpcRequest::notification("notifications/resources/updated", json!(ResourceUpdatedParams { uri }));
+            // The client may have no stream open to receive it
+            let _ = self.notifications.send(json!(notification));
+        }
+    }
+}
+
+impl Default for Subscriptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for Subscriptions {
+    fn drop(&mut self) {
+        if let Some(watcher) = self.watcher.lock().unwrap().take() {
+            watcher.abort();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        changes::ChangeKind,
+        types::{Edge, EdgeId, EntityType, Node, Properties, TemporalRange},
+    };
+    use std::time::Duration;
+    use uuid::Uuid;
+
+    fn node() -> Node {
+        Node {
+            id: NodeId(Uuid::new_v4()),
+            entity_type: EntityType::Person,
+            label: "Ada".to_string(),
+            properties: Properties::new(),
+            valid_time: TemporalRange::from_now(),
+            transaction_time: TemporalRange::from_now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_changes_to_a_node_notify_its_subscribers() {
+        let feed = Arc::new(ChangeFeed::default());
+        let subscriptions = Arc::new(Subscriptions::new());
+        let mut notifications = subscriptions.notifications();
+        let tenant = TenantId::default();
+        let (ada, charles) = (node(), node());
+
+        let page = format!("kg://node/{}", ada.id.0);
+        subscriptions.subscribe(&feed, tenant.clone(), page.clone(), Some(ada.id));
+        subscriptions.subscribe(&feed, tenant.clone(), "kg://status".to_string(), None);
+        subscriptions.subscribe(&feed, TenantId::new("other").unwrap(), format!("kg://node/{}/history", ada.id.0), Some(ada.id));
+
+        // Unrelated nodes and other tenants' changes are not notified
+        feed.publish(ChangeEvent::new(tenant.clone(), ChangeKind::Created, None, Some(Snapshot::Node(charles.clone()))));
+        let edge = Edge {
+            id: EdgeId(Uuid::new_v4()),
+            source_id: charles.id,
+            target_id: ada.id,
+            label: "knows".to_string(),
+            properties: Properties::new(),
+            valid_time: TemporalRange::from_now(),
+            transaction_time: TemporalRange::from_now(),
+        };
+        feed.publish(ChangeEvent::new(tenant.clone(), ChangeKind::Created, None, Some(Snapshot::Edge(edge))));
+
+        let notification = tokio::time::timeout(Duration::from_secs(1), notifications.recv()).await.unwrap().unwrap();
+        assert_eq!(notification, json!({
+            "jsonrpc": "2.0",
+            "method": "notifications/resources/updated",
+            "params": { "uri": page },
+        }));
+        assert!(notifications.try_recv().is_err());
+
+        subscriptions.unsubscribe(&tenant, &page);
+        feed.publish(ChangeEvent::new(tenant, ChangeKind::Updated, Some(Snapshot::Node(ada.clone())), Some(Snapshot::Node(ada))));
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert!(notifications.try_recv().is_err());
+    }
+}
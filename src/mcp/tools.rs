@@ -0,0 +1,211 @@
+//! Graph commands exposed as MCP tools
+//!
+//! Each tool runs the command of the same name through
+//! [`run_request`](super::handlers::run_request) and returns the command's
+//! payload as JSON text. Cancellation is left to the transport's session.
+
+use std::sync::Arc;
+
+use serde_json::{json, Value};
+
+use crate::{api::ApiState, types::TenantId};
+use super::{
+    handlers::run_request,
+    protocol::{CallToolResult, Content, JsonRpcError, Tool, INVALID_PARAMS},
+    MCPRequest, MCPResponse,
+};
+
+/// Tools that write to the graph and need `graph:write`
+pub const WRITE_TOOLS: &[&str] = &["create_entity"];
+
+/// Every tool the server offers
+pub fn list() -> Vec<Tool> {
+    vec![
+        tool(
+            "get_entity",
+            "Get an entity of the knowledge graph by id",
+            json!({
+                "type": "object",
+                "properties": {
+                    "id": { "type": "string", "description": "Entity id" }
+                },
+                "required": ["id"]
+            }),
+        ),
+        tool(
+            "get_connections",
+            "List the relationships connecting an entity to other entities",
+            json!({
+                "type": "object",
+                "properties": {
+                    "id": { "type": "string", "description": "Entity id" },
+                    "limit": { "type": "integer", "minimum": 1, "description": "Most relationships to return" }
+                },
+                "required": ["id"]
+            }),
+        ),
+        tool(
+            "get_entity_history",
+            "List the most recent recorded versions of an entity, oldest first",
+            json!({
+                "type": "object",
+                "properties": {
+                    "id": { "type": "string", "description": "Entity id" },
+                    "limit": { "type": "integer", "minimum": 1, "description": "Most versions to return, keeping the newest" }
+                },
+                "required": ["id"]
+            }),
+        ),
+        tool(
+            "search_entities",
+            "Find entities matching a natural-language query",
+            json!({
+                "type": "object",
+                "properties": {
+                    "query": { "type": "string", "description": "Text to search for" },
+                    "limit": { "type": "integer", "minimum": 1, "description": "Most entities to return" }
+                },
+                "required": ["query"]
+            }),
+        ),
+        tool(
+            "navigate",
+            "Explore the graph step by step from a cursor that remembers the current entity, the trail to it and the time viewed",
+            json!({
+                "type": "object",
+                "properties": {
+                    "session_id": { "type": "string", "description": "Session to continue; omit to start one at id" },
+                    "id": { "type": "string", "description": "Entity to start at, or neighbor to move to" },
+                    "direction": {
+                        "type": "string",
+                        "enum": ["outgoing", "incoming", "both", "back", "forward"],
+                        "description": "Relationships to follow, or back or forward along the trail; omit to keep following the same relationships"
+                    },
+                    "label": { "type": "string", "description": "Only follow relationships with this label" },
+                    "as_of": { "type": "string", "description": "RFC 3339 time to view the graph as of" },
+                    "page": { "type": "integer", "minimum": 0, "description": "Page of neighbors to return, from 0" },
+                    "limit": { "type": "integer", "minimum": 1, "description": "Neighbors per page" }
+                }
+            }),
+        ),
+        tool(
+            "create_entity",
+            "Add an entity to the knowledge graph",
+            json!({
+                "type": "object",
+                "properties": {
+                    "label": { "type": "string", "description": "Label of the new entity" },
+                    "entity_type": { "type": "string", "description": "Type of the new entity, such as Person or Organization" },
+                    "properties": { "type": "object", "description": "Properties of the new entity" }
+                }
+            }),
+        ),
+    ]
+}
+
+fn tool(name: &str, description: &str, input_schema: Value) -> Tool {
+    Tool {
+        name: name.to_string(),
+        description: description.to_string(),
+        input_schema,
+    }
+}
+
+/// Run tool `name` with `arguments` on behalf of request `request_id` of `subject` in `tenant`
+///
+/// Unknown tools and arguments that do not match the tool's schema are
+/// protocol errors; failures of the command itself, such as an unknown
+/// entity id, are tool errors.
+pub async fn call(
+    state: Arc<ApiState>,
+    tenant: TenantId,
+    subject: Option<String>,
+    request_id: String,
+    name: &str,
+    arguments: Option<Value>,
+) -> std::result::Result<CallToolResult, JsonRpcError> {
+    let tool = list().into_iter()
+        .find(|tool| tool.name == name)
+        .ok_or_else(|| JsonRpcError::new(INVALID_PARAMS, format!("Unknown tool: {}", name)))?;
+    let arguments = arguments.unwrap_or_else(|| json!({}));
+    check_arguments(&tool.input_schema, &arguments)?;
+
+    let request = MCPRequest::Command {
+        request_id,
+        command: tool.name,
+        data: arguments,
+        cursor_position: None,
+    };
+    let scope = state.scope(tenant).with_caller(subject);
+    Ok(match run_request(&state, &scope, request).await {
+        Ok(response) => tool_result(response),
+        Err(e) => error_result(e.to_string()),
+    })
+}
+
+/// Tool result reporting `message` as a failure
+pub fn error_result(message: impl Into<String>) -> CallToolResult {
+    CallToolResult {
+        content: vec![Content::Text { text: message.into() }],
+        is_error: true,
+    }
+}
+
+/// Check `arguments` against the required properties and types of `schema`
+fn check_arguments(schema: &Value, arguments: &Value) -> std::result::Result<(), JsonRpcError> {
+    let invalid = |message: String| JsonRpcError::new(INVALID_PARAMS, message);
+    let arguments = arguments.as_object()
+        .ok_or_else(|| invalid("Tool arguments must be an object".to_string()))?;
+
+    let required = schema["required"].as_array().into_iter().flatten().filter_map(Value::as_str);
+    for name in required {
+        if !arguments.contains_key(name) {
+            return Err(invalid(format!("Missing argument: {}", name)));
+        }
+    }
+    for (name, value) in arguments {
+        let matches = match schema["properties"][name]["type"].as_str() {
+            Some("string") => value.is_string(),
+            Some("integer") => value.is_i64() || value.is_u64(),
+            Some("object") => value.is_object(),
+            _ => true,
+        };
+        if !matches {
+            return Err(invalid(format!("Invalid argument: {}", name)));
+        }
+    }
+    Ok(())
+}
+
+/// Tool result carrying a command's payload
+fn tool_result(response: MCPResponse) -> CallToolResult {
+    let payload = match response {
+        MCPResponse::Error { error } => return error_result(error),
+        MCPResponse::Cancelled { request_id } => return error_result(format!("Request {} was cancelled", request_id)),
+        MCPResponse::Entity { entity, .. } => entity,
+        MCPResponse::Connections { connections, .. } => connections,
+        MCPResponse::History { history, .. } => history,
+        MCPResponse::SearchResults { results, .. } => results,
+        MCPResponse::EntityCreated { entity, .. } => entity,
+        MCPResponse::QueryResult { result, .. } => result,
+        MCPResponse::Navigation { navigation, .. } => navigation,
+    };
+    CallToolResult {
+        content: vec![Content::Text { text: serde_json::to_string_pretty(&payload).unwrap_or_default() }],
+        is_error: false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_arguments_are_checked_against_the_schema() {
+        let schema = list().into_iter().find(|tool| tool.name == "get_entity").unwrap().input_schema;
+        assert!(check_arguments(&schema, &json!({ "id": "a" })).is_ok());
+        assert_eq!(check_arguments(&schema, &json!({})).unwrap_err().code, INVALID_PARAMS);
+        assert_eq!(check_arguments(&schema, &json!({ "id": 1 })).unwrap_err().code, INVALID_PARAMS);
+        assert_eq!(check_arguments(&schema, &json!([])).unwrap_err().code, INVALID_PARAMS);
+    }
+}
@@ -0,0 +1,394 @@
+//! Hierarchical navigable small world (HNSW) index
+//!
+//! An in-process approximate nearest-neighbour index over cosine similarity,
+//! used by `LocalMemory`. Vectors are normalized on insert. Replaced or
+//! removed vectors are tombstoned and stay in the graph for navigation until
+//! the index is rebuilt.
+
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+/// Construction and search parameters for an `HnswIndex`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HnswParams {
+    /// Maximum neighbours per node on the upper layers; layer 0 keeps twice as many
+    pub m: usize,
+    /// Size of the candidate list while inserting
+    pub ef_construction: usize,
+    /// Minimum size of the candidate list while searching
+    pub ef_search: usize,
+}
+
+impl Default for HnswParams {
+    fn default() -> Self {
+        Self {
+            m: 16,
+            ef_construction: 128,
+            ef_search: 64,
+        }
+    }
+}
+
+/// A node in the HNSW graph
+#[derive(Debug, Clone)]
+struct HnswNode {
+    id: String,
+    vector: Vec<f32>,
+    /// Neighbour slots for each layer the node appears on
+    neighbors: Vec<Vec<usize>>,
+    deleted: bool,
+}
+
+/// A slot paired with its distance to the query
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Candidate {
+    distance: f32,
+    slot: usize,
+}
+
+impl Eq for Candidate {}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.distance.total_cmp(&other.distance).then(self.slot.cmp(&other.slot))
+    }
+}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// HNSW index over vectors of a single dimension
+#[derive(Debug, Clone)]
+pub struct HnswIndex {
+    params: HnswParams,
+    dimension: usize,
+    nodes: Vec<HnswNode>,
+    /// Slot of the live node for each ID
+    slots: HashMap<String, usize>,
+    entry_point: Option<usize>,
+    max_level: usize,
+    rng: StdRng,
+}
+
+impl HnswIndex {
+    /// Create an empty index for vectors of `dimension`
+    pub fn new(dimension: usize, params: HnswParams) -> Self {
+        Self {
+            params: HnswParams {
+                m: params.m.max(2),
+                ef_construction: params.ef_construction.max(1),
+                ef_search: params.ef_search.max(1),
+            },
+            dimension,
+            nodes: Vec::new(),
+            slots: HashMap::new(),
+            entry_point: None,
+            max_level: 0,
+            rng: StdRng::seed_from_u64(0x5eed),
+        }
+    }
+
+    /// Dimension of the indexed vectors
+    pub fn dimension(&self) -> usize {
+        self.dimension
+    }
+
+    /// Number of live vectors
+    pub fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Whether the index holds no live vectors
+    pub fn is_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
+
+    /// Insert or replace the vector for `id`
+    ///
+    /// Vectors whose length differs from the index dimension are ignored.
+    pub fn insert(&mut self, id: &str, vector: &[f32]) {
+        if vector.len() != self.dimension {
+            return;
+        }
+        self.remove(id);
+        if self.nodes.len() > 2 * self.slots.len() + 64 {
+            self.rebuild();
+        }
+
+        let vector = normalize(vector);
+        let level = self.random_level();
+        let slot = self.nodes.len();
+        self.nodes.push(HnswNode {
+            id: id.to_string(),
+            vector,
+            neighbors: vec![Vec::new(); level + 1],
+            deleted: false,
+        });
+        self.slots.insert(id.to_string(), slot);
+
+        let entry_point = match self.entry_point {
+            Some(entry_point) => entry_point,
+            None => {
+                self.entry_point = Some(slot);
+                self.max_level = level;
+                return;
+            }
+        };
+
+        let query = self.nodes[slot].vector.clone();
+        let mut nearest = vec![self.candidate(&query, entry_point)];
+        for layer in (level + 1..=self.max_level).rev() {
+            nearest = self.search_layer(&query, &nearest, 1, layer);
+        }
+
+        for layer in (0..=level.min(self.max_level)).rev() {
+            nearest = self.search_layer(&query, &nearest, self.params.ef_construction, layer);
+            let max_connections = self.max_connections(layer);
+            let neighbors: Vec<usize> = nearest.iter()
+                .take(max_connections)
+                .map(|candidate| candidate.slot)
+                .collect();
+
+            self.nodes[slot].neighbors[layer] = neighbors.clone();
+            for neighbor in neighbors {
+                self.nodes[neighbor].neighbors[layer].push(slot);
+                if self.nodes[neighbor].neighbors[layer].len() > max_connections {
+                    self.prune(neighbor, layer, max_connections);
+                }
+            }
+        }
+
+        if level > self.max_level {
+            self.max_level = level;
+            self.entry_point = Some(slot);
+        }
+    }
+
+    /// Remove the vector for `id`, returning whether it was present
+    pub fn remove(&mut self, id: &str) -> bool {
+        match self.slots.remove(id) {
+            Some(slot) => {
+                self.nodes[slot].deleted = true;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Find up to `k` live vectors most similar to `query` that `accept` their ID
+    ///
+    /// Returns IDs with their cosine similarity, most similar first. When
+    /// `accept` rejects many candidates, the search widens until `k` matches
+    /// are found or the whole graph has been explored.
+    pub fn search<F>(&self, query: &[f32], k: usize, accept: F) -> Vec<(String, f32)>
+    where
+        F: Fn(&str) -> bool,
+    {
+        let entry_point = match self.entry_point {
+            Some(entry_point) if k > 0 && query.len() == self.dimension => entry_point,
+            _ => return Vec::new(),
+        };
+
+        let query = normalize(query);
+        let mut nearest = vec![self.candidate(&query, entry_point)];
+        for layer in (1..=self.max_level).rev() {
+            nearest = self.search_layer(&query, &nearest, 1, layer);
+        }
+
+        let mut ef = self.params.ef_search.max(k);
+        loop {
+            let found = self.search_layer(&query, &nearest, ef, 0);
+            let results: Vec<(String, f32)> = found.iter()
+                .filter_map(|candidate| {
+                    let node = &self.nodes[candidate.slot];
+                    (!node.deleted && accept(&node.id)).then(|| (node.id.clone(), 1.0 - candidate.distance))
+                })
+                .take(k)
+                .collect();
+
+            if results.len() >= k || ef >= self.nodes.len() {
+                return results;
+            }
+            ef = (ef * 2).min(self.nodes.len());
+        }
+    }
+
+    /// Rebuild the graph from live vectors, dropping tombstones
+    pub fn rebuild(&mut self) {
+        let live: Vec<HnswNode> = std::mem::take(&mut self.nodes)
+            .into_iter()
+            .filter(|node| !node.deleted)
+            .collect();
+        self.slots.clear();
+        self.entry_point = None;
+        self.max_level = 0;
+        for node in live {
+            self.insert(&node.id, &node.vector);
+        }
+    }
+
+    /// Greedy best-first search of one layer, returning up to `ef` candidates nearest first
+    fn search_layer(&self, query: &[f32], entry_points: &[Candidate], ef: usize, layer: usize) -> Vec<Candidate> {
+        let mut visited: HashSet<usize> = entry_points.iter().map(|c| c.slot).collect();
+        // Min-heap of candidates to expand and max-heap of the best found so far
+        let mut to_visit: BinaryHeap<std::cmp::Reverse<Candidate>> =
+            entry_points.iter().copied().map(std::cmp::Reverse).collect();
+        let mut found: BinaryHeap<Candidate> = entry_points.iter().copied().collect();
+        while found.len() > ef {
+            found.pop();
+        }
+
+        while let Some(std::cmp::Reverse(current)) = to_visit.pop() {
+            let furthest = found.peek().map_or(f32::INFINITY, |c| c.distance);
+            if current.distance > furthest && found.len() >= ef {
+                break;
+            }
+
+            let neighbors = match self.nodes[current.slot].neighbors.get(layer) {
+                Some(neighbors) => neighbors,
+                None => continue,
+            };
+            for &neighbor in neighbors {
+                if !visited.insert(neighbor) {
+                    continue;
+                }
+                let candidate = self.candidate(query, neighbor);
+                let furthest = found.peek().map_or(f32::INFINITY, |c| c.distance);
+                if found.len() < ef || candidate.distance < furthest {
+                    to_visit.push(std::cmp::Reverse(candidate));
+                    found.push(candidate);
+                    if found.len() > ef {
+                        found.pop();
+                    }
+                }
+            }
+        }
+
+        found.into_sorted_vec()
+    }
+
+    /// Keep only the `max_connections` nearest neighbours of `slot` on `layer`
+    fn prune(&mut self, slot: usize, layer: usize, max_connections: usize) {
+        let vector = self.nodes[slot].vector.clone();
+        let mut neighbors: Vec<Candidate> = self.nodes[slot].neighbors[layer]
+            .iter()
+            .map(|&neighbor| self.candidate(&vector, neighbor))
+            .collect();
+        neighbors.sort();
+        neighbors.truncate(max_connections);
+        self.nodes[slot].neighbors[layer] = neighbors.into_iter().map(|c| c.slot).collect();
+    }
+
+    fn candidate(&self, query: &[f32], slot: usize) -> Candidate {
+        Candidate {
+            distance: cosine_distance(query, &self.nodes[slot].vector),
+            slot,
+        }
+    }
+
+    fn max_connections(&self, layer: usize) -> usize {
+        if layer == 0 {
+            self.params.m * 2
+        } else {
+            self.params.m
+        }
+    }
+
+    /// Draw a level from the exponential distribution with normalization 1/ln(m)
+    fn random_level(&mut self) -> usize {
+        let level_mult = 1.0 / (self.params.m as f64).ln();
+        let uniform: f64 = self.rng.gen_range(f64::EPSILON..1.0);
+        ((-uniform.ln() * level_mult).floor() as usize).min(16)
+    }
+}
+
+/// Scale `vector` to unit length; zero vectors are returned unchanged
+fn normalize(vector: &[f32]) -> Vec<f32> {
+    let norm = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm == 0.0 {
+        vector.to_vec()
+    } else {
+        vector.iter().map(|x| x / norm).collect()
+    }
+}
+
+/// Cosine distance between unit vectors
+fn cosine_distance(a: &[f32], b: &[f32]) -> f32 {
+    1.0 - a.iter().zip(b).map(|(x, y)| x * y).sum::<f32>()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn random_vectors(count: usize, dimension: usize, seed: u64) -> Vec<Vec<f32>> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        (0..count)
+            .map(|_| (0..dimension).map(|_| rng.gen_range(-1.0..1.0)).collect())
+            .collect()
+    }
+
+    fn exact_top_k(vectors: &[Vec<f32>], query: &[f32], k: usize) -> Vec<String> {
+        let query = normalize(query);
+        let mut scored: Vec<(usize, f32)> = vectors.iter()
+            .enumerate()
+            .map(|(i, v)| (i, cosine_distance(&query, &normalize(v))))
+            .collect();
+        scored.sort_by(|a, b| a.1.total_cmp(&b.1));
+        scored.into_iter().take(k).map(|(i, _)| i.to_string()).collect()
+    }
+
+    #[test]
+    fn test_recall_against_exact_search() {
+        let vectors = random_vectors(500, 16, 1);
+        let mut index = HnswIndex::new(16, HnswParams::default());
+        for (i, vector) in vectors.iter().enumerate() {
+            index.insert(&i.to_string(), vector);
+        }
+        assert_eq!(index.len(), 500);
+
+        let mut hits = 0;
+        for query in random_vectors(20, 16, 2) {
+            let expected = exact_top_k(&vectors, &query, 10);
+            let found: Vec<String> = index.search(&query, 10, |_| true).into_iter().map(|(id, _)| id).collect();
+            hits += found.iter().filter(|id| expected.contains(id)).count();
+        }
+        assert!(hits >= 180, "recall too low: {}/200", hits);
+    }
+
+    #[test]
+    fn test_replace_remove_and_filter() {
+        let mut index = HnswIndex::new(2, HnswParams::default());
+        index.insert("a", &[1.0, 0.0]);
+        index.insert("b", &[0.0, 1.0]);
+        index.insert("c", &[0.7, 0.7]);
+
+        let top = index.search(&[1.0, 0.0], 1, |_| true);
+        assert_eq!(top[0].0, "a");
+        assert!((top[0].1 - 1.0).abs() < 1e-6);
+
+        // Replacing moves "a" away from the query
+        index.insert("a", &[-1.0, 0.0]);
+        assert_eq!(index.len(), 3);
+        assert_eq!(index.search(&[1.0, 0.0], 1, |_| true)[0].0, "c");
+
+        assert!(index.remove("c"));
+        assert!(!index.remove("c"));
+        let ids: Vec<String> = index.search(&[1.0, 0.0], 3, |_| true).into_iter().map(|(id, _)| id).collect();
+        assert_eq!(ids, vec!["b".to_string(), "a".to_string()]);
+
+        let filtered = index.search(&[1.0, 0.0], 3, |id| id == "a");
+        assert_eq!(filtered.len(), 1);
+        assert_eq!(filtered[0].0, "a");
+
+        // Vectors of another dimension are not indexed
+        index.insert("d", &[1.0, 0.0, 0.0]);
+        assert_eq!(index.len(), 2);
+        assert!(index.search(&[1.0, 0.0, 0.0], 3, |_| true).is_empty());
+    }
+}
@@ -0,0 +1,592 @@
+//! File-backed implementation of the `Memory` trait
+//!
+//! Entries are appended as JSON lines to `entries.jsonl` in a local directory
+//! and replayed on open, with the last record for an ID winning. Similarity
+//! search uses an in-process HNSW index per embedding dimension, rebuilt from
+//! the log at startup. Filters accept the subset of the OpenSearch query DSL
+//! produced by `VectorFilter`.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use tokio::fs::{self, File, OpenOptions};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::sync::RwLock;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::{
+    error::{Error, Result},
+    memory::{
+        has_stale_embedding,
+        hnsw::{HnswIndex, HnswParams},
+        Memory, MemoryEntry,
+    },
+    types::{EntityType, TemporalRange},
+};
+
+const LOG_FILE: &str = "entries.jsonl";
+
+/// Compact the log once it holds this many superseded records
+const COMPACTION_THRESHOLD: usize = 1024;
+
+/// Mutable state behind the store's lock
+struct LocalState {
+    entries: HashMap<String, MemoryEntry>,
+    /// One index per embedding dimension, so vectors from different models never mix
+    indexes: HashMap<usize, HnswIndex>,
+    log: File,
+    /// Records in the log, including superseded ones
+    log_records: usize,
+}
+
+impl LocalState {
+    fn index(&mut self, entry: &MemoryEntry, params: HnswParams) {
+        for index in self.indexes.values_mut() {
+            index.remove(&entry.id);
+        }
+        if let Some(embedding) = entry.embedding.as_ref().filter(|e| !e.is_empty()) {
+            self.indexes
+                .entry(embedding.len())
+                .or_insert_with(|| HnswIndex::new(embedding.len(), params))
+                .insert(&entry.id, embedding);
+        }
+    }
+}
+
+/// `Memory` persisted to a local directory with an embedded ANN index
+pub struct LocalMemory {
+    dir: PathBuf,
+    params: HnswParams,
+    state: RwLock<LocalState>,
+}
+
+impl LocalMemory {
+    /// Open or create a store in `dir` with default HNSW parameters
+    pub async fn open(dir: impl AsRef<Path>) -> Result<Self> {
+        Self::open_with_params(dir, HnswParams::default()).await
+    }
+
+    /// Open or create a store in `dir`
+    ///
+    /// A truncated final record, left by a crash mid-write, is discarded.
+    pub async fn open_with_params(dir: impl AsRef<Path>, params: HnswParams) -> Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir).await?;
+        let path = dir.join(LOG_FILE);
+
+        let mut entries = HashMap::new();
+        let mut log_records = 0;
+        let mut truncated = false;
+        if fs::try_exists(&path).await? {
+            let mut lines = BufReader::new(File::open(&path).await?).lines();
+            let mut pending: Option<String> = None;
+            while let Some(line) = lines.next_line().await? {
+                // Only the last line may be incomplete, so parse each line once its successor is known
+                if let Some(previous) = pending.replace(line) {
+                    let entry = parse_record(&previous)?;
+                    entries.insert(entry.id.clone(), entry);
+                    log_records += 1;
+                }
+            }
+            if let Some(last) = pending.filter(|line| !line.trim().is_empty()) {
+                match parse_record(&last) {
+                    Ok(entry) => {
+                        entries.insert(entry.id.clone(), entry);
+                        log_records += 1;
+                    }
+                    Err(e) => {
+                        warn!("Discarding truncated record at the end of {}: {}", path.display(), e);
+                        truncated = true;
+                    }
+                }
+            }
+        }
+
+        let mut indexes: HashMap<usize, HnswIndex> = HashMap::new();
+        for entry in entries.values() {
+            if let Some(embedding) = entry.embedding.as_ref().filter(|e| !e.is_empty()) {
+                indexes
+                    .entry(embedding.len())
+                    .or_insert_with(|| HnswIndex::new(embedding.len(), params))
+                    .insert(&entry.id, embedding);
+            }
+        }
+
+        let log = OpenOptions::new().create(true).append(true).open(&path).await?;
+        let memory = Self {
+            dir,
+            params,
+            state: RwLock::new(LocalState {
+                entries,
+                indexes,
+                log,
+                log_records,
+            }),
+        };
+
+        if truncated {
+            memory.compact().await?;
+        }
+        info!(
+            "Opened local memory at {} with {} entries",
+            memory.dir.display(),
+            memory.state.read().await.entries.len()
+        );
+        Ok(memory)
+    }
+
+    /// Directory holding the store's files
+    pub fn path(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Number of stored entries
+    pub async fn len(&self) -> usize {
+        self.state.read().await.entries.len()
+    }
+
+    /// Whether the store holds no entries
+    pub async fn is_empty(&self) -> bool {
+        self.state.read().await.entries.is_empty()
+    }
+
+    /// Rewrite the log so it holds exactly one record per entry
+    pub async fn compact(&self) -> Result<()> {
+        let mut state = self.state.write().await;
+        self.rewrite_log(&mut state).await
+    }
+
+    async fn rewrite_log(&self, state: &mut LocalState) -> Result<()> {
+        let path = self.dir.join(LOG_FILE);
+        let temp_path = self.dir.join(format!("{}.tmp", LOG_FILE));
+
+        let mut buffer = Vec::new();
+        for entry in state.entries.values() {
+            serde_json::to_writer(&mut buffer, entry)?;
+            buffer.push(b'\n');
+        }
+        let mut temp = File::create(&temp_path).await?;
+        temp.write_all(&buffer).await?;
+        temp.sync_all().await?;
+        drop(temp);
+        fs::rename(&temp_path, &path).await?;
+
+        state.log = OpenOptions::new().append(true).open(&path).await?;
+        state.log_records = state.entries.len();
+        Ok(())
+    }
+
+    /// Append `entries` to the log and apply them to the in-memory state
+    async fn write(&self, entries: Vec<MemoryEntry>) -> Result<()> {
+        if entries.is_empty() {
+            return Ok(());
+        }
+
+        let mut buffer = Vec::new();
+        for entry in &entries {
+            serde_json::to_writer(&mut buffer, entry)?;
+            buffer.push(b'\n');
+        }
+
+        let mut state = self.state.write().await;
+        state.log.write_all(&buffer).await?;
+        state.log.sync_data().await?;
+        state.log_records += entries.len();
+
+        for entry in entries {
+            state.index(&entry, self.params);
+            state.entries.insert(entry.id.clone(), entry);
+        }
+
+        if state.log_records - state.entries.len() >= COMPACTION_THRESHOLD.max(state.entries.len()) {
+            self.rewrite_log(&mut state).await?;
+        }
+        Ok(())
+    }
+
+    /// Entries matching `predicate`, newest first
+    async fn scan<F>(&self, limit: usize, predicate: F) -> Vec<MemoryEntry>
+    where
+        F: Fn(&MemoryEntry) -> bool,
+    {
+        let state = self.state.read().await;
+        let mut matches: Vec<&MemoryEntry> = state.entries.values().filter(|e| predicate(e)).collect();
+        matches.sort_by(|a, b| b.created_at.cmp(&a.created_at).then_with(|| a.id.cmp(&b.id)));
+        matches.into_iter().take(limit).cloned().collect()
+    }
+}
+
+#[async_trait]
+impl Memory for LocalMemory {
+    async fn store(&self, entry: MemoryEntry) -> Result<()> {
+        self.write(vec![entry]).await
+    }
+
+    async fn store_bulk(&self, entries: Vec<MemoryEntry>) -> Result<()> {
+        self.write(entries).await
+    }
+
+    async fn search_similar(&self, embedding: Vec<f32>, k: usize, filter: Option<Value>) -> Result<Vec<MemoryEntry>> {
+        let state = self.state.read().await;
+        let index = match state.indexes.get(&embedding.len()) {
+            Some(index) => index,
+            None => return Ok(Vec::new()),
+        };
+
+        // Validate the filter once so unsupported clauses surface as errors
+        if let Some(filter) = &filter {
+            if let Some(entry) = state.entries.values().next() {
+                matches_filter(entry, filter)?;
+            }
+        }
+
+        let hits = index.search(&embedding, k, |id| match (&filter, state.entries.get(id)) {
+            (_, None) => false,
+            (None, Some(_)) => true,
+            (Some(filter), Some(entry)) => matches_filter(entry, filter).unwrap_or(false),
+        });
+
+        Ok(hits.into_iter()
+            .filter_map(|(id, _)| state.entries.get(&id).cloned())
+            .collect())
+    }
+
+    async fn get_by_node_type(&self, node_type: EntityType, limit: usize) -> Result<Vec<MemoryEntry>> {
+        Ok(self.scan(limit, |entry| entry.node_type.as_ref() == Some(&node_type)).await)
+    }
+
+    async fn get_by_time_range(&self, range: TemporalRange, limit: usize) -> Result<Vec<MemoryEntry>> {
+        Ok(self.scan(limit, |entry| {
+            range.start.map_or(true, |start| entry.created_at >= start.0)
+                && range.end.map_or(true, |end| entry.created_at <= end.0)
+        }).await)
+    }
+
+    async fn get_for_node(&self, node_id: Uuid, limit: usize) -> Result<Vec<MemoryEntry>> {
+        let id = node_id.to_string();
+        Ok(self.scan(limit, |entry| {
+            entry.id == id
+                || entry_field(entry, "source_id").as_deref() == Some(id.as_str())
+                || entry_field(entry, "target_id").as_deref() == Some(id.as_str())
+        }).await)
+    }
+
+    async fn get_for_edge(&self, source_id: Uuid, target_id: Uuid, limit: usize) -> Result<Vec<MemoryEntry>> {
+        let (source, target) = (source_id.to_string(), target_id.to_string());
+        Ok(self.scan(limit, |entry| {
+            entry_field(entry, "source_id").as_deref() == Some(source.as_str())
+                && entry_field(entry, "target_id").as_deref() == Some(target.as_str())
+        }).await)
+    }
+
+    async fn get(&self, id: &str) -> Result<Option<MemoryEntry>> {
+        Ok(self.state.read().await.entries.get(id).cloned())
+    }
+
+    async fn get_stale_embeddings(&self, model: &str, model_version: &str, dimension: usize, limit: usize) -> Result<Vec<MemoryEntry>> {
+        Ok(self.scan(limit, |entry| has_stale_embedding(entry, model, model_version, dimension)).await)
+    }
+}
+
+fn parse_record(line: &str) -> Result<MemoryEntry> {
+    serde_json::from_str(line).map_err(|e| Error::Serialization(e.to_string()))
+}
+
+/// A string field stored in the entry's metadata, either at the top level or
+/// under the `metadata` object written by the hybrid store
+fn entry_field(entry: &MemoryEntry, name: &str) -> Option<String> {
+    entry.metadata.get(name)
+        .or_else(|| entry.metadata.get("metadata").and_then(|m| m.get(name)))
+        .and_then(Value::as_str)
+        .map(str::to_string)
+}
+
+/// Resolve a dotted OpenSearch field path against an entry
+fn field_value(entry: &MemoryEntry, path: &str) -> Option<Value> {
+    match path {
+        "id" => Some(Value::String(entry.id.clone())),
+        "content" => Some(Value::String(entry.content.clone())),
+        "node_type" => entry.node_type.as_ref().map(|t| Value::String(t.to_string())),
+        "created_at" => Some(Value::String(entry.created_at.to_rfc3339())),
+        "updated_at" => Some(Value::String(entry.updated_at.to_rfc3339())),
+        "embedding" => entry.embedding.as_ref().map(|_| Value::Bool(true)),
+        _ => {
+            let rest = path.strip_prefix("metadata.")?;
+            let mut parts = rest.split('.');
+            let mut value = entry.metadata.get(parts.next()?)?;
+            for part in parts {
+                value = value.get(part)?;
+            }
+            Some(value.clone())
+        }
+    }
+}
+
+/// Clauses of a `bool` section, which may be a single clause or an array
+fn clauses(value: Option<&Value>) -> Vec<&Value> {
+    match value {
+        Some(Value::Array(items)) => items.iter().collect(),
+        Some(item) => vec![item],
+        None => Vec::new(),
+    }
+}
+
+/// The single `field: argument` pair of a leaf clause
+fn single_field(body: &Value) -> Result<(&String, &Value)> {
+    body.as_object()
+        .filter(|fields| fields.len() == 1)
+        .and_then(|fields| fields.iter().next())
+        .ok_or_else(|| Error::InvalidInput(format!("Expected a single field in filter clause: {}", body)))
+}
+
+fn values_equal(actual: &Value, expected: &Value) -> bool {
+    match (actual, expected) {
+        (Value::Number(a), Value::Number(b)) => a.as_f64() == b.as_f64(),
+        (Value::String(a), Value::String(b)) => a == b,
+        (a, Value::String(b)) | (Value::String(b), a) => a.to_string() == *b,
+        (a, b) => a == b,
+    }
+}
+
+fn compare_values(actual: &Value, bound: &Value) -> Option<std::cmp::Ordering> {
+    if let (Some(a), Some(b)) = (actual.as_f64(), bound.as_f64()) {
+        return a.partial_cmp(&b);
+    }
+    let (a, b) = (actual.as_str()?, bound.as_str()?);
+    match (DateTime::parse_from_rfc3339(a), DateTime::parse_from_rfc3339(b)) {
+        (Ok(a), Ok(b)) => Some(a.with_timezone(&Utc).cmp(&b.with_timezone(&Utc))),
+        _ => Some(a.cmp(b)),
+    }
+}
+
+/// Evaluate an OpenSearch query clause against an entry
+///
+/// Supports `match_all`, `bool`, `term`, `terms`, `range`, `ids` and `exists`.
+fn matches_filter(entry: &MemoryEntry, filter: &Value) -> Result<bool> {
+    let (kind, body) = single_field(filter)?;
+
+    match kind.as_str() {
+        "match_all" => Ok(true),
+        "bool" => {
+            for clause in clauses(body.get("must")).into_iter().chain(clauses(body.get("filter"))) {
+                if !matches_filter(entry, clause)? {
+                    return Ok(false);
+                }
+            }
+            for clause in clauses(body.get("must_not")) {
+                if matches_filter(entry, clause)? {
+                    return Ok(false);
+                }
+            }
+            let should = clauses(body.get("should"));
+            if should.is_empty() {
+                return Ok(true);
+            }
+            for clause in should {
+                if matches_filter(entry, clause)? {
+                    return Ok(true);
+                }
+            }
+            Ok(false)
+        }
+        "term" => {
+            let (field, expected) = single_field(body)?;
+            let expected = expected.get("value").unwrap_or(expected);
+            Ok(field_value(entry, field).map_or(false, |actual| values_equal(&actual, expected)))
+        }
+        "terms" => {
+            let (field, expected) = single_field(body)?;
+            let expected = expected.as_array()
+                .ok_or_else(|| Error::InvalidInput(format!("terms filter on {} expects an array", field)))?;
+            Ok(field_value(entry, field)
+                .map_or(false, |actual| expected.iter().any(|e| values_equal(&actual, e))))
+        }
+        "range" => {
+            let (field, bounds) = single_field(body)?;
+            let actual = match field_value(entry, field) {
+                Some(actual) => actual,
+                None => return Ok(false),
+            };
+            let bounds = bounds.as_object()
+                .ok_or_else(|| Error::InvalidInput(format!("range filter on {} expects bounds", field)))?;
+            for (op, bound) in bounds {
+                let ordering = compare_values(&actual, bound);
+                let satisfied = match op.as_str() {
+                    "gt" => ordering == Some(std::cmp::Ordering::Greater),
+                    "gte" => matches!(ordering, Some(std::cmp::Ordering::Greater | std::cmp::Ordering::Equal)),
+                    "lt" => ordering == Some(std::cmp::Ordering::Less),
+                    "lte" => matches!(ordering, Some(std::cmp::Ordering::Less | std::cmp::Ordering::Equal)),
+                    _ => true,
+                };
+                if !satisfied {
+                    return Ok(false);
+                }
+            }
+            Ok(true)
+        }
+        "ids" => Ok(body.get("values")
+            .and_then(Value::as_array)
+            .map_or(false, |ids| ids.iter().any(|id| id.as_str() == Some(entry.id.as_str())))),
+        "exists" => {
+            let field = body.get("field").and_then(Value::as_str)
+                .ok_or_else(|| Error::InvalidInput("exists filter expects a field".to_string()))?;
+            Ok(field_value(entry, field).map_or(false, |value| !value.is_null()))
+        }
+        other => Err(Error::InvalidInput(format!("Unsupported filter clause for local memory: {}", other))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::VectorFilter;
+    use crate::types::Timestamp;
+    use chrono::TimeZone;
+    use serde_json::json;
+
+    fn temp_dir() -> PathBuf {
+        std::env::temp_dir().join(format!("local-memory-{}", Uuid::new_v4()))
+    }
+
+    fn entry(id: &str, embedding: Vec<f32>, node_type: EntityType) -> MemoryEntry {
+        let mut entry = MemoryEntry::new(id.to_string(), format!("content of {}", id));
+        entry.embedding = Some(embedding);
+        entry.node_type = Some(node_type);
+        entry
+    }
+
+    #[tokio::test]
+    async fn test_search_survives_reopen() {
+        let dir = temp_dir();
+        {
+            let memory = LocalMemory::open(&dir).await.unwrap();
+            memory.store(entry("alice", vec![1.0, 0.0, 0.0], EntityType::Person)).await.unwrap();
+            memory.store_bulk(vec![
+                entry("acme", vec![0.9, 0.1, 0.0], EntityType::Organization),
+                entry("paris", vec![0.0, 0.0, 1.0], EntityType::Location),
+            ]).await.unwrap();
+            // The last write for an ID wins
+            memory.store(entry("paris", vec![0.0, 1.0, 0.0], EntityType::Location)).await.unwrap();
+        }
+
+        let memory = LocalMemory::open(&dir).await.unwrap();
+        assert_eq!(memory.len().await, 3);
+
+        let results = memory.search_similar(vec![1.0, 0.0, 0.0], 2, None).await.unwrap();
+        let ids: Vec<_> = results.iter().map(|e| e.id.as_str()).collect();
+        assert_eq!(ids, vec!["alice", "acme"]);
+
+        let filter = VectorFilter::new().with_node_types(vec![EntityType::Location]).to_query();
+        let results = memory.search_similar(vec![1.0, 0.0, 0.0], 2, filter).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].embedding, Some(vec![0.0, 1.0, 0.0]));
+
+        // Vectors of another dimension are never compared
+        assert!(memory.search_similar(vec![1.0, 0.0], 2, None).await.unwrap().is_empty());
+
+        let unsupported = json!({ "match": { "content": "alice" } });
+        assert!(memory.search_similar(vec![1.0, 0.0, 0.0], 2, Some(unsupported)).await.is_err());
+
+        fs::remove_dir_all(&dir).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_valid_time_filter() {
+        let dir = temp_dir();
+        let memory = LocalMemory::open(&dir).await.unwrap();
+        let at = |year: i32| Utc.with_ymd_and_hms(year, 1, 1, 0, 0, 0).unwrap();
+        let valid = |id: &str, start: Option<i32>, end: Option<i32>| {
+            let mut entry = entry(id, vec![1.0, 0.0], EntityType::Person);
+            entry.metadata.insert("metadata".to_string(), json!({
+                "valid_time_start": start.map(|y| at(y).to_rfc3339()),
+                "valid_time_end": end.map(|y| at(y).to_rfc3339()),
+            }));
+            entry
+        };
+        memory.store_bulk(vec![
+            valid("ended", Some(2000), Some(2010)),
+            valid("overlapping", Some(2010), Some(2022)),
+            valid("open", None, None),
+            valid("future", Some(2030), None),
+        ]).await.unwrap();
+
+        let filter = VectorFilter::new()
+            .valid_during(TemporalRange {
+                start: Some(Timestamp(at(2020))),
+                end: Some(Timestamp(at(2025))),
+            })
+            .to_query();
+        let results = memory.search_similar(vec![1.0, 0.0], 10, filter).await.unwrap();
+        let mut ids: Vec<_> = results.iter().map(|e| e.id.as_str()).collect();
+        ids.sort();
+        assert_eq!(ids, vec!["open", "overlapping"]);
+
+        fs::remove_dir_all(&dir).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_truncated_record_is_discarded() {
+        let dir = temp_dir();
+        {
+            let memory = LocalMemory::open(&dir).await.unwrap();
+            memory.store(entry("alice", vec![1.0, 0.0], EntityType::Person)).await.unwrap();
+        }
+        let mut log = OpenOptions::new().append(true).open(dir.join(LOG_FILE)).await.unwrap();
+        log.write_all(b"{\"id\":\"bob\",\"crea").await.unwrap();
+        drop(log);
+
+        let memory = LocalMemory::open(&dir).await.unwrap();
+        assert_eq!(memory.len().await, 1);
+        memory.store(entry("carol", vec![0.0, 1.0], EntityType::Person)).await.unwrap();
+        drop(memory);
+
+        let memory = LocalMemory::open(&dir).await.unwrap();
+        assert!(memory.get("alice").await.unwrap().is_some());
+        assert!(memory.get("carol").await.unwrap().is_some());
+
+        fs::remove_dir_all(&dir).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_lookups() {
+        let dir = temp_dir();
+        let memory = LocalMemory::open(&dir).await.unwrap();
+        let (source, target) = (Uuid::new_v4(), Uuid::new_v4());
+
+        let mut old = entry("old", vec![1.0, 0.0], EntityType::Person);
+        old.created_at = Utc::now() - chrono::Duration::days(30);
+        let mut edge = entry("edge", vec![0.0, 1.0], EntityType::Edge);
+        edge.metadata.insert("metadata".to_string(), json!({
+            "type": "edge",
+            "source_id": source.to_string(),
+            "target_id": target.to_string(),
+        }));
+        memory.store_bulk(vec![old, edge, entry(&source.to_string(), vec![0.5, 0.5], EntityType::Person)]).await.unwrap();
+
+        let people = memory.get_by_node_type(EntityType::Person, 10).await.unwrap();
+        assert_eq!(people.len(), 2);
+
+        let recent = memory.get_by_time_range(TemporalRange {
+            start: Some(Timestamp(Utc::now() - chrono::Duration::days(1))),
+            end: None,
+        }, 10).await.unwrap();
+        assert_eq!(recent.len(), 2);
+        assert!(recent.iter().all(|e| e.id != "old"));
+
+        let for_node = memory.get_for_node(source, 10).await.unwrap();
+        assert_eq!(for_node.len(), 2);
+        let for_edge = memory.get_for_edge(source, target, 10).await.unwrap();
+        assert_eq!(for_edge.len(), 1);
+        assert_eq!(for_edge[0].id, "edge");
+        assert!(memory.get_for_edge(target, source, 10).await.unwrap().is_empty());
+
+        let stale = memory.get_stale_embeddings("feature-hashing", "1", 2, 10).await.unwrap();
+        assert_eq!(stale.len(), 3);
+
+        fs::remove_dir_all(&dir).await.unwrap();
+    }
+}
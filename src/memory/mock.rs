@@ -1,34 +1,46 @@
 use async_trait::async_trait;
 use chrono::Utc;
 use serde_json::Value;
+use std::collections::HashMap;
 use std::sync::Arc;
+use tokio::sync::RwLock;
 use uuid::Uuid;
 
 use crate::{
     error::Result,
-    memory::{Memory, MemoryEntry},
+    memory::{has_stale_embedding, Memory, MemoryEntry},
     types::{EntityType, TemporalRange},
 };
 
 /// Mock implementation of the Memory trait for testing
-pub struct MockMemory {}
+///
+/// Stored entries can be read back by ID and scanned for stale embeddings;
+/// every other search returns nothing.
+pub struct MockMemory {
+    entries: RwLock<HashMap<String, MemoryEntry>>,
+}
 
 impl MockMemory {
     /// Create a new MockMemory instance
     pub fn new() -> Self {
-        Self {}
+        Self {
+            entries: RwLock::new(HashMap::new()),
+        }
     }
 }
 
 #[async_trait]
 impl Memory for MockMemory {
-    async fn store(&self, _entry: MemoryEntry) -> Result<()> {
-        // Mock implementation - does nothing
+    async fn store(&self, entry: MemoryEntry) -> Result<()> {
+        self.entries.write().await.insert(entry.id.clone(), entry);
         Ok(())
     }
     
-    async fn store_bulk(&self, _entries: Vec<MemoryEntry>) -> Result<()> {
-        // Mock implementation - does nothing
+    async fn store_bulk(&self, entries: Vec<MemoryEntry>) -> Result<()> {
+        let mut stored = self.entries.write().await;
+        for entry in entries {
+            stored.insert(entry.id.clone(), entry);
+        }
         Ok(())
     }
     
@@ -56,4 +68,17 @@ impl Memory for MockMemory {
         // Return empty vec for mock implementation
         Ok(Vec::new())
     }
+    
+    async fn get(&self, id: &str) -> Result<Option<MemoryEntry>> {
+        Ok(self.entries.read().await.get(id).cloned())
+    }
+    
+    async fn get_stale_embeddings(&self, model: &str, model_version: &str, dimension: usize, limit: usize) -> Result<Vec<MemoryEntry>> {
+        let entries = self.entries.read().await;
+        Ok(entries.values()
+            .filter(|entry| has_stale_embedding(entry, model, model_version, dimension))
+            .take(limit)
+            .cloned()
+            .collect())
+    }
 } 
\ No newline at end of file
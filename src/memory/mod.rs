@@ -13,26 +13,31 @@ use opensearch::{
     IndexParts, 
     OpenSearch, 
     SearchParts,
-    DeleteParts,
-    DeleteByQueryParts,
-    http::StatusCode,
-    indices::{IndicesCreateParts, IndicesExistsParts, IndicesDeleteParts},
-    GetParts,
+    indices::{IndicesCreateParts, IndicesExistsParts, IndicesDeleteParts, IndicesGetSettingsParts},
 };
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use tokio::sync::RwLock;
+use tracing::warn;
 use url::Url;
 use uuid::Uuid;
 
 use crate::{
     error::{Error, Result},
+    config::MemoryBackend,
     Config,
-    types::{TemporalRange, EntityType, Timestamp},
+    telemetry::timed,
+    types::{TemporalRange, EntityType, Timestamp, TENANT_PROPERTY},
 };
 
 const DEFAULT_INDEX: &str = "memory";
 
+/// Backend label on the metrics recorded by `MemorySystem`
+const BACKEND: &str = "opensearch";
+
+/// Metadata key holding the model that produced an entry's embedding
+pub const EMBEDDING_METADATA_KEY: &str = "embedding_metadata";
+
 /// Represents a memory entry in the vector store
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MemoryEntry {
@@ -60,607 +65,273 @@ impl MemoryEntry {
     }
 }
 
-/// Memory operations trait defining the interface for vector storage and retrieval
-#[async_trait]
-pub trait MemoryOperations: Send + Sync {
-    /// Store a new memory entry
-    async fn store(&self, entry: MemoryEntry) -> Result<()>;
-    
-    /// Retrieve similar memories based on vector similarity
-    async fn search_similar(&self, query_vector: &[f32], limit: usize) -> Result<Vec<MemoryEntry>>;
-    
-    /// Get memory by ID
-    async fn get(&self, id: &str) -> Result<Option<MemoryEntry>>;
-    
-    /// Delete a memory entry
-    async fn delete(&self, id: &str) -> Result<()>;
-}
-
-/// OpenSearch implementation of MemoryOperations
-pub struct OpenSearchMemory {
-    client: OpenSearch,
+/// Memory system for efficient vector storage and retrieval
+pub struct MemorySystem {
+    /// OpenSearch client
+    client: Arc<OpenSearch>,
+    /// Index name for vector storage
     index: String,
+    /// Dimension of vector embeddings
+    embedding_dim: usize,
+    /// HNSW parameters used when creating the index
+    knn: KnnSettings,
+    /// Whether the index is served by the k-NN plugin; otherwise similarity falls back to `script_score`
+    knn_enabled: bool,
 }
 
-impl OpenSearchMemory {
-    /// Create a new OpenSearchMemory instance
-    pub async fn new(config: &Config) -> Result<Self> {
-        let url = Url::parse(&config.memory_url)
-            .map_err(|e| Error::OpenSearch(e.to_string()))?;
-        let conn_pool = SingleNodeConnectionPool::new(url);
-        let mut transport_builder = TransportBuilder::new(conn_pool);
-        
-        if !config.memory_username.is_empty() {
-            transport_builder = transport_builder.auth(Credentials::Basic(
-                config.memory_username.clone(),
-                config.memory_password.clone()
-            ));
-        }
-
-        let transport = transport_builder.build()
-            .map_err(|e| Error::OpenSearch(e.to_string()))?;
-        let client = OpenSearch::new(transport);
-        let index = DEFAULT_INDEX.to_string();
-
-        let memory = Self { client, index };
-        memory.initialize().await?;
-        Ok(memory)
-    }
-    
-    /// Initialize the OpenSearch index with proper mappings
-    pub async fn initialize(&self) -> Result<()> {
-        // Check if index exists
-        let exists = self.client
-            .indices()
-            .exists(IndicesExistsParts::Index(&[&self.index]))
-            .send()
-            .await?
-            .status_code()
-            .is_success();
-
-        if exists {
-            return Ok(());
-        }
-
-        let mapping = json!({
-            "mappings": {
-                "properties": {
-                    "id": { "type": "keyword" },
-                    "created_at": { "type": "date" },
-                    "updated_at": { "type": "date" },
-                    "content": { "type": "text" },
-                    "embedding": { "type": "dense_vector", "dims": 768 },
-                    "metadata": { "type": "object" },
-                    "node_type": { "type": "keyword" }
-                }
-            }
-        });
-        
-        let response = self.client
-            .indices()
-            .create(IndicesCreateParts::Index(&self.index))
-            .body(mapping)
-            .send()
-            .await
-            .map_err(|e| Error::OpenSearch(e.to_string()))?;
-            
-        if !response.status_code().is_success() {
-            return Err(Error::OpenSearch("Failed to initialize index".to_string()));
-        }
-            
-        Ok(())
-    }
-    
-    /// Convert vector to OpenSearch format
-    fn format_vector(vector: &[f32]) -> String {
-        vector
-            .iter()
-            .map(|x| x.to_string())
-            .collect::<Vec<_>>()
-            .join(",")
-    }
-
-    pub async fn index_document(&self, document: Value) -> Result<()> {
-        let response = self.client
-            .index(IndexParts::Index(&self.index))
-            .body(document)
-            .send()
-            .await
-            .map_err(|e| Error::OpenSearch(e.to_string()))?;
-
-        if !response.status_code().is_success() {
-            return Err(Error::OpenSearch(format!("Failed to index document: {}", response.status_code())));
-        }
-
-        Ok(())
-    }
-
-    pub async fn search_documents(&self, query: Value) -> Result<Vec<Value>> {
-        let response = self.client
-            .search(SearchParts::Index(&[&self.index]))
-            .body(query)
-            .send()
-            .await
-            .map_err(|e| Error::OpenSearch(e.to_string()))?;
-
-        if !response.status_code().is_success() {
-            return Err(Error::OpenSearch(format!("Failed to search documents: {}", response.status_code())));
-        }
-
-        let search_response: Value = response.json().await
-            .map_err(|e| Error::OpenSearch(e.to_string()))?;
-
-        let hits = search_response["hits"]["hits"]
-            .as_array()
-            .unwrap_or(&Vec::new())
-            .iter()
-            .map(|hit| hit["_source"].clone())
-            .collect();
-
-        Ok(hits)
-    }
-
-    pub async fn get(&self, id: &str) -> Result<Option<MemoryEntry>> {
-        let response = self.client
-            .get(GetParts::IndexId(&self.index, id))
-            .send()
-            .await
-            .map_err(|e| Error::OpenSearch(e.to_string()))?;
-
-        if response.status_code() == StatusCode::NOT_FOUND {
-            return Ok(None);
-        }
-
-        if !response.status_code().is_success() {
-            return Err(Error::OpenSearch(format!("Failed to get document: {}", response.status_code())));
-        }
-
-        let search_response: Value = response.json().await
-            .map_err(|e| Error::OpenSearch(e.to_string()))?;
+/// HNSW parameters for OpenSearch's k-NN plugin
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KnnSettings {
+    /// k-NN engine; `lucene` and `faiss` support filtering during the graph search
+    pub engine: String,
+    /// Distance function, e.g. `cosinesimil`, `l2` or `innerproduct`
+    pub space_type: String,
+    /// Number of bidirectional links per node in the HNSW graph
+    pub m: usize,
+    /// Size of the candidate list while building the graph
+    pub ef_construction: usize,
+    /// Size of the candidate list while searching
+    pub ef_search: usize,
+}
 
-        if let Some(hits) = search_response["hits"]["hits"].as_array() {
-            if let Some(hit) = hits.first() {
-                if let Some(_) = hit["_source"].as_object() {
-                    let entry: MemoryEntry = serde_json::from_value(hit["_source"].clone())
-                        .map_err(|e| Error::Serialization(e.to_string()))?;
-                    return Ok(Some(entry));
-                }
-            }
+impl Default for KnnSettings {
+    fn default() -> Self {
+        Self {
+            engine: "lucene".to_string(),
+            space_type: "cosinesimil".to_string(),
+            m: 16,
+            ef_construction: 128,
+            ef_search: 100,
         }
-
-        Ok(None)
     }
+}
 
-    fn parse_memory_entry(&self, source: &Value) -> Result<MemoryEntry> {
-        let metadata = source["metadata"].as_object()
-            .ok_or_else(|| Error::OpenSearch("Invalid metadata format".to_string()))?
-            .iter()
-            .map(|(k, v)| (k.clone(), v.clone()))
-            .collect();
-
-        let node_type = source.get("node_type")
-            .and_then(|v| v.as_str())
-            .map(|s| EntityType::from_str(s))
-            .transpose()?;
-
-        Ok(MemoryEntry {
-            id: source["id"].as_str()
-                .ok_or_else(|| Error::OpenSearch("Missing id field".to_string()))?
-                .to_string(),
-            created_at: chrono::DateTime::parse_from_rfc3339(
-                source["created_at"].as_str()
-                    .ok_or_else(|| Error::OpenSearch("Missing created_at field".to_string()))?
-            )?.with_timezone(&chrono::Utc),
-            updated_at: chrono::DateTime::parse_from_rfc3339(
-                source["updated_at"].as_str()
-                    .ok_or_else(|| Error::OpenSearch("Missing updated_at field".to_string()))?
-            )?.with_timezone(&chrono::Utc),
-            content: source["content"].as_str()
-                .ok_or_else(|| Error::OpenSearch("Missing content field".to_string()))?
-                .to_string(),
-            metadata,
-            embedding: source.get("embedding")
-                .and_then(|e| e.as_array())
-                .map(|arr| arr.iter()
-                    .filter_map(|v| v.as_f64())
-                    .map(|v| v as f32)
-                    .collect()
-                ),
-            node_type,
-        })
-    }
+/// Pre-filter applied to vector similarity searches
+///
+/// Converted to an OpenSearch query clause with `to_query` and passed as the
+/// `filter` argument of `Memory::search_similar`.
+#[derive(Debug, Clone, Default)]
+pub struct VectorFilter {
+    /// Only match entries with one of these node types
+    pub node_types: Vec<EntityType>,
+    /// Only match entries whose valid time overlaps this range
+    pub valid_time: Option<TemporalRange>,
+    /// Only match entries whose metadata field has exactly this value
+    pub metadata: HashMap<String, Value>,
+    /// Additional raw query clause that must match
+    pub query: Option<Value>,
+}
 
-    async fn delete_document(&self, index: &str, id: &str) -> Result<()> {
-        let request = self.client
-            .delete(DeleteParts::IndexId(index, id));
-            
-        request.send().await.map_err(|e| Error::OpenSearch(e.to_string()))?;
-        Ok(())
+impl VectorFilter {
+    /// Create an empty filter
+    pub fn new() -> Self {
+        Self::default()
     }
 
-    pub async fn store(&self, entry: MemoryEntry) -> Result<()> {
-        let document = json!({
-            "id": entry.id,
-            "embedding": entry.embedding,
-            "metadata": entry.metadata,
-            "created_at": entry.created_at.to_rfc3339(),
-            "updated_at": entry.updated_at.to_rfc3339(),
-            "content": entry.content,
-            "node_type": entry.node_type.map(|nt| nt.to_string()),
-        });
-
-        let response = self.client
-            .index(IndexParts::Index(&self.index))
-            .body(document)
-            .send()
-            .await
-            .map_err(|e| Error::OpenSearch(e.to_string()))?;
-
-        if !response.status_code().is_success() {
-            return Err(Error::OpenSearch("Failed to store entry".to_string()));
-        }
-
-        Ok(())
+    /// Restrict matches to the given node types
+    pub fn with_node_types(mut self, node_types: Vec<EntityType>) -> Self {
+        self.node_types = node_types;
+        self
     }
 
-    /// Store multiple entries in a single bulk request
-    pub async fn bulk_store(&self, entries: Vec<MemoryEntry>) -> Result<()> {
-        if entries.is_empty() {
-            return Ok(());
-        }
-
-        let mut bulk_operations: Vec<BulkOperation<Value>> = Vec::with_capacity(entries.len());
-        for entry in entries {
-            let operation = BulkOperation::index(json!({
-                "id": entry.id,
-                "created_at": entry.created_at,
-                "updated_at": entry.updated_at,
-                "content": entry.content,
-                "metadata": entry.metadata,
-                "embedding": entry.embedding,
-                "node_type": entry.node_type
-            }))
-            .id(&entry.id)
-            .into();
-            bulk_operations.push(operation);
-        }
-
-        let response = self.client
-            .bulk(BulkParts::Index(&self.index))
-            .body(bulk_operations)
-            .send()
-            .await
-            .map_err(|e| Error::OpenSearch(e.to_string()))?;
-
-        if !response.status_code().is_success() {
-            return Err(Error::OpenSearch(format!("Failed to bulk store documents: {}", response.status_code())));
-        }
-
-        Ok(())
+    /// Restrict matches to entries whose valid time overlaps `range`
+    ///
+    /// Entries without a valid-time bound are treated as open on that side.
+    pub fn valid_during(mut self, range: TemporalRange) -> Self {
+        self.valid_time = Some(range);
+        self
     }
 
-    async fn parse_search_results(&self, response_body: Value) -> Result<Vec<MemoryEntry>> {
-        let hits = response_body["hits"]["hits"]
-            .as_array()
-            .ok_or_else(|| Error::OpenSearch("Invalid response format".to_string()))?;
-
-        let mut results = Vec::new();
-        for hit in hits {
-            let source = hit["_source"].clone();
-            let metadata = source["metadata"]
-                .as_object()
-                .map(|obj| {
-                    obj.iter()
-                        .map(|(k, v)| (k.clone(), v.clone()))
-                        .collect::<HashMap<String, Value>>()
-                })
-                .unwrap_or_default();
-
-            let node_type = if let Some(type_str) = source.get("node_type").and_then(|v| v.as_str()) {
-                match EntityType::from_str(type_str) {
-                    Ok(nt) => Some(nt),
-                    Err(e) => return Err(Error::OpenSearch(e.to_string())),
-                }
-            } else {
-                None
-            };
-
-            let entry = MemoryEntry {
-                id: source["id"].as_str()
-                    .ok_or_else(|| Error::OpenSearch("Missing id field".to_string()))?
-                    .to_string(),
-                embedding: serde_json::from_value(source["embedding"].clone())
-                    .map_err(|e| Error::OpenSearch(e.to_string()))?,
-                metadata,
-                created_at: DateTime::parse_from_rfc3339(source["created_at"].as_str().unwrap_or_default())
-                    .map_err(|e| Error::OpenSearch(e.to_string()))?
-                    .with_timezone(&Utc),
-                updated_at: DateTime::parse_from_rfc3339(source["updated_at"].as_str().unwrap_or_default())
-                    .map_err(|e| Error::OpenSearch(e.to_string()))?
-                    .with_timezone(&Utc),
-                content: source["content"].as_str().unwrap_or_default().to_string(),
-                node_type,
-            };
-            results.push(entry);
-        }
-
-        Ok(results)
+    /// Require a metadata field to equal `value`
+    pub fn with_metadata(mut self, key: impl Into<String>, value: Value) -> Self {
+        self.metadata.insert(key.into(), value);
+        self
     }
 
-    pub async fn search(&self, query: &Value) -> Result<Vec<MemoryEntry>> {
-        let response = self.client
-            .search(SearchParts::Index(&[&self.index]))
-            .body(query)
-            .send()
-            .await
-            .map_err(|e| Error::OpenSearch(e.to_string()))?;
-
-        if !response.status_code().is_success() {
-            return Err(Error::OpenSearch("Search failed".to_string()));
-        }
-
-        let response_body = response.json::<Value>().await
-            .map_err(|e| Error::OpenSearch(e.to_string()))?;
-
-        self.parse_search_results(response_body).await
+    /// Require an additional raw query clause to match
+    pub fn with_query(mut self, query: Value) -> Self {
+        self.query = Some(query);
+        self
     }
 
-    /// Search by ID
-    pub async fn search_by_id(&self, id: &str) -> Result<Option<MemoryEntry>> {
-        let query = json!({
-            "query": {
-                "term": {
-                    "id": id
-                }
-            }
-        });
+    /// Build the OpenSearch query clause, or `None` when nothing is filtered
+    pub fn to_query(&self) -> Option<Value> {
+        let mut clauses = Vec::new();
 
-        let response = self.client
-            .search(SearchParts::Index(&[&self.index]))
-            .body(query)
-            .send()
-            .await
-            .map_err(|e| Error::OpenSearch(e.to_string()))?;
-
-        if !response.status_code().is_success() {
-            return Err(Error::OpenSearch(format!("Failed to search documents: {}", response.status_code())));
+        if !self.node_types.is_empty() {
+            let types: Vec<String> = self.node_types.iter().map(|t| t.to_string()).collect();
+            clauses.push(json!({ "terms": { "node_type": types } }));
         }
 
-        let search_response: Value = response.json().await
-            .map_err(|e| Error::OpenSearch(e.to_string()))?;
-
-        if let Some(hits) = search_response["hits"]["hits"].as_array() {
-            if let Some(hit) = hits.first() {
-                if let Some(_) = hit["_source"].as_object() {
-                    let entry: MemoryEntry = serde_json::from_value(hit["_source"].clone())
-                        .map_err(|e| Error::Serialization(e.to_string()))?;
-                    return Ok(Some(entry));
-                }
+        if let Some(range) = &self.valid_time {
+            // The entry's interval overlaps the range when it starts before the
+            // range ends and ends after the range starts
+            if let Some(end) = range.end {
+                clauses.push(open_or_bounded(VALID_TIME_START_FIELD, json!({ "lte": end.0.to_rfc3339() })));
             }
-        }
-
-        Ok(None)
-    }
-
-    /// Delete entries by query
-    pub async fn delete_by_query(&self, query: &Value) -> Result<usize> {
-        let response = self.client
-            .delete_by_query(DeleteByQueryParts::Index(&[&self.index]))
-            .body(query)
-            .send()
-            .await
-            .map_err(|e| Error::OpenSearch(e.to_string()))?;
-            
-        let response_body = response.json::<Value>().await
-            .map_err(|e| Error::OpenSearch(e.to_string()))?;
-
-        let deleted = response_body["deleted"]
-            .as_u64()
-            .ok_or_else(|| Error::OpenSearch("Invalid response format".to_string()))?;
-
-        Ok(deleted as usize)
-    }
-
-    /// Bulk delete memory entries in OpenSearch
-    pub async fn bulk_delete(&self, entries: &[MemoryEntry]) -> Result<usize> {
-        if entries.is_empty() {
-            return Ok(0);
-        }
-
-        let mut bulk_operations: Vec<BulkOperation<Value>> = Vec::with_capacity(entries.len());
-        for entry in entries {
-            let operation = BulkOperation::delete(&entry.id).into();
-            bulk_operations.push(operation);
-        }
-
-        let response = self.client
-            .bulk(BulkParts::Index(&self.index))
-            .body(bulk_operations)
-            .send()
-            .await
-            .map_err(|e| Error::OpenSearch(e.to_string()))?;
-        
-        if !response.status_code().is_success() {
-            return Err(Error::OpenSearch("Bulk delete operation failed".to_string()));
-        }
-        
-        let response_body = response.json::<Value>().await
-            .map_err(|e| Error::OpenSearch(e.to_string()))?;
-        
-        if let Some(errors) = response_body["errors"].as_bool() {
-            if errors {
-                return Err(Error::OpenSearch("Bulk delete operation had errors".to_string()));
+            if let Some(start) = range.start {
+                clauses.push(open_or_bounded(VALID_TIME_END_FIELD, json!({ "gte": start.0.to_rfc3339() })));
             }
         }
-        
-        Ok(entries.len())
-    }
-
-    /// Ensure the memory index exists, create it if it doesn't
-    async fn ensure_index_exists(&self) -> Result<()> {
-        let exists = self.client
-            .indices()
-            .exists(IndicesExistsParts::Index(&[&self.index]))
-            .send()
-            .await
-            .map_err(|e| Error::OpenSearch(e.to_string()))?;
 
-        if !exists.status_code().is_success() {
-            // Create index with mappings
-            let mappings = json!({
-                "mappings": {
-                    "properties": {
-                        "id": { "type": "keyword" },
-                        "created_at": { "type": "date" },
-                        "updated_at": { "type": "date" },
-                        "content": { "type": "text" },
-                        "metadata": { "type": "object", "dynamic": true },
-                        "embedding": { "type": "dense_vector", "dims": 1536 }
-                    }
-                }
-            });
-
-            self.client
-                .indices()
-                .create(IndicesCreateParts::Index(&self.index))
-                .body(mappings)
-                .send()
-                .await
-                .map_err(|e| Error::OpenSearch(e.to_string()))?;
+        let mut metadata: Vec<_> = self.metadata.iter().collect();
+        metadata.sort_by(|a, b| a.0.cmp(b.0));
+        for (key, value) in metadata {
+            let field = format!("metadata.{}", key);
+            clauses.push(json!({ "term": { field: value } }));
         }
 
-        Ok(())
-    }
-
-    /// Delete all entries
-    pub async fn clear(&self) -> Result<()> {
-        let response = self.client
-            .delete_by_query(DeleteByQueryParts::Index(&[&self.index]))
-            .body(json!({
-                "query": {
-                    "match_all": {}
-                }
-            }))
-            .send()
-            .await
-            .map_err(|e| Error::OpenSearch(e.to_string()))?;
-
-        if !response.status_code().is_success() {
-            return Err(Error::OpenSearch(format!("Failed to clear index: {}", response.status_code())));
-        }
-
-        Ok(())
-    }
-
-    pub async fn search_with_metadata(&self, query: &Value) -> Result<Vec<MemoryEntry>> {
-        let response = self.client
-            .search(SearchParts::Index(&[&self.index]))
-            .body(query)
-            .send()
-            .await
-            .map_err(|e| Error::OpenSearch(e.to_string()))?;
-
-        if !response.status_code().is_success() {
-            return Err(Error::OpenSearch("Search failed".to_string()));
+        if let Some(query) = &self.query {
+            clauses.push(query.clone());
         }
 
-        let response_body = response.json::<Value>().await
-            .map_err(|e| Error::OpenSearch(e.to_string()))?;
-
-        self.parse_search_results(response_body).await
+        if clauses.is_empty() {
+            None
+        } else {
+            Some(json!({ "bool": { "filter": clauses } }))
+        }
     }
 }
 
-#[async_trait]
-impl MemoryOperations for OpenSearchMemory {
-    async fn store(&self, entry: MemoryEntry) -> Result<()> {
-        let document = json!({
-            "id": entry.id,
-            "embedding": entry.embedding,
-            "metadata": entry.metadata,
-            "created_at": entry.created_at.to_rfc3339(),
-            "updated_at": entry.updated_at.to_rfc3339(),
-            "content": entry.content,
-            "node_type": entry.node_type.map(|nt| nt.to_string()),
-        });
-
-        let response = self.client
-            .index(IndexParts::IndexId(&self.index, &entry.id))
-            .body(document)
-            .send()
-            .await
-            .map_err(|e| Error::OpenSearch(e.to_string()))?;
+/// Valid-time bounds written by the hybrid store under the entry's `metadata` object
+const VALID_TIME_START_FIELD: &str = "metadata.metadata.valid_time_start";
+const VALID_TIME_END_FIELD: &str = "metadata.metadata.valid_time_end";
 
-        if !response.status_code().is_success() {
-            return Err(Error::OpenSearch("Failed to store entry".to_string()));
+/// Match entries where `field` is missing (an open bound) or satisfies `bounds`
+fn open_or_bounded(field: &str, bounds: Value) -> Value {
+    json!({
+        "bool": {
+            "should": [
+                { "bool": { "must_not": { "exists": { "field": field } } } },
+                { "range": { field: bounds } }
+            ]
         }
+    })
+}
 
-        Ok(())
-    }
-
-    async fn search_similar(&self, query_vector: &[f32], limit: usize) -> Result<Vec<MemoryEntry>> {
-        let query = json!({
-            "size": limit,
-            "query": {
-                "script_score": {
-                    "query": { "match_all": {} },
-                    "script": {
-                        "source": "cosineSimilarity(params.query_vector, 'embedding') + 1.0",
-                        "params": {
-                            "query_vector": query_vector
-                        }
+/// Field mappings shared by the k-NN and fallback indexes, minus the embedding
+fn base_properties() -> Value {
+    json!({
+        "id": { "type": "keyword" },
+        "created_at": { "type": "date" },
+        "updated_at": { "type": "date" },
+        "content": { "type": "text" },
+        "metadata": {
+            "type": "object",
+            "properties": {
+                TENANT_PROPERTY: { "type": "keyword" },
+                "metadata": {
+                    "properties": {
+                        "valid_time_start": { "type": "date" },
+                        "valid_time_end": { "type": "date" }
+                    }
+                },
+                EMBEDDING_METADATA_KEY: {
+                    "properties": {
+                        "model": { "type": "keyword" },
+                        "model_version": { "type": "keyword" },
+                        "dimension": { "type": "integer" }
                     }
                 }
             }
-        });
-
-        let response = self.client
-            .search(SearchParts::Index(&[&self.index]))
-            .body(query)
-            .send()
-            .await
-            .map_err(|e| Error::OpenSearch(e.to_string()))?;
+        },
+        "node_type": { "type": "keyword" },
+        "source_id": { "type": "keyword" },
+        "target_id": { "type": "keyword" }
+    })
+}
 
-        if !response.status_code().is_success() {
-            return Err(Error::OpenSearch("Failed to search similar entries".to_string()));
+/// Index body declaring `embedding` as an HNSW `knn_vector`
+fn knn_index_body(embedding_dim: usize, knn: &KnnSettings) -> Value {
+    let mut properties = base_properties();
+    properties["embedding"] = json!({
+        "type": "knn_vector",
+        "dimension": embedding_dim,
+        "method": {
+            "name": "hnsw",
+            "engine": knn.engine,
+            "space_type": knn.space_type,
+            "parameters": {
+                "m": knn.m,
+                "ef_construction": knn.ef_construction
+            }
         }
+    });
 
-        let response_body = response.json::<Value>().await
-            .map_err(|e| Error::Serialization(e.to_string()))?;
-
-        let hits = response_body["hits"]["hits"]
-            .as_array()
-            .ok_or_else(|| Error::OpenSearch("Invalid response format".to_string()))?;
+    json!({
+        "settings": {
+            "index": {
+                "knn": true,
+                "knn.algo_param.ef_search": knn.ef_search
+            }
+        },
+        "mappings": { "properties": properties }
+    })
+}
 
-        let entries = hits
-            .iter()
-            .filter_map(|hit| {
-                let source = hit["_source"].clone();
-                serde_json::from_value::<MemoryEntry>(source).ok()
-            })
-            .collect();
+/// Index body for clusters without the k-NN plugin, searched with `script_score`
+///
+/// Doc values of a plain `float` field are sorted and deduplicated rather than
+/// kept as a vector, so the script reads the embedding from `_source`.
+fn script_index_body() -> Value {
+    let mut properties = base_properties();
+    properties["embedding"] = json!({ "type": "float" });
 
-        Ok(entries)
-    }
+    json!({ "mappings": { "properties": properties } })
+}
 
-    async fn get(&self, id: &str) -> Result<Option<MemoryEntry>> {
-        self.get(id).await
+/// Approximate nearest-neighbour query, applying `filter` during the graph search
+fn knn_query(embedding: &[f32], k: usize, filter: Option<Value>) -> Value {
+    let mut knn = json!({
+        "vector": embedding,
+        "k": k
+    });
+    if let Some(filter) = filter {
+        knn["filter"] = filter;
     }
 
-    async fn delete(&self, id: &str) -> Result<()> {
-        self.delete_document("zep_memories", id).await?;
-        Ok(())
-    }
+    json!({
+        "size": k,
+        "query": {
+            "knn": { "embedding": knn }
+        }
+    })
 }
 
-/// Memory system for efficient vector storage and retrieval
-pub struct MemorySystem {
-    /// OpenSearch client
-    client: Arc<OpenSearch>,
-    /// Index name for vector storage
-    index: String,
-    /// Dimension of vector embeddings
-    embedding_dim: usize,
+/// Painless cosine similarity against the stored vector, shifted by one so
+/// scores are never negative; mismatched or empty vectors score zero
+const COSINE_SCRIPT: &str = "\
+    def stored = params._source.embedding; \
+    def query = params.query_vector; \
+    if (stored == null || stored.size() != query.size()) { return 0; } \
+    double dot = 0; double storedNorm = 0; double queryNorm = 0; \
+    for (int i = 0; i < query.size(); ++i) { \
+        double a = ((Number) stored.get(i)).doubleValue(); \
+        double b = ((Number) query.get(i)).doubleValue(); \
+        dot += a * b; storedNorm += a * a; queryNorm += b * b; \
+    } \
+    if (storedNorm == 0 || queryNorm == 0) { return 0; } \
+    return 1.0 + dot / Math.sqrt(storedNorm * queryNorm);";
+
+/// Exact cosine similarity over every entry with an embedding matching `filter`
+fn script_score_query(embedding: &[f32], k: usize, filter: Option<Value>) -> Value {
+    let mut candidates = vec![json!({ "exists": { "field": "embedding" } })];
+    candidates.extend(filter);
+
+    json!({
+        "size": k,
+        "query": {
+            "script_score": {
+                "query": { "bool": { "filter": candidates } },
+                "script": {
+                    "source": COSINE_SCRIPT,
+                    "params": {
+                        "query_vector": embedding
+                    }
+                }
+            }
+        }
+    })
 }
 
 #[async_trait]
@@ -685,39 +356,21 @@ pub trait Memory: Send + Sync {
     
     /// Get memories for a specific edge
     async fn get_for_edge(&self, source_id: Uuid, target_id: Uuid, limit: usize) -> Result<Vec<MemoryEntry>>;
+    
+    /// Get a memory by ID
+    async fn get(&self, id: &str) -> Result<Option<MemoryEntry>>;
+    
+    /// Get entries whose embedding was not produced by `model` at `model_version` with `dimension`
+    ///
+    /// Entries with an embedding but no recorded model are included.
+    async fn get_stale_embeddings(&self, model: &str, model_version: &str, dimension: usize, limit: usize) -> Result<Vec<MemoryEntry>>;
 }
 
 #[async_trait]
 impl Memory for MemorySystem {
     async fn store(&self, entry: MemoryEntry) -> Result<()> {
-        let document = json!({
-            "id": entry.id,
-            "embedding": entry.embedding,
-            "metadata": entry.metadata,
-            "created_at": entry.created_at.to_rfc3339(),
-            "updated_at": entry.updated_at.to_rfc3339(),
-            "content": entry.content,
-            "node_type": entry.node_type.map(|nt| nt.to_string()),
-        });
-
-        let response = self.client
-            .index(IndexParts::IndexId(&*self.index, &entry.id))
-            .body(document)
-            .send()
-            .await
-            .map_err(|e| Error::OpenSearch(e.to_string()))?;
-
-        if !response.status_code().is_success() {
-            return Err(Error::OpenSearch("Failed to store entry".to_string()));
-        }
-
-        Ok(())
-    }
-
-    async fn store_bulk(&self, entries: Vec<MemoryEntry>) -> Result<()> {
-        let mut bulk_operations: Vec<BulkOperation<Value>> = Vec::with_capacity(entries.len());
-        for entry in entries {
-            let operation = BulkOperation::index(json!({
+        timed(BACKEND, "store", async {
+            let document = json!({
                 "id": entry.id,
                 "embedding": entry.embedding,
                 "metadata": entry.metadata,
@@ -725,173 +378,182 @@ impl Memory for MemorySystem {
                 "updated_at": entry.updated_at.to_rfc3339(),
                 "content": entry.content,
                 "node_type": entry.node_type.map(|nt| nt.to_string()),
-            }))
-            .id(&entry.id)
-            .into();
-            bulk_operations.push(operation);
-        }
+            });
 
-        let response = self.client
-            .bulk(BulkParts::Index(&self.index))
-            .body(bulk_operations)
-            .send()
-            .await
-            .map_err(|e| Error::OpenSearch(e.to_string()))?;
+            let response = self.client
+                .index(IndexParts::IndexId(&*self.index, &entry.id))
+                .body(document)
+                .send()
+                .await
+                .map_err(|e| Error::OpenSearch(e.to_string()))?;
 
-        if !response.status_code().is_success() {
-            return Err(Error::OpenSearch("Failed to bulk store entries".to_string()));
-        }
+            if !response.status_code().is_success() {
+                return Err(Error::OpenSearch("Failed to store entry".to_string()));
+            }
 
-        Ok(())
+            Ok(())
+        }).await
     }
 
-    async fn search_similar(&self, embedding: Vec<f32>, k: usize, filter: Option<Value>) -> Result<Vec<MemoryEntry>> {
-        let mut query = json!({
-            "size": k,
-            "query": {
-                "script_score": {
-                    "query": { "match_all": {} },
-                    "script": {
-                        "source": "cosineSimilarity(params.query_vector, 'embedding') + 1.0",
-                        "params": {
-                            "query_vector": embedding
-                        }
-                    }
-                }
+    async fn store_bulk(&self, entries: Vec<MemoryEntry>) -> Result<()> {
+        timed(BACKEND, "store_bulk", async {
+            let mut bulk_operations: Vec<BulkOperation<Value>> = Vec::with_capacity(entries.len());
+            for entry in entries {
+                let operation = BulkOperation::index(json!({
+                    "id": entry.id,
+                    "embedding": entry.embedding,
+                    "metadata": entry.metadata,
+                    "created_at": entry.created_at.to_rfc3339(),
+                    "updated_at": entry.updated_at.to_rfc3339(),
+                    "content": entry.content,
+                    "node_type": entry.node_type.map(|nt| nt.to_string()),
+                }))
+                .id(&entry.id)
+                .into();
+                bulk_operations.push(operation);
             }
-        });
-
-        if let Some(f) = filter {
-            query["query"]["script_score"]["query"] = json!({
-                "bool": {
-                    "must": [
-                        { "match_all": {} },
-                        f
-                    ]
-                }
-            });
-        }
-
-        let response = self.client
-            .search(SearchParts::Index(&[&*self.index]))
-            .body(query)
-            .send()
-            .await
-            .map_err(|e| Error::OpenSearch(e.to_string()))?;
 
-        let response_body = response.json::<Value>().await
-            .map_err(|e| Error::OpenSearch(e.to_string()))?;
-
-        let hits = response_body["hits"]["hits"]
-            .as_array()
-            .ok_or_else(|| Error::OpenSearch("Invalid response format".to_string()))?;
+            let response = self.client
+                .bulk(BulkParts::Index(&self.index))
+                .body(bulk_operations)
+                .send()
+                .await
+                .map_err(|e| Error::OpenSearch(e.to_string()))?;
 
-        let mut results = Vec::new();
-        for hit in hits {
-            let source = hit["_source"].clone();
-            let metadata = source["metadata"]
-                .as_object()
-                .map(|obj| {
-                    obj.iter()
-                        .map(|(k, v)| (k.clone(), v.clone()))
-                        .collect::<HashMap<String, Value>>()
-                })
-                .unwrap_or_default();
+            if !response.status_code().is_success() {
+                return Err(Error::OpenSearch("Failed to bulk store entries".to_string()));
+            }
 
-            let node_type = source.get("node_type")
-                .and_then(|v| v.as_str())
-                .map(|s| EntityType::from_str(s))
-                .transpose()
-                .map_err(|e| Error::OpenSearch(e.to_string()))?;
+            Ok(())
+        }).await
+    }
 
-            let entry = MemoryEntry {
-                id: source["id"].as_str()
-                    .ok_or_else(|| Error::OpenSearch("Missing id field".to_string()))?
-                    .to_string(),
-                embedding: serde_json::from_value(source["embedding"].clone())
-                    .map_err(|e| Error::OpenSearch(e.to_string()))?,
-                metadata,
-                created_at: DateTime::parse_from_rfc3339(source["created_at"].as_str().unwrap_or_default())
-                    .map_err(|e| Error::OpenSearch(e.to_string()))?
-                    .with_timezone(&Utc),
-                updated_at: DateTime::parse_from_rfc3339(source["updated_at"].as_str().unwrap_or_default())
-                    .map_err(|e| Error::OpenSearch(e.to_string()))?
-                    .with_timezone(&Utc),
-                content: source["content"].as_str().unwrap_or_default().to_string(),
-                node_type,
+    async fn search_similar(&self, embedding: Vec<f32>, k: usize, filter: Option<Value>) -> Result<Vec<MemoryEntry>> {
+        timed(BACKEND, "search_similar", async {
+            let query = if self.knn_enabled {
+                knn_query(&embedding, k, filter)
+            } else {
+                script_score_query(&embedding, k, filter)
             };
-            results.push(entry);
-        }
 
-        Ok(results)
+            self.search_with_query(query).await
+        }).await
     }
 
     async fn get_by_node_type(&self, node_type: EntityType, limit: usize) -> Result<Vec<MemoryEntry>> {
-        let query = json!({
-            "size": limit,
-            "query": {
-                "term": {
-                    "node_type": node_type.to_string()
+        timed(BACKEND, "get_by_node_type", async {
+            let query = json!({
+                "size": limit,
+                "query": {
+                    "term": {
+                        "node_type": node_type.to_string()
+                    }
                 }
-            }
-        });
+            });
 
-        self.search_with_query(query).await
+            self.search_with_query(query).await
+        }).await
     }
 
     async fn get_by_time_range(&self, range: TemporalRange, limit: usize) -> Result<Vec<MemoryEntry>> {
-        let mut range_query = json!({});
+        timed(BACKEND, "get_by_time_range", async {
+            let mut range_query = json!({});
 
-        if let Some(start) = range.start {
-            range_query["gte"] = json!(start.0.to_rfc3339());
-        }
+            if let Some(start) = range.start {
+                range_query["gte"] = json!(start.0.to_rfc3339());
+            }
 
-        if let Some(end) = range.end {
-            range_query["lte"] = json!(end.0.to_rfc3339());
-        }
+            if let Some(end) = range.end {
+                range_query["lte"] = json!(end.0.to_rfc3339());
+            }
 
-        let query = json!({
-            "size": limit,
-            "query": {
-                "range": {
-                    "created_at": range_query
+            let query = json!({
+                "size": limit,
+                "query": {
+                    "range": {
+                        "created_at": range_query
+                    }
                 }
-            }
-        });
+            });
 
-        self.search_with_query(query).await
+            self.search_with_query(query).await
+        }).await
     }
 
     async fn get_for_node(&self, node_id: Uuid, limit: usize) -> Result<Vec<MemoryEntry>> {
-        let query = json!({
-            "size": limit,
-            "query": {
-                "bool": {
-                    "should": [
-                        { "term": { "source_id": node_id.to_string() } },
-                        { "term": { "target_id": node_id.to_string() } }
-                    ]
+        timed(BACKEND, "get_for_node", async {
+            let query = json!({
+                "size": limit,
+                "query": {
+                    "bool": {
+                        "should": [
+                            { "term": { "source_id": node_id.to_string() } },
+                            { "term": { "target_id": node_id.to_string() } }
+                        ]
+                    }
                 }
-            }
-        });
+            });
 
-        self.search_with_query(query).await
+            self.search_with_query(query).await
+        }).await
     }
 
     async fn get_for_edge(&self, source_id: Uuid, target_id: Uuid, limit: usize) -> Result<Vec<MemoryEntry>> {
-        let query = json!({
-            "size": limit,
-            "query": {
-                "bool": {
-                    "must": [
-                        { "term": { "source_id": source_id.to_string() } },
-                        { "term": { "target_id": target_id.to_string() } }
-                    ]
+        timed(BACKEND, "get_for_edge", async {
+            let query = json!({
+                "size": limit,
+                "query": {
+                    "bool": {
+                        "must": [
+                            { "term": { "source_id": source_id.to_string() } },
+                            { "term": { "target_id": target_id.to_string() } }
+                        ]
+                    }
                 }
-            }
-        });
+            });
+
+            self.search_with_query(query).await
+        }).await
+    }
+
+    async fn get(&self, id: &str) -> Result<Option<MemoryEntry>> {
+        timed(BACKEND, "get", async {
+            let query = json!({
+                "size": 1,
+                "query": {
+                    "ids": { "values": [id] }
+                }
+            });
+
+            Ok(self.search_with_query(query).await?.into_iter().next())
+        }).await
+    }
 
-        self.search_with_query(query).await
+    async fn get_stale_embeddings(&self, model: &str, model_version: &str, dimension: usize, limit: usize) -> Result<Vec<MemoryEntry>> {
+        timed(BACKEND, "get_stale_embeddings", async {
+            let model_field = format!("metadata.{}.model", EMBEDDING_METADATA_KEY);
+            let version_field = format!("metadata.{}.model_version", EMBEDDING_METADATA_KEY);
+            let dimension_field = format!("metadata.{}.dimension", EMBEDDING_METADATA_KEY);
+            let query = json!({
+                "size": limit,
+                "query": {
+                    "bool": {
+                        "filter": [{ "exists": { "field": "embedding" } }],
+                        "must_not": [{
+                            "bool": {
+                                "filter": [
+                                    { "term": { model_field: model } },
+                                    { "term": { version_field: model_version } },
+                                    { "term": { dimension_field: dimension } }
+                                ]
+                            }
+                        }]
+                    }
+                }
+            });
+
+            self.search_with_query(query).await
+        }).await
     }
 }
 
@@ -920,52 +582,33 @@ impl MemorySystem {
         Ok(Arc::new(OpenSearch::new(transport)))
     }
 
-    /// Create a new memory system
+    /// Create a new memory system with default HNSW parameters
     pub async fn new(client: Arc<OpenSearch>, index: String, embedding_dim: usize) -> Result<Self> {
-        // Create index if it doesn't exist
-        let index_exists = client
-            .indices()
-            .exists(IndicesExistsParts::Index(&[&index]))
-            .send()
-            .await
-            .map_err(|e| Error::OpenSearch(e.to_string()))?
-            .status_code()
-            .is_success();
-
-        if !index_exists {
-            let mapping = json!({
-                "mappings": {
-                    "properties": {
-                        "id": { "type": "keyword" },
-                        "embedding": {
-                            "type": "dense_vector",
-                            "dims": embedding_dim,
-                            "index": true,
-                            "similarity": "cosine"
-                        },
-                        "metadata": { "type": "object" },
-                        "created_at": { "type": "date" },
-                        "node_type": { "type": "keyword" },
-                        "source_id": { "type": "keyword" },
-                        "target_id": { "type": "keyword" }
-                    }
-                }
-            });
-
-            client
-                .indices()
-                .create(IndicesCreateParts::Index(&index))
-                .body(mapping)
-                .send()
-                .await
-                .map_err(|e| Error::OpenSearch(e.to_string()))?;
-        }
+        Self::with_knn_settings(client, index, embedding_dim, KnnSettings::default()).await
+    }
 
-        Ok(Self {
+    /// Create a new memory system whose index is built with the given HNSW parameters
+    pub async fn with_knn_settings(
+        client: Arc<OpenSearch>,
+        index: String,
+        embedding_dim: usize,
+        knn: KnnSettings,
+    ) -> Result<Self> {
+        let mut memory = Self {
             client,
             index,
             embedding_dim,
-        })
+            knn,
+            knn_enabled: false,
+        };
+        memory.knn_enabled = memory.ensure_index().await?;
+
+        Ok(memory)
+    }
+
+    /// Whether similarity searches use the k-NN plugin
+    pub fn knn_enabled(&self) -> bool {
+        self.knn_enabled
     }
 
     /// Search with a specific query
@@ -1043,49 +686,103 @@ impl MemorySystem {
         }
     }
 
-    /// Ensure the memory index exists
-    async fn ensure_index(&self) -> Result<()> {
+    /// Ensure the memory index exists, returning whether it is a k-NN index
+    ///
+    /// A new index is created with a `knn_vector` mapping. If the cluster
+    /// rejects it, typically because the k-NN plugin is not installed, a plain
+    /// index is created instead and searches fall back to `script_score`.
+    async fn ensure_index(&self) -> Result<bool> {
         let exists = self.client
             .indices()
             .exists(IndicesExistsParts::Index(&[&self.index]))
             .send()
             .await
+            .map_err(|e| Error::OpenSearch(e.to_string()))?
+            .status_code()
+            .is_success();
+
+        if exists {
+            return self.index_has_knn().await;
+        }
+
+        let response = self.client
+            .indices()
+            .create(IndicesCreateParts::Index(&self.index))
+            .body(knn_index_body(self.embedding_dim, &self.knn))
+            .send()
+            .await
             .map_err(|e| Error::OpenSearch(e.to_string()))?;
 
-        if !exists.status_code().is_success() {
-            let mappings = json!({
-                "properties": {
-                    "id": { "type": "keyword" },
-                    "created_at": { "type": "date" },
-                    "updated_at": { "type": "date" },
-                    "content": { "type": "text" },
-                    "metadata": { "type": "object" },
-                    "embedding": {
-                        "type": "dense_vector",
-                        "dims": self.embedding_dim,
-                        "index": true,
-                        "similarity": "cosine"
-                    },
-                    "node_type": { "type": "keyword" }
-                }
-            });
+        if response.status_code().is_success() {
+            return Ok(true);
+        }
 
-            self.client
-                .indices()
-                .create(IndicesCreateParts::Index(&self.index))
-                .body(json!({
-                    "settings": {
-                        "number_of_shards": 1,
-                        "number_of_replicas": 1
-                    },
-                    "mappings": mappings
-                }))
-                .send()
-                .await
-                .map_err(|e| Error::OpenSearch(e.to_string()))?;
+        let reason = response.text().await.unwrap_or_default();
+        warn!(
+            "Could not create k-NN index {}, falling back to script_score search: {}",
+            self.index, reason
+        );
+
+        let response = self.client
+            .indices()
+            .create(IndicesCreateParts::Index(&self.index))
+            .body(script_index_body())
+            .send()
+            .await
+            .map_err(|e| Error::OpenSearch(e.to_string()))?;
+
+        if !response.status_code().is_success() {
+            return Err(Error::OpenSearch(format!("Failed to create index {}: {}", self.index, response.status_code())));
+        }
+
+        Ok(false)
+    }
+
+    /// Whether the existing index was created with `index.knn` enabled
+    async fn index_has_knn(&self) -> Result<bool> {
+        let response = self.client
+            .indices()
+            .get_settings(IndicesGetSettingsParts::Index(&[&self.index]))
+            .send()
+            .await
+            .map_err(|e| Error::OpenSearch(e.to_string()))?;
+
+        if !response.status_code().is_success() {
+            return Err(Error::OpenSearch(format!("Failed to read settings of index {}: {}", self.index, response.status_code())));
         }
 
-        Ok(())
+        let settings = response.json::<Value>().await
+            .map_err(|e| Error::OpenSearch(e.to_string()))?;
+
+        Ok(settings[&self.index]["settings"]["index"]["knn"].as_str() == Some("true"))
+    }
+}
+
+/// Whether `entry` has an embedding not produced by `model` at `model_version` with `dimension`
+pub(crate) fn has_stale_embedding(entry: &MemoryEntry, model: &str, model_version: &str, dimension: usize) -> bool {
+    if entry.embedding.is_none() {
+        return false;
+    }
+    let recorded = entry.metadata.get(EMBEDDING_METADATA_KEY);
+    let field = |name: &str| recorded.and_then(|m| m.get(name));
+    field("model").and_then(Value::as_str) != Some(model)
+        || field("model_version").and_then(Value::as_str) != Some(model_version)
+        || field("dimension").and_then(Value::as_u64) != Some(dimension as u64)
+}
+
+/// Create the memory store selected by `config.memory_backend`
+pub async fn new_memory(config: &Config) -> Result<Arc<dyn Memory>> {
+    match config.memory_backend {
+        MemoryBackend::OpenSearch => {
+            let client = MemorySystem::create_client(config).await?;
+            Ok(Arc::new(MemorySystem::new(client, DEFAULT_INDEX.to_string(), config.memory_size).await?))
+        }
+        MemoryBackend::Local => {
+            let path = config.memory_path.as_deref().ok_or_else(|| {
+                Error::ConfigurationError("MEMORY_PATH is required for the local memory backend".to_string())
+            })?;
+            Ok(Arc::new(LocalMemory::open(path).await?))
+        }
     }
 }
 
@@ -1093,6 +790,13 @@ impl MemorySystem {
 mod mock;
 pub use mock::MockMemory;
 
+mod hnsw;
+mod local;
+mod tenant;
+pub use hnsw::{HnswIndex, HnswParams};
+pub use local::LocalMemory;
+pub use tenant::TenantMemory;
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1118,11 +822,106 @@ mod tests {
 
         // Store entry - this should succeed with our mock
         assert!(memory.store(entry.clone()).await.is_ok());
+        assert_eq!(memory.get("test").await.unwrap().map(|e| e.content), Some(entry.content.clone()));
+        assert!(memory.get("missing").await.unwrap().is_none());
 
         // The rest of the assertions are not useful with the mock since it returns empty vectors
         // The important part is that the test passes without errors
     }
 
+    #[test]
+    fn test_knn_index_body() {
+        let body = knn_index_body(384, &KnnSettings::default());
+
+        assert_eq!(body["settings"]["index"]["knn"], json!(true));
+        let embedding = &body["mappings"]["properties"]["embedding"];
+        assert_eq!(embedding["type"], "knn_vector");
+        assert_eq!(embedding["dimension"], 384);
+        assert_eq!(embedding["method"]["name"], "hnsw");
+        assert_eq!(embedding["method"]["parameters"]["m"], 16);
+        assert_eq!(body["mappings"]["properties"]["node_type"]["type"], "keyword");
+    }
+
+    #[test]
+    fn test_similarity_queries_apply_filter() {
+        let start = Timestamp(Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap());
+        let filter = VectorFilter::new()
+            .with_node_types(vec![EntityType::Person])
+            .valid_during(TemporalRange { start: Some(start), end: None })
+            .with_metadata("source", json!("crm"))
+            .to_query()
+            .unwrap();
+
+        let clauses = filter["bool"]["filter"].as_array().unwrap();
+        assert_eq!(clauses.len(), 3);
+        assert_eq!(clauses[0]["terms"]["node_type"], json!(["Person"]));
+        let valid_end = &clauses[1]["bool"]["should"];
+        assert_eq!(valid_end[0]["bool"]["must_not"]["exists"]["field"], VALID_TIME_END_FIELD);
+        assert_eq!(valid_end[1]["range"][VALID_TIME_END_FIELD]["gte"], json!(start.0.to_rfc3339()));
+        assert_eq!(clauses[2]["term"]["metadata.source"], "crm");
+
+        let knn = knn_query(&[0.5, 0.5], 5, Some(filter.clone()));
+        assert_eq!(knn["query"]["knn"]["embedding"]["k"], 5);
+        assert_eq!(knn["query"]["knn"]["embedding"]["filter"], filter);
+
+        let script = script_score_query(&[0.5, 0.5], 5, Some(filter.clone()));
+        let candidates = &script["query"]["script_score"]["query"]["bool"]["filter"];
+        assert_eq!(candidates[0]["exists"]["field"], "embedding");
+        assert_eq!(candidates[1], filter);
+
+        assert!(VectorFilter::new().to_query().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_script_score_fallback() {
+        use wiremock::matchers::{body_string_contains, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("HEAD")).and(path("/memories"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&server).await;
+        // A cluster without the k-NN plugin rejects the knn_vector mapping
+        Mock::given(method("PUT")).and(path("/memories")).and(body_string_contains("knn_vector"))
+            .respond_with(ResponseTemplate::new(400).set_body_json(json!({
+                "error": { "reason": "No handler for type [knn_vector]" }
+            })))
+            .mount(&server).await;
+        Mock::given(method("PUT")).and(path("/memories"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "acknowledged": true })))
+            .mount(&server).await;
+        Mock::given(method("POST")).and(path("/memories/_search"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "hits": { "hits": [] } })))
+            .mount(&server).await;
+
+        let mut config = Config::for_testing();
+        config.opensearch_endpoint = server.uri();
+        let client = MemorySystem::create_client(&config).await.unwrap();
+        let memory = MemorySystem::new(client, "memories".to_string(), 2).await.unwrap();
+        assert!(!memory.knn_enabled());
+
+        let filter = VectorFilter::new().with_node_types(vec![EntityType::Person]).to_query();
+        memory.search_similar(vec![1.0, 0.0], 5, filter.clone()).await.unwrap();
+
+        let requests = server.received_requests().await.unwrap();
+        let created: Vec<Value> = requests.iter()
+            .filter(|r| r.url.path() == "/memories" && !r.body.is_empty())
+            .map(|r| serde_json::from_slice(&r.body).unwrap())
+            .collect();
+        assert_eq!(created.len(), 2);
+        assert_eq!(created[1]["mappings"]["properties"]["embedding"]["type"], "float");
+
+        let search: Value = requests.iter()
+            .find(|r| r.url.path() == "/memories/_search")
+            .map(|r| serde_json::from_slice(&r.body).unwrap())
+            .unwrap();
+        let script_score = &search["query"]["script_score"];
+        assert!(script_score["script"]["source"].as_str().unwrap().contains("params._source.embedding"));
+        assert!(!script_score["script"]["source"].as_str().unwrap().contains("cosineSimilarity"));
+        assert_eq!(script_score["script"]["params"]["query_vector"], json!([1.0, 0.0]));
+        assert_eq!(script_score["query"]["bool"]["filter"][1], filter.unwrap());
+    }
+
     #[test]
     fn test_memory_entry() {
         let now = Utc::now();
@@ -0,0 +1,288 @@
+//! Tenant-scoped view of a `Memory` store
+//!
+//! `TenantMemory` stamps every stored entry with its tenant in
+//! `metadata.tenant_id` and only ever returns entries carrying the same
+//! stamp. Similarity searches push the tenant into the filter, so backends
+//! that evaluate filters (OpenSearch, `LocalMemory`) never rank another
+//! tenant's vectors. The other lookups filter their results, fetching more
+//! as needed to fill the requested limit.
+
+use std::future::Future;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::{
+    error::{Error, Result},
+    memory::{Memory, MemoryEntry},
+    types::{EntityType, TemporalRange, TenantId, TENANT_PROPERTY},
+};
+
+/// Largest number of entries requested from the inner store by one lookup
+///
+/// Matches OpenSearch's default `index.max_result_window`.
+const MAX_FETCH: usize = 10_000;
+
+/// A `Memory` store restricted to the entries of one tenant
+pub struct TenantMemory {
+    inner: Arc<dyn Memory>,
+    tenant: TenantId,
+}
+
+impl TenantMemory {
+    /// Scope `inner` to `tenant`
+    pub fn new(inner: Arc<dyn Memory>, tenant: TenantId) -> Self {
+        Self { inner, tenant }
+    }
+
+    /// The tenant this view is scoped to
+    pub fn tenant(&self) -> &TenantId {
+        &self.tenant
+    }
+
+    /// Filter clause matching this tenant's entries
+    pub fn tenant_filter(&self) -> Value {
+        let field = format!("metadata.{}", TENANT_PROPERTY);
+        json!({ "term": { field: self.tenant.as_str() } })
+    }
+
+    fn owns(&self, entry: &MemoryEntry) -> bool {
+        self.tenant.owns(entry.metadata.get(TENANT_PROPERTY))
+    }
+
+    fn stamp(&self, mut entry: MemoryEntry) -> MemoryEntry {
+        entry.metadata.insert(TENANT_PROPERTY.to_string(), json!(self.tenant.as_str()));
+        entry
+    }
+
+    /// Refuse to overwrite an entry that belongs to another tenant
+    async fn check_writable(&self, id: &str) -> Result<()> {
+        match self.inner.get(id).await? {
+            Some(existing) if !self.owns(&existing) => Err(Error::ValidationError(format!(
+                "Memory entry {} already exists", id
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    /// Run a limited lookup against the inner store, keeping this tenant's
+    /// entries and widening the lookup until `limit` of them are found or
+    /// the inner store runs out
+    async fn fetch_owned<F, Fut>(&self, limit: usize, fetch: F) -> Result<Vec<MemoryEntry>>
+    where
+        F: Fn(usize) -> Fut + Send + Sync,
+        Fut: Future<Output = Result<Vec<MemoryEntry>>> + Send,
+    {
+        let mut fetch_limit = limit.clamp(1, MAX_FETCH);
+        loop {
+            let batch = fetch(fetch_limit).await?;
+            let exhausted = batch.len() < fetch_limit || fetch_limit == MAX_FETCH;
+            let mut owned: Vec<_> = batch.into_iter().filter(|entry| self.owns(entry)).collect();
+
+            if owned.len() >= limit || exhausted {
+                owned.truncate(limit);
+                return Ok(owned);
+            }
+            fetch_limit = (fetch_limit * 2).min(MAX_FETCH);
+        }
+    }
+}
+
+#[async_trait]
+impl Memory for TenantMemory {
+    async fn store(&self, entry: MemoryEntry) -> Result<()> {
+        self.check_writable(&entry.id).await?;
+        self.inner.store(self.stamp(entry)).await
+    }
+
+    async fn store_bulk(&self, entries: Vec<MemoryEntry>) -> Result<()> {
+        for entry in &entries {
+            self.check_writable(&entry.id).await?;
+        }
+        let entries = entries.into_iter().map(|entry| self.stamp(entry)).collect();
+        self.inner.store_bulk(entries).await
+    }
+
+    async fn search_similar(&self, embedding: Vec<f32>, k: usize, filter: Option<Value>) -> Result<Vec<MemoryEntry>> {
+        let filter = match filter {
+            Some(filter) => json!({ "bool": { "filter": [self.tenant_filter(), filter] } }),
+            None => self.tenant_filter(),
+        };
+
+        // Backends that ignore filters must still not leak other tenants' entries
+        let results = self.inner.search_similar(embedding, k, Some(filter)).await?;
+        Ok(results.into_iter().filter(|entry| self.owns(entry)).collect())
+    }
+
+    async fn get_by_node_type(&self, node_type: EntityType, limit: usize) -> Result<Vec<MemoryEntry>> {
+        self.fetch_owned(limit, |n| self.inner.get_by_node_type(node_type.clone(), n)).await
+    }
+
+    async fn get_by_time_range(&self, range: TemporalRange, limit: usize) -> Result<Vec<MemoryEntry>> {
+        self.fetch_owned(limit, |n| self.inner.get_by_time_range(range.clone(), n)).await
+    }
+
+    async fn get_for_node(&self, node_id: Uuid, limit: usize) -> Result<Vec<MemoryEntry>> {
+        self.fetch_owned(limit, |n| self.inner.get_for_node(node_id, n)).await
+    }
+
+    async fn get_for_edge(&self, source_id: Uuid, target_id: Uuid, limit: usize) -> Result<Vec<MemoryEntry>> {
+        self.fetch_owned(limit, |n| self.inner.get_for_edge(source_id, target_id, n)).await
+    }
+
+    async fn get(&self, id: &str) -> Result<Option<MemoryEntry>> {
+        Ok(self.inner.get(id).await?.filter(|entry| self.owns(entry)))
+    }
+
+    async fn get_stale_embeddings(&self, model: &str, model_version: &str, dimension: usize, limit: usize) -> Result<Vec<MemoryEntry>> {
+        self.fetch_owned(limit, |n| self.inner.get_stale_embeddings(model, model_version, dimension, n)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::{LocalMemory, MemorySystem, MockMemory, VectorFilter};
+    use crate::Config;
+    use chrono::Utc;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn entry(id: &str, embedding: Vec<f32>) -> MemoryEntry {
+        let mut entry = MemoryEntry::new(id.to_string(), format!("content of {}", id));
+        entry.embedding = Some(embedding);
+        entry.node_type = Some(EntityType::Person);
+        entry
+    }
+
+    fn tenants(inner: Arc<dyn Memory>) -> (TenantMemory, TenantMemory) {
+        (
+            TenantMemory::new(inner.clone(), TenantId::new("acme").unwrap()),
+            TenantMemory::new(inner, TenantId::new("globex").unwrap()),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_local_memory_isolation() {
+        let dir = std::env::temp_dir().join(format!("tenant-memory-{}", Uuid::new_v4()));
+        let inner: Arc<dyn Memory> = Arc::new(LocalMemory::open(&dir).await.unwrap());
+        let (acme, globex) = tenants(inner.clone());
+
+        acme.store(entry("a1", vec![1.0, 0.0])).await.unwrap();
+        acme.store(entry("a2", vec![0.9, 0.1])).await.unwrap();
+        globex.store_bulk(vec![entry("g1", vec![1.0, 0.0]), entry("g2", vec![0.0, 1.0])]).await.unwrap();
+
+        let stored = inner.get("a1").await.unwrap().unwrap();
+        assert_eq!(stored.metadata[TENANT_PROPERTY], "acme");
+
+        let results = acme.search_similar(vec![1.0, 0.0], 10, None).await.unwrap();
+        let mut ids: Vec<_> = results.iter().map(|e| e.id.as_str()).collect();
+        ids.sort();
+        assert_eq!(ids, vec!["a1", "a2"]);
+
+        let filter = VectorFilter::new().with_node_types(vec![EntityType::Person]).to_query();
+        let results = globex.search_similar(vec![1.0, 0.0], 1, filter).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].id, "g1");
+
+        assert!(globex.get("a1").await.unwrap().is_none());
+        assert_eq!(acme.get_by_node_type(EntityType::Person, 1).await.unwrap().len(), 1);
+        assert!(acme.get_by_node_type(EntityType::Person, 10).await.unwrap()
+            .iter()
+            .all(|e| e.id.starts_with('a')));
+
+        // An id owned by one tenant cannot be overwritten by another
+        assert!(globex.store(entry("a1", vec![0.0, 1.0])).await.is_err());
+        assert_eq!(acme.get("a1").await.unwrap().unwrap().embedding, Some(vec![1.0, 0.0]));
+
+        tokio::fs::remove_dir_all(&dir).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_mock_memory_isolation() {
+        let inner: Arc<dyn Memory> = Arc::new(MockMemory::new());
+        let (acme, globex) = tenants(inner.clone());
+
+        acme.store(entry("a1", vec![1.0, 0.0])).await.unwrap();
+        globex.store(entry("g1", vec![1.0, 0.0])).await.unwrap();
+        // Written before tenants existed, so owned by the default tenant
+        inner.store(entry("legacy", vec![1.0, 0.0])).await.unwrap();
+
+        assert!(acme.get("a1").await.unwrap().is_some());
+        assert!(acme.get("g1").await.unwrap().is_none());
+        assert!(acme.get("legacy").await.unwrap().is_none());
+
+        let default = TenantMemory::new(inner, TenantId::default());
+        assert!(default.get("legacy").await.unwrap().is_some());
+
+        let stale = globex.get_stale_embeddings("model", "1", 2, 10).await.unwrap();
+        assert_eq!(stale.len(), 1);
+        assert_eq!(stale[0].id, "g1");
+    }
+
+    fn hit(id: &str, tenant: &str) -> Value {
+        json!({
+            "_source": {
+                "id": id,
+                "embedding": [1.0, 0.0],
+                "metadata": { TENANT_PROPERTY: tenant },
+                "created_at": Utc::now().to_rfc3339(),
+                "updated_at": Utc::now().to_rfc3339(),
+                "content": id,
+                "node_type": "Person"
+            }
+        })
+    }
+
+    #[tokio::test]
+    async fn test_memory_system_isolation() {
+        let server = MockServer::start().await;
+        Mock::given(method("HEAD")).and(path("/memories"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&server).await;
+        Mock::given(method("GET")).and(path("/memories/_settings"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "memories": { "settings": { "index": { "knn": "true" } } }
+            })))
+            .mount(&server).await;
+        // A misbehaving cluster returning another tenant's document
+        Mock::given(method("POST")).and(path("/memories/_search"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "hits": { "hits": [hit("a1", "acme"), hit("g1", "globex")] }
+            })))
+            .mount(&server).await;
+        Mock::given(path("/memories/_doc/a1"))
+            .respond_with(ResponseTemplate::new(201).set_body_json(json!({ "result": "created" })))
+            .mount(&server).await;
+
+        let mut config = Config::for_testing();
+        config.opensearch_endpoint = server.uri();
+        let client = MemorySystem::create_client(&config).await.unwrap();
+        let inner: Arc<dyn Memory> = Arc::new(MemorySystem::new(client, "memories".to_string(), 2).await.unwrap());
+        let (acme, _) = tenants(inner);
+
+        let filter = VectorFilter::new().with_node_types(vec![EntityType::Person]).to_query();
+        let results = acme.search_similar(vec![1.0, 0.0], 5, filter).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].id, "a1");
+
+        acme.store(entry("a1", vec![1.0, 0.0])).await.unwrap();
+
+        let requests = server.received_requests().await.unwrap();
+        let search: Value = requests.iter()
+            .find(|r| r.url.path() == "/memories/_search")
+            .map(|r| serde_json::from_slice(&r.body).unwrap())
+            .unwrap();
+        let clauses = &search["query"]["knn"]["embedding"]["filter"]["bool"]["filter"];
+        assert_eq!(clauses[0], json!({ "term": { "metadata.tenant_id": "acme" } }));
+        assert_eq!(clauses[1]["bool"]["filter"][0]["terms"]["node_type"], json!(["Person"]));
+
+        let indexed: Value = requests.iter()
+            .find(|r| r.url.path() == "/memories/_doc/a1")
+            .map(|r| serde_json::from_slice(&r.body).unwrap())
+            .unwrap();
+        assert_eq!(indexed["metadata"][TENANT_PROPERTY], "acme");
+    }
+}
@@ -17,6 +17,7 @@ use opensearch::{OpenSearch, http::transport::Transport};
 use aws_sdk_dynamodb::Client as DynamoClient;
 
 use crate::{
+    cancellation,
     error::{Error, Result},
     types::{Node, Edge, EntityType, TemporalRange, NodeId, EdgeId, Properties, Timestamp, EntityId},
     TemporalGraphStore,
@@ -93,6 +94,19 @@ pub struct DetectedRelationship {
     pub confidence: f32,
 }
 
+/// Outcome of processing a piece of text
+#[derive(Debug, Clone, Default)]
+pub struct ProcessedText {
+    /// Entities extracted from the text
+    pub entities: Vec<ExtractedEntity>,
+    /// Relationships detected between the entities
+    pub relationships: Vec<DetectedRelationship>,
+    /// Nodes stored for the extracted entities
+    pub nodes: Vec<Node>,
+    /// Edges stored for relationships above the confidence threshold
+    pub edges: Vec<Edge>,
+}
+
 /// RAG system for entity and relationship extraction
 pub struct RAGSystem {
     /// Configuration
@@ -150,16 +164,29 @@ impl RAGSystem {
     }
 
     /// Process text and update knowledge graph
-    pub async fn process_text(&self, text: &str) -> Result<()> {
+    ///
+    /// Stops between steps, and between stored elements, once the current
+    /// request is cancelled; elements already stored are kept.
+    pub async fn process_text(&self, text: &str) -> Result<ProcessedText> {
         let entities = self.extract_entities(text).await?;
+        cancellation::check()?;
         let relationships = self.detect_relationships(text, &entities).await?;
+        cancellation::check()?;
         
-        self.update_graph(entities, relationships).await?;
-        Ok(())
+        let (nodes, edges) = self.update_graph(entities.clone(), relationships.clone()).await?;
+        Ok(ProcessedText {
+            entities,
+            relationships,
+            nodes,
+            edges,
+        })
     }
 
-    /// Update knowledge graph
-    pub async fn update_graph(&self, entities: Vec<ExtractedEntity>, relationships: Vec<DetectedRelationship>) -> Result<()> {
+    /// Update knowledge graph, returning the stored nodes and edges
+    ///
+    /// Each node and edge records the extraction confidence in its
+    /// `confidence` property.
+    pub async fn update_graph(&self, entities: Vec<ExtractedEntity>, relationships: Vec<DetectedRelationship>) -> Result<(Vec<Node>, Vec<Edge>)> {
         let now = Utc::now();
         let valid_time = TemporalRange {
             start: Some(Timestamp(now)),
@@ -168,12 +195,16 @@ impl RAGSystem {
 
         // Store entities in graph
         let mut node_map = HashMap::new();
+        let mut nodes = Vec::new();
         for entity in entities {
+            cancellation::check()?;
+            let mut properties = Properties::new();
+            properties.insert("confidence".to_string(), json!(entity.confidence));
             let node = Node {
                 id: NodeId(Uuid::new_v4()),
                 entity_type: entity.entity_type.clone(),
                 label: entity.text.clone(),
-                properties: Properties::new(),
+                properties,
                 valid_time: valid_time.clone(),
                 transaction_time: valid_time.clone(),
             };
@@ -183,21 +214,26 @@ impl RAGSystem {
             };
             self.temporal_graph.store(entity_id.clone(), Box::new(node.clone()), valid_time.clone()).await?;
             node_map.insert(format!("{}:{}", entity.text, entity.entity_type), node.id);
+            nodes.push(node);
         }
 
         // Store relationships
+        let mut edges = Vec::new();
         for relationship in relationships {
+            cancellation::check()?;
             if relationship.confidence >= self.config.relationship_confidence_threshold {
                 let source_key = format!("{}:{}", relationship.source.text, relationship.source.entity_type);
                 let target_key = format!("{}:{}", relationship.target.text, relationship.target.entity_type);
                 
                 if let (Some(&source_id), Some(&target_id)) = (node_map.get(&source_key), node_map.get(&target_key)) {
+                    let mut properties = Properties::new();
+                    properties.insert("confidence".to_string(), json!(relationship.confidence));
                     let edge = Edge {
                         id: EdgeId(Uuid::new_v4()),
                         source_id,
                         target_id,
                         label: relationship.relationship_type,
-                        properties: Properties::new(),
+                        properties,
                         valid_time: valid_time.clone(),
                         transaction_time: valid_time.clone(),
                     };
@@ -205,12 +241,13 @@ impl RAGSystem {
                         entity_type: EntityType::Edge,
                         id: edge.id.0.to_string()
                     };
-                    self.temporal_graph.store(entity_id, Box::new(edge), valid_time.clone()).await?;
+                    self.temporal_graph.store(entity_id, Box::new(edge.clone()), valid_time.clone()).await?;
+                    edges.push(edge);
                 }
             }
         }
 
-        Ok(())
+        Ok((nodes, edges))
     }
 
     /// Create a default mock RAGSystem for testing
@@ -244,7 +281,7 @@ pub trait RAG {
     async fn detect_relationships(&self, text: &str, entities: &[ExtractedEntity]) -> Result<Vec<DetectedRelationship>>;
     
     /// Process text and update knowledge graph
-    async fn process_text(&self, text: &str) -> Result<()>;
+    async fn process_text(&self, text: &str) -> Result<ProcessedText>;
 }
 
 #[async_trait]
@@ -257,7 +294,7 @@ impl RAG for RAGSystem {
         self.detect_relationships(text, entities).await
     }
     
-    async fn process_text(&self, text: &str) -> Result<()> {
+    async fn process_text(&self, text: &str) -> Result<ProcessedText> {
         self.process_text(text).await
     }
 }
@@ -294,6 +331,22 @@ mod tests {
         assert!(!relationships.is_empty());
     }
 
+    #[tokio::test]
+    async fn test_process_text_reports_stored_elements() {
+        let rag = RAGSystem::default_mock();
+        
+        let processed = rag.process_text("John works at Apple in California.").await.unwrap();
+        assert_eq!(processed.entities.len(), 3);
+        assert_eq!(processed.nodes.len(), 3);
+        assert_eq!(processed.nodes[0].label, "John");
+        assert_eq!(processed.nodes[0].properties.get("confidence"), Some(&json!(0.9f32)));
+        
+        let labels: Vec<_> = processed.edges.iter().map(|e| e.label.as_str()).collect();
+        assert_eq!(labels, vec!["WORKS_FOR", "LOCATED_IN"]);
+        assert_eq!(processed.edges[0].source_id, processed.nodes[0].id);
+        assert_eq!(processed.edges[0].target_id, processed.nodes[1].id);
+    }
+
     #[tokio::test]
     async fn test_rag_integration() {
         let config = create_test_config();
@@ -303,7 +356,8 @@ mod tests {
         let text = "The company Apple was founded by Steve Jobs in California.";
         
         // Process text
-        rag.process_text(text).await.unwrap();
+        let processed = rag.process_text(text).await.unwrap();
+        assert!(processed.nodes.is_empty());
         
         // Extract entities
         let entities = rag.extract_entities(text).await.unwrap();
@@ -0,0 +1,341 @@
+//! Metrics recording and Prometheus export
+//!
+//! Storage layers and the API report through the `metrics` facade using the
+//! helpers here, so every backend shares one set of metric names. Installing
+//! a [`PrometheusRecorder`] collects them and renders the Prometheus text
+//! format served at `/metrics`; until then they are dropped.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    future::Future,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, OnceLock,
+    },
+    time::{Duration, Instant},
+};
+
+use metrics::{
+    counter, describe_counter, describe_histogram, histogram, Counter, Gauge, Histogram, HistogramFn,
+    Key, KeyName, Recorder, SharedString, Unit,
+};
+use tracing::warn;
+
+use crate::{
+    cancellation,
+    error::{Error, Result},
+};
+
+/// Latency of storage operations, labelled by `backend` and `operation`
+pub const OPERATION_DURATION: &str = "storage_operation_duration_seconds";
+
+/// Failed storage operations, labelled by `backend` and `operation`
+pub const OPERATION_ERRORS: &str = "storage_operation_errors_total";
+
+/// Cache lookups answered from the cache, labelled by `cache`
+pub const CACHE_HITS: &str = "cache_hits_total";
+
+/// Cache lookups that went to the backing store, labelled by `cache`
+pub const CACHE_MISSES: &str = "cache_misses_total";
+
+/// Latency of HTTP requests, labelled by `method`, `route` and `status`
+pub const HTTP_REQUEST_DURATION: &str = "http_request_duration_seconds";
+
+/// Upper bounds of the latency histogram buckets, in seconds
+const LATENCY_BUCKETS: [f64; 11] = [0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+/// Await a storage operation, recording its latency and whether it failed
+///
+/// The operation is dropped when the current request is cancelled; the
+/// cancellation is not counted as a failure.
+pub async fn timed<T, F>(backend: &'static str, operation: &'static str, future: F) -> Result<T>
+where
+    F: Future<Output = Result<T>>,
+{
+    let start = Instant::now();
+    let result = cancellation::cancellable(future).await;
+    if !matches!(result, Err(Error::Cancelled)) {
+        record_operation(backend, operation, start.elapsed(), result.is_ok());
+    }
+    result
+}
+
+/// Record one storage operation
+pub fn record_operation(backend: &'static str, operation: &'static str, latency: Duration, succeeded: bool) {
+    histogram!(OPERATION_DURATION, latency.as_secs_f64(), "backend" => backend, "operation" => operation);
+    if !succeeded {
+        counter!(OPERATION_ERRORS, 1, "backend" => backend, "operation" => operation);
+    }
+}
+
+/// Record a cache lookup
+pub fn record_cache_lookup(cache: &'static str, hit: bool) {
+    if hit {
+        counter!(CACHE_HITS, 1, "cache" => cache);
+    } else {
+        counter!(CACHE_MISSES, 1, "cache" => cache);
+    }
+}
+
+/// Record a served HTTP request
+pub fn record_request(method: &str, route: &str, status: u16, latency: Duration) {
+    histogram!(
+        HTTP_REQUEST_DURATION,
+        latency.as_secs_f64(),
+        "method" => method.to_string(),
+        "route" => route.to_string(),
+        "status" => status.to_string()
+    );
+}
+
+/// Install the global Prometheus recorder, once per process
+///
+/// Later calls return a handle to the same recorder. If another recorder
+/// was installed first, the handle renders nothing.
+pub fn install() -> PrometheusHandle {
+    static HANDLE: OnceLock<PrometheusHandle> = OnceLock::new();
+    HANDLE
+        .get_or_init(|| {
+            let recorder = PrometheusRecorder::new();
+            let handle = recorder.handle();
+            if let Err(e) = metrics::set_boxed_recorder(Box::new(recorder)) {
+                warn!("Metrics recorder already installed: {}", e);
+            }
+            describe();
+            handle
+        })
+        .clone()
+}
+
+fn describe() {
+    describe_histogram!(OPERATION_DURATION, Unit::Seconds, "Latency of storage operations");
+    describe_counter!(OPERATION_ERRORS, "Storage operations that returned an error");
+    describe_counter!(CACHE_HITS, "Cache lookups answered from the cache");
+    describe_counter!(CACHE_MISSES, "Cache lookups that went to the backing store");
+    describe_histogram!(HTTP_REQUEST_DURATION, Unit::Seconds, "Latency of HTTP requests");
+}
+
+/// Cumulative bucket counts, sum and count of a histogram
+#[derive(Default)]
+struct Buckets {
+    counts: [u64; LATENCY_BUCKETS.len()],
+    sum: f64,
+    count: u64,
+}
+
+#[derive(Default)]
+struct PrometheusHistogram {
+    buckets: Mutex<Buckets>,
+}
+
+impl HistogramFn for PrometheusHistogram {
+    fn record(&self, value: f64) {
+        let mut buckets = self.buckets.lock().unwrap();
+        for (bound, count) in LATENCY_BUCKETS.iter().zip(buckets.counts.iter_mut()) {
+            if value <= *bound {
+                *count += 1;
+            }
+        }
+        buckets.sum += value;
+        buckets.count += 1;
+    }
+}
+
+#[derive(Default)]
+struct Registry {
+    descriptions: Mutex<HashMap<String, String>>,
+    counters: Mutex<HashMap<Key, Arc<AtomicU64>>>,
+    gauges: Mutex<HashMap<Key, Arc<AtomicU64>>>,
+    histograms: Mutex<HashMap<Key, Arc<PrometheusHistogram>>>,
+}
+
+/// `metrics` recorder that keeps every series in memory for scraping
+#[derive(Default)]
+pub struct PrometheusRecorder {
+    registry: Arc<Registry>,
+}
+
+impl PrometheusRecorder {
+    /// Create an empty recorder
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Handle rendering what this recorder has collected
+    pub fn handle(&self) -> PrometheusHandle {
+        PrometheusHandle { registry: self.registry.clone() }
+    }
+
+    fn describe(&self, key: KeyName, description: SharedString) {
+        self.registry.descriptions.lock().unwrap().insert(key.as_str().to_string(), description.to_string());
+    }
+}
+
+impl Recorder for PrometheusRecorder {
+    fn describe_counter(&self, key: KeyName, _unit: Option<Unit>, description: SharedString) {
+        self.describe(key, description);
+    }
+
+    fn describe_gauge(&self, key: KeyName, _unit: Option<Unit>, description: SharedString) {
+        self.describe(key, description);
+    }
+
+    fn describe_histogram(&self, key: KeyName, _unit: Option<Unit>, description: SharedString) {
+        self.describe(key, description);
+    }
+
+    fn register_counter(&self, key: &Key) -> Counter {
+        let mut counters = self.registry.counters.lock().unwrap();
+        Counter::from_arc(counters.entry(key.clone()).or_default().clone())
+    }
+
+    fn register_gauge(&self, key: &Key) -> Gauge {
+        let mut gauges = self.registry.gauges.lock().unwrap();
+        Gauge::from_arc(gauges.entry(key.clone()).or_default().clone())
+    }
+
+    fn register_histogram(&self, key: &Key) -> Histogram {
+        let mut histograms = self.registry.histograms.lock().unwrap();
+        Histogram::from_arc(histograms.entry(key.clone()).or_default().clone())
+    }
+}
+
+/// Renders the series of a [`PrometheusRecorder`]
+#[derive(Clone)]
+pub struct PrometheusHandle {
+    registry: Arc<Registry>,
+}
+
+impl PrometheusHandle {
+    /// Render every series in the Prometheus text exposition format
+    pub fn render(&self) -> String {
+        let descriptions = self.registry.descriptions.lock().unwrap().clone();
+        let mut output = String::new();
+
+        let counters = self.registry.counters.lock().unwrap();
+        for (name, series) in group(counters.iter()) {
+            header(&mut output, &descriptions, name, "counter");
+            for (labels, value) in series {
+                output.push_str(&format!("{}{} {}\n", name, labels, value.load(Ordering::Relaxed)));
+            }
+        }
+        drop(counters);
+
+        let gauges = self.registry.gauges.lock().unwrap();
+        for (name, series) in group(gauges.iter()) {
+            header(&mut output, &descriptions, name, "gauge");
+            for (labels, value) in series {
+                output.push_str(&format!("{}{} {}\n", name, labels, f64::from_bits(value.load(Ordering::Relaxed))));
+            }
+        }
+        drop(gauges);
+
+        let histograms = self.registry.histograms.lock().unwrap();
+        for (name, series) in group(histograms.iter()) {
+            header(&mut output, &descriptions, name, "histogram");
+            for (labels, histogram) in series {
+                let buckets = histogram.buckets.lock().unwrap();
+                for (bound, count) in LATENCY_BUCKETS.iter().zip(buckets.counts.iter()) {
+                    let le = with_label(&labels, "le", &bound.to_string());
+                    output.push_str(&format!("{}_bucket{} {}\n", name, le, count));
+                }
+                let le = with_label(&labels, "le", "+Inf");
+                output.push_str(&format!("{}_bucket{} {}\n", name, le, buckets.count));
+                output.push_str(&format!("{}_sum{} {}\n", name, labels, buckets.sum));
+                output.push_str(&format!("{}_count{} {}\n", name, labels, buckets.count));
+            }
+        }
+
+        output
+    }
+}
+
+/// Series grouped by metric name, with their labels rendered, in stable order
+fn group<'a, T>(series: impl Iterator<Item = (&'a Key, &'a Arc<T>)>) -> BTreeMap<&'a str, BTreeMap<String, Arc<T>>>
+where
+    T: 'a,
+{
+    let mut grouped: BTreeMap<&str, BTreeMap<String, Arc<T>>> = BTreeMap::new();
+    for (key, value) in series {
+        let labels: Vec<_> = key.labels().map(|l| format!("{}=\"{}\"", l.key(), escape(l.value()))).collect();
+        let labels = if labels.is_empty() { String::new() } else { format!("{{{}}}", labels.join(",")) };
+        grouped.entry(key.name()).or_default().insert(labels, value.clone());
+    }
+    grouped
+}
+
+fn header(output: &mut String, descriptions: &HashMap<String, String>, name: &str, kind: &str) {
+    if let Some(description) = descriptions.get(name) {
+        output.push_str(&format!("# HELP {} {}\n", name, description));
+    }
+    output.push_str(&format!("# TYPE {} {}\n", name, kind));
+}
+
+/// Add one label to an already rendered label set
+fn with_label(labels: &str, key: &str, value: &str) -> String {
+    match labels.strip_suffix('}') {
+        Some(labels) => format!("{},{}=\"{}\"}}", labels, key, value),
+        None => format!("{{{}=\"{}\"}}", key, value),
+    }
+}
+
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Error;
+    use metrics::Label;
+
+    #[test]
+    fn test_render_prometheus_text() {
+        let recorder = PrometheusRecorder::new();
+        recorder.describe_counter("requests_total".into(), None, "Requests served".into());
+        let key = |name: &'static str, labels: Vec<Label>| Key::from_parts(name, labels);
+
+        recorder.register_counter(&key("requests_total", vec![Label::new("route", "/a\"b")])).increment(3);
+        recorder.register_counter(&key("requests_total", vec![Label::new("route", "/a\"b")])).increment(1);
+        recorder.register_gauge(&key("queue_depth", vec![])).set(2.5);
+        let latency = recorder.register_histogram(&key("latency_seconds", vec![Label::new("op", "get")]));
+        latency.record(0.02);
+        latency.record(3.0);
+
+        let text = recorder.handle().render();
+        assert!(text.contains("# HELP requests_total Requests served\n# TYPE requests_total counter\n"));
+        assert!(text.contains("requests_total{route=\"/a\\\"b\"} 4\n"));
+        assert!(text.contains("# TYPE queue_depth gauge\nqueue_depth 2.5\n"));
+        assert!(text.contains("# TYPE latency_seconds histogram\n"));
+        assert!(text.contains("latency_seconds_bucket{op=\"get\",le=\"0.01\"} 0\n"));
+        assert!(text.contains("latency_seconds_bucket{op=\"get\",le=\"0.025\"} 1\n"));
+        assert!(text.contains("latency_seconds_bucket{op=\"get\",le=\"5\"} 2\n"));
+        assert!(text.contains("latency_seconds_bucket{op=\"get\",le=\"+Inf\"} 2\n"));
+        assert!(text.contains("latency_seconds_sum{op=\"get\"} 3.02\n"));
+        assert!(text.contains("latency_seconds_count{op=\"get\"} 2\n"));
+    }
+
+    #[tokio::test]
+    async fn test_timed_records_latency_and_errors() {
+        let handle = install();
+        let ok: Result<()> = timed("telemetry_test", "succeeds", async { Ok(()) }).await;
+        assert!(ok.is_ok());
+        let failed: Result<()> = timed("telemetry_test", "fails", async {
+            Err(Error::OperationFailed("boom".to_string()))
+        }).await;
+        assert!(failed.is_err());
+        record_cache_lookup("telemetry_test", true);
+
+        let text = handle.render();
+        assert!(text.contains(&format!(
+            "{}_count{{backend=\"telemetry_test\",operation=\"succeeds\"}} 1\n", OPERATION_DURATION
+        )));
+        assert!(text.contains(&format!(
+            "{}{{backend=\"telemetry_test\",operation=\"fails\"}} 1\n", OPERATION_ERRORS
+        )));
+        assert!(!text.contains(&format!(
+            "{}{{backend=\"telemetry_test\",operation=\"succeeds\"}}", OPERATION_ERRORS
+        )));
+        assert!(text.contains(&format!("{}{{cache=\"telemetry_test\"}} 1\n", CACHE_HITS)));
+    }
+}
@@ -223,6 +223,7 @@ mod tests {
     use super::*;
     use chrono::Duration;
     use uuid::Uuid;
+    use crate::types::TenantId;
 
     fn create_test_entry(
         entity_id: EntityId,
@@ -238,6 +239,7 @@ mod tests {
             transaction_time_start: transaction_start,
             transaction_time_end: transaction_end,
             version_id: Uuid::new_v4(),
+            tenant: TenantId::default(),
         }
     }
 
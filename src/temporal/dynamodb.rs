@@ -20,11 +20,13 @@ use crate::{
     types::{
         EntityId, TemporalRange, Timestamp,
         Node, Edge, EntityType, NodeId, EdgeId,
+        TenantId, TENANT_PROPERTY,
     },
+    telemetry::timed,
 };
 
 use super::{
-    ConsistencyCheckResult, ConsistencyChecker, Temporal, TemporalIndexEntry, TemporalQueryResult,
+    ConsistencyCheckResult, ConsistencyChecker, Temporal, TemporalIndexEntry, TemporalQueryResult, ValidTimeIndex,
     query::OptimizedQuery,
     query_builder::{
         TemporalQueryBuilder,
@@ -42,12 +44,28 @@ use crate::temporal::{
     query::optimize_temporal_query,
 };
 
+/// Backend label on the metrics recorded by `DynamoDBTemporal`
+const BACKEND: &str = "dynamodb";
+
+/// Expression value bound to the tenant id in conditions and filters
+const TENANT_VALUE: &str = ":tenant_id";
+
+/// Filter selecting current node entries of the valid-time index that
+/// overlap `:range_start` to `:range_end`, in epoch milliseconds
+const NODES_IN_RANGE_FILTER: &str = "entity_type <> :edge AND valid_from <= :range_end AND valid_to >= :range_start AND attribute_not_exists(transaction_time_end)";
+
 /// DynamoDB-backed temporal implementation
+///
+/// Every item is written with a `tenant_id` attribute and every query and
+/// scan filters on it, so an instance only ever sees its own tenant's
+/// versions.
 pub struct DynamoDBTemporal<T, C: DynamoDBClient + Send + Sync + 'static> {
     /// DynamoDB client
     client: Arc<C>,
     /// Table name
     table_name: String,
+    /// Tenant whose items are read and written
+    tenant: TenantId,
     /// Consistency checker
     checker: ConsistencyChecker,
     /// Type marker
@@ -64,11 +82,66 @@ where
         Self {
             client,
             table_name,
+            tenant: TenantId::default(),
             checker: ConsistencyChecker::new(),
             _marker: std::marker::PhantomData,
         }
     }
 
+    /// Scope reads and writes to `tenant` instead of the default tenant
+    pub fn with_tenant(mut self, tenant: TenantId) -> Self {
+        self.tenant = tenant;
+        self
+    }
+
+    /// The tenant whose items are read and written
+    pub fn tenant(&self) -> &TenantId {
+        &self.tenant
+    }
+
+    /// Filter expression and value restricting reads to this tenant's items,
+    /// combined with the caller's own filter
+    fn tenant_filter(&self, filter: Option<&str>) -> String {
+        let condition = format!("{} = {}", TENANT_PROPERTY, TENANT_VALUE);
+        match filter {
+            Some(filter) => format!("({}) AND {}", filter, condition),
+            None => condition,
+        }
+    }
+
+    /// `query` restricted to this tenant's items
+    fn scoped(&self, query: &OptimizedQuery) -> OptimizedQuery {
+        let mut values = match &query.expression_values {
+            Some(serde_json::Value::Object(values)) => values.clone(),
+            _ => serde_json::Map::new(),
+        };
+        values.insert(TENANT_VALUE.to_string(), json!(self.tenant.as_str()));
+
+        let mut scoped = query.clone();
+        scoped.filter_expression = Some(self.tenant_filter(query.filter_expression.as_deref()));
+        scoped.expression_values = Some(serde_json::Value::Object(values));
+        scoped
+    }
+
+    /// Write an item stamped with this tenant
+    ///
+    /// The write is conditional, so it cannot replace an item with the same
+    /// key that belongs to another tenant.
+    async fn put_item(&self, mut item: HashMap<String, AttributeValue>) -> Result<()> {
+        item.insert(TENANT_PROPERTY.to_string(), AttributeValue::S(self.tenant.to_string()));
+
+        let request = self.client.put_item()
+            .table_name(&self.table_name)
+            .set_item(Some(item))
+            .condition_expression(format!("attribute_not_exists(entity_id) OR {} = {}", TENANT_PROPERTY, TENANT_VALUE))
+            .expression_attribute_values(TENANT_VALUE, AttributeValue::S(self.tenant.to_string()));
+        timed(BACKEND, "put_item", async {
+            request.send().await.map_err(|e| Error::DynamoDB(e.to_string()))
+        }).await?;
+
+        Ok(())
+    }
+
     /// Store an item in DynamoDB
     pub async fn store(&self, entity_id: &EntityId, temporal_range: &TemporalRange, data: &T) -> Result<()> {
         // Validate temporal range
@@ -90,13 +163,7 @@ where
             ("data".to_string(), AttributeValue::S(serialized_data)),
         ]);
 
-        self.client.put_item()
-            .table_name(&self.table_name)
-            .set_item(Some(item))
-            .send()
-            .await?;
-
-        Ok(())
+        self.put_item(item).await
     }
 
     /// Query items from DynamoDB
@@ -104,6 +171,7 @@ where
     where
         U: DeserializeOwned,
     {
+        let query = self.scoped(query);
         let mut builder = self.client.query()
             .table_name(&self.table_name);
 
@@ -128,7 +196,7 @@ where
             }
         }
 
-        let result = builder.send().await?;
+        let result = timed(BACKEND, "query", async { Ok(builder.send().await?) }).await?;
         Ok(result.items.unwrap_or_default())
     }
 
@@ -138,11 +206,9 @@ where
         U: DeserializeOwned,
     {
         let mut builder = self.client.scan()
-            .table_name(&self.table_name);
-
-        if let Some(filter_expr) = filter {
-            builder = builder.filter_expression(filter_expr);
-        }
+            .table_name(&self.table_name)
+            .filter_expression(self.tenant_filter(filter.as_deref()))
+            .expression_attribute_values(TENANT_VALUE, AttributeValue::S(self.tenant.to_string()));
 
         if let Some(expr_values) = values {
             for (k, v) in expr_values {
@@ -150,7 +216,7 @@ where
             }
         }
 
-        let result = builder.send().await?;
+        let result = timed(BACKEND, "scan", async { Ok(builder.send().await?) }).await?;
         
         let items = result.items.unwrap_or_default();
         let mut results = Vec::with_capacity(items.len());
@@ -221,6 +287,7 @@ where
             transaction_time_start: DateTime::from_timestamp(tx_start, 0).unwrap(),
             transaction_time_end: tx_end,
             version_id: version,
+            tenant: self.tenant.clone(),
         })
     }
 
@@ -284,6 +351,7 @@ where
                 transaction_time_start,
                 transaction_time_end: None,
                 version_id,
+                tenant: self.tenant.clone(),
             };
             
             entries.push(entry);
@@ -303,6 +371,7 @@ where
         query: OptimizedQuery,
         last_evaluated_key: Option<HashMap<String, AttributeValue>>,
     ) -> Result<QueryResult<T>> {
+        let query = self.scoped(&query);
         let mut request = self.client
             .query()
             .table_name(&self.table_name)
@@ -345,10 +414,9 @@ where
             request = request.set_scan_index_forward(Some(scan_direction));
         }
 
-        let result = request
-            .send()
-            .await
-            .map_err(|e| Error::DatabaseError(e.to_string()))?;
+        let result = timed(BACKEND, "query", async {
+            request.send().await.map_err(|e| Error::DatabaseError(e.to_string()))
+        }).await?;
 
         let items = result.items.unwrap_or_default();
         let mut results = Vec::with_capacity(items.len());
@@ -383,6 +451,7 @@ where
 
     /// Execute a query built with TemporalQueryBuilder
     pub async fn execute_query(&self, query: &OptimizedQuery) -> Result<QueryOutput> {
+        let query = &self.scoped(query);
         let mut builder = self.client.query()
             .table_name(&self.table_name)
             .scan_index_forward(query.scan_direction.unwrap_or(true));
@@ -412,9 +481,9 @@ where
             builder = builder.limit(limit);
         }
 
-        builder.send()
-            .await
-            .map_err(|e| Error::DynamoDB(e.to_string()))
+        timed(BACKEND, "query", async {
+            builder.send().await.map_err(|e| Error::DynamoDB(e.to_string()))
+        }).await
     }
 
     /// Execute a query with relationship filters
@@ -708,14 +777,7 @@ where
         item.insert("data".to_string(), AttributeValue::S(json_data));
         item.insert("version_id".to_string(), AttributeValue::S(Uuid::new_v4().to_string()));
         
-        self.client.put_item()
-            .table_name(&self.table_name)
-            .set_item(Some(item))
-            .send()
-            .await
-            .map_err(|e| Error::DynamoDB(e.to_string()))?;
-        
-        Ok(())
+        self.put_item(item).await
     }
 
     /// Process query results and convert to TemporalQueryResult objects
@@ -759,6 +821,113 @@ where
     }
 }
 
+/// DynamoDB item recording a valid-time index entry
+///
+/// Keyed like the items written by [`DynamoDBTemporal::store`], with the
+/// valid time repeated in epoch milliseconds so scans can compare it.
+fn index_item(entry: &TemporalIndexEntry) -> Result<HashMap<String, AttributeValue>> {
+    let data = serde_json::to_string(entry)
+        .map_err(|e| Error::Serialization(e.to_string()))?;
+
+    Ok(HashMap::from([
+        ("entity_id".to_string(), AttributeValue::S(entry.entity_id.id.clone())),
+        ("entity_type".to_string(), AttributeValue::S(entry.entity_id.entity_type.to_string())),
+        ("valid_time_start".to_string(), AttributeValue::S(entry.valid_time_start.to_rfc3339())),
+        ("valid_from".to_string(), AttributeValue::N(entry.valid_time_start.timestamp_millis().to_string())),
+        ("valid_to".to_string(), AttributeValue::N(entry.valid_time_end.timestamp_millis().to_string())),
+        ("transaction_time_start".to_string(), AttributeValue::S(entry.transaction_time_start.to_rfc3339())),
+        ("version_id".to_string(), AttributeValue::S(entry.version_id.to_string())),
+        ("data".to_string(), AttributeValue::S(data)),
+    ]))
+}
+
+impl<C> DynamoDBTemporal<TemporalIndexEntry, C>
+where
+    C: DynamoDBClient + Send + Sync + 'static,
+{
+    /// The same table, read and written as `tenant`
+    fn scoped_to(&self, tenant: &TenantId) -> Self {
+        Self::new(self.client.clone(), self.table_name.clone()).with_tenant(tenant.clone())
+    }
+
+    /// Mark the current index items of `entity_id` as superseded at `at`
+    ///
+    /// Each item is written back under its own key, whatever the table's
+    /// sort key is.
+    async fn retire_current(&self, entity_id: &EntityId, at: DateTime<Utc>) -> Result<()> {
+        let query = OptimizedQuery::new(self.table_name.clone())
+            .with_key_condition("entity_id = :entity_id".to_string())
+            .with_filter("attribute_not_exists(transaction_time_end)".to_string())
+            .with_values(json!({ ":entity_id": entity_id.id }));
+
+        for mut item in self.query_items::<TemporalIndexEntry>(&query).await? {
+            let Some(data) = item.get("data").and_then(|data| data.as_s().ok()) else {
+                continue;
+            };
+            let mut entry: TemporalIndexEntry = serde_json::from_str(data)
+                .map_err(|e| Error::Serialization(e.to_string()))?;
+            entry.transaction_time_end = Some(at);
+            let data = serde_json::to_string(&entry)
+                .map_err(|e| Error::Serialization(e.to_string()))?;
+
+            item.insert("transaction_time_end".to_string(), AttributeValue::S(at.to_rfc3339()));
+            item.insert("data".to_string(), AttributeValue::S(data));
+            self.put_item(item).await?;
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<C> ValidTimeIndex for DynamoDBTemporal<TemporalIndexEntry, C>
+where
+    C: DynamoDBClient + Send + Sync + 'static,
+{
+    async fn record(&self, entry: TemporalIndexEntry) -> Result<()> {
+        let temporal = self.scoped_to(&entry.tenant);
+        temporal.retire_current(&entry.entity_id, entry.transaction_time_start).await?;
+        temporal.put_item(index_item(&entry)?).await
+    }
+
+    async fn retire(&self, tenant: &TenantId, entity_id: &EntityId, at: DateTime<Utc>) -> Result<()> {
+        self.scoped_to(tenant).retire_current(entity_id, at).await
+    }
+
+    async fn nodes_in_range(&self, tenant: &TenantId, range: &TemporalRange, limit: usize) -> Result<Vec<TemporalIndexEntry>> {
+        let start = range.start.map_or(DateTime::<Utc>::MIN_UTC, |ts| ts.0);
+        let end = range.end.map_or(DateTime::<Utc>::MAX_UTC, |ts| ts.0);
+        let filter = self.tenant_filter(Some(NODES_IN_RANGE_FILTER));
+
+        let mut entries = Vec::new();
+        let mut start_key = None;
+        // Scans filter each page after reading it, so page until enough entries match
+        loop {
+            let request = self.client.scan()
+                .table_name(&self.table_name)
+                .filter_expression(&filter)
+                .expression_attribute_values(TENANT_VALUE, AttributeValue::S(tenant.to_string()))
+                .expression_attribute_values(":edge", AttributeValue::S(EntityType::Edge.to_string()))
+                .expression_attribute_values(":range_start", AttributeValue::N(start.timestamp_millis().to_string()))
+                .expression_attribute_values(":range_end", AttributeValue::N(end.timestamp_millis().to_string()))
+                .set_exclusive_start_key(start_key);
+            let page = timed(BACKEND, "scan", async { Ok(request.send().await?) }).await?;
+
+            for item in page.items.unwrap_or_default() {
+                if let Some(data) = item.get("data").and_then(|data| data.as_s().ok()) {
+                    entries.push(serde_json::from_str(data).map_err(|e| Error::Serialization(e.to_string()))?);
+                }
+            }
+            start_key = page.last_evaluated_key.filter(|key| !key.is_empty());
+            if entries.len() >= limit || start_key.is_none() {
+                break;
+            }
+        }
+
+        entries.truncate(limit);
+        Ok(entries)
+    }
+}
+
 /// Result of a query operation
 pub struct QueryResult<T> {
     /// Query result items
@@ -965,14 +1134,7 @@ where
             item.insert("data".to_string(), AttributeValue::S(json_data));
             item.insert("version_id".to_string(), AttributeValue::S(Uuid::new_v4().to_string()));
             
-            self.client.put_item()
-                .table_name(&self.table_name)
-                .set_item(Some(item))
-                .send()
-                .await
-                .map_err(|e| Error::DynamoDB(e.to_string()))?;
-            
-            Ok(())
+            self.put_item(item).await
         } else if let Some(edge) = data.as_any().downcast_ref::<Edge>() {
             let edge = edge.clone();
             let json_data = serde_json::to_string(&edge)
@@ -985,14 +1147,7 @@ where
             item.insert("data".to_string(), AttributeValue::S(json_data));
             item.insert("version_id".to_string(), AttributeValue::S(Uuid::new_v4().to_string()));
             
-            self.client.put_item()
-                .table_name(&self.table_name)
-                .set_item(Some(item))
-                .send()
-                .await
-                .map_err(|e| Error::DynamoDB(e.to_string()))?;
-            
-            Ok(())
+            self.put_item(item).await
         } else {
             Err(Error::InvalidDataType("Unsupported data type".to_string()))
         }
@@ -1037,6 +1192,9 @@ where
 mod tests {
     use super::*;
     use crate::temporal::query_builder::{PropertyOperator, RelationshipDirection};
+    use aws_sdk_dynamodb::config::{BehaviorVersion, Credentials, Region};
+    use wiremock::matchers::{header, method};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
 
     #[derive(Debug, Serialize, Deserialize)]
     struct TestData {
@@ -1048,6 +1206,128 @@ mod tests {
         // Test implementation
     }
 
+    /// Client for a DynamoDB endpoint served by `server`
+    fn mock_client(server: &MockServer) -> Arc<DynamoClient> {
+        let config = aws_sdk_dynamodb::Config::builder()
+            .behavior_version(BehaviorVersion::latest())
+            .endpoint_url(server.uri())
+            .region(Region::new("us-east-1"))
+            .credentials_provider(Credentials::new("test", "test", None, None, "test"))
+            .build();
+        Arc::new(DynamoClient::from_conf(config))
+    }
+
+    async fn mock_operation(server: &MockServer, operation: &str, body: serde_json::Value) {
+        Mock::given(method("POST"))
+            .and(header("x-amz-target", format!("DynamoDB_20120810.{}", operation).as_str()))
+            .respond_with(ResponseTemplate::new(200)
+                .insert_header("content-type", "application/x-amz-json-1.0")
+                .set_body_json(body))
+            .mount(server)
+            .await;
+    }
+
+    /// Bodies of the requests made for `operation`
+    async fn requests_for(server: &MockServer, operation: &str) -> Vec<serde_json::Value> {
+        let target = format!("DynamoDB_20120810.{}", operation);
+        server.received_requests().await.unwrap()
+            .into_iter()
+            .filter(|r| r.headers.get(&"x-amz-target".into()).map_or(false, |v| *v == target))
+            .map(|r| serde_json::from_slice(&r.body).unwrap())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_reads_and_writes_are_scoped_to_tenant() {
+        let server = MockServer::start().await;
+        mock_operation(&server, "PutItem", json!({})).await;
+        mock_operation(&server, "Query", json!({ "Items": [], "Count": 0 })).await;
+        mock_operation(&server, "Scan", json!({ "Items": [], "Count": 0 })).await;
+
+        let temporal = DynamoDBTemporal::<TestData, _>::new(mock_client(&server), "temporal".to_string())
+            .with_tenant(TenantId::new("acme").unwrap());
+        let entity_id = EntityId::new(EntityType::Node, Uuid::new_v4().to_string());
+        let now = Timestamp::now();
+
+        Temporal::store(&temporal, entity_id.clone(), TestData { value: "v1".to_string() }, TemporalRange::from_now())
+            .await
+            .unwrap();
+        temporal.query_at(&entity_id, now.0).await.unwrap();
+        temporal.query_evolution(&entity_id, &TemporalRange::new(Some(now), Some(now))).await.unwrap();
+        temporal.validate_consistency().await.unwrap();
+
+        let puts = requests_for(&server, "PutItem").await;
+        assert_eq!(puts.len(), 1);
+        assert_eq!(puts[0]["Item"]["tenant_id"], json!({ "S": "acme" }));
+        assert_eq!(puts[0]["ConditionExpression"], "attribute_not_exists(entity_id) OR tenant_id = :tenant_id");
+        assert_eq!(puts[0]["ExpressionAttributeValues"][":tenant_id"], json!({ "S": "acme" }));
+
+        let reads = [requests_for(&server, "Query").await, requests_for(&server, "Scan").await].concat();
+        assert_eq!(reads.len(), 3);
+        for read in reads {
+            let filter = read["FilterExpression"].as_str().unwrap();
+            assert!(filter.ends_with("tenant_id = :tenant_id"), "unscoped filter: {}", filter);
+            assert_eq!(read["ExpressionAttributeValues"][":tenant_id"], json!({ "S": "acme" }));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_valid_time_index_is_kept_in_the_table() {
+        let server = MockServer::start().await;
+        let now = Utc::now();
+        let acme = TenantId::new("acme").unwrap();
+        let entity_id = EntityId::new(EntityType::Person, Uuid::new_v4().to_string());
+        let previous = TemporalIndexEntry::new(entity_id.clone(), now - chrono::Duration::days(2), now, now - chrono::Duration::days(1))
+            .with_tenant(acme.clone());
+        let stored = json!({
+            "entity_id": { "S": entity_id.id },
+            "valid_time_start": { "S": previous.valid_time_start.to_rfc3339() },
+            "tenant_id": { "S": "acme" },
+            "data": { "S": serde_json::to_string(&previous).unwrap() },
+        });
+        mock_operation(&server, "Query", json!({ "Items": [stored.clone()], "Count": 1 })).await;
+        mock_operation(&server, "PutItem", json!({})).await;
+        mock_operation(&server, "Scan", json!({ "Items": [stored], "Count": 1 })).await;
+
+        let index = DynamoDBTemporal::<TemporalIndexEntry, _>::new(mock_client(&server), "temporal".to_string());
+        let entry = TemporalIndexEntry::new(entity_id, now, DateTime::<Utc>::MAX_UTC, now).with_tenant(acme.clone());
+        index.record(entry.clone()).await.unwrap();
+
+        // The replaced version is written back as superseded, under its own key
+        let puts = requests_for(&server, "PutItem").await;
+        assert_eq!(puts.len(), 2);
+        assert_eq!(puts[0]["Item"]["valid_time_start"], json!({ "S": previous.valid_time_start.to_rfc3339() }));
+        assert_eq!(puts[0]["Item"]["transaction_time_end"], json!({ "S": now.to_rfc3339() }));
+        assert_eq!(puts[1]["Item"]["version_id"], json!({ "S": entry.version_id.to_string() }));
+        assert_eq!(puts[1]["Item"]["tenant_id"], json!({ "S": "acme" }));
+        let queries = requests_for(&server, "Query").await;
+        assert_eq!(queries[0]["ExpressionAttributeValues"][":tenant_id"], json!({ "S": "acme" }));
+
+        let range = TemporalRange::new(Some(Timestamp(now)), None);
+        let found = index.nodes_in_range(&acme, &range, 10).await.unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].version_id, previous.version_id);
+        let scans = requests_for(&server, "Scan").await;
+        let filter = scans[0]["FilterExpression"].as_str().unwrap();
+        assert!(filter.starts_with("(entity_type <> :edge AND"), "edges not filtered: {}", filter);
+        assert!(filter.ends_with("tenant_id = :tenant_id"), "unscoped filter: {}", filter);
+        assert_eq!(scans[0]["ExpressionAttributeValues"][":tenant_id"], json!({ "S": "acme" }));
+    }
+
+    #[tokio::test]
+    async fn test_scoped_filter_keeps_caller_filter() {
+        let server = MockServer::start().await;
+        let temporal = DynamoDBTemporal::<TestData, _>::new(mock_client(&server), "temporal".to_string());
+
+        let query = OptimizedQuery::new("temporal".to_string())
+            .with_filter("valid_time_end >= :ts".to_string())
+            .with_values(json!({ ":ts": "1" }));
+        let scoped = temporal.scoped(&query);
+
+        assert_eq!(scoped.filter_expression.as_deref(), Some("(valid_time_end >= :ts) AND tenant_id = :tenant_id"));
+        assert_eq!(scoped.expression_values, Some(json!({ ":ts": "1", ":tenant_id": "default" })));
+    }
+
     fn build_condition(property_name: &str, operator: &PropertyOperator, property_value: &str) -> String {
         match operator {
             PropertyOperator::Equal => format!("{} = {}", property_name, property_value),
@@ -1,4 +1,5 @@
 use std::collections::BTreeMap;
+use async_trait::async_trait;
 use chrono::{DateTime, Utc};
 use serde::{Serialize, Deserialize};
 use tokio::sync::RwLock;
@@ -6,7 +7,7 @@ use uuid::Uuid;
 
 use crate::{
     error::{Error, Result},
-    types::{EntityId, EntityType, TemporalRange, Timestamp},
+    types::{EntityId, EntityType, TemporalRange, TenantId, Timestamp},
 };
 
 /// Represents a temporal index entry
@@ -24,6 +25,9 @@ pub struct TemporalIndexEntry {
     pub transaction_time_end: Option<DateTime<Utc>>,
     /// Version identifier
     pub version_id: Uuid,
+    /// Tenant the entity belongs to
+    #[serde(default)]
+    pub tenant: TenantId,
 }
 
 impl TemporalIndexEntry {
@@ -41,9 +45,16 @@ impl TemporalIndexEntry {
             transaction_time_start,
             transaction_time_end: None,
             version_id: Uuid::new_v4(),
+            tenant: TenantId::default(),
         }
     }
 
+    /// Attribute the entry to `tenant`
+    pub fn with_tenant(mut self, tenant: TenantId) -> Self {
+        self.tenant = tenant;
+        self
+    }
+
     /// Check if this entry is valid at the given timestamp
     pub fn is_valid_at(&self, timestamp: &DateTime<Utc>) -> bool {
         self.valid_time_start <= *timestamp && self.valid_time_end >= *timestamp
@@ -55,6 +66,24 @@ impl TemporalIndexEntry {
     }
 }
 
+/// Valid time of the current version of each tenant's nodes and edges
+///
+/// Answers which nodes were valid during a time range. [`TemporalIndex`]
+/// keeps it in process; [`DynamoDBTemporal`](super::DynamoDBTemporal) keeps
+/// it in a DynamoDB table shared by every server.
+#[async_trait]
+pub trait ValidTimeIndex: Send + Sync {
+    /// Record `entry` as the current version of its entity in its tenant,
+    /// retiring the version it replaces
+    async fn record(&self, entry: TemporalIndexEntry) -> Result<()>;
+
+    /// Retire the current version of `entity_id` of `tenant`, if any, at transaction time `at`
+    async fn retire(&self, tenant: &TenantId, entity_id: &EntityId, at: DateTime<Utc>) -> Result<()>;
+
+    /// Current entries of up to `limit` of `tenant`'s nodes whose valid time overlaps `range`
+    async fn nodes_in_range(&self, tenant: &TenantId, range: &TemporalRange, limit: usize) -> Result<Vec<TemporalIndexEntry>>;
+}
+
 /// Temporal index for managing temporal data
 pub struct TemporalIndex {
     /// Index entries organized by entity ID
@@ -142,6 +171,28 @@ impl TemporalIndex {
             .unwrap_or_default())
     }
 
+    /// Get current entries of `tenant`'s nodes whose valid time overlaps `range`
+    ///
+    /// Edges are skipped before `limit` is applied, so they never take the
+    /// place of a node.
+    pub async fn get_in_range(&self, tenant: &TenantId, range: &TemporalRange, limit: usize) -> Result<Vec<TemporalIndexEntry>> {
+        let entries = self.entries.read().await;
+
+        Ok(entries
+            .values()
+            .flatten()
+            .filter(|entry| {
+                entry.is_current() &&
+                entry.tenant == *tenant &&
+                entry.entity_id.entity_type != EntityType::Edge &&
+                range.end.map_or(true, |end| entry.valid_time_start <= end.0) &&
+                range.start.map_or(true, |start| entry.valid_time_end >= start.0)
+            })
+            .take(limit)
+            .cloned()
+            .collect())
+    }
+
     /// Get the evolution of an entity over time
     pub async fn get_evolution(
         &self,
@@ -213,6 +264,29 @@ impl TemporalIndex {
     }
 }
 
+#[async_trait]
+impl ValidTimeIndex for TemporalIndex {
+    async fn record(&self, entry: TemporalIndexEntry) -> Result<()> {
+        self.retire(&entry.tenant, &entry.entity_id, entry.transaction_time_start).await?;
+        self.add_entry(entry).await
+    }
+
+    async fn retire(&self, tenant: &TenantId, entity_id: &EntityId, at: DateTime<Utc>) -> Result<()> {
+        let mut entries = self.entries.write().await;
+
+        for entry in entries.get_mut(entity_id).into_iter().flatten() {
+            if entry.is_current() && entry.tenant == *tenant {
+                entry.transaction_time_end = Some(at);
+            }
+        }
+        Ok(())
+    }
+
+    async fn nodes_in_range(&self, tenant: &TenantId, range: &TemporalRange, limit: usize) -> Result<Vec<TemporalIndexEntry>> {
+        self.get_in_range(tenant, range, limit).await
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -354,4 +428,35 @@ mod tests {
         assert!(entry.transaction_time_end.is_some());
         assert_eq!(entry.transaction_time_end.unwrap(), now + Duration::hours(2));
     }
+
+    #[tokio::test]
+    async fn test_get_in_range_returns_the_tenants_nodes() {
+        let index = TemporalIndex::new();
+        let now = Utc::now();
+        let acme = TenantId::new("acme").unwrap();
+        let globex = TenantId::new("globex").unwrap();
+
+        let entry = |entity_type: EntityType, id: &str, tenant: &TenantId| {
+            TemporalIndexEntry::new(
+                EntityId::new(entity_type, id.to_string()),
+                now - Duration::hours(1),
+                now + Duration::hours(1),
+                now,
+            )
+            .with_tenant(tenant.clone())
+        };
+        index.add_entry(entry(EntityType::Edge, "acme-edge-1", &acme)).await.unwrap();
+        index.add_entry(entry(EntityType::Edge, "acme-edge-2", &acme)).await.unwrap();
+        index.add_entry(entry(EntityType::Node, "globex-node", &globex)).await.unwrap();
+        index.add_entry(entry(EntityType::Person, "acme-person", &acme)).await.unwrap();
+
+        let range = TemporalRange::new(Some(Timestamp(now)), Some(Timestamp(now)));
+        let entries = index.get_in_range(&acme, &range, 1).await.unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].entity_id.id, "acme-person");
+
+        let entries = index.get_in_range(&globex, &range, 10).await.unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].entity_id.id, "globex-node");
+    }
 } 
\ No newline at end of file
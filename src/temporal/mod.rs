@@ -7,7 +7,7 @@ mod dynamodb;
 pub mod graph;
 
 pub use consistency::{ConsistencyChecker, ConsistencyCheckResult, ConsistencyViolation, ConsistencyViolationType};
-pub use index::{TemporalIndex, TemporalIndexEntry};
+pub use index::{TemporalIndex, TemporalIndexEntry, ValidTimeIndex};
 pub use query::OptimizedQuery;
 pub use query_builder::{
     TemporalQueryBuilder,
@@ -32,6 +32,7 @@ impl RelationshipType for &str {}
 use async_trait::async_trait;
 use chrono::{DateTime, Duration, TimeZone, Utc, NaiveDateTime};
 use std::sync::Arc;
+use aws_config::{meta::region::RegionProviderChain, BehaviorVersion, Region};
 use aws_sdk_dynamodb::{Client as DynamoClient};
 use serde::{de::DeserializeOwned, Serialize};
 use std::any::Any;
@@ -41,8 +42,9 @@ use uuid::Uuid;
 use crate::{
     aws::dynamodb::DynamoDBClient,
     error::{Error, Result},
+    config::GraphBackend,
     graph::{Edge, Graph, Node},
-    types::{EntityId, EntityType, EdgeId, NodeId, TemporalRange, Timestamp, TemporalQueryResult},
+    types::{EntityId, EntityType, EdgeId, NodeId, TemporalFilter, TemporalRange, Timestamp, TemporalQueryResult},
     Config,
 };
 
@@ -170,22 +172,39 @@ pub trait Temporal {
     async fn validate_consistency(&self) -> Result<ConsistencyCheckResult>;
 }
 
+/// DynamoDB client for the region of `config`
+async fn dynamodb_client(config: &Config) -> DynamoClient {
+    let region = RegionProviderChain::first_try(Region::new(config.aws_region.clone()));
+    let shared_config = aws_config::defaults(BehaviorVersion::latest()).region(region).load().await;
+    DynamoClient::new(&shared_config)
+}
+
 /// Create a new temporal instance with DynamoDB backend
 pub async fn new_temporal_dynamodb<T>(config: &Config) -> Result<DynamoDBTemporal<T, DynamoClient>>
 where
     T: DeserializeOwned + Serialize + Send + Sync + 'static,
 {
-    let shared_config = aws_config::from_env().load().await;
-    let client = DynamoClient::new(&shared_config);
+    let client = dynamodb_client(config).await;
     
     let temporal = DynamoDBTemporal::new(
         Arc::new(client),
-        config.dynamodb_table.clone(),
+        config.temporal_table.clone(),
     );
     
     Ok(temporal)
 }
 
+/// Create the valid-time index selected by `config`
+///
+/// With the Neptune backend the index is kept in `config.temporal_table`,
+/// so every server sees the same one; otherwise it is held in process.
+pub async fn new_valid_time_index(config: &Config) -> Result<Arc<dyn ValidTimeIndex>> {
+    match config.graph_backend {
+        GraphBackend::Neptune => Ok(Arc::new(new_temporal_dynamodb::<TemporalIndexEntry>(config).await?)),
+        GraphBackend::InMemory => Ok(Arc::new(TemporalIndex::new())),
+    }
+}
+
 /// Temporal operations trait defining the interface for time-based operations
 #[async_trait]
 pub trait TemporalOperations: Send + Sync {
@@ -284,7 +303,7 @@ where
     async fn get_node_evolution(&self, node_id: uuid::Uuid, range: TimeRange) -> Result<Vec<Node>> {
         // Get the node from the underlying graph
         let node_id = NodeId(node_id);
-        match self.graph.get_node(node_id).await {
+        match self.graph.get_node(node_id, TemporalFilter::current()).await {
             Ok(node) => {
                 // For now, just check if the node exists
                 // In a full implementation, we would check if it falls within the time range
@@ -9,6 +9,7 @@ use aws_sdk_dynamodb::Client as DynamoClient;
 use serde_json;
 
 use crate::{
+    cancellation,
     error::{Error, Result},
     types::{EntityId, EntityType, Timestamp, TemporalQueryResult},
     aws::dynamodb::DynamoDBClient,
@@ -102,7 +103,7 @@ where
             let temporal = Arc::clone(&self.temporal);
             let chunk_vec = chunk.to_vec(); // Clone the chunk for the task
 
-            let task = tokio::spawn(async move {
+            let task = cancellation::spawn(async move {
                 for filter in chunk_vec {
                     match temporal.execute_relationship_filter(&builder, &filter).await {
                         Ok(result) => {
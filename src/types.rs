@@ -1,5 +1,5 @@
 use std::collections::HashMap;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, TimeZone, Utc};
 use serde::{Serialize, Deserialize};
 use uuid::Uuid;
 use std::str::FromStr;
@@ -61,6 +61,173 @@ impl std::fmt::Display for EntityId {
     }
 }
 
+/// Property (or metadata key, or DynamoDB attribute) that records which
+/// tenant owns a stored element
+pub const TENANT_PROPERTY: &str = "tenant_id";
+
+/// Identifier of the tenant that owns a slice of the stores
+///
+/// Tenant ids are non-empty and limited to ASCII alphanumerics, `-`, `_`
+/// and `.` so they can be embedded safely in filters and attribute values.
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct TenantId(String);
+
+impl TenantId {
+    /// Tenant used when a request carries no tenant claim
+    pub const DEFAULT: &'static str = "default";
+
+    /// Create a tenant id, rejecting empty or malformed values
+    pub fn new(id: impl Into<String>) -> Result<Self> {
+        let id = id.into();
+        let valid = !id.is_empty()
+            && id.len() <= 128
+            && id.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+        if !valid {
+            return Err(Error::ValidationError(format!("Invalid tenant id: {:?}", id)));
+        }
+        Ok(Self(id))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Whether an element whose tenant stamp is `stamp` belongs to this tenant
+    ///
+    /// Elements written before tenants were introduced carry no stamp and
+    /// belong to the default tenant.
+    pub fn owns(&self, stamp: Option<&Value>) -> bool {
+        match stamp {
+            Some(stamp) => stamp.as_str() == Some(self.as_str()),
+            None => self.0 == Self::DEFAULT,
+        }
+    }
+}
+
+impl Default for TenantId {
+    fn default() -> Self {
+        Self(Self::DEFAULT.to_string())
+    }
+}
+
+impl fmt::Display for TenantId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl FromStr for TenantId {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::new(s)
+    }
+}
+
+impl TryFrom<String> for TenantId {
+    type Error = Error;
+
+    fn try_from(s: String) -> Result<Self> {
+        Self::new(s)
+    }
+}
+
+impl From<TenantId> for String {
+    fn from(tenant: TenantId) -> Self {
+        tenant.0
+    }
+}
+
+/// Opaque token identifying the current version of a node or edge
+///
+/// Every write stamps a fresh transaction-time start on the element, so the
+/// token is that start in microseconds. Callers pass back the token they read
+/// to make an update conditional on nobody having written in between.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct VersionToken(pub i64);
+
+impl VersionToken {
+    /// Token of a version recorded at `recorded_at`
+    pub fn from_timestamp(recorded_at: Timestamp) -> Self {
+        Self(recorded_at.0.timestamp_micros())
+    }
+
+    /// Token of the version with the given transaction time, if it has started
+    pub fn of(transaction_time: &TemporalRange) -> Option<Self> {
+        transaction_time.start.map(Self::from_timestamp)
+    }
+
+    /// Identifier of the version of element `id` carrying this token
+    ///
+    /// Mixes the token into the low bits of the element id, so it is derived
+    /// from stored data alone and survives restarts.
+    pub fn version_id(self, id: Uuid) -> Uuid {
+        Uuid::from_u128(id.as_u128() ^ u128::from(self.0 as u64))
+    }
+}
+
+/// Access to the temporal ranges of a stored node or edge version
+pub(crate) trait Versioned: Clone {
+    fn valid_time(&self) -> &TemporalRange;
+    fn transaction_time(&self) -> &TemporalRange;
+    fn transaction_time_mut(&mut self) -> &mut TemporalRange;
+    fn version_id(&self) -> Option<Uuid>;
+}
+
+impl Versioned for Node {
+    fn valid_time(&self) -> &TemporalRange {
+        &self.valid_time
+    }
+
+    fn transaction_time(&self) -> &TemporalRange {
+        &self.transaction_time
+    }
+
+    fn transaction_time_mut(&mut self) -> &mut TemporalRange {
+        &mut self.transaction_time
+    }
+
+    fn version_id(&self) -> Option<Uuid> {
+        Node::version_id(self)
+    }
+}
+
+impl Versioned for Edge {
+    fn valid_time(&self) -> &TemporalRange {
+        &self.valid_time
+    }
+
+    fn transaction_time(&self) -> &TemporalRange {
+        &self.transaction_time
+    }
+
+    fn transaction_time_mut(&mut self) -> &mut TemporalRange {
+        &mut self.transaction_time
+    }
+
+    fn version_id(&self) -> Option<Uuid> {
+        Edge::version_id(self)
+    }
+}
+
+impl fmt::Display for VersionToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl FromStr for VersionToken {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        s.parse()
+            .map(Self)
+            .map_err(|_| Error::InvalidInput(format!("Invalid version token: {:?}", s)))
+    }
+}
+
 /// Timestamp wrapper
 #[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub struct Timestamp(pub DateTime<Utc>);
@@ -147,6 +314,73 @@ impl TemporalRange {
     }
 }
 
+/// Bi-temporal filter applied to graph reads
+///
+/// `valid_time` restricts results to elements whose valid time overlaps the
+/// range. `as_of_transaction` reads the graph as it was recorded at that
+/// moment; when unset, only the current version of each element is visible.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TemporalFilter {
+    /// Valid-time range that returned elements must overlap
+    pub valid_time: Option<TemporalRange>,
+    /// Transaction time to read the graph as of
+    pub as_of_transaction: Option<Timestamp>,
+}
+
+impl TemporalFilter {
+    /// Filter that returns the current version of every element
+    pub fn current() -> Self {
+        Self::default()
+    }
+
+    /// Filter on valid time only
+    pub fn valid_during(range: TemporalRange) -> Self {
+        Self {
+            valid_time: Some(range),
+            as_of_transaction: None,
+        }
+    }
+
+    /// Read the graph as it was recorded at `timestamp`
+    pub fn as_of(mut self, timestamp: Timestamp) -> Self {
+        self.as_of_transaction = Some(timestamp);
+        self
+    }
+
+    /// Check whether an element version passes this filter
+    ///
+    /// Transaction-time ranges are treated as half-open, `[start, end)`, so
+    /// exactly one version of an element is visible at any instant.
+    pub fn matches(&self, valid_time: &TemporalRange, transaction_time: &TemporalRange) -> bool {
+        let valid = self.valid_time.as_ref().map_or(true, |range| valid_time.overlaps(range));
+
+        let recorded = match &self.as_of_transaction {
+            None => transaction_time.end.is_none(),
+            Some(as_of) => {
+                transaction_time.start.map_or(true, |start| start.0 <= as_of.0)
+                    && transaction_time.end.map_or(true, |end| as_of.0 < end.0)
+            }
+        };
+
+        valid && recorded
+    }
+}
+
+impl From<TemporalRange> for TemporalFilter {
+    fn from(range: TemporalRange) -> Self {
+        Self::valid_during(range)
+    }
+}
+
+impl From<Option<TemporalRange>> for TemporalFilter {
+    fn from(range: Option<TemporalRange>) -> Self {
+        Self {
+            valid_time: range,
+            as_of_transaction: None,
+        }
+    }
+}
+
 /// Type of entity in the graph
 #[derive(Debug, Clone, Eq, PartialEq, Hash, Serialize, Deserialize, Ord, PartialOrd)]
 pub enum EntityType {
@@ -274,6 +508,16 @@ impl Node {
     pub fn properties(&self) -> &Properties {
         &self.properties
     }
+
+    /// Token of this version, used for optimistic concurrency
+    pub fn version(&self) -> Option<VersionToken> {
+        VersionToken::of(&self.transaction_time)
+    }
+
+    /// Identifier of this version, listed in the node's history
+    pub fn version_id(&self) -> Option<Uuid> {
+        self.version().map(|token| token.version_id(self.id.0))
+    }
 }
 
 /// An edge in the temporal knowledge graph
@@ -308,6 +552,16 @@ impl Edge {
     pub fn in_v(&self) -> &NodeId {
         &self.target_id
     }
+
+    /// Token of this version, used for optimistic concurrency
+    pub fn version(&self) -> Option<VersionToken> {
+        VersionToken::of(&self.transaction_time)
+    }
+
+    /// Identifier of this version, listed in the edge's history
+    pub fn version_id(&self) -> Option<Uuid> {
+        self.version().map(|token| token.version_id(self.id.0))
+    }
 }
 
 /// Represents a temporal operation type
@@ -490,6 +744,156 @@ fn convert_property_value(value: GValue) -> serde_json::Value {
     }
 }
 
+/// First value of each vertex property, as JSON
+fn vertex_properties(vertex: &Vertex) -> HashMap<String, Value> {
+    vertex.iter()
+        .filter(|(_, values)| !values.is_empty())
+        .map(|(key, values)| (key.clone(), convert_property_value(values[0].value().clone())))
+        .collect()
+}
+
+/// Property keys graph elements are persisted under
+///
+/// Shared by the Gremlin traversals that write elements and the parsing
+/// below that reads them back.
+pub(crate) mod keys {
+    pub const ID: &str = "id";
+    pub const LABEL: &str = "label";
+    pub const ENTITY_TYPE: &str = "entity_type";
+    pub const PROPERTIES: &str = "properties";
+    pub const VALID_START: &str = "valid_time.start";
+    pub const VALID_END: &str = "valid_time.end";
+    pub const TRANSACTION_START: &str = "transaction_time.start";
+    pub const TRANSACTION_END: &str = "transaction_time.end";
+    pub const SOURCE_ID: &str = "source_id";
+    pub const TARGET_ID: &str = "target_id";
+    pub const EDGE_LABEL: &str = "edge_label";
+
+    /// Vertex label for archived edge versions
+    pub const EDGE_VERSION_LABEL: &str = "edge_version";
+}
+
+/// Stored value for an open range start
+pub(crate) const OPEN_START: i64 = i64::MIN;
+
+/// Stored value for an open range end
+pub(crate) const OPEN_END: i64 = i64::MAX;
+
+/// Convert a timestamp to stored epoch milliseconds
+pub(crate) fn to_millis(timestamp: Timestamp) -> i64 {
+    timestamp.0.timestamp_millis()
+}
+
+/// Convert stored epoch milliseconds back to a timestamp, mapping open bounds to `None`
+pub(crate) fn from_millis(millis: i64) -> Option<Timestamp> {
+    if millis == OPEN_START || millis == OPEN_END {
+        return None;
+    }
+    Utc.timestamp_millis_opt(millis).single().map(Timestamp)
+}
+
+/// Read a temporal range persisted as two epoch-millisecond properties
+fn range_from_properties(props: &HashMap<String, Value>, start_key: &str, end_key: &str) -> TemporalRange {
+    let read = |key: &str| props.get(key).and_then(Value::as_i64).and_then(from_millis);
+    TemporalRange {
+        start: read(start_key),
+        end: read(end_key),
+    }
+}
+
+/// Read user properties persisted as a JSON string
+fn user_properties(props: &HashMap<String, Value>) -> Result<Properties> {
+    match props.get(keys::PROPERTIES) {
+        Some(Value::String(json)) => Ok(Properties::from_json(serde_json::from_str(json)?)?),
+        Some(Value::Object(map)) => Ok(Properties(map.clone().into_iter().collect())),
+        _ => Ok(Properties::new()),
+    }
+}
+
+/// Parse a UUID stored under `key`, falling back to the element's own ID
+fn uuid_property(props: &HashMap<String, Value>, key: &str, fallback: &str) -> Result<Uuid> {
+    let raw = props.get(key).and_then(Value::as_str).unwrap_or(fallback);
+    Ok(Uuid::from_str(raw)?)
+}
+
+/// Rebuild a node from the vertex properties written by `graph::query`
+pub(crate) fn node_from_properties(gid: &str, vertex_label: &str, props: &HashMap<String, Value>) -> Result<Node> {
+    let label = props.get(keys::LABEL)
+        .and_then(Value::as_str)
+        .unwrap_or(vertex_label)
+        .to_string();
+    let entity_type = props.get(keys::ENTITY_TYPE)
+        .and_then(Value::as_str)
+        .unwrap_or(vertex_label);
+
+    Ok(Node {
+        id: NodeId(uuid_property(props, keys::ID, gid)?),
+        entity_type: EntityType::from_str(entity_type)?,
+        label,
+        properties: user_properties(props)?,
+        valid_time: range_from_properties(props, keys::VALID_START, keys::VALID_END),
+        transaction_time: range_from_properties(props, keys::TRANSACTION_START, keys::TRANSACTION_END),
+    })
+}
+
+/// Rebuild an edge from the properties written by `graph::query`
+///
+/// `source` and `target` are only used when the properties do not record
+/// the endpoints themselves, as is the case for live Gremlin edges.
+pub(crate) fn edge_from_properties(
+    gid: &str,
+    label: &str,
+    source: &str,
+    target: &str,
+    props: &HashMap<String, Value>,
+) -> Result<Edge> {
+    let label = props.get(keys::EDGE_LABEL)
+        .and_then(Value::as_str)
+        .unwrap_or(label)
+        .to_string();
+
+    Ok(Edge {
+        id: EdgeId(uuid_property(props, keys::ID, gid)?),
+        source_id: NodeId(uuid_property(props, keys::SOURCE_ID, source)?),
+        target_id: NodeId(uuid_property(props, keys::TARGET_ID, target)?),
+        label,
+        properties: user_properties(props)?,
+        valid_time: range_from_properties(props, keys::VALID_START, keys::VALID_END),
+        transaction_time: range_from_properties(props, keys::TRANSACTION_START, keys::TRANSACTION_END),
+    })
+}
+
+/// Convert a vertex result into a node
+fn node_from_gvalue(value: GValue) -> Result<Node> {
+    match value {
+        GValue::Vertex(v) => node_from_properties(&vertex_id_to_string(&v), v.label(), &vertex_properties(&v)),
+        _ => Err(Error::Neptune("Expected vertex result".to_string())),
+    }
+}
+
+/// Convert an edge result, or an archived edge version vertex, into an edge
+fn edge_from_gvalue(value: GValue) -> Result<Edge> {
+    match value {
+        GValue::Edge(e) => {
+            let props = e.iter()
+                .map(|(key, value)| (key.clone(), convert_property_value(value.value().clone())))
+                .collect();
+            edge_from_properties(
+                &edge_id_to_string(&e),
+                e.label(),
+                &vertex_id_to_string(e.out_v()),
+                &vertex_id_to_string(e.in_v()),
+                &props,
+            )
+        },
+        GValue::Vertex(v) if v.label() == keys::EDGE_VERSION_LABEL => {
+            let gid = vertex_id_to_string(&v);
+            edge_from_properties(&gid, v.label(), &gid, &gid, &vertex_properties(&v))
+        },
+        _ => Err(Error::Neptune("Expected edge result".to_string())),
+    }
+}
+
 // Implement for Node
 impl FromLocalResultSet for Node {
     fn from_local_result_set(result_set: LocalResultSet) -> Result<Self> {
@@ -498,43 +902,7 @@ impl FromLocalResultSet for Node {
             return Err(Error::NotFound("Node not found".to_string()));
         }
         
-        let vertex = results.remove(0);
-        match vertex {
-            GValue::Vertex(v) => {
-                let now = Utc::now();
-                let id = vertex_id_to_string(&v);
-                
-                let label = v.label().to_string();
-                
-                // Process properties
-                let properties = {
-                    let mut props = HashMap::new();
-                    for (key, value) in v.iter() {
-                        if !value.is_empty() {
-                            // Just take the first property value for each key
-                            props.insert(key.clone(), convert_property_value(value[0].value().clone()));
-                        }
-                    }
-                    Properties(props)
-                };
-
-                Ok(Node {
-                    id: NodeId(Uuid::from_str(&id)?),
-                    entity_type: EntityType::from_str(&label)?,
-                    label,
-                    properties,
-                    valid_time: TemporalRange {
-                        start: Some(Timestamp(now)),
-                        end: None,
-                    },
-                    transaction_time: TemporalRange {
-                        start: Some(Timestamp(now)),
-                        end: None,
-                    },
-                })
-            },
-            _ => Err(Error::Neptune("Expected vertex result".to_string())),
-        }
+        node_from_gvalue(results.remove(0))
     }
 }
 
@@ -546,43 +914,7 @@ impl FromLocalResultSet for Edge {
             return Err(Error::NotFound("Edge not found".to_string()));
         }
         
-        let edge = results.remove(0);
-        match edge {
-            GValue::Edge(e) => {
-                let now = Utc::now();
-                let id = edge_id_to_string(&e);
-                
-                let out_v_str = vertex_id_to_string(e.out_v());
-                
-                let in_v_str = vertex_id_to_string(e.in_v());
-                
-                // Process properties
-                let properties = {
-                    let mut props = HashMap::new();
-                    for (key, value) in e.iter() {
-                        props.insert(key.clone(), convert_property_value(value.value().clone()));
-                    }
-                    Properties(props)
-                };
-
-                Ok(Edge {
-                    id: EdgeId(Uuid::from_str(&id)?),
-                    source_id: NodeId(Uuid::from_str(&out_v_str)?),
-                    target_id: NodeId(Uuid::from_str(&in_v_str)?),
-                    label: e.label().to_string(),
-                    properties,
-                    valid_time: TemporalRange {
-                        start: Some(Timestamp(now)),
-                        end: None,
-                    },
-                    transaction_time: TemporalRange {
-                        start: Some(Timestamp(now)),
-                        end: None,
-                    },
-                })
-            },
-            _ => Err(Error::Neptune("Expected edge result".to_string())),
-        }
+        edge_from_gvalue(results.remove(0))
     }
 }
 
@@ -590,39 +922,7 @@ impl FromLocalResultSet for Edge {
 impl FromLocalResultSet for Vec<Node> {
     fn from_local_result_set(result_set: LocalResultSet) -> Result<Self> {
         result_set.0.into_iter()
-            .map(|value| match value {
-                GValue::Vertex(v) => {
-                    let now = Utc::now();
-                    let id = vertex_id_to_string(&v);
-                    let label = v.label().to_string();
-                    let properties = {
-                        let mut props = HashMap::new();
-                        for (key, value) in v.iter() {
-                            if !value.is_empty() {
-                                // Just take the first property value for each key
-                                props.insert(key.clone(), convert_property_value(value[0].value().clone()));
-                            }
-                        }
-                        Properties(props)
-                    };
-
-                    Ok(Node {
-                        id: NodeId(Uuid::from_str(&id)?),
-                        entity_type: EntityType::from_str(&label)?,
-                        label,
-                        properties,
-                        valid_time: TemporalRange {
-                            start: Some(Timestamp(now)),
-                            end: None,
-                        },
-                        transaction_time: TemporalRange {
-                            start: Some(Timestamp(now)),
-                            end: None,
-                        },
-                    })
-                },
-                _ => Err(Error::Neptune("Expected vertex result".to_string())),
-            })
+            .map(node_from_gvalue)
             .collect()
     }
 }
@@ -631,36 +931,7 @@ impl FromLocalResultSet for Vec<Node> {
 impl FromLocalResultSet for Vec<Edge> {
     fn from_local_result_set(result_set: LocalResultSet) -> Result<Self> {
         result_set.0.into_iter()
-            .map(|value| match value {
-                GValue::Edge(e) => {
-                    let now = Utc::now();
-                    let id = edge_id_to_string(&e);
-                    let properties = {
-                        let mut props = HashMap::new();
-                        for (key, value) in e.iter() {
-                            props.insert(key.clone(), convert_property_value(value.value().clone()));
-                        }
-                        Properties(props)
-                    };
-
-                    Ok(Edge {
-                        id: EdgeId(Uuid::from_str(&id)?),
-                        source_id: NodeId(Uuid::from_str(&vertex_id_to_string(e.out_v()))?),
-                        target_id: NodeId(Uuid::from_str(&vertex_id_to_string(e.in_v()))?),
-                        label: e.label().to_string(),
-                        properties,
-                        valid_time: TemporalRange {
-                            start: Some(Timestamp(now)),
-                            end: None,
-                        },
-                        transaction_time: TemporalRange {
-                            start: Some(Timestamp(now)),
-                            end: None,
-                        },
-                    })
-                },
-                _ => Err(Error::Neptune("Expected edge result".to_string())),
-            })
+            .map(edge_from_gvalue)
             .collect()
     }
 }
@@ -673,40 +944,7 @@ impl FromLocalResultSet for Option<Node> {
             return Ok(None);
         }
         
-        let vertex = results.remove(0);
-        match vertex {
-            GValue::Vertex(v) => {
-                let now = Utc::now();
-                let id = vertex_id_to_string(&v);
-                let label = v.label().to_string();
-                let properties = {
-                    let mut props = HashMap::new();
-                    for (key, value) in v.iter() {
-                        if !value.is_empty() {
-                            // Just take the first property value for each key
-                            props.insert(key.clone(), convert_property_value(value[0].value().clone()));
-                        }
-                    }
-                    Properties(props)
-                };
-
-                Ok(Some(Node {
-                    id: NodeId(Uuid::from_str(&id)?),
-                    entity_type: EntityType::from_str(&label)?,
-                    label,
-                    properties,
-                    valid_time: TemporalRange {
-                        start: Some(Timestamp(now)),
-                        end: None,
-                    },
-                    transaction_time: TemporalRange {
-                        start: Some(Timestamp(now)),
-                        end: None,
-                    },
-                }))
-            },
-            _ => Err(Error::Neptune("Expected vertex result".to_string())),
-        }
+        node_from_gvalue(results.remove(0)).map(Some)
     }
 }
 
@@ -901,6 +1139,22 @@ mod tests {
         assert_ne!(id1, id2);
     }
 
+    #[test]
+    fn test_tenant_id() {
+        assert_eq!(TenantId::default().as_str(), "default");
+        assert_eq!("acme-corp".parse::<TenantId>().unwrap().to_string(), "acme-corp");
+        assert!(TenantId::new("").is_err());
+        assert!(TenantId::new("a b").is_err());
+        assert!(TenantId::new("x\" OR 1=1").is_err());
+        assert!(serde_json::from_str::<TenantId>("\"bad tenant\"").is_err());
+
+        let acme = TenantId::new("acme").unwrap();
+        assert!(acme.owns(Some(&Value::from("acme"))));
+        assert!(!acme.owns(Some(&Value::from("globex"))));
+        assert!(!acme.owns(None));
+        assert!(TenantId::default().owns(None));
+    }
+
     #[test]
     fn test_timestamp() {
         let now = Utc::now();
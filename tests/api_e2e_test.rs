@@ -1,14 +1,29 @@
+use std::{net::SocketAddr, sync::Arc, time::Duration};
+
 use graph::{
-    api::{create_router},
-    Config,
+    api::{
+        auth::{hash_api_key, ApiKeyAuthenticator, StaticKeyStore, GRAPH_READ, GRAPH_WRITE},
+        create_router,
+        health::{HealthChecker, HealthProbe, ProbeResult},
+        middleware::RateLimiter,
+        ApiState,
+    },
+    config::ApiKeyConfig,
+    graph::InMemoryGraph,
+    hybrid::HashingEmbeddings,
+    memory::LocalMemory,
+    telemetry,
+    Config, HybridStore, RAGSystem, TemporalIndex,
 };
 use axum::{
     body::{Body},
+    extract::ConnectInfo,
     http::{Request, StatusCode},
 };
+use futures::StreamExt;
 use http_body_util::BodyExt;
 use bytes::Bytes;
-use serde_json::Value;
+use serde_json::{json, Value};
 use tower::ServiceExt;
 
 // Helper function to read body into bytes
@@ -16,15 +31,64 @@ async fn read_body(body: Body) -> Bytes {
     body.collect().await.unwrap().to_bytes()
 }
 
-// Create a test app instance
-fn test_app() -> axum::Router {
-    let _config = Config::for_testing();
-    create_router()
+// Create a test app instance backed by in-process stores
+async fn test_app() -> axum::Router {
+    create_router(Arc::new(test_state().await))
+}
+
+async fn test_state() -> ApiState {
+    state_over(Arc::new(InMemoryGraph::new())).await
+}
+
+// State over an existing graph, with fresh in-process indexes as after a restart
+async fn state_over(graph: Arc<InMemoryGraph>) -> ApiState {
+    let config = Config::for_testing();
+    let dir = std::env::temp_dir().join(format!("api-e2e-{}", uuid::Uuid::new_v4()));
+    let memory = Arc::new(LocalMemory::open(dir).await.unwrap());
+    let temporal = Arc::new(TemporalIndex::new());
+    let hybrid = HybridStore::from_shared(
+        &config,
+        graph.clone(),
+        memory.clone(),
+        temporal.clone(),
+        Arc::new(HashingEmbeddings::new(64)),
+    );
+    ApiState::new(graph, temporal, Arc::new(RAGSystem::default_mock()), Arc::new(hybrid), memory)
+}
+
+async fn send(app: &axum::Router, request: Request<Body>) -> (StatusCode, Value) {
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let body = read_body(response.into_body()).await;
+    (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+// Serve `app` on an ephemeral port, for clients that need a real connection
+async fn serve(app: axum::Router) -> SocketAddr {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>()).await.unwrap();
+    });
+    addr
+}
+
+fn empty_request(method: &str, uri: &str) -> Request<Body> {
+    Request::builder().method(method).uri(uri).body(Body::empty()).unwrap()
+}
+
+fn json_request(method: &str, uri: &str, body: Value) -> Request<Body> {
+    Request::builder()
+        .method(method)
+        .uri(uri)
+        .header("content-type", "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap()
 }
 
 #[tokio::test]
 async fn test_health_check() {
-    let app = test_app();
+    let app = test_app().await;
     let response = app
         .oneshot(Request::builder().uri("/health").body(Body::empty()).unwrap())
         .await
@@ -36,9 +100,66 @@ async fn test_health_check() {
     assert_eq!(json["status"], "healthy");
 }
 
+#[tokio::test]
+async fn test_liveness_and_readiness() {
+    struct Down;
+
+    #[async_trait::async_trait]
+    impl HealthProbe for Down {
+        fn name(&self) -> &str {
+            "neptune"
+        }
+
+        async fn probe(&self) -> graph::Result<ProbeResult> {
+            Err(graph::Error::Neptune("connection refused".to_string()))
+        }
+    }
+
+    let app = test_app().await;
+    let (status, body) = send(&app, empty_request("GET", "/health/ready")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["status"], "healthy");
+
+    let health = HealthChecker::new(Duration::from_millis(100)).with_probe(Arc::new(Down));
+    let app = create_router(Arc::new(test_state().await.with_health_checker(health)));
+    let (status, body) = send(&app, empty_request("GET", "/health/live")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["components"], json!([]));
+
+    let (status, body) = send(&app, empty_request("GET", "/health/ready")).await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(body["status"], "unhealthy");
+    assert_eq!(body["components"][0]["name"], "neptune");
+    assert_eq!(body["components"][0]["status"], "Unhealthy");
+
+    let (status, body) = send(&app, empty_request("GET", "/health")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["status"], "unhealthy");
+}
+
+#[tokio::test]
+async fn test_metrics_endpoint() {
+    let app = test_app().await;
+    let (status, _) = send(&app, empty_request("GET", "/metrics")).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let app = create_router(Arc::new(test_state().await.with_metrics(telemetry::install())));
+    let node_id = uuid::Uuid::new_v4();
+    let (status, _) = send(&app, empty_request("GET", &format!("/nodes/{}", node_id))).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let response = app.clone().oneshot(empty_request("GET", "/metrics")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.headers()["content-type"].to_str().unwrap().starts_with("text/plain"));
+    let body = String::from_utf8(read_body(response.into_body()).await.to_vec()).unwrap();
+    assert!(body.contains("# TYPE http_request_duration_seconds histogram"));
+    assert!(body.contains(r#"http_request_duration_seconds_count{method="GET",route="/nodes/:id",status="404"}"#));
+    assert!(!body.contains(&node_id.to_string()));
+}
+
 #[tokio::test]
 async fn test_version() {
-    let app = test_app();
+    let app = test_app().await;
     let response = app
         .oneshot(Request::builder().uri("/version").body(Body::empty()).unwrap())
         .await
@@ -48,4 +169,788 @@ async fn test_version() {
     let body = read_body(response.into_body()).await;
     let json: Value = serde_json::from_slice(&body).unwrap();
     assert!(json["version"].is_string());
-} 
\ No newline at end of file
+}
+
+#[tokio::test]
+async fn test_store_then_query_knowledge() {
+    let app = test_app().await;
+
+    let text = "John works at Apple in California.";
+    let response = app.clone()
+        .oneshot(json_request("POST", "/knowledge/store", json!({ "text": text })))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let stored: Value = serde_json::from_slice(&read_body(response.into_body()).await).unwrap();
+    assert_eq!(stored["entities_extracted"], 3);
+    assert_eq!(stored["relationships_detected"], 2);
+    assert_eq!(stored["relationships"][0]["relationship_type"], "WORKS_FOR");
+
+    let response = app.clone()
+        .oneshot(json_request("POST", "/knowledge/query", json!({ "query": "Apple", "limit": 3 })))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let results: Value = serde_json::from_slice(&read_body(response.into_body()).await).unwrap();
+    assert_eq!(results["results"][0]["content"], "Apple");
+    assert_eq!(results["context"], json!([text]));
+
+    // Nothing was valid a year ago
+    let last_year = (chrono::Utc::now() - chrono::Duration::days(365)).to_rfc3339();
+    let response = app
+        .oneshot(json_request("POST", "/knowledge/query", json!({
+            "query": "Apple",
+            "timestamp": last_year,
+            "time_window": 3600,
+        })))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let results: Value = serde_json::from_slice(&read_body(response.into_body()).await).unwrap();
+    assert_eq!(results["results"], json!([]));
+}
+
+#[tokio::test]
+async fn test_streamed_store_and_query() {
+    let app = test_app().await;
+    let text = "John works at Apple in California.";
+
+    let response = app.clone()
+        .oneshot(json_request("POST", "/knowledge/store/stream", json!({ "text": text })))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["content-type"], "application/x-ndjson");
+    let body = read_body(response.into_body()).await;
+    let events: Vec<Value> = body.split(|b| *b == b'\n')
+        .filter(|line| !line.is_empty())
+        .map(|line| serde_json::from_slice(line).unwrap())
+        .collect();
+    let kinds: Vec<_> = events.iter().map(|e| e["event"].as_str().unwrap()).collect();
+    assert_eq!(kinds, [
+        "entity", "entity", "entity", "relationship", "relationship",
+        "persisted", "persisted", "persisted", "persisted", "persisted", "persisted", "done",
+    ]);
+    assert_eq!(events[5]["kind"], "node");
+    assert_eq!(events[5]["id"], events[0]["id"]);
+    assert_eq!(events[10]["kind"], "document");
+    assert_eq!(events[11]["document_id"], events[10]["id"]);
+    assert_eq!(events[11]["entities_extracted"], 3);
+
+    let response = app.clone()
+        .oneshot(Request::builder()
+            .method("POST")
+            .uri("/knowledge/query/stream")
+            .header("content-type", "application/json")
+            .header("accept", "text/event-stream")
+            .body(Body::from(json!({ "query": "Apple", "limit": 3 }).to_string()))
+            .unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["content-type"], "text/event-stream");
+    let body = String::from_utf8(read_body(response.into_body()).await.to_vec()).unwrap();
+    let events: Vec<(&str, Value)> = body.split("\n\n")
+        .filter(|frame| !frame.is_empty())
+        .map(|frame| {
+            let (name, data) = frame.split_once('\n').unwrap();
+            (name.strip_prefix("event: ").unwrap(), serde_json::from_str(data.strip_prefix("data: ").unwrap()).unwrap())
+        })
+        .collect();
+    assert_eq!(events.len(), 2);
+    assert_eq!(events[0].0, "results");
+    assert_eq!(events[0].1["stage"], "vector");
+    assert_eq!(events[1].0, "done");
+    assert_eq!(events[1].1["results"][0]["content"], "Apple");
+    assert_eq!(events[1].1["context"], json!([text]));
+
+    // Invalid requests fail before the stream starts
+    let (status, _) = send(&app, json_request("POST", "/knowledge/query/stream", json!({ "query": "" }))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_queries_run_under_a_request_id() {
+    let app = test_app().await;
+
+    let (status, body) = send(&app, json_request("POST", "/knowledge/query", json!({
+        "query": "engines",
+        "request_id": "q1",
+    }))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body["results"].is_array());
+
+    // The query has finished, so there is nothing left to cancel
+    let (status, _) = send(&app, empty_request("DELETE", "/requests/q1")).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_knowledge_requests_are_validated() {
+    let app = test_app().await;
+
+    let response = app.clone()
+        .oneshot(json_request("POST", "/knowledge/store", json!({ "text": "  " })))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let response = app
+        .oneshot(json_request("POST", "/knowledge/query", json!({ "query": "Apple", "time_window": -1 })))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_node_crud() {
+    let app = test_app().await;
+
+    let (status, node) = send(&app, json_request("POST", "/nodes", json!({
+        "entity_type": "Person",
+        "label": "Ada",
+        "properties": { "born": 1815, "field": "math" },
+    }))).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(node["label"], "Ada");
+    let id = node["id"].as_str().unwrap().to_string();
+
+    let (status, fetched) = send(&app, empty_request("GET", &format!("/nodes/{id}"))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(fetched["properties"]["born"], 1815);
+
+    // Patch merges properties and leaves the label alone
+    let (status, updated) = send(&app, json_request("PATCH", &format!("/nodes/{id}"), json!({
+        "properties": { "field": null, "title": "Countess" },
+    }))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(updated["label"], "Ada");
+    // Writes are stamped with the caller's tenant
+    assert_eq!(updated["properties"], json!({ "born": 1815, "title": "Countess", "tenant_id": "default" }));
+
+    let (status, deleted) = send(&app, empty_request("DELETE", &format!("/nodes/{id}"))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(deleted, json!({ "success": true, "id": id }));
+
+    let (status, _) = send(&app, empty_request("GET", &format!("/nodes/{id}"))).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = send(&app, empty_request("GET", "/nodes/not-a-uuid")).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_node_as_of_reads_and_history() {
+    let app = test_app().await;
+    let timestamp = |at: chrono::DateTime<chrono::Utc>| at.to_rfc3339_opts(chrono::SecondsFormat::Micros, true);
+
+    let (status, node) = send(&app, json_request("POST", "/nodes", json!({
+        "entity_type": "Person", "label": "Ada", "properties": {},
+    }))).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let uri = format!("/nodes/{}", node["id"].as_str().unwrap());
+    let (_, original) = send(&app, empty_request("GET", &uri)).await;
+    let first_version = original["version_id"].clone();
+    assert!(first_version.is_string());
+
+    tokio::time::sleep(Duration::from_millis(5)).await;
+    let before_update = timestamp(chrono::Utc::now());
+    tokio::time::sleep(Duration::from_millis(5)).await;
+    let (status, _) = send(&app, json_request("PATCH", &uri, json!({ "label": "Countess" }))).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, current) = send(&app, empty_request("GET", &uri)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(current["label"], "Countess");
+    assert_ne!(current["version_id"], first_version);
+
+    let (status, past) = send(&app, empty_request("GET", &format!("{uri}?tx_as_of={before_update}"))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(past["label"], "Ada");
+    assert_eq!(past["version_id"], first_version);
+
+    // The node was not valid before it was created
+    let (status, _) = send(&app, empty_request("GET", &format!("{uri}?as_of=1900-01-01T00:00:00Z"))).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    // History pages oldest first and survives deletion
+    let (status, _) = send(&app, empty_request("DELETE", &uri)).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, page) = send(&app, empty_request("GET", &format!("{uri}/history?limit=1"))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(page["versions"][0]["version_id"], first_version);
+    assert_eq!(page["versions"][0]["node"]["label"], "Ada");
+    assert!(page["versions"][0]["superseded_at"].is_string());
+    let cursor = page["next_cursor"].as_str().unwrap().to_string();
+
+    let (status, page) = send(&app, empty_request("GET", &format!("{uri}/history?limit=1&cursor={cursor}"))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(page["versions"][0]["version_id"], current["version_id"]);
+    assert_eq!(page["versions"][0]["node"]["label"], "Countess");
+    assert!(page["versions"][0]["superseded_at"].is_string());
+    assert_eq!(page["next_cursor"], Value::Null);
+
+    let (status, page) = send(&app, empty_request("GET", &format!("{uri}/history?to=1900-01-01T00:00:00Z"))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(page["versions"], json!([]));
+
+    let missing = uuid::Uuid::new_v4();
+    let (status, _) = send(&app, empty_request("GET", &format!("/nodes/{missing}/history"))).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_history_is_read_from_the_graph() {
+    let graph = Arc::new(InMemoryGraph::new());
+    let app = create_router(Arc::new(state_over(graph.clone()).await));
+
+    let (_, node) = send(&app, json_request("POST", "/nodes", json!({
+        "entity_type": "Person", "label": "Ada", "properties": {},
+    }))).await;
+    let uri = format!("/nodes/{}", node["id"].as_str().unwrap());
+    let (status, _) = send(&app, json_request("PATCH", &uri, json!({ "label": "Countess" }))).await;
+    assert_eq!(status, StatusCode::OK);
+    let (_, before) = send(&app, empty_request("GET", &format!("{uri}/history"))).await;
+    let (_, current) = send(&app, empty_request("GET", &uri)).await;
+
+    // A restarted server keeps only the graph
+    let app = create_router(Arc::new(state_over(graph).await));
+    let (status, after) = send(&app, empty_request("GET", &format!("{uri}/history"))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(after["versions"].as_array().unwrap().len(), 2);
+    assert_eq!(after, before);
+    let (_, reread) = send(&app, empty_request("GET", &uri)).await;
+    assert_eq!(reread["version_id"], current["version_id"]);
+    assert_eq!(after["versions"][1]["version_id"], current["version_id"]);
+}
+
+fn if_match_request(uri: &str, etag: &str, body: Value) -> Request<Body> {
+    let mut request = json_request("PATCH", uri, body);
+    request.headers_mut().insert("if-match", etag.parse().unwrap());
+    request
+}
+
+// Like `send`, also returning the response's ETag
+async fn send_tagged(app: &axum::Router, request: Request<Body>) -> (StatusCode, String, Value) {
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let etag = response.headers().get("etag")
+        .map(|value| value.to_str().unwrap().to_string())
+        .unwrap_or_default();
+    let body = read_body(response.into_body()).await;
+    (status, etag, serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+#[tokio::test]
+async fn test_conditional_updates_with_etags() {
+    let app = test_app().await;
+
+    let (status, created, node) = send_tagged(&app, json_request("POST", "/nodes", json!({
+        "entity_type": "Person",
+        "label": "Grace",
+        "properties": {},
+    }))).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let uri = format!("/nodes/{}", node["id"].as_str().unwrap());
+
+    let (status, read, _) = send_tagged(&app, empty_request("GET", &uri)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(read.starts_with('"') && read.ends_with('"'));
+    assert_eq!(read, created);
+
+    // The first writer wins and gets the new version's tag
+    let (status, written, _) = send_tagged(&app, if_match_request(&uri, &read, json!({ "label": "Grace Hopper" }))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_ne!(written, read);
+
+    // A second writer holding the old tag is rejected
+    let (status, body) = send(&app, if_match_request(&uri, &read, json!({ "label": "Amazing Grace" }))).await;
+    assert_eq!(status, StatusCode::PRECONDITION_FAILED);
+    assert_eq!(body["error"]["code"], 412);
+    let (_, current) = send(&app, empty_request("GET", &uri)).await;
+    assert_eq!(current["label"], "Grace Hopper");
+
+    // Any of several tags may match, `*` matches any version and If-Match is optional by default
+    let (status, _) = send(&app, if_match_request(&uri, &format!("{read}, {written}"), json!({ "label": "Grace" }))).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = send(&app, if_match_request(&uri, "*", json!({ "label": "Grace" }))).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = send(&app, json_request("PATCH", &uri, json!({ "label": "Grace" }))).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = send(&app, if_match_request(&uri, "W/\"1\"", json!({ "label": "Grace" }))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_if_match_can_be_required() {
+    let app = create_router(Arc::new(test_state().await.with_required_if_match()));
+
+    let (_, ada) = send(&app, json_request("POST", "/nodes", json!({ "entity_type": "Person", "label": "Ada", "properties": {} }))).await;
+    let (_, charles) = send(&app, json_request("POST", "/nodes", json!({ "entity_type": "Person", "label": "Charles", "properties": {} }))).await;
+    let uri = format!("/nodes/{}", ada["id"].as_str().unwrap());
+    let (status, _) = send(&app, json_request("PATCH", &uri, json!({ "label": "Ada Lovelace" }))).await;
+    assert_eq!(status, StatusCode::PRECONDITION_REQUIRED);
+    let (_, read, _) = send_tagged(&app, empty_request("GET", &uri)).await;
+    let (status, _) = send(&app, if_match_request(&uri, &read, json!({ "label": "Ada Lovelace" }))).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, created, edge) = send_tagged(&app, json_request("POST", "/edges", json!({
+        "source_id": ada["id"],
+        "target_id": charles["id"],
+        "label": "corresponds_with",
+        "properties": {},
+    }))).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let uri = format!("/edges/{}", edge["id"].as_str().unwrap());
+    let (status, _) = send(&app, json_request("PATCH", &uri, json!({ "properties": { "since": 1833 } }))).await;
+    assert_eq!(status, StatusCode::PRECONDITION_REQUIRED);
+    let (status, written, _) = send_tagged(&app, if_match_request(&uri, &created, json!({ "properties": { "since": 1833 } }))).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = send(&app, if_match_request(&uri, &created, json!({ "properties": { "since": 1834 } }))).await;
+    assert_eq!(status, StatusCode::PRECONDITION_FAILED);
+    let (_, read, current) = send_tagged(&app, empty_request("GET", &uri)).await;
+    assert_eq!(read, written);
+    assert_eq!(current["properties"]["since"], 1833);
+}
+
+#[tokio::test]
+async fn test_edge_crud_and_batches() {
+    let app = test_app().await;
+
+    let (status, batch) = send(&app, json_request("POST", "/nodes/batch", json!({
+        "nodes": [
+            { "entity_type": "Person", "label": "Ada", "properties": {} },
+            { "entity_type": "Person", "label": "Charles", "properties": {} },
+            { "entity_type": "Person", "label": "Broken", "properties": {},
+              "valid_time": { "start": "2024-02-01T00:00:00Z", "end": "2024-01-01T00:00:00Z" } },
+        ]
+    }))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(batch["success_count"], 2);
+    assert_eq!(batch["failures"][0]["index"], 2);
+    let ada = batch["ids"][0].as_str().unwrap().to_string();
+    let charles = batch["ids"][1].as_str().unwrap().to_string();
+
+    let (status, edge) = send(&app, json_request("POST", "/edges", json!({
+        "source_id": ada,
+        "target_id": charles,
+        "label": "KNOWS",
+        "properties": { "since": 1833 },
+    }))).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let edge_id = edge["id"].as_str().unwrap().to_string();
+
+    // Labels cannot be changed
+    let (status, _) = send(&app, json_request("PATCH", &format!("/edges/{edge_id}"), json!({
+        "label": "COLLABORATES_WITH",
+    }))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, updated) = send(&app, json_request("PATCH", &format!("/edges/{edge_id}"), json!({
+        "label": "KNOWS",
+        "properties": { "since": 1834 },
+    }))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(updated["label"], "KNOWS");
+    assert_eq!(updated["properties"]["since"], 1834);
+
+    let (status, history) = send(&app, empty_request("GET", &format!("/edges/{edge_id}/history"))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(history["versions"].as_array().unwrap().len(), 2);
+    assert_eq!(history["versions"][0]["edge"]["properties"]["since"], 1833);
+    assert_eq!(history["versions"][1]["edge"]["properties"]["since"], 1834);
+    assert_eq!(history["versions"][1]["superseded_at"], Value::Null);
+
+    let missing = uuid::Uuid::new_v4().to_string();
+    let (status, batch) = send(&app, json_request("POST", "/edges/batch", json!({
+        "edges": [
+            { "source_id": ada, "target_id": missing, "label": "KNOWS", "properties": {} },
+        ]
+    }))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(batch["success_count"], 0);
+    assert_eq!(batch["failures"][0]["index"], 0);
+
+    // Deleting a node removes its edges
+    let (status, _) = send(&app, empty_request("DELETE", &format!("/nodes/{charles}"))).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = send(&app, empty_request("GET", &format!("/edges/{edge_id}"))).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = send(&app, empty_request("DELETE", &format!("/edges/{edge_id}"))).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_change_feed_over_websocket() {
+    type Socket = tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>>;
+    async fn next_change(socket: &mut Socket) -> Value {
+        let message = tokio::time::timeout(Duration::from_secs(5), socket.next()).await.unwrap().unwrap().unwrap();
+        serde_json::from_str(message.to_text().unwrap()).unwrap()
+    }
+
+    let app = test_app().await;
+    let addr = serve(app.clone()).await;
+    let (mut people, _) = tokio_tungstenite::connect_async(format!("ws://{addr}/changes?entity_type=Person")).await.unwrap();
+
+    let (_, ada) = send(&app, json_request("POST", "/nodes", json!({
+        "entity_type": "Person", "label": "Ada", "properties": {},
+    }))).await;
+    let (_, _) = send(&app, json_request("POST", "/nodes", json!({
+        "entity_type": "Topic", "label": "Engines", "properties": {},
+    }))).await;
+    let uri = format!("/nodes/{}", ada["id"].as_str().unwrap());
+    send(&app, json_request("PATCH", &uri, json!({ "label": "Countess" }))).await;
+    send(&app, empty_request("DELETE", &uri)).await;
+
+    let created = next_change(&mut people).await;
+    assert_eq!(created["kind"], "created");
+    assert_eq!(created["id"], ada["id"]);
+    assert_eq!(created["before"], Value::Null);
+    assert_eq!(created["after"]["type"], "node");
+    assert_eq!(created["after"]["label"], "Ada");
+    let superseded = next_change(&mut people).await;
+    assert_eq!(superseded["kind"], "superseded");
+    assert_eq!(superseded["version_id"], created["version_id"]);
+    let updated = next_change(&mut people).await;
+    assert_eq!(updated["kind"], "updated");
+    assert_eq!(updated["before"]["label"], "Ada");
+    assert_eq!(updated["after"]["label"], "Countess");
+    assert_ne!(updated["version_id"], created["version_id"]);
+    let deleted = next_change(&mut people).await;
+    assert_eq!(deleted["kind"], "deleted");
+    assert_eq!(deleted["version_id"], updated["version_id"]);
+    assert!(deleted["sequence"].as_u64() > created["sequence"].as_u64());
+
+    // A reconnecting client resumes after the last sequence it saw
+    let resume = format!("ws://{addr}/changes?node_id={}&since={}", ada["id"].as_str().unwrap(), created["sequence"]);
+    let (mut resumed, _) = tokio_tungstenite::connect_async(resume).await.unwrap();
+    assert_eq!(next_change(&mut resumed).await["sequence"], superseded["sequence"]);
+    assert_eq!(next_change(&mut resumed).await["sequence"], updated["sequence"]);
+    assert_eq!(next_change(&mut resumed).await["sequence"], deleted["sequence"]);
+
+    match tokio_tungstenite::connect_async(format!("ws://{addr}/changes?node_id=not-a-uuid")).await {
+        Err(tokio_tungstenite::tungstenite::Error::Http(response)) => assert_eq!(response.status(), StatusCode::BAD_REQUEST),
+        other => panic!("expected a rejected upgrade, got {:?}", other.map(|_| ())),
+    }
+}
+
+#[tokio::test]
+async fn test_mcp_over_streamable_http() {
+    let app = test_app().await;
+    let rpc = |session: Option<&str>, message: Value| {
+        let mut request = json_request("POST", "/mcp", message);
+        if let Some(session) = session {
+            request.headers_mut().insert("mcp-session-id", session.parse().unwrap());
+        }
+        request
+    };
+
+    // Messages outside a session are rejected
+    let (status, body) = send(&app, rpc(None, json!({ "jsonrpc": "2.0", "id": 1, "method": "tools/list" }))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["error"]["code"], -32600);
+
+    let response = app.clone().oneshot(rpc(None, json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "initialize",
+        "params": { "protocolVersion": "2025-03-26", "capabilities": {}, "clientInfo": { "name": "e2e", "version": "1" } },
+    }))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let session = response.headers()["mcp-session-id"].to_str().unwrap().to_string();
+    let body: Value = serde_json::from_slice(&read_body(response.into_body()).await).unwrap();
+    assert_eq!(body["result"]["protocolVersion"], "2025-03-26");
+
+    let (status, _) = send(&app, rpc(Some(&session), json!({ "jsonrpc": "2.0", "method": "notifications/initialized" }))).await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+
+    let (status, body) = send(&app, rpc(Some(&session), json!({ "jsonrpc": "2.0", "id": 2, "method": "tools/list" }))).await;
+    assert_eq!(status, StatusCode::OK);
+    let names: Vec<_> = body["result"]["tools"].as_array().unwrap().iter().map(|tool| tool["name"].clone()).collect();
+    assert_eq!(names, ["get_entity", "get_connections", "get_entity_history", "search_entities", "navigate", "create_entity"]);
+
+    let (_, body) = send(&app, rpc(Some(&session), json!({
+        "jsonrpc": "2.0", "id": 3, "method": "tools/call",
+        "params": { "name": "search_entities", "arguments": { "query": "engines" } },
+    }))).await;
+    assert_eq!(body["id"], 3);
+    assert_eq!(body["result"]["isError"], false);
+
+    let (_, body) = send(&app, rpc(Some(&session), json!({
+        "jsonrpc": "2.0", "id": 5, "method": "tools/call",
+        "params": { "name": "create_entity", "arguments": { "label": "Ada Lovelace", "entity_type": "Person" } },
+    }))).await;
+    let entity: Value = serde_json::from_str(body["result"]["content"][0]["text"].as_str().unwrap()).unwrap();
+    let (status, node) = send(&app, empty_request("GET", &format!("/nodes/{}", entity["id"].as_str().unwrap()))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(node["label"], "Ada Lovelace");
+
+    let (_, body) = send(&app, rpc(Some(&session), json!({
+        "jsonrpc": "2.0", "id": 6, "method": "tools/call",
+        "params": { "name": "get_entity", "arguments": { "id": uuid::Uuid::new_v4().to_string() } },
+    }))).await;
+    assert_eq!(body["result"]["isError"], true);
+
+    let (_, body) = send(&app, rpc(Some(&session), json!({
+        "jsonrpc": "2.0", "id": 9, "method": "tools/call",
+        "params": { "name": "navigate", "arguments": { "id": entity["id"] } },
+    }))).await;
+    let navigation: Value = serde_json::from_str(body["result"]["content"][0]["text"].as_str().unwrap()).unwrap();
+    assert_eq!(navigation["current"]["label"], "Ada Lovelace");
+    let (_, body) = send(&app, rpc(Some(&session), json!({
+        "jsonrpc": "2.0", "id": 10, "method": "tools/call",
+        "params": { "name": "navigate", "arguments": { "session_id": navigation["session_id"], "direction": "back" } },
+    }))).await;
+    assert_eq!(body["result"]["isError"], true);
+
+    // Updates to subscribed resources arrive on the session's event stream
+    let (status, _) = send(&app, empty_request("GET", "/mcp")).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let mut open = empty_request("GET", "/mcp");
+    open.headers_mut().insert("mcp-session-id", session.parse().unwrap());
+    let response = app.clone().oneshot(open).await.unwrap();
+    assert_eq!(response.headers()["content-type"], "text/event-stream");
+    let mut events = response.into_body();
+
+    let uri = format!("kg://node/{}", entity["id"].as_str().unwrap());
+    let (_, body) = send(&app, rpc(Some(&session), json!({
+        "jsonrpc": "2.0", "id": 7, "method": "resources/read", "params": { "uri": uri },
+    }))).await;
+    assert!(body["result"]["contents"][0]["text"].as_str().unwrap().starts_with("# Ada Lovelace\n"));
+    let (_, body) = send(&app, rpc(Some(&session), json!({
+        "jsonrpc": "2.0", "id": 8, "method": "resources/subscribe", "params": { "uri": uri },
+    }))).await;
+    assert_eq!(body["result"], json!({}));
+
+    let (_, engine) = send(&app, json_request("POST", "/nodes", json!({ "entity_type": "Product", "label": "Analytical Engine", "properties": {} }))).await;
+    let (status, _) = send(&app, json_request("POST", "/edges", json!({
+        "source_id": entity["id"],
+        "target_id": engine["id"],
+        "label": "designed_for",
+        "properties": {},
+    }))).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let frame = tokio::time::timeout(Duration::from_secs(5), events.frame()).await.unwrap().unwrap().unwrap();
+    let event = String::from_utf8(frame.into_data().unwrap().to_vec()).unwrap();
+    let data: Value = serde_json::from_str(event.lines().find_map(|line| line.strip_prefix("data: ")).unwrap()).unwrap();
+    assert_eq!(data["method"], "notifications/resources/updated");
+    assert_eq!(data["params"]["uri"], uri);
+
+    let mut delete = empty_request("DELETE", "/mcp");
+    delete.headers_mut().insert("mcp-session-id", session.parse().unwrap());
+    let (status, _) = send(&app, delete).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    // Ending the session ends its stream
+    assert!(tokio::time::timeout(Duration::from_secs(5), events.frame()).await.unwrap().is_none());
+    let (status, _) = send(&app, rpc(Some(&session), json!({ "jsonrpc": "2.0", "id": 4, "method": "ping" }))).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_routes_require_credentials_and_scopes() {
+    let key = |secret: &str, scopes: &[&str]| ApiKeyConfig {
+        hash: hash_api_key(secret),
+        subject: secret.to_string(),
+        tenant: None,
+        scopes: scopes.iter().map(|s| s.to_string()).collect(),
+    };
+    let store = StaticKeyStore::new(vec![
+        key("reader", &[GRAPH_READ]),
+        key("writer", &[GRAPH_READ, GRAPH_WRITE]),
+    ]);
+    let state = test_state().await
+        .with_authenticator(Arc::new(ApiKeyAuthenticator::new(Arc::new(store))));
+    let app = create_router(Arc::new(state));
+
+    let with_key = |mut request: Request<Body>, key: &str| {
+        request.headers_mut().insert("X-API-Key", key.parse().unwrap());
+        request
+    };
+    let new_node = || json_request("POST", "/nodes", json!({
+        "entity_type": "Topic", "label": "Graphs", "properties": {},
+    }));
+
+    // Health stays public
+    let (status, _) = send(&app, empty_request("GET", "/health")).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, _) = send(&app, new_node()).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, _) = send(&app, with_key(new_node(), "guess")).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, _) = send(&app, with_key(new_node(), "reader")).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let mut request = new_node();
+    request.headers_mut().insert("Authorization", "Bearer writer".parse().unwrap());
+    let (status, node) = send(&app, request).await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    let uri = format!("/nodes/{}", node["id"].as_str().unwrap());
+    let (status, _) = send(&app, with_key(empty_request("GET", &uri), "reader")).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = send(&app, with_key(empty_request("DELETE", &uri), "reader")).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn test_requests_are_rate_limited_per_client() {
+    let limiter = RateLimiter::new(3, 0.001).with_route_cost("/nodes/batch", 3);
+    let app = create_router(Arc::new(test_state().await.with_rate_limiter(limiter)));
+
+    let from = |peer: [u8; 4], request: Request<Body>| {
+        let mut request = request;
+        request.extensions_mut().insert(ConnectInfo(SocketAddr::from((peer, 5000))));
+        request
+    };
+
+    let response = app.clone().oneshot(from([192, 0, 2, 1], empty_request("GET", "/health"))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["x-ratelimit-limit"], "3");
+    assert_eq!(response.headers()["x-ratelimit-remaining"], "2");
+
+    // A batch costs the rest of the budget
+    let batch = json_request("POST", "/nodes/batch", json!({ "nodes": [] }));
+    let response = app.clone().oneshot(from([192, 0, 2, 1], batch)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert!(response.headers().contains_key("retry-after"));
+
+    let response = app.clone().oneshot(from([192, 0, 2, 1], empty_request("GET", "/version"))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = app.clone().oneshot(from([192, 0, 2, 1], empty_request("GET", "/version"))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = app.clone().oneshot(from([192, 0, 2, 1], empty_request("GET", "/version"))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(response.headers()["x-ratelimit-remaining"], "0");
+
+    // Another client has its own bucket
+    let response = app.oneshot(from([192, 0, 2, 2], empty_request("GET", "/version"))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_failed_authentication_is_rate_limited_per_peer() {
+    let store = StaticKeyStore::new(vec![ApiKeyConfig {
+        hash: hash_api_key("reader"),
+        subject: "reader".to_string(),
+        tenant: None,
+        scopes: vec![GRAPH_READ.to_string()],
+    }]);
+    let state = test_state().await
+        .with_authenticator(Arc::new(ApiKeyAuthenticator::new(Arc::new(store))))
+        .with_rate_limiter(RateLimiter::new(2, 0.001));
+    let app = create_router(Arc::new(state));
+
+    let uri = format!("/nodes/{}", uuid::Uuid::new_v4());
+    let from = |peer: [u8; 4], key: &str| {
+        let mut request = empty_request("GET", &uri);
+        request.headers_mut().insert("X-API-Key", key.parse().unwrap());
+        request.extensions_mut().insert(ConnectInfo(SocketAddr::from((peer, 5000))));
+        request
+    };
+
+    // Authenticated requests are not charged to the peer
+    let (status, _) = send(&app, from([192, 0, 2, 1], "reader")).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, _) = send(&app, from([192, 0, 2, 1], "guess-1")).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, _) = send(&app, from([192, 0, 2, 1], "guess-2")).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    // Once its failures are spent the peer is refused before authenticating
+    let response = app.clone().oneshot(from([192, 0, 2, 1], "guess-3")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert!(response.headers().contains_key("retry-after"));
+    let (status, _) = send(&app, from([192, 0, 2, 1], "reader")).await;
+    assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+
+    let (status, _) = send(&app, from([192, 0, 2, 2], "reader")).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_tenants_cannot_see_each_others_data() {
+    let key = |secret: &str, tenant: &str| ApiKeyConfig {
+        hash: hash_api_key(secret),
+        subject: secret.to_string(),
+        tenant: Some(tenant.to_string()),
+        scopes: vec![GRAPH_READ.to_string(), GRAPH_WRITE.to_string()],
+    };
+    let store = StaticKeyStore::new(vec![key("acme-key", "acme"), key("globex-key", "globex")]);
+    let state = test_state().await
+        .with_authenticator(Arc::new(ApiKeyAuthenticator::new(Arc::new(store))));
+    let app = create_router(Arc::new(state));
+
+    let as_tenant = |key: &str, mut request: Request<Body>| {
+        request.headers_mut().insert("X-API-Key", key.parse().unwrap());
+        request
+    };
+
+    let text = "John works at Apple in California.";
+    let (status, _) = send(&app, as_tenant("acme-key", json_request("POST", "/knowledge/store", json!({ "text": text })))).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, node) = send(&app, as_tenant("acme-key", json_request("POST", "/nodes", json!({
+        "entity_type": "Topic", "label": "Secret plans", "properties": { "tenant_id": "globex" },
+    })))).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(node["properties"]["tenant_id"], "acme");
+    let node_uri = format!("/nodes/{}", node["id"].as_str().unwrap());
+
+    // The other tenant finds nothing, and cannot modify or link to it
+    let query = || json_request("POST", "/knowledge/query", json!({ "query": "Apple" }));
+    let (status, results) = send(&app, as_tenant("globex-key", query())).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(results["results"], json!([]));
+    assert_eq!(results["context"], json!([]));
+
+    let (status, _) = send(&app, as_tenant("globex-key", empty_request("GET", &node_uri))).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = send(&app, as_tenant("globex-key", empty_request("GET", &format!("{node_uri}/history")))).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = send(&app, as_tenant("globex-key", json_request("PATCH", &node_uri, json!({ "label": "Mine" })))).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = send(&app, as_tenant("globex-key", empty_request("DELETE", &node_uri))).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, other) = send(&app, as_tenant("globex-key", json_request("POST", "/nodes", json!({
+        "entity_type": "Topic", "label": "Other plans", "properties": {},
+    })))).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let (status, _) = send(&app, as_tenant("globex-key", json_request("POST", "/edges", json!({
+        "source_id": other["id"], "target_id": node["id"], "label": "copies", "properties": {},
+    })))).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    // The owner still sees everything it wrote
+    let (status, results) = send(&app, as_tenant("acme-key", query())).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(results["results"][0]["content"], "Apple");
+    assert_eq!(results["context"], json!([text]));
+    let (status, fetched) = send(&app, as_tenant("acme-key", empty_request("GET", &node_uri))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(fetched["label"], "Secret plans");
+
+    // MCP sessions are only usable by the caller that opened them
+    let response = app.clone().oneshot(as_tenant("acme-key", json_request("POST", "/mcp", json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "initialize",
+        "params": { "protocolVersion": "2025-03-26", "capabilities": {}, "clientInfo": { "name": "e2e", "version": "1" } },
+    })))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let session = response.headers()["mcp-session-id"].to_str().unwrap().to_string();
+    let in_session = |key: &str, mut request: Request<Body>| {
+        request.headers_mut().insert("mcp-session-id", session.parse().unwrap());
+        as_tenant(key, request)
+    };
+    let ping = || json_request("POST", "/mcp", json!({ "jsonrpc": "2.0", "id": 2, "method": "ping" }));
+
+    let (status, _) = send(&app, in_session("globex-key", ping())).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = send(&app, in_session("globex-key", empty_request("GET", "/mcp"))).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = send(&app, in_session("globex-key", empty_request("DELETE", "/mcp"))).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = send(&app, in_session("acme-key", ping())).await;
+    assert_eq!(status, StatusCode::OK);
+}
@@ -1,7 +1,7 @@
 use graph::{
     Config,
     graph::{Graph, new_graph},
-    types::{Node, Edge, NodeId, EdgeId, EntityType, Properties, TemporalRange, Timestamp},
+    types::{Node, Edge, NodeId, EdgeId, EntityType, Properties, TemporalFilter, TemporalRange, Timestamp},
 };
 use chrono::Utc;
 use std::collections::HashMap;
@@ -56,7 +56,7 @@ async fn test_graph_operations() {
     assert_eq!(node2_id, node2.id);
     
     // Test node retrieval
-    let retrieved_node1 = graph.get_node(node1_id).await.expect("Failed to get node 1");
+    let retrieved_node1 = graph.get_node(node1_id, TemporalFilter::current()).await.expect("Failed to get node 1");
     assert_eq!(retrieved_node1.id, node1.id);
     assert_eq!(retrieved_node1.label, node1.label);
     
@@ -82,19 +82,19 @@ async fn test_graph_operations() {
     assert_eq!(edge_id, edge.id);
     
     // Test edge retrieval
-    let retrieved_edge = graph.get_edge(edge_id).await.expect("Failed to get edge");
+    let retrieved_edge = graph.get_edge(edge_id, TemporalFilter::current()).await.expect("Failed to get edge");
     assert_eq!(retrieved_edge.id, edge.id);
     assert_eq!(retrieved_edge.label, edge.label);
     
     // Test getting connected nodes
-    let connected_nodes = graph.get_connected_nodes(node1_id, None)
+    let connected_nodes = graph.get_connected_nodes(node1_id, TemporalFilter::current())
         .await
         .expect("Failed to get connected nodes");
     assert_eq!(connected_nodes.len(), 1);
     assert_eq!(connected_nodes[0].id, node2_id);
     
     // Test getting edges for node
-    let node_edges = graph.get_edges_for_node(node1_id, None)
+    let node_edges = graph.get_edges_for_node(node1_id, TemporalFilter::current())
         .await
         .expect("Failed to get edges for node");
     assert_eq!(node_edges.len(), 1);
@@ -106,18 +106,18 @@ async fn test_graph_operations() {
         end: Some(Timestamp(Utc::now() + chrono::Duration::hours(2))),
     };
     
-    let future_edges = graph.get_edges_for_node(node1_id, Some(future_time))
+    let future_edges = graph.get_edges_for_node(node1_id, TemporalFilter::valid_during(future_time))
         .await
         .expect("Failed to get future edges");
     assert_eq!(future_edges.len(), 0);
     
     // Test node deletion
     graph.delete_node(node1_id).await.expect("Failed to delete node 1");
-    let node1_result = graph.get_node(node1_id).await;
+    let node1_result = graph.get_node(node1_id, TemporalFilter::current()).await;
     assert!(node1_result.is_err());
     
     // Test edge deletion
     graph.delete_edge(edge_id).await.expect("Failed to delete edge");
-    let edge_result = graph.get_edge(edge_id).await;
+    let edge_result = graph.get_edge(edge_id, TemporalFilter::current()).await;
     assert!(edge_result.is_err());
 } 
\ No newline at end of file
@@ -7,7 +7,7 @@ use opensearch::{
 };
 use graph::{
     error::{Error, Result},
-    memory::{MemoryEntry, MemoryOperations},
+    memory::MemoryEntry,
     types::EntityType,
 };
 use serde_json::{json, Value};